@@ -0,0 +1,45 @@
+// How long a frame takes to update and draw with each star quality tier, and
+// with bloom on top of the standard one, at 1080p and 4K: `cargo bench`.
+// Prints the mean over FRAMES frames once the intro has settled.
+
+use rand::SeedableRng;
+use std::time::Instant;
+use wl_starfield::config::{Config, Quality};
+use wl_starfield::rng::FastRng;
+use wl_starfield::{ScreenDetails, Starfield};
+
+const WARM_UP: usize = 180;
+const FRAMES: usize = 300;
+const DT: f32 = 1.0 / 60.0;
+
+fn main() {
+    for (width, height) in [(1920, 1080), (3840, 2160)] {
+        for (quality, bloom) in [
+            (Quality::Standard, false),
+            (Quality::Smooth, false),
+            (Quality::High, false),
+            (Quality::Standard, true),
+        ] {
+            let config = Config {
+                quality,
+                bloom,
+                ..Config::default()
+            };
+            let screen_details = ScreenDetails { width, height };
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(1), screen_details, config);
+            let mut frame = vec![0; width as usize * height as usize * 4];
+            for _ in 0..WARM_UP {
+                starfield.update(DT);
+                starfield.render(&mut frame);
+            }
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                starfield.update(DT);
+                starfield.render(&mut frame);
+            }
+            let per_frame = start.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+            let bloom = if bloom { " + bloom" } else { "" };
+            println!("{width}x{height} {quality:?}{bloom}: {per_frame:.2} ms a frame");
+        }
+    }
+}
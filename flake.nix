@@ -37,6 +37,7 @@
           nativeBuildInputs = with pkgs; [
             pkg-config
             makeWrapper
+            installShellFiles
           ];
 
           buildInputs =
@@ -60,6 +61,12 @@
             RUST_BACKTRACE = "1";
 
             postInstall = ''
+              installShellCompletion --cmd wl-starfield \
+                --bash <($out/bin/wl-starfield completions bash) \
+                --zsh <($out/bin/wl-starfield completions zsh) \
+                --fish <($out/bin/wl-starfield completions fish)
+              $out/bin/wl-starfield --generate-man > wl-starfield.1
+              installManPage wl-starfield.1
               wrapProgram $out/bin/wl-starfield \
                 --set LD_LIBRARY_PATH "${libPath}"
             '';
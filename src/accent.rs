@@ -0,0 +1,145 @@
+// An accent color to tint the sky with, to match the rest of the desktop.
+// It's either given outright as "#rrggbb" or taken from the colors pywal
+// generated from the wallpaper, which wallust and other theming tools can
+// write too. Stars and shooting stars keep their brightness and are pulled
+// part of the way towards the accent's hue.
+
+use crate::render::{luma, pack};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// The pywal color used when none is named: color1, the first one it picks
+// out of the wallpaper
+const PYWAL_DEFAULT: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Accent {
+    Color((u8, u8, u8)),
+    // One of the sixteen colors in pywal's color file, by number
+    Pywal(usize),
+}
+
+impl FromStr for Accent {
+    type Err = ();
+
+    // "#rrggbb", "pywal", or "pywal:N" for colorN
+    fn from_str(text: &str) -> Result<Self, ()> {
+        match text.split_once(':') {
+            Some(("pywal", index)) => match index.parse() {
+                Ok(index) if index < 16 => Ok(Accent::Pywal(index)),
+                _ => Err(()),
+            },
+            _ if text == "pywal" => Ok(Accent::Pywal(PYWAL_DEFAULT)),
+            _ => hex_color(text).map(Accent::Color).ok_or(()),
+        }
+    }
+}
+
+impl Accent {
+    // The file the color comes from, if it comes from one
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            Accent::Color(_) => None,
+            Accent::Pywal(_) => pywal_path(),
+        }
+    }
+
+    // The color as it is now, reading it afresh from its file if it has one
+    pub fn color(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Accent::Color(color) => Some(color),
+            Accent::Pywal(index) => {
+                let text = fs::read_to_string(pywal_path()?).ok()?;
+                pywal_color(&text, index)
+            }
+        }
+    }
+}
+
+// $XDG_CACHE_HOME/wal/colors, or under ~/.cache without one
+fn pywal_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("wal").join("colors"))
+}
+
+// pywal's plain colors file: color0 to color15, a "#rrggbb" to a line
+fn pywal_color(text: &str, index: usize) -> Option<(u8, u8, u8)> {
+    hex_color(text.lines().nth(index)?.trim())
+}
+
+pub(crate) fn hex_color(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+// An accent ready to mix into colors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Tint {
+    color: (u8, u8, u8),
+    luma: f32,
+    // How far colors are pulled towards it, 0..1
+    strength: f32,
+}
+
+impl Tint {
+    pub fn new(color: (u8, u8, u8), strength: f32) -> Self {
+        Self {
+            color,
+            luma: luma(pack(color)).max(1.0),
+            strength,
+        }
+    }
+
+    // `color` moved towards the accent, keeping roughly its brightness
+    pub fn apply(&self, color: (u8, u8, u8)) -> (u8, u8, u8) {
+        let scale = luma(pack(color)) / self.luma;
+        let mix = |from: u8, to: u8| {
+            let to = (to as f32 * scale).min(255.0);
+            (from as f32 + (to - from as f32) * self.strength).round() as u8
+        };
+        (
+            mix(color.0, self.color.0),
+            mix(color.1, self.color.1),
+            mix(color.2, self.color.2),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors_and_pywal() {
+        assert_eq!("#ff8000".parse(), Ok(Accent::Color((255, 128, 0))));
+        assert_eq!("pywal".parse(), Ok(Accent::Pywal(1)));
+        assert_eq!("pywal:4".parse(), Ok(Accent::Pywal(4)));
+        assert_eq!("pywal:16".parse::<Accent>(), Err(()));
+        assert_eq!("ff8000".parse::<Accent>(), Err(()));
+        assert_eq!("#ff80".parse::<Accent>(), Err(()));
+
+        let colors = "#101010\n#d3869b\n#8ec07c\n";
+        assert_eq!(pywal_color(colors, 1), Some((0xd3, 0x86, 0x9b)));
+        assert_eq!(pywal_color(colors, 5), None);
+    }
+
+    #[test]
+    fn tints_keep_brightness_and_shift_hue() {
+        let white = (200, 200, 200);
+        let tint = Tint::new((255, 0, 0), 0.5);
+        let (r, g, b) = tint.apply(white);
+        assert!(r > 200 && g < 200 && b < 200, "{:?}", (r, g, b));
+        // Black stays black, and no strength leaves colors alone
+        assert_eq!(tint.apply((0, 0, 0)), (0, 0, 0));
+        assert_eq!(Tint::new((255, 0, 0), 0.0).apply(white), white);
+    }
+}
@@ -0,0 +1,53 @@
+// Following an accent color that comes from a file, so the sky changes along
+// with the rest of the desktop when pywal (or whatever writes its colors) is
+// run again for a new wallpaper. The file is looked at every CHECK_INTERVAL
+// and only read when it has been written since.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use wl_starfield::accent::Accent;
+use wl_starfield::config::Config;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct AccentWatch {
+    accent: Option<Accent>,
+    path: Option<PathBuf>,
+    // When the file was written, as of the last look
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl AccentWatch {
+    pub fn new(config: &Config) -> Self {
+        let path = config.accent.and_then(|accent| accent.path());
+        let modified = path.as_deref().and_then(modified);
+        Self {
+            accent: config.accent,
+            path,
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    // The accent color afresh, if its file has been written since it was
+    // last read and still has one
+    pub fn check(&mut self) -> Option<(u8, u8, u8)> {
+        let path = self.path.as_ref()?;
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+        let modified = modified(path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        self.accent?.color()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
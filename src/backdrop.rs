@@ -0,0 +1,314 @@
+// What the sky is drawn on, in place of flat black: a vertical gradient of
+// two or three colors, top first, or a PNG image scaled to cover the screen
+// and cropped to it, keeping its shape. Either can be tinted by the time of
+// day: dim and blue at night, warm at dawn and dusk, as it is by day. Both
+// are laid out once for the size of the screen and again only when the tint
+// moves on, a minute at a time, so each frame just copies them in.
+
+use crate::gradient::Gradient;
+use crate::render::{Pixel, Rect, Target, pack};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+// The tint through the day, by hour, in multiples of each channel
+const DAY_TINTS: [(f32, (f32, f32, f32)); 8] = [
+    (0.0, (0.35, 0.4, 0.6)),
+    (5.0, (0.4, 0.42, 0.6)),
+    (7.0, (1.0, 0.75, 0.6)),
+    (10.0, (1.0, 1.0, 1.0)),
+    (16.0, (1.0, 1.0, 1.0)),
+    (19.0, (1.0, 0.65, 0.5)),
+    (21.0, (0.45, 0.45, 0.65)),
+    (24.0, (0.35, 0.4, 0.6)),
+];
+const SECONDS_PER_MINUTE: u64 = 60;
+
+pub(crate) struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<(u8, u8, u8)>,
+}
+
+impl Image {
+    // Any PNG, with transparency laid over black
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(io::Error::other)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(io::Error::other)?;
+        let (width, height) = (info.width as usize, info.height as usize);
+        let over_black = |value: u8, alpha: u8| (value as u16 * alpha as u16 / 255) as u8;
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .map(|rgb| (rgb[0], rgb[1], rgb[2]))
+                .collect(),
+            png::ColorType::Rgba => buffer
+                .chunks_exact(4)
+                .map(|rgba| {
+                    let a = rgba[3];
+                    (
+                        over_black(rgba[0], a),
+                        over_black(rgba[1], a),
+                        over_black(rgba[2], a),
+                    )
+                })
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().map(|&v| (v, v, v)).collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .map(|va| {
+                    let v = over_black(va[0], va[1]);
+                    (v, v, v)
+                })
+                .collect(),
+            png::ColorType::Indexed => {
+                return Err(io::Error::other("palette images aren't expanded"));
+            }
+        };
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // The color at `(x, y)` in the image, smoothly between pixels' centers
+    pub fn sample(&self, x: f32, y: f32) -> (f32, f32, f32) {
+        let place = |at: f32, len: usize| {
+            let at = (at - 0.5).clamp(0.0, (len - 1) as f32);
+            let before = at.floor() as usize;
+            (before, (before + 1).min(len - 1), at - before as f32)
+        };
+        let (left, right, tx) = place(x, self.width);
+        let (top, bottom, ty) = place(y, self.height);
+        let at = |x: usize, y: usize| {
+            let (r, g, b) = self.pixels[y * self.width + x];
+            (r as f32, g as f32, b as f32)
+        };
+        let lerp = |a: (f32, f32, f32), b: (f32, f32, f32), t: f32| {
+            (
+                a.0 + (b.0 - a.0) * t,
+                a.1 + (b.1 - a.1) * t,
+                a.2 + (b.2 - a.2) * t,
+            )
+        };
+        lerp(
+            lerp(at(left, top), at(right, top), tx),
+            lerp(at(left, bottom), at(right, bottom), tx),
+            ty,
+        )
+    }
+}
+
+pub(crate) enum Source {
+    Gradient(Gradient),
+    Image(Image),
+}
+
+pub(crate) struct Backdrop {
+    source: Source,
+    tinted: bool,
+    // The view it's laid out for, and the minute of the day it's tinted for
+    size: (usize, usize),
+    minute: Option<u64>,
+    // The image scaled to cover the view, before tinting, if it's tinted
+    scaled: Vec<Pixel>,
+    // What's drawn: one pixel a row for a gradient, or the whole image
+    shown: Vec<Pixel>,
+}
+
+impl Backdrop {
+    // With `tinted`, by the time of day
+    pub fn new(source: Source, tinted: bool) -> Self {
+        Self {
+            source,
+            tinted,
+            size: (0, 0),
+            minute: None,
+            scaled: Vec::new(),
+            shown: Vec::new(),
+        }
+    }
+
+    // Lay out for a view this size, at `local` seconds since the epoch in
+    // local time, if either has changed
+    pub fn prepare(&mut self, width: usize, height: usize, local: u64) {
+        let minute = self
+            .tinted
+            .then_some(local / SECONDS_PER_MINUTE % (24 * 60));
+        if (width, height) == self.size && minute == self.minute {
+            return;
+        }
+        if (width, height) != self.size {
+            self.size = (width, height);
+            self.scale();
+        }
+        self.minute = minute;
+        let Some(minute) = minute else {
+            // Shown as it is, with no need to keep a copy
+            std::mem::swap(&mut self.shown, &mut self.scaled);
+            return;
+        };
+        let tint = day_tint(minute as f32 / 60.0);
+        let tinted = |&[r, g, b, _]: &Pixel| {
+            let channel = |value: u8, tint: f32| (value as f32 * tint).round().min(255.0) as u8;
+            pack((channel(r, tint.0), channel(g, tint.1), channel(b, tint.2)))
+        };
+        self.shown.clear();
+        self.shown.extend(self.scaled.iter().map(tinted));
+    }
+
+    // The untinted colors: a row's worth each for a gradient, or the image
+    // scaled so it covers the view and centered
+    fn scale(&mut self) {
+        let (width, height) = self.size;
+        self.scaled.clear();
+        match &self.source {
+            Source::Gradient(gradient) => {
+                let along = |y: usize| (y as f32 + 0.5) / height as f32;
+                self.scaled
+                    .extend((0..height).map(|y| pack(gradient.color_at(along(y)))));
+            }
+            Source::Image(image) => {
+                let scale =
+                    (image.width as f32 / width as f32).min(image.height as f32 / height as f32);
+                let left = (image.width as f32 - width as f32 * scale) / 2.0;
+                let top = (image.height as f32 - height as f32 * scale) / 2.0;
+                self.scaled.reserve(width * height);
+                for y in 0..height {
+                    let image_y = top + (y as f32 + 0.5) * scale;
+                    self.scaled.extend((0..width).map(|x| {
+                        let (r, g, b) = image.sample(left + (x as f32 + 0.5) * scale, image_y);
+                        pack((r.round() as u8, g.round() as u8, b.round() as u8))
+                    }));
+                }
+            }
+        }
+    }
+
+    // Fill the part of a frame covering `view` that the target holds
+    pub fn draw(&self, target: &mut Target, view: Rect) {
+        let (width, _) = self.size;
+        for (y, left, row) in target.span_rows(view) {
+            let y = (y - view.y0) as usize;
+            match self.source {
+                Source::Gradient(_) => row.fill(self.shown[y]),
+                Source::Image(_) => {
+                    let start = y * width + (left - view.x0) as usize;
+                    row.copy_from_slice(&self.shown[start..start + row.len()]);
+                }
+            }
+        }
+    }
+}
+
+// The tint `hour` hours into the day
+fn day_tint(hour: f32) -> (f32, f32, f32) {
+    let next = DAY_TINTS
+        .iter()
+        .position(|&(at, _)| at > hour)
+        .unwrap_or(DAY_TINTS.len() - 1)
+        .max(1);
+    let ((from, a), (to, b)) = (DAY_TINTS[next - 1], DAY_TINTS[next]);
+    let t = ((hour - from) / (to - from)).clamp(0.0, 1.0);
+    (
+        a.0 + (b.0 - a.0) * t,
+        a.1 + (b.1 - a.1) * t,
+        a.2 + (b.2 - a.2) * t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    const NOON: u64 = 12 * 3600;
+    const MIDNIGHT: u64 = 0;
+
+    #[test]
+    fn a_gradient_runs_top_to_bottom() {
+        let mut backdrop =
+            Backdrop::new(Source::Gradient("#0b1030, #000000".parse().unwrap()), false);
+        backdrop.prepare(1920, 1080, NOON);
+        let mut frame = Frame::new(&LANDSCAPE);
+        backdrop.draw(&mut frame.target(), Rect::new(0, 0, 1920, 1080));
+        assert_eq!(frame.pixel(5, 0), pack((11, 16, 48)));
+        assert_eq!(frame.pixel(1900, 0), frame.pixel(5, 0));
+        assert_eq!(frame.pixel(960, 1079), pack((0, 0, 0)));
+        assert!(frame.brightness(960, 300) > frame.brightness(960, 800));
+    }
+
+    #[test]
+    fn an_image_covers_the_screen_and_is_tinted_by_the_time_of_day() {
+        // A square, left half red and right half blue
+        let image = Image {
+            width: 100,
+            height: 100,
+            pixels: (0..100 * 100)
+                .map(|i| {
+                    if i % 100 < 50 {
+                        (200, 0, 0)
+                    } else {
+                        (0, 0, 200)
+                    }
+                })
+                .collect(),
+        };
+        let mut backdrop = Backdrop::new(Source::Image(image), true);
+        let view = Rect::new(0, 0, 1920, 1080);
+        let mut frame = Frame::new(&LANDSCAPE);
+        backdrop.prepare(1920, 1080, NOON);
+        backdrop.draw(&mut frame.target(), view);
+        // Scaled up to the width, cropped top and bottom
+        assert_eq!(frame.pixel(10, 10), pack((200, 0, 0)));
+        assert_eq!(frame.pixel(1910, 1070), pack((0, 0, 200)));
+        let [r, _, b, _] = frame.pixel(960, 540);
+        assert!(r > 50 && b > 50, "{r} {b}");
+
+        backdrop.prepare(1920, 1080, MIDNIGHT);
+        backdrop.draw(&mut frame.target(), view);
+        let [r, _, _, _] = frame.pixel(10, 10);
+        assert_eq!(r, 70);
+        // A day later it's midnight again
+        backdrop.prepare(1920, 1080, MIDNIGHT + 24 * 3600);
+        assert_eq!(backdrop.minute, Some(0));
+    }
+
+    #[test]
+    fn images_load_with_transparency_over_black() {
+        let path = std::env::temp_dir().join(format!("wl-starfield-bg-{}.png", std::process::id()));
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[255, 128, 0, 255, 200, 200, 200, 0])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let image = Image::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let image = image.unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, [(255, 128, 0), (0, 0, 0)]);
+        assert!(Image::load(Path::new("/nonexistent.png")).is_err());
+    }
+
+    #[test]
+    fn the_tint_is_warm_at_dusk_and_plain_by_day() {
+        assert_eq!(day_tint(13.0), (1.0, 1.0, 1.0));
+        let (r, g, b) = day_tint(19.0);
+        assert!(r > g && g > b);
+        assert_eq!(day_tint(0.0), day_tint(24.0));
+        let (r, _, b) = day_tint(2.5);
+        assert!(b > r && r < 0.5);
+    }
+}
@@ -0,0 +1,112 @@
+// `wl-starfield bench`: how long frames take on this machine, and where the
+// time goes. A seeded sky with the default config is run and drawn with no
+// window, at a fixed step and full detail, so runs before and after a change
+// draw exactly the same frames and can be compared.
+
+use crate::cli::BenchArgs;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+use wl_starfield::config::Config;
+use wl_starfield::profile::Profile;
+use wl_starfield::rng::FastRng;
+use wl_starfield::{ScreenDetails, Starfield};
+
+const DT: f32 = 1.0 / 60.0;
+// Frames run first, untimed, so the stars have flown out from the middle
+const WARM_UP: u32 = 180;
+
+pub fn run(args: &BenchArgs) {
+    let (width, height) = args.size;
+    let screen_details = ScreenDetails { width, height };
+    let mut starfield = Starfield::new(
+        FastRng::seed_from_u64(args.seed),
+        screen_details,
+        Config::default(),
+    );
+    starfield.set_frame_budget(None);
+    starfield.set_fixed_clock(true);
+
+    let mut frame = vec![0; width as usize * height as usize * 4];
+    for _ in 0..WARM_UP {
+        starfield.update(DT);
+        starfield.render(&mut frame);
+    }
+    starfield.set_profiling(true);
+    let mut profiles = Vec::with_capacity(args.frames as usize);
+    let mut frames = Vec::with_capacity(args.frames as usize);
+    for _ in 0..args.frames {
+        let started = Instant::now();
+        starfield.update(DT);
+        starfield.render(&mut frame);
+        frames.push(started.elapsed());
+        profiles.push(starfield.take_profile().unwrap_or_default());
+    }
+
+    println!(
+        "{} frames at {width}x{height}, seed {}",
+        args.frames, args.seed
+    );
+    println!("{:<20}{:>8}{:>8}{:>8}", "ms a frame", "min", "avg", "p99");
+    let part = |part: fn(&Profile) -> Duration| profiles.iter().map(part).collect();
+    print_row("star update", part(|profile| profile.star_update));
+    print_row("star draw", part(|profile| profile.star_draw));
+    print_row(
+        "shooting star draw",
+        part(|profile| profile.shooting_star_draw),
+    );
+    print_row("whole frame", frames);
+    println!("(drawing is summed over the render threads)");
+}
+
+fn print_row(name: &str, times: Vec<Duration>) {
+    let Some(Summary { min, avg, p99 }) = summarize(times) else {
+        return;
+    };
+    let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+    println!("{name:<20}{:>8.3}{:>8.3}{:>8.3}", ms(min), ms(avg), ms(p99));
+}
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    min: Duration,
+    avg: Duration,
+    p99: Duration,
+}
+
+fn summarize(mut times: Vec<Duration>) -> Option<Summary> {
+    times.sort_unstable();
+    let min = *times.first()?;
+    let avg = times.iter().sum::<Duration>() / times.len() as u32;
+    // The time 99 in 100 frames come in under
+    let p99 = times[(times.len() * 99).div_ceil(100) - 1];
+    Some(Summary { min, avg, p99 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_frame_times() {
+        let ms = Duration::from_millis;
+        let mut times: Vec<Duration> = (1..=200).map(ms).collect();
+        times.reverse();
+        assert_eq!(
+            summarize(times),
+            Some(Summary {
+                min: ms(1),
+                avg: Duration::from_micros(100_500),
+                p99: ms(198),
+            })
+        );
+        assert_eq!(
+            summarize(vec![ms(3)]),
+            Some(Summary {
+                min: ms(3),
+                avg: ms(3),
+                p99: ms(3),
+            })
+        );
+        assert_eq!(summarize(Vec::new()), None);
+    }
+}
@@ -0,0 +1,105 @@
+// Star colors from temperature. A star's color is set by how hot its surface
+// is: cool ones glow orange-red, the sun is a warm white and the hottest are
+// blue-white. Temperatures are drawn in the proportions of the spectral
+// classes among the stars bright enough to see with the naked eye, where K
+// giants and A stars are common and red dwarfs, however many there are, too
+// faint to show.
+
+use rand::Rng;
+
+// Spectral classes: share of naked-eye stars and range of temperatures (K)
+const CLASSES: [(f32, f32, f32); 6] = [
+    (0.10, 10_000.0, 30_000.0), // O and B
+    (0.22, 7_500.0, 10_000.0),  // A
+    (0.19, 6_000.0, 7_500.0),   // F
+    (0.14, 5_200.0, 6_000.0),   // G
+    (0.31, 3_700.0, 5_200.0),   // K
+    (0.04, 2_400.0, 3_700.0),   // M
+];
+pub const COOLEST: f32 = 2_400.0;
+pub const HOTTEST: f32 = 30_000.0;
+
+// A star's surface temperature in kelvin
+pub fn temperature(rng: &mut impl Rng) -> f32 {
+    let mut pick = rng.r#gen::<f32>();
+    let &(_, low, high) = CLASSES
+        .iter()
+        .find(|&&(share, ..)| {
+            pick -= share;
+            pick < 0.0
+        })
+        .unwrap_or(&CLASSES[CLASSES.len() - 1]);
+    // Spread evenly in log temperature across the class
+    (low.ln() + rng.r#gen::<f32>() * (high.ln() - low.ln())).exp()
+}
+
+// Where a temperature falls between COOLEST (0) and HOTTEST (1), evenly in
+// log temperature; hotter stars are also the bigger, brighter ones
+pub fn heat(kelvin: f32) -> f32 {
+    ((kelvin / COOLEST).ln() / (HOTTEST / COOLEST).ln()).clamp(0.0, 1.0)
+}
+
+// The color of a blackbody at this temperature, scaled so its brightest
+// channel is full. A fit to the CIE color matching functions (Tanner
+// Helland's), good to a few percent over the range stars come in.
+pub fn color(kelvin: f32) -> (u8, u8, u8) {
+    let t = kelvin.clamp(1_000.0, 40_000.0) / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.699 * (t - 60.0).powf(-0.133_205)
+    };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    let [red, green, blue] = [red, green, blue].map(|c: f32| c.clamp(0.0, 255.0));
+    let scale = 255.0 / red.max(green).max(blue);
+    (
+        (red * scale) as u8,
+        (green * scale) as u8,
+        (blue * scale) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::seeded_rng;
+
+    #[test]
+    fn hot_stars_are_blue_and_cool_ones_red() {
+        let (r, g, b) = color(3_000.0);
+        assert!(r > g && g > b, "{r} {g} {b}");
+        // The sun is close to white
+        let (r, g, b) = color(5_800.0);
+        assert!(r == 255 && g > 220 && b > 200, "{r} {g} {b}");
+        let (r, g, b) = color(20_000.0);
+        assert!(b == 255 && r < 200, "{r} {g} {b}");
+    }
+
+    #[test]
+    fn temperatures_follow_the_classes() {
+        let mut rng = seeded_rng(4);
+        let temperatures: Vec<f32> = (0..10_000).map(|_| temperature(&mut rng)).collect();
+        assert!(temperatures.iter().all(|t| (COOLEST..=HOTTEST).contains(t)));
+        // K stars are the commonest, M stars rare
+        let share = |low: f32, high: f32| {
+            temperatures
+                .iter()
+                .filter(|&&t| t >= low && t < high)
+                .count() as f32
+                / 10_000.0
+        };
+        assert!((share(3_700.0, 5_200.0) - 0.31).abs() < 0.02);
+        assert!((share(2_400.0, 3_700.0) - 0.04).abs() < 0.01);
+    }
+}
@@ -0,0 +1,269 @@
+// Bloom: light from the brightest parts of the frame bleeding softly into the
+// sky around them, as it would through a lens. Whatever is brighter than the
+// threshold is gathered into a buffer BLOOM_SCALE times smaller each way,
+// blurred there with a Gaussian, one direction at a time, and added back
+// over the frame, smoothly scaled up. The blur spreads light from the few
+// bright cells rather than gathering into every cell, so it costs next to
+// nothing on a mostly dark sky.
+
+use crate::render::{Pixel, Rect, Target, luma};
+use crate::workers::Workers;
+use std::sync::Mutex;
+
+// Frame pixels to a side of each cell of the buffer
+const BLOOM_SCALE: usize = 4;
+// Pixels looked over at once for any bright enough to bloom
+const SCAN_RUN: usize = 16;
+// Cells with less light than this, a small part of a step, are too faint to
+// show even spread over their neighbours, and are left out of the blur
+const FAINT: f32 = 1.0 / 64.0;
+// Spread of the blur in cells, and how many spreads it reaches out to
+const BLOOM_SIGMA: f32 = 3.0;
+const BLOOM_REACH: f32 = 3.0;
+
+pub(crate) struct Bloom {
+    // Brightness, 0..1, above which pixels bloom, and how strongly
+    threshold: f32,
+    strength: f32,
+    cols: usize,
+    rows: usize,
+    // Light in each cell, row-major, per channel out of 255
+    light: Vec<[f32; 3]>,
+    // The buffer blurred across, on its way to being blurred down
+    scratch: Vec<[f32; 3]>,
+    // Whether a cell or any next to it has light enough to show, as blocks
+    // of pixels with none nearby are left alone
+    lit: Vec<bool>,
+    lit_rows: Vec<bool>,
+    // Blur weights out from the middle, adding up to 1 both ways
+    kernel: Vec<f32>,
+}
+
+impl Bloom {
+    pub fn new(threshold: f32, strength: f32) -> Self {
+        let reach = (BLOOM_SIGMA * BLOOM_REACH).ceil() as i32;
+        let weights: Vec<f32> = (0..=reach)
+            .map(|d| (-(d * d) as f32 / (2.0 * BLOOM_SIGMA * BLOOM_SIGMA)).exp())
+            .collect();
+        let total = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+        Self {
+            threshold,
+            strength,
+            cols: 0,
+            rows: 0,
+            light: Vec::new(),
+            scratch: Vec::new(),
+            lit: Vec::new(),
+            lit_rows: Vec::new(),
+            kernel: weights.iter().map(|w| w / total).collect(),
+        }
+    }
+
+    // Gather what blooms from a frame `width` pixels wide, spread over the
+    // `workers`, and blur it
+    pub fn gather(&mut self, pixels: &[Pixel], width: usize, workers: &Workers) {
+        let height = pixels.len() / width;
+        self.cols = width.div_ceil(BLOOM_SCALE);
+        self.rows = height.div_ceil(BLOOM_SCALE);
+        let cells = self.cols * self.rows;
+        self.light.clear();
+        self.light.resize(cells, [0.0; 3]);
+        self.scratch.clear();
+        self.scratch.resize(cells, [0.0; 3]);
+
+        // Bands of whole rows of cells, one to a thread
+        let band_rows = self.rows.div_ceil(workers.threads());
+        let bands = self
+            .light
+            .chunks_mut(band_rows * self.cols)
+            .zip(pixels.chunks(band_rows * BLOOM_SCALE * width));
+        let count = bands.len();
+        let bands = Mutex::new(bands);
+        let threshold = self.threshold;
+        workers.run(count, |_| {
+            let next = bands.lock().unwrap().next();
+            if let Some((cells, pixels)) = next {
+                scan(pixels, width, cells, threshold);
+            }
+        });
+
+        // Across into the scratch buffer, then down back into the light
+        let (cols, rows) = (self.cols as i32, self.rows as i32);
+        let kernel = &self.kernel;
+        let reach = kernel.len() as i32 - 1;
+        for y in 0..rows {
+            for x in 0..cols {
+                let light = self.light[(y * cols + x) as usize];
+                if light.iter().all(|&channel| channel < FAINT) {
+                    continue;
+                }
+                for to in (x - reach).max(0)..=(x + reach).min(cols - 1) {
+                    let weight = kernel[(to - x).unsigned_abs() as usize];
+                    let cell = &mut self.scratch[(y * cols + to) as usize];
+                    for (cell, light) in cell.iter_mut().zip(light) {
+                        *cell += light * weight;
+                    }
+                }
+            }
+        }
+        self.light.fill([0.0; 3]);
+        for y in 0..rows {
+            for x in 0..cols {
+                let light = self.scratch[(y * cols + x) as usize];
+                if light.iter().all(|&channel| channel < FAINT) {
+                    continue;
+                }
+                for to in (y - reach).max(0)..=(y + reach).min(rows - 1) {
+                    let weight = kernel[(to - y).unsigned_abs() as usize] * self.strength;
+                    let cell = &mut self.light[(to * cols + x) as usize];
+                    for (cell, light) in cell.iter_mut().zip(light) {
+                        *cell += light * weight;
+                    }
+                }
+            }
+        }
+
+        // Less than a whole step of light adds nothing to a pixel, so only
+        // blocks near a cell with more need drawing over
+        self.lit.clear();
+        self.lit.resize(cells, false);
+        self.lit_rows.clear();
+        self.lit_rows.resize(self.rows, false);
+        for y in 0..rows {
+            for x in 0..cols {
+                let light = self.light[(y * cols + x) as usize];
+                if light.iter().all(|&channel| channel < 1.0) {
+                    continue;
+                }
+                for ny in (y - 1).max(0)..=(y + 1).min(rows - 1) {
+                    self.lit_rows[ny as usize] = true;
+                    for nx in (x - 1).max(0)..=(x + 1).min(cols - 1) {
+                        self.lit[(ny * cols + nx) as usize] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Add the blurred light over the part of a frame covering `view` that
+    // the target holds
+    pub fn draw(&self, target: &mut Target, view: Rect) {
+        if self.light.is_empty() {
+            return;
+        }
+        // Where a frame pixel's center falls among the cells' centers
+        let place = |at: i32, cells: usize| {
+            let at = (at as f32 + 0.5) / BLOOM_SCALE as f32 - 0.5;
+            let before = at.floor();
+            let last = cells as i32 - 1;
+            (
+                (before as i32).clamp(0, last) as usize,
+                (before as i32 + 1).clamp(0, last) as usize,
+                at - before,
+            )
+        };
+        for (y, left, mut row) in target.span_rows(view) {
+            let cell_row = (y - view.y0) as usize / BLOOM_SCALE;
+            if !self.lit_rows[cell_row] {
+                continue;
+            }
+            let lit = &self.lit[cell_row * self.cols..][..self.cols];
+            let (above, below, ty) = place(y - view.y0, self.rows);
+            let above = &self.light[above * self.cols..][..self.cols];
+            let below = &self.light[below * self.cols..][..self.cols];
+            // A cell's worth of pixels at a time
+            let mut x = left;
+            while !row.is_empty() {
+                let from = (x - view.x0) as usize;
+                let run = (BLOOM_SCALE - from % BLOOM_SCALE).min(row.len());
+                let (pixels, rest) = row.split_at_mut(run);
+                row = rest;
+                if lit[from / BLOOM_SCALE] {
+                    for (x, pixel) in (x..).zip(pixels) {
+                        let (before, after, tx) = place(x - view.x0, self.cols);
+                        let corners = [above[before], above[after], below[before], below[after]];
+                        for (channel, value) in pixel.iter_mut().take(3).enumerate() {
+                            let [tl, tr, bl, br] = corners.map(|c| c[channel]);
+                            let top = tl + (tr - tl) * tx;
+                            let bottom = bl + (br - bl) * tx;
+                            let light = top + (bottom - top) * ty;
+                            *value = (*value as f32 + light).min(255.0) as u8;
+                        }
+                    }
+                }
+                x += run as i32;
+            }
+        }
+    }
+}
+
+// Add each of a band of `width` pixel wide rows' light to the cells it's in,
+// by how far it's above the threshold
+fn scan(pixels: &[Pixel], width: usize, cells: &mut [[f32; 3]], threshold: f32) {
+    let floor = threshold * 255.0;
+    let share = 1.0 / (255.0 - floor) / (BLOOM_SCALE * BLOOM_SCALE) as f32;
+    let cols = width.div_ceil(BLOOM_SCALE);
+    // Brightness is never more than the brightest channel, which lets whole
+    // runs of the dark majority go by cheaply
+    let dark = floor as u8;
+    for (y, row) in pixels.chunks_exact(width).enumerate() {
+        let cells = &mut cells[y / BLOOM_SCALE * cols..][..cols];
+        let (runs, rest) = row.as_chunks::<SCAN_RUN>();
+        let runs = runs.iter().map(|run| (brightest(run), run.as_slice()));
+        for (i, (brightest, pixels)) in runs.chain([(u8::MAX, rest)]).enumerate() {
+            if brightest <= dark {
+                continue;
+            }
+            for (x, &pixel @ [r, g, b, _]) in (i * SCAN_RUN..).zip(pixels) {
+                let luma = luma(pixel);
+                if luma <= floor {
+                    continue;
+                }
+                let amount = (luma - floor) * share;
+                let cell = &mut cells[x / BLOOM_SCALE];
+                for (light, channel) in cell.iter_mut().zip([r, g, b]) {
+                    *light += channel as f32 * amount;
+                }
+            }
+        }
+    }
+}
+
+// The brightest channel of any of a run of pixels
+fn brightest(run: &[Pixel; SCAN_RUN]) -> u8 {
+    // Channel by channel first, which vectorizes
+    let [r, g, b, _] = run.iter().fold([0; 4], |max, pixel| {
+        [0, 1, 2, 3].map(|channel| max[channel].max(pixel[channel]))
+    });
+    r.max(g).max(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::pack;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    #[test]
+    fn bright_light_bleeds_into_the_dark_around_it() {
+        let mut frame = Frame::new(&LANDSCAPE);
+        let view = Rect::new(0, 0, 1920, 1080);
+        for (_, _, row) in frame.target().span_rows(Rect::new(400, 300, 404, 304)) {
+            row.fill(pack((255, 255, 255)));
+        }
+        // Bright but not bright enough
+        for (_, _, row) in frame.target().span_rows(Rect::new(1000, 300, 1008, 308)) {
+            row.fill(pack((150, 150, 150)));
+        }
+        let mut bloom = Bloom::new(0.7, 1.0);
+        bloom.gather(frame.pixels(), 1920, &Workers::new(2));
+        bloom.draw(&mut frame.target(), view);
+
+        frame.assert_brighter_than(402, 315, 0);
+        assert!(frame.brightness(402, 310) > frame.brightness(402, 330));
+        frame.assert_dark(402, 360);
+        frame.assert_dark(1004, 316);
+        // Light already at full stays there
+        assert_eq!(frame.pixel(401, 301), pack((255, 255, 255)));
+    }
+}
@@ -0,0 +1,625 @@
+// Command-line options. Anything given here overrides config.toml for this
+// run. Parsed by hand rather than with clap: the crate builds offline against a
+// fixed set of five dependencies, and clap isn't among the crates available to
+// it. The help text below is the one definition of the flags; completions.rs
+// reads it, and a test there checks every flag it lists parses.
+
+use crate::completions::{self, Shell};
+use crate::{HEIGHT, WIDTH};
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+use wl_starfield::Scene;
+use wl_starfield::accent::Accent;
+use wl_starfield::config;
+use wl_starfield::debug::DebugOverlays;
+
+pub const USAGE: &str = "\
+Usage: wl-starfield [OPTIONS]
+       wl-starfield status [--json] [--follow]
+       wl-starfield stats
+       wl-starfield render [RENDER OPTIONS]
+       wl-starfield bench [BENCH OPTIONS]
+       wl-starfield completions bash|zsh|fish
+
+Options:
+      --star-count <N>    Number of stars (overrides config.toml)
+      --fps-cap <FPS>     Limit the frame rate, 0 for no limit (default 60)
+      --windowed [WxH]    Open a resizable window instead of going fullscreen
+      --seed <SEED>       Seed the simulation for a reproducible sky
+      --monitor <OUTPUT>  Output to cover, by name (e.g. DP-1) or index
+      --focus             Start a focus session: a dim, quiet sky that keeps
+                          the screen from going idle
+      --debug <OVERLAYS>  Draw debug overlays, a comma-separated list of
+                          stars, shooting-stars, comet, satellites,
+                          spawning, all, or heatmap (in place of the sky)
+      --commands <PIPE>   Take commands a line at a time from a named pipe
+                          (made if missing), or from stdin if PIPE is -
+      --timelapse <SPEED> Run the sky faster than real time, e.g. 120x to
+                          watch a night go by in minutes
+      --scene <SCENE>     What to show: starfield (the default), orrery (the
+                          planets going round the sun) or tunnel (a warp
+                          tunnel of stars)
+      --location <LAT,LON>
+                          Show the real sky over this place, in degrees
+                          north and east, e.g. 52.2,0.12
+      --record <SECONDS>  Record the first output for this long, e.g. 10s,
+                          as an animated PNG
+      --record-video <FILE>
+                          Render a video with ffmpeg instead of showing the
+                          sky: --record seconds of it (or record_seconds),
+                          --fps-cap frames a second, at the --windowed size
+                          or 1920x1080
+      --export-frames <PATH>
+                          Share every frame of the first output in this
+                          file, e.g. /dev/shm/wl-starfield, for OBS and
+                          other tools to read
+      --pipewire          Offer the first output as a PipeWire video source
+                          (pipewire_size and pipewire_fps set its format)
+      --accent <COLOR>    Tint the stars towards an accent color, #rrggbb,
+                          or pywal (or pywal:N) for the current pywal colors
+      --retune            Measure this machine again to pick the quality
+                          and star count, as on first run
+      --dump-default-config
+                          Print a config.toml with every key at its default,
+                          each explained
+      --generate-man      Print the man page, in roff
+  -h, --help              Print this help
+  -V, --version           Print the version
+
+Status:
+  Print what the running sky is showing, for status bars
+      --json              Print the running sky's status as waybar JSON
+      --follow            Keep printing it whenever it changes
+
+Stats:
+  Print how many shooting stars, comets and so on the sky has shown so far
+
+Render:
+  Draw frames with no window and save them as PNGs, numbered from 1
+      --frames <N>        How many frames (default 1)
+      --size <WxH>        Their size (default 1920x1080)
+      --seed <SEED>       Seed the sky, for the same frames every time
+      --fps <FPS>         Frames per second of sky time between them
+                          (default 60)
+      --after <SECONDS>   Let the sky run this long before the first frame
+                          (default 3, once the stars have flown out from
+                          the middle)
+      --output <DIR>      Directory to save them in (default the current
+                          one), or - to write them one after another to
+                          stdout
+
+Bench:
+  Time frames of a seeded sky with the default config, drawn with no window,
+  and print milliseconds a frame (min, average and 99th percentile) for star
+  updates, star drawing and shooting star drawing
+      --frames <N>        How many frames to time (default 600)
+      --size <WxH>        Their size (default 1920x1080)
+      --seed <SEED>       Seed the sky (default 1)
+
+Completions:
+  Print a completion script for bash, zsh or fish, e.g.
+  wl-starfield completions fish > ~/.config/fish/completions/wl-starfield.fish";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub star_count: Option<usize>,
+    pub fps_cap: Option<f32>,
+    // Initial window size, if running in a window
+    pub windowed: Option<(u32, u32)>,
+    pub seed: Option<u64>,
+    pub monitor: Option<String>,
+    pub focus: bool,
+    pub debug: DebugOverlays,
+    // Named pipe to take commands from, or "-" for stdin
+    pub commands: Option<String>,
+    // How many times faster than real time the sky runs
+    pub timelapse: Option<f32>,
+    pub scene: Option<Scene>,
+    // Latitude and longitude for the planetarium, in degrees
+    pub location: Option<(f32, f32)>,
+    pub retune: bool,
+    // Seconds to record as soon as the sky starts
+    pub record: Option<f32>,
+    pub accent: Option<Accent>,
+    // Render a video to this file instead of showing the sky
+    pub record_video: Option<PathBuf>,
+    // Share frames in this file
+    pub export_frames: Option<PathBuf>,
+    pub pipewire: bool,
+}
+
+// Options for the `render` subcommand
+#[derive(Debug, PartialEq)]
+pub struct RenderArgs {
+    pub frames: u32,
+    pub size: (u32, u32),
+    pub seed: Option<u64>,
+    pub fps: f32,
+    // Seconds of sky to run before the first frame
+    pub after: f32,
+    // Directory for the frames, or "-" for stdout
+    pub output: PathBuf,
+}
+
+impl Default for RenderArgs {
+    fn default() -> Self {
+        Self {
+            frames: 1,
+            size: (WIDTH, HEIGHT),
+            seed: None,
+            fps: 60.0,
+            after: 3.0,
+            output: PathBuf::from("."),
+        }
+    }
+}
+
+// Options for the `bench` subcommand
+#[derive(Debug, PartialEq)]
+pub struct BenchArgs {
+    pub frames: u32,
+    pub size: (u32, u32),
+    pub seed: u64,
+}
+
+impl Default for BenchArgs {
+    fn default() -> Self {
+        Self {
+            frames: 600,
+            size: (WIDTH, HEIGHT),
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(Args),
+    Status { json: bool, follow: bool },
+    Stats,
+    Render(RenderArgs),
+    Bench(BenchArgs),
+    DumpDefaultConfig,
+    Completions(Shell),
+    GenerateMan,
+    Help,
+    Version,
+}
+
+impl Args {
+    // Parse the process arguments, exiting on --help, --version or bad input
+    pub fn parse() -> Self {
+        match parse_from(env::args().skip(1)) {
+            Ok(Command::Run(args)) => args,
+            Ok(Command::Status { json, follow }) => {
+                // Stdout closing (the bar going away) is the usual way to stop
+                let _ = crate::status::show(json, follow);
+                std::process::exit(0);
+            }
+            Ok(Command::Stats) => {
+                crate::stats::show();
+                std::process::exit(0);
+            }
+            Ok(Command::Render(args)) => {
+                if let Err(err) = crate::headless::render(&args) {
+                    eprintln!("wl-starfield: {err}");
+                    std::process::exit(1);
+                }
+                std::process::exit(0);
+            }
+            Ok(Command::Bench(args)) => {
+                crate::bench::run(&args);
+                std::process::exit(0);
+            }
+            Ok(Command::DumpDefaultConfig) => {
+                print!("{}", config::DEFAULT_FILE);
+                std::process::exit(0);
+            }
+            Ok(Command::Completions(shell)) => {
+                print!("{}", completions::script(shell));
+                std::process::exit(0);
+            }
+            Ok(Command::GenerateMan) => {
+                print!("{}", completions::man_page());
+                std::process::exit(0);
+            }
+            Ok(Command::Help) => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            Ok(Command::Version) => {
+                println!("wl-starfield {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            Err(err) => {
+                eprintln!("wl-starfield: {err}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+fn parse_from(raw: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = Args::default();
+    let mut raw = raw.into_iter().peekable();
+    if raw.next_if(|first| first == "status").is_some() {
+        return parse_status(raw);
+    }
+    if raw.next_if(|first| first == "render").is_some() {
+        return parse_render(raw);
+    }
+    if raw.next_if(|first| first == "bench").is_some() {
+        return parse_bench(raw);
+    }
+    if raw.next_if(|first| first == "completions").is_some() {
+        return match (raw.next(), raw.next()) {
+            (Some(arg), None) if arg == "-h" || arg == "--help" => Ok(Command::Help),
+            (Some(shell), None) => shell
+                .parse()
+                .map(Command::Completions)
+                .map_err(|()| format!("completions are for bash, zsh or fish, not `{shell}`")),
+            _ => Err("completions needs one shell: bash, zsh or fish".into()),
+        };
+    }
+    if raw.next_if(|first| first == "stats").is_some() {
+        return match raw.next() {
+            None => Ok(Command::Stats),
+            Some(arg) if arg == "-h" || arg == "--help" => Ok(Command::Help),
+            Some(arg) => Err(format!("unexpected argument `{arg}` to stats")),
+        };
+    }
+
+    while let Some(arg) = raw.next() {
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.into())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| raw.next())
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+
+        match flag.as_str() {
+            "--star-count" => args.star_count = Some(number(&flag, &value()?)?),
+            "--fps-cap" => {
+                let fps: f32 = number(&flag, &value()?)?;
+                if !(fps >= 0.0 && fps.is_finite()) {
+                    return Err(format!("{flag} can't be negative"));
+                }
+                args.fps_cap = Some(fps);
+            }
+            "--seed" => args.seed = Some(number(&flag, &value()?)?),
+            "--monitor" => args.monitor = Some(value()?),
+            "--commands" => args.commands = Some(value()?),
+            "--timelapse" => {
+                let value = value()?;
+                let speed: f32 = number(&flag, value.strip_suffix('x').unwrap_or(&value))?;
+                if !(speed > 0.0 && speed.is_finite()) {
+                    return Err(format!("{flag} must be above 0"));
+                }
+                args.timelapse = Some(speed);
+            }
+            "--record" => {
+                let value = value()?;
+                let seconds: f32 = number(&flag, value.strip_suffix('s').unwrap_or(&value))?;
+                if !(seconds > 0.0 && seconds.is_finite()) {
+                    return Err(format!("{flag} must be above 0"));
+                }
+                args.record = Some(seconds);
+            }
+            "--record-video" => args.record_video = Some(value()?.into()),
+            "--export-frames" => args.export_frames = Some(value()?.into()),
+            "--accent" => {
+                let value = value()?;
+                args.accent = Some(value.parse().map_err(|()| {
+                    format!("{flag} must be like #rrggbb, pywal or pywal:N, got `{value}`")
+                })?);
+            }
+            "--scene" => {
+                let value = value()?;
+                args.scene = Some(value.parse().map_err(|()| {
+                    format!("{flag} must be starfield, orrery or tunnel, got `{value}`")
+                })?);
+            }
+            "--location" => args.location = Some(location(&flag, &value()?)?),
+            "--focus" if inline.is_none() => args.focus = true,
+            "--retune" if inline.is_none() => args.retune = true,
+            "--pipewire" if inline.is_none() => args.pipewire = true,
+            "--debug" => args.debug = value()?.parse().map_err(|err| format!("{flag}: {err}"))?,
+            "--windowed" => {
+                // The size is optional, so only take the next argument if it is one
+                let size = inline.or_else(|| raw.next_if(|next| window_size(next).is_some()));
+                args.windowed = Some(match size {
+                    Some(size) => window_size(&size)
+                        .ok_or_else(|| format!("{flag} expects a size like 1280x720"))?,
+                    None => (WIDTH, HEIGHT),
+                });
+            }
+            "--dump-default-config" if inline.is_none() => return Ok(Command::DumpDefaultConfig),
+            "--generate-man" if inline.is_none() => return Ok(Command::GenerateMan),
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            _ => return Err(format!("unexpected argument `{flag}`")),
+        }
+    }
+    Ok(Command::Run(args))
+}
+
+fn parse_render(mut raw: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = RenderArgs::default();
+    while let Some(arg) = raw.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.into())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| raw.next())
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "--frames" => args.frames = number(&flag, &value()?)?,
+            "--size" => {
+                args.size = window_size(&value()?)
+                    .ok_or_else(|| format!("{flag} expects a size like 1280x720"))?;
+            }
+            "--seed" => args.seed = Some(number(&flag, &value()?)?),
+            "--fps" => {
+                args.fps = number(&flag, &value()?)?;
+                if !(args.fps > 0.0 && args.fps.is_finite()) {
+                    return Err(format!("{flag} must be above 0"));
+                }
+            }
+            "--after" => {
+                let value = value()?;
+                args.after = number(&flag, value.strip_suffix('s').unwrap_or(&value))?;
+                if !(args.after >= 0.0 && args.after.is_finite()) {
+                    return Err(format!("{flag} can't be negative"));
+                }
+            }
+            "--output" => args.output = value()?.into(),
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unexpected argument `{flag}` to render")),
+        }
+    }
+    Ok(Command::Render(args))
+}
+
+fn parse_bench(mut raw: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = BenchArgs::default();
+    while let Some(arg) = raw.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.into())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| raw.next())
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "--frames" => {
+                args.frames = number(&flag, &value()?)?;
+                if args.frames == 0 {
+                    return Err(format!("{flag} must be above 0"));
+                }
+            }
+            "--size" => {
+                args.size = window_size(&value()?)
+                    .ok_or_else(|| format!("{flag} expects a size like 1280x720"))?;
+            }
+            "--seed" => args.seed = number(&flag, &value()?)?,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unexpected argument `{flag}` to bench")),
+        }
+    }
+    Ok(Command::Bench(args))
+}
+
+fn parse_status(raw: impl Iterator<Item = String>) -> Result<Command, String> {
+    let (mut json, mut follow) = (false, false);
+    for arg in raw {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--follow" => follow = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unexpected argument `{arg}` to status")),
+        }
+    }
+    Ok(Command::Status { json, follow })
+}
+
+// Parse "WxH", e.g. 1280x720
+fn window_size(value: &str) -> Option<(u32, u32)> {
+    let (w, h) = value.split_once('x')?;
+    let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+    (w > 0 && h > 0).then_some((w, h))
+}
+
+// Parse "LAT,LON" in degrees, e.g. -33.9,151.2
+fn location(flag: &str, value: &str) -> Result<(f32, f32), String> {
+    let (latitude, longitude) = value
+        .split_once(',')
+        .ok_or_else(|| format!("{flag} expects a place like 52.2,0.12"))?;
+    let (latitude, longitude): (f32, f32) = (
+        number(flag, latitude.trim())?,
+        number(flag, longitude.trim())?,
+    );
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!(
+            "{flag} needs a latitude within ±90 and a longitude within ±180"
+        ));
+    }
+    Ok((latitude, longitude))
+}
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got `{value}`"))
+}
+
+// Whether these arguments would be accepted, for checking the help text
+#[cfg(test)]
+pub fn parses(raw: Vec<String>) -> bool {
+    parse_from(raw).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_every_option() {
+        assert_eq!(
+            parse(&[
+                "--star-count",
+                "800",
+                "--fps-cap=30",
+                "--windowed",
+                "1280x720",
+                "--seed",
+                "42",
+                "--monitor",
+                "DP-1",
+                "--focus",
+                "--debug",
+                "comet,spawning",
+                "--commands",
+                "/tmp/starfield.cmd",
+                "--timelapse",
+                "120x",
+                "--location",
+                "-33.9,151.2",
+                "--scene",
+                "orrery",
+                "--retune",
+                "--record=10s",
+                "--accent",
+                "#d3869b",
+                "--record-video",
+                "loop.mp4",
+                "--export-frames",
+                "/dev/shm/sky",
+                "--pipewire",
+            ]),
+            Ok(Command::Run(Args {
+                star_count: Some(800),
+                fps_cap: Some(30.0),
+                windowed: Some((1280, 720)),
+                seed: Some(42),
+                monitor: Some("DP-1".into()),
+                focus: true,
+                debug: DebugOverlays {
+                    comet: true,
+                    spawning: true,
+                    ..DebugOverlays::default()
+                },
+                commands: Some("/tmp/starfield.cmd".into()),
+                timelapse: Some(120.0),
+                scene: Some(Scene::Orrery),
+                location: Some((-33.9, 151.2)),
+                retune: true,
+                record: Some(10.0),
+                accent: Some(Accent::Color((0xd3, 0x86, 0x9b))),
+                record_video: Some("loop.mp4".into()),
+                export_frames: Some("/dev/shm/sky".into()),
+                pipewire: true,
+            }))
+        );
+        assert_eq!(parse(&[]), Ok(Command::Run(Args::default())));
+        assert_eq!(parse(&["--seed", "1", "-h"]), Ok(Command::Help));
+        assert_eq!(
+            parse(&["--dump-default-config"]),
+            Ok(Command::DumpDefaultConfig)
+        );
+        assert_eq!(parse(&["--generate-man"]), Ok(Command::GenerateMan));
+        assert_eq!(
+            parse(&["completions", "zsh"]),
+            Ok(Command::Completions(Shell::Zsh))
+        );
+        assert_eq!(
+            parse(&["status", "--follow", "--json"]),
+            Ok(Command::Status {
+                json: true,
+                follow: true
+            })
+        );
+        assert_eq!(parse(&["stats"]), Ok(Command::Stats));
+        assert_eq!(
+            parse(&[
+                "render",
+                "--frames=3",
+                "--size",
+                "640x360",
+                "--seed",
+                "7",
+                "--output",
+                "-"
+            ]),
+            Ok(Command::Render(RenderArgs {
+                frames: 3,
+                size: (640, 360),
+                seed: Some(7),
+                output: "-".into(),
+                ..RenderArgs::default()
+            }))
+        );
+        assert!(parse(&["render", "--fps", "0"]).is_err());
+        assert!(parse(&["render", "--monitor", "DP-1"]).is_err());
+        assert_eq!(
+            parse(&["bench", "--frames", "120", "--seed=9"]),
+            Ok(Command::Bench(BenchArgs {
+                frames: 120,
+                seed: 9,
+                ..BenchArgs::default()
+            }))
+        );
+        assert!(parse(&["bench", "--frames", "0"]).is_err());
+        assert!(parse(&["bench", "--output", "-"]).is_err());
+        assert!(parse(&["stats", "--json"]).is_err());
+        assert_eq!(
+            parse(&["--windowed", "--seed", "1"]),
+            Ok(Command::Run(Args {
+                windowed: Some((WIDTH, HEIGHT)),
+                seed: Some(1),
+                ..Args::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&["--star-count"]).is_err());
+        assert!(parse(&["--star-count", "many"]).is_err());
+        assert!(parse(&["--fps-cap", "-5"]).is_err());
+        assert!(parse(&["--windowed=yes"]).is_err());
+        assert!(parse(&["--windowed=0x720"]).is_err());
+        assert!(parse(&["--stars", "10"]).is_err());
+        assert!(parse(&["--focus=yes"]).is_err());
+        assert!(parse(&["--debug"]).is_err());
+        assert!(parse(&["--commands"]).is_err());
+        assert!(parse(&["status", "--seed", "1"]).is_err());
+        assert!(parse(&["--seed", "1", "status"]).is_err());
+        assert!(parse(&["--debug=everything"]).is_err());
+        assert!(parse(&["--timelapse", "0x"]).is_err());
+        assert!(parse(&["--record", "0s"]).is_err());
+        assert!(parse(&["--timelapse", "fast"]).is_err());
+        assert!(parse(&["--scene", "planets"]).is_err());
+        assert!(parse(&["--location", "52.2"]).is_err());
+        assert!(parse(&["--location", "91,0"]).is_err());
+        assert!(parse(&["completions"]).is_err());
+        assert!(parse(&["completions", "powershell"]).is_err());
+        assert!(parse(&["completions", "bash", "zsh"]).is_err());
+    }
+}
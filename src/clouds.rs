@@ -0,0 +1,126 @@
+// Thin cloud layer: a few octaves of value noise scrolled by the wind and
+// drawn as dark, semi-transparent wisps that dim the stars behind them.
+//
+// Noise is only evaluated on a coarse grid every CLOUD_CELL pixels and
+// interpolated between, which keeps the per-frame cost to a few tens of
+// thousands of samples however large the output is.
+
+use crate::lod::Detail;
+use crate::render::{Rect, Target, blend};
+use crate::rng::value_noise;
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+
+// Density is sampled every CLOUD_CELL pixels and interpolated in between
+const CLOUD_CELL: usize = 8;
+const OCTAVES: u32 = 3;
+// Size in pixels of the coarsest noise features
+const FEATURE_SIZE: f32 = 360.0;
+// Noise below COVER is clear sky; density ramps up over SOFTNESS above it
+const COVER: f32 = 0.5;
+const SOFTNESS: f32 = 0.3;
+const MAX_ALPHA: f32 = 0.65;
+const COLOR: (u8, u8, u8) = (20, 22, 32);
+// Finer octaves drift this much faster per octave, so wisps churn as they go
+const CHURN: f32 = 0.35;
+
+pub struct Clouds {
+    seed: i64,
+    // Distance the wind has carried the clouds so far
+    drift: (f32, f32),
+    wind: (f32, f32),
+    // Octaves of noise sampled, fewer when detail is low
+    octaves: u32,
+    cols: usize,
+    rows: usize,
+    // Opacity at each grid point, row-major
+    density: Vec<f32>,
+}
+
+impl Clouds {
+    // `direction` is in degrees clockwise from rightwards, `speed` in pixels/s
+    pub fn new(
+        rng: &mut impl Rng,
+        screen_details: &ScreenDetails,
+        speed: f32,
+        direction: f32,
+    ) -> Self {
+        let (sin, cos) = direction.to_radians().sin_cos();
+        let mut clouds = Self {
+            seed: rng.r#gen(),
+            drift: (0.0, 0.0),
+            wind: (cos * speed, sin * speed),
+            octaves: OCTAVES,
+            cols: 0,
+            rows: 0,
+            density: Vec::new(),
+        };
+        clouds.resize(screen_details);
+        clouds
+    }
+
+    pub fn resize(&mut self, screen_details: &ScreenDetails) {
+        self.cols = screen_details.width as usize / CLOUD_CELL + 2;
+        self.rows = screen_details.height as usize / CLOUD_CELL + 2;
+        self.density.resize(self.cols * self.rows, 0.0);
+        self.sample();
+    }
+
+    pub fn update(&mut self, ctx: &UpdateContext, detail: Detail) {
+        self.drift.0 += self.wind.0 * ctx.dt;
+        self.drift.1 += self.wind.1 * ctx.dt;
+        self.octaves = OCTAVES - detail.octaves_dropped;
+        self.sample();
+    }
+
+    fn sample(&mut self) {
+        for (i, density) in self.density.iter_mut().enumerate() {
+            let x = (i % self.cols * CLOUD_CELL) as f32;
+            let y = (i / self.cols * CLOUD_CELL) as f32;
+
+            let (mut total, mut weight) = (0.0, 0.0);
+            for octave in 0..self.octaves {
+                let frequency = (1 << octave) as f32 / FEATURE_SIZE;
+                let amplitude = 1.0 / (1 << octave) as f32;
+                let speed = 1.0 + CHURN * octave as f32;
+                let layer = self.seed.wrapping_add(octave as i64 * 0x5851_F42D);
+                total += amplitude
+                    * value_noise(
+                        (x - self.drift.0 * speed) * frequency,
+                        (y - self.drift.1 * speed) * frequency,
+                        layer,
+                    );
+                weight += amplitude;
+            }
+
+            let cover = ((total / weight - COVER) / SOFTNESS).clamp(0.0, 1.0);
+            *density = cover * cover * (3.0 - 2.0 * cover) * MAX_ALPHA;
+        }
+    }
+
+    pub fn draw(&self, target: &mut Target, screen_details: &ScreenDetails) {
+        let screen = Rect::new(
+            0,
+            0,
+            screen_details.width as i32,
+            screen_details.height as i32,
+        );
+        let cell = CLOUD_CELL as i32;
+
+        for (y, left, row) in target.span_rows(screen) {
+            let (grid_y, ty) = ((y / cell) as usize, (y % cell) as f32 / cell as f32);
+            let above = &self.density[grid_y * self.cols..][..self.cols];
+            let below = &self.density[(grid_y + 1) * self.cols..][..self.cols];
+
+            for (x, pixel) in (left..).zip(row) {
+                let (grid_x, tx) = ((x / cell) as usize, (x % cell) as f32 / cell as f32);
+                let top = above[grid_x] + (above[grid_x + 1] - above[grid_x]) * tx;
+                let bottom = below[grid_x] + (below[grid_x + 1] - below[grid_x]) * tx;
+                let alpha = top + (bottom - top) * ty;
+                if alpha > 0.004 {
+                    blend(pixel, COLOR, alpha);
+                }
+            }
+        }
+    }
+}
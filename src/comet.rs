@@ -0,0 +1,219 @@
+// A rare, slow comet that takes about a minute to cross the sky. Its tails
+// point away from an imaginary sun somewhere off-screen: a straight, narrow,
+// bluish ion tail, and a broader dust tail that curves back along the path the
+// comet came from.
+
+use crate::render::{Rect, Target, blend};
+use crate::{CelestialObject, ScreenDetails, UpdateContext};
+use rand::Rng;
+
+// Seconds to cross the screen's diagonal
+const CROSSING_TIME: f32 = 60.0;
+const DUST_LENGTH: f32 = 260.0;
+const ION_LENGTH: f32 = 340.0;
+// How far the dust tail bends back along the comet's path, as a share of its length
+const DUST_CURVE: f32 = 0.5;
+// Tails are built from overlapping glows every this many pixels
+const TAIL_STEP: f32 = 3.0;
+const HEAD_RADIUS: f32 = 5.0;
+const COMA_RADIUS: f32 = 14.0;
+const HEAD_COLOR: (u8, u8, u8) = (255, 252, 235);
+const DUST_COLOR: (u8, u8, u8) = (255, 235, 200);
+const ION_COLOR: (u8, u8, u8) = (120, 165, 255);
+// Seconds to fade in after appearing
+const FADE_IN: f32 = 4.0;
+
+pub struct Comet {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    // Unit vector pointing away from the sun
+    tail: (f32, f32),
+    age: f32,
+}
+
+impl Comet {
+    // Start just beyond one edge, headed across the screen past its middle
+    pub fn spawn(rng: &mut impl Rng, screen_details: &ScreenDetails) -> Self {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        let reach = width.hypot(height) / 2.0 + ION_LENGTH;
+        let heading = rng.gen_range(0.0..std::f32::consts::TAU);
+        let (sin, cos) = heading.sin_cos();
+        // Miss the exact center by up to a third of the short side
+        let offset = rng.gen_range(-1.0..1.0) * width.min(height) / 3.0;
+        let (cx, cy) = (width / 2.0 - sin * offset, height / 2.0 + cos * offset);
+        let speed = 2.0 * reach / CROSSING_TIME;
+
+        // The sun sits somewhere ahead and to the side, so the tails stream
+        // out behind at an angle rather than straight back along the path
+        let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        let away = heading + std::f32::consts::PI + side * rng.gen_range(0.5..1.1);
+        Self {
+            x: cx - cos * reach,
+            y: cy - sin * reach,
+            vx: cos * speed,
+            vy: sin * speed,
+            tail: (away.cos(), away.sin()),
+            age: 0.0,
+        }
+    }
+
+    // Seconds since it appeared
+    pub fn age(&self) -> f32 {
+        self.age
+    }
+
+    pub fn brightness(&self) -> f32 {
+        (self.age / FADE_IN).min(1.0)
+    }
+
+    // Point `t` (0 at the head, 1 at the end) along the dust tail
+    fn dust_point(&self, t: f32) -> (f32, f32) {
+        let speed = self.vx.hypot(self.vy);
+        let (back_x, back_y) = (-self.vx / speed, -self.vy / speed);
+        let along = DUST_LENGTH * t;
+        let bend = DUST_LENGTH * DUST_CURVE * t * t;
+        (
+            self.x + self.tail.0 * along + back_x * bend,
+            self.y + self.tail.1 * along + back_y * bend,
+        )
+    }
+
+    fn ion_point(&self, t: f32) -> (f32, f32) {
+        (
+            self.x + self.tail.0 * ION_LENGTH * t,
+            self.y + self.tail.1 * ION_LENGTH * t,
+        )
+    }
+}
+
+impl CelestialObject for Comet {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        self.x += self.vx * ctx.dt;
+        self.y += self.vy * ctx.dt;
+        self.age += ctx.dt;
+    }
+
+    fn draw(&self, target: &mut Target) {
+        let brightness = self.brightness();
+
+        // Dust: broad and warm, widening and fading toward the end
+        let steps = (DUST_LENGTH / TAIL_STEP) as usize;
+        for i in (1..steps).rev() {
+            let t = i as f32 / steps as f32;
+            let (x, y) = self.dust_point(t);
+            let radius = 3.0 + 14.0 * t;
+            glow(
+                target,
+                x,
+                y,
+                radius,
+                DUST_COLOR,
+                0.16 * (1.0 - t) * brightness,
+            );
+        }
+
+        // Ion: thin, straight and blue
+        let steps = (ION_LENGTH / TAIL_STEP) as usize;
+        for i in (1..steps).rev() {
+            let t = i as f32 / steps as f32;
+            let (x, y) = self.ion_point(t);
+            glow(target, x, y, 2.5, ION_COLOR, 0.35 * (1.0 - t) * brightness);
+        }
+
+        glow(
+            target,
+            self.x,
+            self.y,
+            COMA_RADIUS,
+            HEAD_COLOR,
+            0.35 * brightness,
+        );
+        glow(target, self.x, self.y, HEAD_RADIUS, HEAD_COLOR, brightness);
+    }
+
+    fn bounds(&self) -> Rect {
+        let head = Rect::around(self.x, self.y, COMA_RADIUS);
+        let (dx, dy) = self.dust_point(1.0);
+        let (ix, iy) = self.ion_point(1.0);
+        // The dust curve stays within the triangle of its ends and its
+        // control point, halfway along the unbent tail
+        let (mx, my) = (
+            self.x + self.tail.0 * DUST_LENGTH / 2.0,
+            self.y + self.tail.1 * DUST_LENGTH / 2.0,
+        );
+        head.union(&Rect::around(dx, dy, 17.0))
+            .union(&Rect::around(mx, my, 17.0))
+            .union(&Rect::around(ix, iy, 3.0))
+    }
+
+    fn is_alive(&self, screen_details: &ScreenDetails) -> bool {
+        // It starts well off-screen, so only give up on it once it has had
+        // time to cross and nothing of it is left in view
+        let screen = Rect::new(
+            0,
+            0,
+            screen_details.width as i32,
+            screen_details.height as i32,
+        );
+        self.age < CROSSING_TIME || self.bounds().intersect(&screen).is_some()
+    }
+
+    fn motion(&self, _: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        ((self.x, self.y), (self.vx, self.vy))
+    }
+}
+
+// Soft round light with a quadratic falloff to nothing at `radius`
+pub(crate) fn glow(
+    target: &mut Target,
+    x: f32,
+    y: f32,
+    radius: f32,
+    color: (u8, u8, u8),
+    alpha: f32,
+) {
+    let rect = Rect::around(x, y, radius);
+    if alpha < 0.004 || !target.overlaps(rect) {
+        return;
+    }
+    for (py, left, row) in target.span_rows(rect) {
+        let dy = py as f32 + 0.5 - y;
+        for (px, pixel) in (left..).zip(row) {
+            let dx = px as f32 + 0.5 - x;
+            let falloff = 1.0 - (dx.hypot(dy) / radius).min(1.0);
+            if falloff > 0.0 {
+                blend(pixel, color, alpha * falloff * falloff);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockScreen;
+
+    #[test]
+    fn comets_cross_the_screen_in_about_a_minute() {
+        let mut mock = MockScreen::landscape().with_seed(8);
+        let screen = mock.details;
+        for _ in 0..20 {
+            let mut comet = Comet::spawn(&mut mock.rng, &screen);
+            let mut ctx = mock.ctx(0.1);
+            let mut seen = false;
+            while comet.is_alive(&screen) {
+                comet.update(&mut ctx);
+                let (x, y) = (comet.x, comet.y);
+                seen |= x > 0.0 && x < 1920.0 && y > 0.0 && y < 1080.0;
+                assert!(
+                    comet.age < CROSSING_TIME * 1.1,
+                    "comet outstayed its welcome"
+                );
+            }
+            assert!(seen, "comet never came on screen");
+            assert!(comet.age > CROSSING_TIME * 0.5);
+        }
+    }
+}
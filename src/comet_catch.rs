@@ -0,0 +1,329 @@
+// A toy for idle hands: a small comet comes in from the edge of the sky, and
+// the aim is to steer it through a highlighted ring. Clicking drops an
+// attractor where the pointer is, pulling the comet towards it for a few
+// seconds before it fades. Whether it's caught or lost, the score shows in
+// the corner for a moment and another comet comes in at a new ring.
+
+use crate::ScreenDetails;
+use crate::comet::glow;
+use crate::i18n::Locale;
+use crate::render::{Rect, Target, blend};
+use rand::Rng;
+use std::collections::VecDeque;
+
+// Pixels a second the comet comes in at, and seconds it has to get through
+// before it counts as lost
+const COMET_SPEED: f32 = 220.0;
+const COMET_LIFE: f32 = 20.0;
+const COMET_RADIUS: f32 = 6.0;
+const COMET_COLOR: (u8, u8, u8) = (255, 250, 230);
+// Positions kept for the tail, one a frame
+const TAIL_LEN: usize = 40;
+const TAIL_COLOR: (u8, u8, u8) = (150, 190, 255);
+// An attractor's pull, in pixels cubed per second squared, softened within
+// ATTRACTOR_SOFTENING pixels so passing close doesn't fling the comet away.
+// It fades out over ATTRACTOR_LIFE seconds, and only the last few placed pull.
+const ATTRACTOR_PULL: f32 = 1.5e7;
+const ATTRACTOR_SOFTENING: f32 = 40.0;
+const ATTRACTOR_LIFE: f32 = 4.0;
+const MAX_ATTRACTORS: usize = 3;
+const ATTRACTOR_RADIUS: f32 = 18.0;
+const ATTRACTOR_COLOR: (u8, u8, u8) = (200, 150, 255);
+// The ring, somewhere in the middle of the sky away from the edges
+const RING_RADIUS: f32 = 48.0;
+const RING_MARGIN: f32 = 0.25;
+const RING_WIDTH: f32 = 3.0;
+const RING_COLOR: (u8, u8, u8) = (120, 255, 190);
+const RING_ALPHA: f32 = 0.6;
+// Seconds the score shows for, and before the next comet comes in
+const BANNER_TIME: f32 = 3.0;
+const ROUND_GAP: f32 = 1.5;
+
+struct Attractor {
+    x: f32,
+    y: f32,
+    age: f32,
+}
+
+impl Attractor {
+    // How hard it pulls now, 1 when new
+    fn strength(&self) -> f32 {
+        (1.0 - self.age / ATTRACTOR_LIFE).max(0.0)
+    }
+}
+
+struct Puck {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    age: f32,
+    // Whether it has come into view yet
+    seen: bool,
+    tail: VecDeque<(f32, f32)>,
+}
+
+pub(crate) struct CometCatch {
+    ring: (f32, f32),
+    comet: Option<Puck>,
+    attractors: VecDeque<Attractor>,
+    caught: u32,
+    tries: u32,
+    // The score line, and seconds it has left on screen
+    banner: Option<(String, f32)>,
+    // Seconds until the next comet comes in
+    wait: f32,
+    // The language the score is shown in
+    locale: Locale,
+}
+
+impl CometCatch {
+    pub fn new(rng: &mut impl Rng, screen_details: &ScreenDetails, locale: Locale) -> Self {
+        Self {
+            ring: place_ring(rng, screen_details),
+            comet: None,
+            attractors: VecDeque::with_capacity(MAX_ATTRACTORS + 1),
+            caught: 0,
+            tries: 0,
+            banner: None,
+            wait: ROUND_GAP,
+            locale,
+        }
+    }
+
+    // Drop an attractor at `(x, y)` in the sky, in place of the oldest if
+    // there are already as many as pull at once
+    pub fn attract(&mut self, (x, y): (f32, f32)) {
+        if self.attractors.len() == MAX_ATTRACTORS {
+            self.attractors.pop_front();
+        }
+        self.attractors.push_back(Attractor { x, y, age: 0.0 });
+    }
+
+    // The score line while it's showing
+    pub fn banner(&self) -> Option<&str> {
+        self.banner.as_ref().map(|(line, _)| line.as_str())
+    }
+
+    pub fn update(&mut self, rng: &mut impl Rng, screen_details: &ScreenDetails, dt: f32) {
+        for attractor in &mut self.attractors {
+            attractor.age += dt;
+        }
+        self.attractors
+            .retain(|attractor| attractor.age < ATTRACTOR_LIFE);
+        if let Some((_, left)) = &mut self.banner {
+            *left -= dt;
+            if *left <= 0.0 {
+                self.banner = None;
+            }
+        }
+
+        let Some(comet) = &mut self.comet else {
+            self.wait -= dt;
+            if self.wait <= 0.0 {
+                self.comet = Some(launch(rng, screen_details, self.ring));
+            }
+            return;
+        };
+        let (ax, ay) = self.attractors.iter().fold((0.0, 0.0), |(ax, ay), a| {
+            let (dx, dy) = (a.x - comet.x, a.y - comet.y);
+            let d2 = dx * dx + dy * dy + ATTRACTOR_SOFTENING * ATTRACTOR_SOFTENING;
+            let pull = ATTRACTOR_PULL * a.strength() / (d2 * d2.sqrt());
+            (ax + dx * pull, ay + dy * pull)
+        });
+        comet.vx += ax * dt;
+        comet.vy += ay * dt;
+        comet.x += comet.vx * dt;
+        comet.y += comet.vy * dt;
+        comet.age += dt;
+        if comet.tail.len() == TAIL_LEN {
+            comet.tail.pop_back();
+        }
+        comet.tail.push_front((comet.x, comet.y));
+
+        let through =
+            (comet.x - self.ring.0).hypot(comet.y - self.ring.1) < RING_RADIUS - COMET_RADIUS;
+        let screen = Rect::new(
+            0,
+            0,
+            screen_details.width as i32,
+            screen_details.height as i32,
+        );
+        // It comes in from off-screen, so it's only lost once it has been in
+        // view and left again, or run out of time
+        let in_view = screen.contains(comet.x as i32, comet.y as i32);
+        comet.seen |= in_view;
+        let gone = comet.age > COMET_LIFE || (comet.seen && !in_view);
+        if !through && !gone {
+            return;
+        }
+        self.tries += 1;
+        if through {
+            self.caught += 1;
+            self.ring = place_ring(rng, screen_details);
+        }
+        let verdict = if through {
+            "catch.caught"
+        } else {
+            "catch.missed"
+        };
+        let line = self.locale.format(
+            verdict,
+            &[
+                ("caught", &self.caught.to_string()),
+                ("tries", &self.tries.to_string()),
+            ],
+        );
+        self.banner = Some((line, BANNER_TIME));
+        self.comet = None;
+        self.attractors.clear();
+        self.wait = ROUND_GAP;
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        let (rx, ry) = self.ring;
+        let outer = RING_RADIUS + RING_WIDTH;
+        let ring = Rect::around(rx, ry, outer + 1.0);
+        if target.overlaps(ring) {
+            for (y, left, row) in target.span_rows(ring) {
+                let dy = y as f32 + 0.5 - ry;
+                for (x, pixel) in (left..).zip(row) {
+                    let d = (x as f32 + 0.5 - rx).hypot(dy);
+                    // Anti-aliased edges, a pixel wide
+                    let cover = (RING_WIDTH / 2.0 + 0.5 - (d - RING_RADIUS).abs()).clamp(0.0, 1.0);
+                    if cover > 0.0 {
+                        blend(pixel, RING_COLOR, RING_ALPHA * cover);
+                    }
+                }
+            }
+        }
+        for attractor in &self.attractors {
+            let strength = attractor.strength();
+            glow(
+                target,
+                attractor.x,
+                attractor.y,
+                ATTRACTOR_RADIUS * (0.5 + strength / 2.0),
+                ATTRACTOR_COLOR,
+                0.6 * strength,
+            );
+        }
+        if let Some(comet) = &self.comet {
+            for (i, &(x, y)) in comet.tail.iter().enumerate().skip(1) {
+                let fade = 1.0 - i as f32 / TAIL_LEN as f32;
+                glow(target, x, y, COMET_RADIUS * fade, TAIL_COLOR, 0.4 * fade);
+            }
+            glow(
+                target,
+                comet.x,
+                comet.y,
+                COMET_RADIUS * 2.0,
+                COMET_COLOR,
+                0.4,
+            );
+            glow(target, comet.x, comet.y, COMET_RADIUS, COMET_COLOR, 1.0);
+        }
+    }
+}
+
+fn place_ring(rng: &mut impl Rng, screen_details: &ScreenDetails) -> (f32, f32) {
+    let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+    (
+        width * rng.gen_range(RING_MARGIN..1.0 - RING_MARGIN),
+        height * rng.gen_range(RING_MARGIN..1.0 - RING_MARGIN),
+    )
+}
+
+// A comet just off one edge, headed across the sky so that, left alone, it
+// passes the ring by to one side
+fn launch(rng: &mut impl Rng, screen_details: &ScreenDetails, (rx, ry): (f32, f32)) -> Puck {
+    let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+    let (x, y) = match rng.gen_range(0..4) {
+        0 => (-COMET_RADIUS, rng.gen_range(0.0..height)),
+        1 => (width + COMET_RADIUS, rng.gen_range(0.0..height)),
+        2 => (rng.gen_range(0.0..width), -COMET_RADIUS),
+        _ => (rng.gen_range(0.0..width), height + COMET_RADIUS),
+    };
+    let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let aim = (ry - y).atan2(rx - x) + side * rng.gen_range(0.25..0.5);
+    let (sin, cos) = aim.sin_cos();
+    Puck {
+        x,
+        y,
+        vx: cos * COMET_SPEED,
+        vy: sin * COMET_SPEED,
+        age: 0.0,
+        seen: false,
+        tail: VecDeque::with_capacity(TAIL_LEN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE, seeded_rng};
+
+    // Play until `done`, steering with `steer` each frame
+    fn play(
+        game: &mut CometCatch,
+        done: impl Fn(&CometCatch) -> bool,
+        mut steer: impl FnMut(&mut CometCatch),
+    ) {
+        let mut rng = seeded_rng(1);
+        for _ in 0..60 * 60 {
+            if done(game) {
+                return;
+            }
+            steer(game);
+            game.update(&mut rng, &LANDSCAPE, 1.0 / 60.0);
+        }
+        panic!("the round never ended");
+    }
+
+    #[test]
+    fn left_alone_the_comet_misses() {
+        let mut game = CometCatch::new(&mut seeded_rng(4), &LANDSCAPE, Locale::En);
+        play(&mut game, |game| game.comet.is_some(), |_| {});
+        let mut frame = Frame::new(&LANDSCAPE);
+        game.draw(&mut frame.target());
+        let (rx, ry) = game.ring;
+        frame.assert_brighter_than((rx + RING_RADIUS) as u32, ry as u32, 40);
+        frame.assert_dark(rx as u32, ry as u32);
+
+        play(&mut game, |game| game.tries == 1, |_| {});
+        assert_eq!((game.caught, game.tries), (0, 1));
+        assert_eq!(game.banner(), Some("missed 0 of 1"));
+    }
+
+    #[test]
+    fn an_attractor_in_the_ring_steers_it_through() {
+        // A comet set to pass the ring by, twice its radius off to one side
+        let near_miss = |attract: bool| {
+            let mut game = CometCatch::new(&mut seeded_rng(4), &LANDSCAPE, Locale::En);
+            let (rx, ry) = game.ring;
+            let mut comet = launch(&mut seeded_rng(4), &LANDSCAPE, game.ring);
+            (comet.x, comet.y) = (rx - 400.0, ry + RING_RADIUS * 2.0);
+            (comet.vx, comet.vy) = (COMET_SPEED, 0.0);
+            game.comet = Some(comet);
+            if attract {
+                game.attract(game.ring);
+            }
+            play(&mut game, |game| game.tries == 1, |_| {});
+            game
+        };
+        assert_eq!(near_miss(false).banner(), Some("missed 0 of 1"));
+
+        let mut game = near_miss(true);
+        assert_eq!(game.banner(), Some("caught! 1 of 1"));
+        assert!(game.attractors.is_empty());
+        // It's somewhere else next time
+        assert_ne!(
+            game.ring,
+            CometCatch::new(&mut seeded_rng(4), &LANDSCAPE, Locale::En).ring
+        );
+
+        // The score only shows for a moment
+        play(&mut game, |game| game.banner.is_none(), |_| {});
+        assert_eq!(game.tries, 1);
+    }
+}
@@ -0,0 +1,317 @@
+// Line-based control for scripts and keybind daemons: one command per line on
+// stdin or a named pipe, e.g. `echo "focus on" > /tmp/starfield.cmd`. Lines
+// are read on a background thread and picked up by the render loop between
+// frames. Bad lines are reported and skipped; they never stop the sky.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use wl_starfield::debug::DebugOverlays;
+use wl_starfield::tween::{Animation, Param, Timeline};
+use wl_starfield::{Scene, Starfield};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Switch {
+    On,
+    Off,
+    Toggle,
+}
+
+impl Switch {
+    fn apply(self, current: bool) -> bool {
+        match self {
+            Switch::On => true,
+            Switch::Off => false,
+            Switch::Toggle => !current,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Focus(Switch),
+    Breathing(Switch),
+    Constellations(Switch),
+    Warp(Switch),
+    Pause(Switch),
+    Scene(Scene),
+    Debug(DebugOverlays),
+    // Ease a setting to a new value
+    Animate(Animation),
+    // Play animations one after another
+    Timeline(Timeline),
+    // Start a long exposure on every output
+    Capture,
+    Quit,
+}
+
+impl Command {
+    // Resolve toggles against how `sky` is now, so every output ends up the same
+    pub fn settle(self, sky: &Starfield) -> Self {
+        match self {
+            Command::Focus(switch) => Command::Focus(on_off(switch.apply(sky.is_focused()))),
+            Command::Breathing(switch) => {
+                Command::Breathing(on_off(switch.apply(sky.is_breathing())))
+            }
+            Command::Constellations(switch) => {
+                Command::Constellations(on_off(switch.apply(sky.showing_constellations())))
+            }
+            Command::Warp(switch) => Command::Warp(on_off(switch.apply(sky.is_warping()))),
+            Command::Pause(switch) => Command::Pause(on_off(switch.apply(sky.is_paused()))),
+            command => command,
+        }
+    }
+
+    // Apply to one output's sky. Captures and quitting are up to the caller.
+    pub fn apply(&self, starfield: &mut Starfield) {
+        match self {
+            Command::Focus(switch) => starfield.set_focus(switch.apply(starfield.is_focused())),
+            Command::Breathing(switch) => {
+                starfield.set_breathing(switch.apply(starfield.is_breathing()))
+            }
+            Command::Constellations(switch) => {
+                starfield.set_constellations(switch.apply(starfield.showing_constellations()))
+            }
+            Command::Warp(switch) => starfield.set_warp(switch.apply(starfield.is_warping())),
+            Command::Pause(switch) => starfield.set_paused(switch.apply(starfield.is_paused())),
+            Command::Scene(scene) => starfield.fade_to(*scene),
+            Command::Debug(overlays) => starfield.debug = *overlays,
+            Command::Animate(animation) => starfield.animate(animation),
+            Command::Timeline(timeline) => starfield.play(timeline),
+            Command::Capture | Command::Quit => {}
+        }
+    }
+}
+
+fn on_off(on: bool) -> Switch {
+    if on { Switch::On } else { Switch::Off }
+}
+
+// `focus [on|off|toggle]`, `breathing [on|off|toggle]`,
+// `constellations [on|off|toggle]`, `warp [on|off|toggle]`,
+// `pause [on|off|toggle]`, `scene <name>`, `debug <overlays|off>`,
+// `speed <times>`, `brightness <0..1>`, `stars <count>`,
+// `animate <param> [<from>->]<to> [over <N>s] [<easing>]`,
+// `timeline <animations and waits separated by ;|off>`, `capture` or `quit`
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, String> {
+        // These take the rest of the line
+        let line = line.trim();
+        if let Some(animation) = line.strip_prefix("animate ") {
+            return animation.parse().map(Command::Animate);
+        }
+        if let Some(timeline) = line.strip_prefix("timeline ") {
+            return timeline.parse().map(Command::Timeline);
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("too many arguments to `{name}`"));
+        }
+        let switch = || match argument {
+            None | Some("toggle") => Ok(Switch::Toggle),
+            Some("on") => Ok(Switch::On),
+            Some("off") => Ok(Switch::Off),
+            Some(other) => Err(format!("`{name}` takes on, off or toggle, got `{other}`")),
+        };
+        // A value for `param` to ease to
+        let animate = |param: Param| {
+            let value = argument.ok_or_else(|| format!("`{name}` needs a number"))?;
+            Ok(Command::Animate(Animation::to(param, param.value(value)?)))
+        };
+        let bare = |command| match argument {
+            None => Ok(command),
+            Some(_) => Err(format!("`{name}` takes no arguments")),
+        };
+
+        match name {
+            "focus" => Ok(Command::Focus(switch()?)),
+            "breathing" => Ok(Command::Breathing(switch()?)),
+            "constellations" => Ok(Command::Constellations(switch()?)),
+            "warp" => Ok(Command::Warp(switch()?)),
+            "pause" => Ok(Command::Pause(switch()?)),
+            "scene" => match argument {
+                Some(scene) => scene
+                    .parse()
+                    .map(Command::Scene)
+                    .map_err(|()| format!("unknown scene `{scene}`")),
+                None => Err("`scene` needs starfield, orrery or tunnel".into()),
+            },
+            "debug" => match argument {
+                Some("off") => Ok(Command::Debug(DebugOverlays::default())),
+                Some(list) => list.parse().map(Command::Debug),
+                None => Err("`debug` needs a list of overlays, or off".into()),
+            },
+            "speed" => animate(Param::Speed),
+            "brightness" => animate(Param::Brightness),
+            "stars" => animate(Param::Stars),
+            "capture" => bare(Command::Capture),
+            "quit" => bare(Command::Quit),
+            _ => Err(format!("unknown command `{name}`")),
+        }
+    }
+}
+
+// Start reading commands from `source`: "-" for stdin, otherwise the path of
+// a named pipe, made if it doesn't exist yet. With no source the channel
+// stays quiet.
+pub fn listen(source: Option<&str>) -> Receiver<Command> {
+    let (sender, receiver) = mpsc::channel();
+    match source {
+        None => {}
+        Some("-") => {
+            thread::spawn(move || read(io::stdin().lock(), &sender));
+        }
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if let Err(err) = make_fifo(&path) {
+                eprintln!(
+                    "wl-starfield: can't take commands from {}: {err}",
+                    path.display()
+                );
+                return receiver;
+            }
+            thread::spawn(move || {
+                // Each writer's EOF closes our end; reopen for the next one
+                loop {
+                    match File::open(&path) {
+                        Ok(fifo) => {
+                            if !read(BufReader::new(fifo), &sender) {
+                                return;
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("wl-starfield: can't read {}: {err}", path.display());
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    }
+    receiver
+}
+
+// Pass on commands until the input ends. Returns false once nobody is
+// listening any more.
+fn read(input: impl BufRead, sender: &Sender<Command>) -> bool {
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.parse() {
+            Ok(command) => {
+                if sender.send(command).is_err() {
+                    return false;
+                }
+            }
+            Err(err) => eprintln!("wl-starfield: {err}"),
+        }
+    }
+    true
+}
+
+fn make_fifo(path: &Path) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(meta) if meta.file_type().is_fifo() => Ok(()),
+        Ok(_) => Err(io::Error::other("not a named pipe")),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            // std has no mkfifo, and it isn't worth a libc dependency
+            let status = process::Command::new("mkfifo").arg(path).status()?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other("mkfifo failed"))
+            }
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("focus on".parse(), Ok(Command::Focus(Switch::On)));
+        assert_eq!(
+            "  breathing  ".parse(),
+            Ok(Command::Breathing(Switch::Toggle))
+        );
+        assert_eq!(
+            "constellations off".parse(),
+            Ok(Command::Constellations(Switch::Off))
+        );
+        assert_eq!(
+            "debug comet,spawning".parse(),
+            Ok(Command::Debug(DebugOverlays {
+                comet: true,
+                spawning: true,
+                ..DebugOverlays::default()
+            }))
+        );
+        assert_eq!(
+            "debug off".parse(),
+            Ok(Command::Debug(DebugOverlays::default()))
+        );
+        assert_eq!("warp".parse(), Ok(Command::Warp(Switch::Toggle)));
+        assert_eq!("pause off".parse(), Ok(Command::Pause(Switch::Off)));
+        assert_eq!("scene tunnel".parse(), Ok(Command::Scene(Scene::Tunnel)));
+        assert_eq!(
+            "speed 120x".parse(),
+            Ok(Command::Animate(Animation::to(Param::Speed, 120.0)))
+        );
+        assert_eq!(
+            "brightness 0.4".parse(),
+            Ok(Command::Animate(Animation::to(Param::Brightness, 0.4)))
+        );
+        assert_eq!(
+            "stars 2000".parse(),
+            Ok(Command::Animate(Animation::to(Param::Stars, 2000.0)))
+        );
+        assert_eq!(
+            "animate brightness 0.3→1.0 over 10s ease-in-out".parse(),
+            Ok(Command::Animate(Animation {
+                from: Some(0.3),
+                seconds: Some(10.0),
+                ..Animation::to(Param::Brightness, 1.0)
+            }))
+        );
+        assert_eq!(
+            "timeline off".parse(),
+            Ok(Command::Timeline(Timeline::default()))
+        );
+        assert!(matches!(
+            "timeline speed 60x over 5s; wait 10s; speed 1x over 5s".parse(),
+            Ok(Command::Timeline(_))
+        ));
+        assert_eq!("capture".parse(), Ok(Command::Capture));
+        assert_eq!("quit".parse(), Ok(Command::Quit));
+
+        assert!("theme synthwave".parse::<Command>().is_err());
+        assert!("focus maybe".parse::<Command>().is_err());
+        assert!("focus on now".parse::<Command>().is_err());
+        assert!("capture 10".parse::<Command>().is_err());
+        assert!("debug".parse::<Command>().is_err());
+        assert!("speed 0".parse::<Command>().is_err());
+        assert!("brightness 2".parse::<Command>().is_err());
+        assert!("stars many".parse::<Command>().is_err());
+        assert!("animate".parse::<Command>().is_err());
+        assert!("animate speed 2 over".parse::<Command>().is_err());
+        assert!("scene".parse::<Command>().is_err());
+        assert!("scene hyperspace".parse::<Command>().is_err());
+    }
+}
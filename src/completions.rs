@@ -0,0 +1,435 @@
+// Shell completions (`wl-starfield completions bash|zsh|fish`) and the man
+// page (`wl-starfield --generate-man`) for packagers to ship. Both are read
+// off the --help text rather than kept by hand, so a new flag shows up in them
+// as soon as it is documented there.
+
+use crate::cli::USAGE;
+use std::str::FromStr;
+
+// Flag descriptions start in this column of the help text
+const HELP_COLUMN: usize = 26;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Flag {
+    long: String,
+    short: Option<char>,
+    // Placeholder for its value, e.g. <N>, or [WxH] when the value is optional
+    value: Option<String>,
+    help: String,
+}
+
+impl Flag {
+    fn needs_value(&self) -> bool {
+        self.value
+            .as_deref()
+            .is_some_and(|value| value.starts_with('<'))
+    }
+
+    // Whether its value is a file name, which is worth completing
+    fn takes_path(&self) -> bool {
+        matches!(
+            self.value.as_deref(),
+            Some("<FILE>" | "<PATH>" | "<DIR>" | "<PIPE>")
+        )
+    }
+}
+
+// One block of the help text: the main options, or a subcommand's
+#[derive(Debug, Default)]
+struct Section {
+    // None for the options of a plain `wl-starfield`
+    subcommand: Option<String>,
+    about: String,
+    flags: Vec<Flag>,
+}
+
+// The usage lines and each section of the help text
+fn sections() -> (Vec<&'static str>, Vec<Section>) {
+    let mut synopsis = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
+    for line in USAGE.lines() {
+        if let Some(usage) = line.strip_prefix("Usage: ") {
+            synopsis.push(usage);
+        } else if let Some(name) = line.strip_suffix(':')
+            && !line.starts_with(' ')
+        {
+            let subcommand = (name != "Options").then(|| name.to_lowercase());
+            sections.push(Section {
+                subcommand,
+                ..Section::default()
+            });
+        } else if let Some(section) = sections.last_mut() {
+            read_line(section, line);
+        } else if !line.trim().is_empty() {
+            synopsis.push(line.trim());
+        }
+    }
+    (synopsis, sections)
+}
+
+fn read_line(section: &mut Section, line: &str) {
+    let text = line.trim();
+    if text.is_empty() {
+        return;
+    }
+    // A description carried over from the line before
+    if line.len() > HELP_COLUMN && line[..HELP_COLUMN].trim().is_empty() {
+        if let Some(flag) = section.flags.last_mut() {
+            append(&mut flag.help, text);
+        }
+        return;
+    }
+    if !text.starts_with('-') {
+        append(&mut section.about, text);
+        return;
+    }
+    // A long flag and its value can run into the description's column, which
+    // then starts on the next line
+    let (spec, help) = match line.as_bytes().get(HELP_COLUMN - 1) {
+        Some(b' ') if line.len() > HELP_COLUMN => (&line[..HELP_COLUMN], &line[HELP_COLUMN..]),
+        _ => (line, ""),
+    };
+    let mut flag = Flag {
+        help: help.trim().to_string(),
+        ..Flag::default()
+    };
+    for word in spec.split_whitespace() {
+        let word = word.trim_end_matches(',');
+        if let Some(long) = word.strip_prefix("--") {
+            flag.long = long.to_string();
+        } else if let Some(short) = word.strip_prefix('-') {
+            flag.short = short.chars().next();
+        } else {
+            flag.value = Some(word.to_string());
+        }
+    }
+    section.flags.push(flag);
+}
+
+fn append(text: &mut String, more: &str) {
+    if !text.is_empty() {
+        text.push(' ');
+    }
+    text.push_str(more);
+}
+
+// The flag's description up to the end of its first sentence or clause, short
+// enough for a completion menu
+fn summary(help: &str) -> &str {
+    let end = help.find([':', '(', ';']).unwrap_or(help.len());
+    help[..end].trim_end_matches([' ', ',', '.'])
+}
+
+pub fn script(shell: Shell) -> String {
+    let (_, sections) = sections();
+    match shell {
+        Shell::Bash => bash(&sections),
+        Shell::Zsh => zsh(&sections),
+        Shell::Fish => fish(&sections),
+    }
+}
+
+fn subcommands(sections: &[Section]) -> Vec<&str> {
+    sections
+        .iter()
+        .filter_map(|section| section.subcommand.as_deref())
+        .collect()
+}
+
+fn bash(sections: &[Section]) -> String {
+    let mut script = String::from(
+        "# bash completion for wl-starfield\n\
+         _wl_starfield() {\n\
+         \x20   local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD-1]} words\n\
+         \x20   case $prev in\n",
+    );
+    // After a flag that needs a value, offer file names for a path and
+    // nothing for anything else
+    let named = |paths: bool| -> Vec<String> {
+        sections
+            .iter()
+            .flat_map(|section| &section.flags)
+            .filter(|flag| flag.needs_value() && flag.takes_path() == paths)
+            .map(|flag| format!("--{}", flag.long))
+            .collect()
+    };
+    script += &format!(
+        "        {})\n            COMPREPLY=($(compgen -f -- \"$cur\"))\n            return ;;\n\
+         \x20       {})\n            COMPREPLY=()\n            return ;;\n",
+        named(true).join("|"),
+        named(false).join("|")
+    );
+    script += "    esac\n    case ${COMP_WORDS[1]} in\n";
+    // Anything that isn't a subcommand is the main options, so they go last
+    let mut main = String::new();
+    for section in sections {
+        let words = section.flags.iter().map(|flag| format!("--{}", flag.long));
+        match &section.subcommand {
+            Some(name) if name == "completions" => {
+                script += "        completions) words=\"bash zsh fish\" ;;\n";
+            }
+            Some(name) => {
+                let words: Vec<String> = words.chain(["--help".into()]).collect();
+                script += &format!("        {name}) words=\"{}\" ;;\n", words.join(" "));
+            }
+            None => {
+                let words: Vec<String> = subcommands(sections)
+                    .into_iter()
+                    .map(String::from)
+                    .chain(words)
+                    .collect();
+                main = format!("        *) words=\"{}\" ;;\n", words.join(" "));
+            }
+        }
+    }
+    script += &main;
+    script += "    esac\n    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n}\n\
+               complete -F _wl_starfield wl-starfield\n";
+    script
+}
+
+// A zsh _arguments spec for the flag
+fn zsh_spec(flag: &Flag) -> String {
+    let help = summary(&flag.help)
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+    let value = match flag.value.as_deref() {
+        Some(value) if flag.needs_value() => {
+            let action = if flag.takes_path() { "_files" } else { "" };
+            format!(":{}:{action}", value.trim_matches(['<', '>']))
+        }
+        Some(value) => format!("::{}:", value.trim_matches(['[', ']'])),
+        None => String::new(),
+    };
+    match flag.short {
+        Some(short) => format!(
+            "'(-{short} --{long})'{{-{short},--{long}}}'[{help}]{value}'",
+            long = flag.long
+        ),
+        None => format!("'--{}[{help}]{value}'", flag.long),
+    }
+}
+
+fn zsh(sections: &[Section]) -> String {
+    let mut script = String::from("#compdef wl-starfield\n\n_wl-starfield() {\n");
+    let names = subcommands(sections).join(" ");
+    script += &format!(
+        "    if (( CURRENT > 2 )) && [[ ${{words[2]}} == ({}) ]]; then\n\
+         \x20       local command=${{words[2]}}\n\
+         \x20       shift words\n\
+         \x20       (( CURRENT-- ))\n\
+         \x20       case $command in\n",
+        names.replace(' ', "|")
+    );
+    let mut main = Vec::new();
+    for section in sections {
+        let mut specs: Vec<String> = section.flags.iter().map(zsh_spec).collect();
+        match &section.subcommand {
+            Some(name) if name == "completions" => {
+                script += "            completions) _arguments '1:shell:(bash zsh fish)' ;;\n";
+            }
+            Some(name) => {
+                specs.push("'--help[Print help]'".into());
+                script += &format!("            {name}) _arguments {} ;;\n", specs.join(" "));
+            }
+            None => main = specs,
+        }
+    }
+    script += &format!(
+        "        esac\n    else\n        _arguments {} '1:command:({names})'\n    fi\n}}\n\n\
+         _wl-starfield \"$@\"\n",
+        main.join(" \\\n            ")
+    );
+    script
+}
+
+fn fish(sections: &[Section]) -> String {
+    let mut script =
+        String::from("# fish completion for wl-starfield\ncomplete -c wl-starfield -f\n");
+    for section in sections {
+        let condition = match &section.subcommand {
+            Some(name) => {
+                script += &format!(
+                    "complete -c wl-starfield -n __fish_use_subcommand -a {name} -d '{}'\n",
+                    fish_quote(summary(&section.about))
+                );
+                format!("'__fish_seen_subcommand_from {name}'")
+            }
+            None => "__fish_use_subcommand".to_string(),
+        };
+        if section.subcommand.as_deref() == Some("completions") {
+            script += &format!("complete -c wl-starfield -n {condition} -a 'bash zsh fish'\n");
+        }
+        for flag in &section.flags {
+            let mut line = format!("complete -c wl-starfield -n {condition} -l {}", flag.long);
+            if let Some(short) = flag.short {
+                line += &format!(" -s {short}");
+            }
+            match (flag.needs_value(), flag.takes_path()) {
+                (true, true) => line += " -r -F",
+                (true, false) => line += " -x",
+                _ => {}
+            }
+            line += &format!(" -d '{}'\n", fish_quote(summary(&flag.help)));
+            script += &line;
+        }
+    }
+    script
+}
+
+fn fish_quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// The man page, in roff
+pub fn man_page() -> String {
+    let (synopsis, sections) = sections();
+    let mut page = format!(
+        ".TH WL-STARFIELD 1 \"\" \"wl-starfield {}\" \"User Commands\"\n\
+         .SH NAME\n\
+         wl-starfield \\- a drifting starfield for Wayland, as a wallpaper or in a window\n\
+         .SH SYNOPSIS\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for usage in synopsis {
+        page += &format!("{}\n.br\n", roff(usage));
+    }
+    for section in &sections {
+        match &section.subcommand {
+            None => page += ".SH OPTIONS\n",
+            Some(name) => {
+                page += &format!(".SH {}\n", name.to_uppercase());
+                if !section.about.is_empty() {
+                    page += &format!("{}\n", roff(&section.about));
+                }
+            }
+        }
+        for flag in &section.flags {
+            let mut names = format!("\\fB\\-\\-{}\\fR", roff(&flag.long));
+            if let Some(short) = flag.short {
+                names = format!("\\fB\\-{short}\\fR, {names}");
+            }
+            if let Some(value) = &flag.value {
+                names += &format!(" \\fI{}\\fR", roff(value));
+            }
+            page += &format!(".TP\n{names}\n{}\n", roff(&flag.help));
+        }
+    }
+    page += ".SH FILES\n\
+             .TP\n\
+             \\fI$XDG_CONFIG_HOME/wl\\-starfield/config.toml\\fR\n\
+             Settings, every one optional; \\fBwl\\-starfield \\-\\-dump\\-default\\-config\\fR \
+             prints them all at their defaults.\n";
+    page
+}
+
+// Escape text for roff: backslashes, hyphens, and a leading dot or quote
+fn roff(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    match text.starts_with(['.', '\'']) {
+        true => format!("\\&{text}"),
+        false => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_flags_off_the_help_text() {
+        let (synopsis, sections) = sections();
+        assert_eq!(synopsis[0], "wl-starfield [OPTIONS]");
+        let main = &sections[0];
+        assert_eq!(main.subcommand, None);
+        let flag = |long: &str| main.flags.iter().find(|flag| flag.long == long).unwrap();
+        assert_eq!(
+            flag("star-count"),
+            &Flag {
+                long: "star-count".into(),
+                short: None,
+                value: Some("<N>".into()),
+                help: "Number of stars (overrides config.toml)".into(),
+            }
+        );
+        assert_eq!(flag("windowed").value.as_deref(), Some("[WxH]"));
+        assert_eq!(flag("help").short, Some('h'));
+        assert!(flag("timelapse").help.starts_with("Run the sky faster"));
+        // Described on the lines after the flag
+        assert!(flag("location").help.starts_with("Show the real sky"));
+        assert_eq!(flag("location").value.as_deref(), Some("<LAT,LON>"));
+        let render = sections
+            .iter()
+            .find(|section| section.subcommand.as_deref() == Some("render"))
+            .unwrap();
+        assert!(render.about.starts_with("Draw frames"));
+        assert!(render.flags.iter().any(|flag| flag.long == "output"));
+    }
+
+    #[test]
+    fn every_flag_in_the_help_is_accepted() {
+        let (_, sections) = sections();
+        for section in &sections {
+            for flag in &section.flags {
+                let mut args: Vec<String> = section.subcommand.iter().cloned().collect();
+                args.push(format!("--{}", flag.long));
+                // A value that parses for every flag that takes one
+                let value = match flag.long.as_str() {
+                    "size" => "640x360",
+                    "location" => "52.2,0.12",
+                    "scene" => "tunnel",
+                    "debug" => "all",
+                    "accent" => "#ffffff",
+                    _ => "2",
+                };
+                if flag.needs_value() {
+                    args.push(value.into());
+                }
+                assert!(crate::cli::parses(args.clone()), "{args:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn scripts_and_man_page_cover_every_flag() {
+        let scripts = [Shell::Bash, Shell::Zsh, Shell::Fish].map(script);
+        let page = man_page();
+        for flag in sections().1.iter().flat_map(|section| &section.flags) {
+            for script in &scripts {
+                assert!(script.contains(&flag.long), "{}", flag.long);
+            }
+            assert!(page.contains(&roff(&flag.long)), "{}", flag.long);
+        }
+        assert!(scripts[1].contains("'(-h --help)'{-h,--help}'[Print this help]'"));
+        assert!(scripts[1].contains("'--output[Directory to save them in]:DIR:_files'"));
+        assert!(scripts[2].contains("-l frames -x -d 'How many frames'"));
+        assert!(page.contains(".TP\n\\fB\\-\\-seed\\fR \\fI<SEED>\\fR\n"));
+    }
+
+    #[test]
+    fn escapes_roff() {
+        assert_eq!(roff("a-b \\n"), "a\\-b \\en");
+        assert_eq!(roff(".TH"), "\\&.TH");
+    }
+}
@@ -0,0 +1,1258 @@
+// User configuration from $XDG_CONFIG_HOME/wl-starfield/config.toml. Only the
+// flat `key = value` subset of TOML is understood, which is all the tuning
+// knobs need. Missing keys keep their defaults; a file that fails to parse is
+// reported and ignored as a whole.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::accent::Accent;
+use crate::exposure::Crop;
+use crate::gradient::Gradient;
+use crate::planet::MAX_PLANETS;
+use crate::quote::QUOTE_SHOW_TIME;
+use crate::schedule::{self, Schedule};
+use crate::skyline::SkylineShape;
+use crate::tween::Timeline;
+use crate::{Scene, SkyEvent};
+
+// How stars get their colors
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StarColors {
+    // From a temperature, which also sets how big and bright they are
+    #[default]
+    Blackbody,
+    // From five fixed tints, independent of size
+    Palette,
+}
+
+impl FromStr for StarColors {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "blackbody" => Ok(StarColors::Blackbody),
+            "palette" => Ok(StarColors::Palette),
+            _ => Err(()),
+        }
+    }
+}
+
+// How carefully stars are drawn on the CPU
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Quality {
+    // Solid squares on the pixel grid
+    #[default]
+    Standard,
+    // Round discs placed between pixels, each pixel covered by as much of
+    // the disc as overlaps it, so slow stars glide rather than step
+    Smooth,
+    // Gaussian splats, each pixel getting exactly the share of the star that
+    // falls on it, so they sit between pixels and scale smoothly
+    High,
+}
+
+impl FromStr for Quality {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "standard" => Ok(Quality::Standard),
+            "smooth" => Ok(Quality::Smooth),
+            "high" => Ok(Quality::High),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub star_count: usize,
+    pub star_min_speed: f32,
+    pub star_max_speed: f32,
+    // Rate per second at which a star's speed relaxes toward its cruise
+    // speed; 0 keeps each star at the speed it started with
+    pub star_drag: f32,
+    pub star_min_size: u32,
+    pub star_max_size: u32,
+    pub star_colors: StarColors,
+    pub quality: Quality,
+    // A soft glow and four diffraction spikes on stars at least
+    // spike_min_size pixels across, reaching spike_length times their size
+    pub diffraction_spikes: bool,
+    pub spike_min_size: u32,
+    pub spike_length: f32,
+    // Light above bloom_threshold (0..1) bleeding into the sky around it, by
+    // bloom_strength
+    pub bloom: bool,
+    pub bloom_threshold: f32,
+    pub bloom_strength: f32,
+    // Each frame drawn over the last faded rather than on empty sky, leaving
+    // trails; trail_decay of the light left fades each second
+    pub star_trails: bool,
+    pub trail_decay: f32,
+    // A color to tint stars and shooting stars towards, and how far, 0..1
+    pub accent: Option<Accent>,
+    pub accent_strength: f32,
+    // Which way stars drift, in degrees counterclockwise from rightward, or
+    // along the long axis of the screen
+    pub drift_angle: Option<f32>,
+    pub shooting_star_gravity: f32,
+    // Average rate of slow comets; 0 for none
+    pub comets_per_hour: f32,
+    // The comet catch toy: clicks place attractors to steer a comet through
+    // a ring
+    pub comet_catch: bool,
+    // Average rate of satellite flyovers, 0 for none, and how bright they are, 0..1
+    pub satellites_per_hour: f32,
+    pub satellite_brightness: f32,
+    // Bright wandering planets among the stars, up to four, 0 for none
+    pub planets: usize,
+    // Chance each minute of a star going supernova, 0..1
+    pub supernova_chance: f32,
+    // Calmness budget: how much light from shooting stars, comets and sparkles
+    // may stack up before new ones are held back; 0 for no limit
+    pub loudness_budget: f32,
+    // Frames per second to stop at; 0 redraws as fast as possible
+    pub fps_cap: f32,
+    // Seconds that speed, brightness and star count changes from commands
+    // take to ease in, 0 to change at once
+    pub transition_seconds: f32,
+    // Open with every star flying out from the center to its place
+    pub intro: bool,
+    // Animations to play one after another from the start
+    pub timeline: Option<Timeline>,
+    // Draw the costliest effects more cheaply when frames run long
+    pub adaptive_detail: bool,
+    // Pick the quality tier and star count for the machine on first run,
+    // for whichever of them aren't set here
+    pub auto_tune: bool,
+    // How strongly the most distant stars fade into the background, 0..1
+    pub haze: f32,
+    pub clouds: bool,
+    // Pixels per second, and degrees clockwise from rightwards
+    pub cloud_wind_speed: f32,
+    pub cloud_wind_direction: f32,
+    // A band of faint stars and glow across the sky, at an angle in degrees
+    // clockwise from rightwards and a brightness of 0..1
+    pub milky_way: bool,
+    pub milky_way_angle: f32,
+    pub milky_way_brightness: f32,
+    // The moon, at a fixed phase (0 new, 0.5 full) or by default today's
+    pub moon: bool,
+    pub moon_phase: Option<f32>,
+    // Colored gas behind the stars: base hue in degrees, how much of the sky
+    // it covers (0..1), and how fast it drifts in pixels per second
+    pub nebula: bool,
+    pub nebula_hue: f32,
+    pub nebula_density: f32,
+    pub nebula_drift_speed: f32,
+    // Relaxation mode: the whole sky slowly brightens and dims to pace breathing
+    pub breathing: bool,
+    pub breaths_per_minute: f32,
+    // Join the brightest stars into faint constellation figures
+    pub constellations: bool,
+    // React to the pointer in a window: hovering a star shows its name and
+    // lore, and triple-clicking one sets it off as a supernova
+    pub interactive: bool,
+    // A magnified "telescope view" inset in the bottom-right corner
+    pub telescope: bool,
+    // Hyperspace: stars streak out from a vanishing point, given as fractions
+    // of the width and height, instead of drifting
+    pub warp: bool,
+    pub warp_center: (f32, f32),
+    // 3D: stars have places in space and the camera flies forward through
+    // them, this fraction of the way from the far end each second
+    pub perspective: bool,
+    pub camera_speed: f32,
+    // Stars shift a little away from the pointer, the nearer the more
+    pub parallax: bool,
+    // What to show, and in the orrery how many days pass each second (0 to
+    // keep the planets where they really are)
+    pub scene: Scene,
+    pub orrery_days_per_second: f32,
+    // When to change scenes, for every output and for particular ones by
+    // name, and how long each change takes to fade across
+    pub scene_schedule: Option<Schedule>,
+    pub output_scene_schedules: Vec<(String, Schedule)>,
+    pub scene_fade_seconds: f32,
+    // Planetarium mode: the real sky over this place in degrees (north and
+    // east positive), turning with the clock, instead of drifting stars
+    pub latitude: Option<f32>,
+    pub longitude: Option<f32>,
+    // Threads to draw frames with; 0 uses one per core
+    pub render_threads: usize,
+    // Accessibility: the gentler profile, which turns the flash limiter on at
+    // ACCESSIBLE_FLASH_LIMIT unless flash_limit is given
+    pub accessibility: bool,
+    // Accessibility: brief colored flashes on the brightest stars
+    pub sparkles: bool,
+    // Accessibility: most any part of the screen may brighten or darken by
+    // in a second, as a share of black to white; 0 for no limit
+    pub flash_limit: f32,
+    // Length of a long-exposure capture
+    pub exposure_seconds: f32,
+    // Keep just this part of the screen in captures, and scale them by this
+    pub capture_crop: Option<Crop>,
+    pub capture_scale: f32,
+    // Where captures and screenshots are saved, rather than the pictures
+    // directory
+    pub capture_dir: Option<PathBuf>,
+    // Animated recordings: how long they run, their frames per second and
+    // how much they are scaled down by
+    pub record_seconds: f32,
+    pub record_fps: f32,
+    pub record_scale: f32,
+    // Where every frame of the first output is shared for OBS and the like,
+    // if anywhere
+    pub frame_export: Option<PathBuf>,
+    // Offer the first output as a PipeWire video source, scaled to this size
+    // (or its own) at this many frames a second
+    pub pipewire_source: bool,
+    pub pipewire_size: Option<(u32, u32)>,
+    pub pipewire_fps: u32,
+    // Draw stars with a GPU shader instead of on the CPU (window backend only,
+    // built with the `gpu` feature)
+    pub gpu: bool,
+    // Simulate at 1/pixel_scale of the output's size and blow every pixel up
+    // into a pixel_scale square, for chunky pixel art; 1 for none
+    pub pixel_scale: u32,
+    // In pixel art, bring the sky down to a small palette whose nebula and
+    // water shades rotate
+    pub palette_cycling: bool,
+    // Monochrome: map the sky's luminance through this gradient
+    pub gradient: Option<Gradient>,
+    // What the sky is drawn on instead of black: a PNG image scaled to
+    // cover the screen, or failing that a gradient from top to bottom,
+    // tinted by the time of day with background_tint
+    pub background_image: Option<PathBuf>,
+    pub background_gradient: Option<Gradient>,
+    pub background_tint: bool,
+    // A dark silhouette of the ground along the bottom of each screen, in
+    // front of the sky: shapes skyline_height of it tall, or a PNG mask
+    // standing on the bottom edge; a city's windows light with skyline_lights
+    pub skyline: Option<SkylineShape>,
+    pub skyline_mask: Option<PathBuf>,
+    pub skyline_height: f32,
+    pub skyline_lights: bool,
+    // With several outputs, start shooting stars and comets on the leftmost
+    // one and sweep each across the others from left to right
+    pub stagger_events: bool,
+    // With several outputs, show one sky across all of them laid out as the
+    // monitors are, instead of a separate sky on each
+    pub span_outputs: bool,
+    // Show lifetime statistics (shooting stars wished on and so on) in a
+    // corner of the screen
+    pub stats_overlay: bool,
+    // Quotes from quotes.txt: seconds from one to the next, and where the
+    // middle of the text sits, as fractions across and down
+    pub quote_interval: f32,
+    pub quote_anchor: (f32, f32),
+    // Local time of day, in seconds, to send a notification about tonight's
+    // sky
+    pub sky_report_time: Option<u64>,
+    // Shell commands to run when things happen in the sky, from `on_<event>`
+    // keys, and the fewest seconds between runs of any one of them
+    pub hooks: Vec<(SkyEvent, String)>,
+    pub hook_min_interval: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            star_count: 5000,
+            star_min_speed: 5.0,
+            star_max_speed: 25.0,
+            star_drag: 0.2,
+            star_min_size: 1,
+            star_max_size: 4,
+            star_colors: StarColors::Blackbody,
+            quality: Quality::Standard,
+            diffraction_spikes: false,
+            spike_min_size: 4,
+            spike_length: 3.0,
+            bloom: false,
+            bloom_threshold: 0.7,
+            bloom_strength: 1.0,
+            star_trails: false,
+            trail_decay: 0.5,
+            accent: None,
+            accent_strength: 0.35,
+            drift_angle: None,
+            shooting_star_gravity: 30.0,
+            comets_per_hour: 2.0,
+            comet_catch: false,
+            satellites_per_hour: 12.0,
+            satellite_brightness: 0.6,
+            planets: 0,
+            supernova_chance: 0.02,
+            loudness_budget: 2.0,
+            fps_cap: 60.0,
+            transition_seconds: 1.5,
+            intro: true,
+            timeline: None,
+            adaptive_detail: true,
+            auto_tune: true,
+            haze: 0.35,
+            clouds: false,
+            cloud_wind_speed: 6.0,
+            cloud_wind_direction: 180.0,
+            milky_way: false,
+            milky_way_angle: -35.0,
+            milky_way_brightness: 0.6,
+            moon: false,
+            moon_phase: None,
+            nebula: false,
+            nebula_hue: 280.0,
+            nebula_density: 0.5,
+            nebula_drift_speed: 1.5,
+            breathing: false,
+            breaths_per_minute: 6.0,
+            constellations: false,
+            interactive: true,
+            telescope: false,
+            warp: false,
+            warp_center: (0.5, 0.5),
+            perspective: false,
+            camera_speed: 0.05,
+            parallax: true,
+            scene: Scene::Starfield,
+            orrery_days_per_second: 10.0,
+            scene_schedule: None,
+            output_scene_schedules: Vec::new(),
+            scene_fade_seconds: 3.0,
+            latitude: None,
+            longitude: None,
+            render_threads: 0,
+            accessibility: false,
+            sparkles: true,
+            flash_limit: 0.0,
+            exposure_seconds: 4.0,
+            capture_crop: None,
+            capture_scale: 1.0,
+            capture_dir: None,
+            record_seconds: 10.0,
+            record_fps: 15.0,
+            frame_export: None,
+            pipewire_source: false,
+            pipewire_size: None,
+            pipewire_fps: 30,
+            record_scale: 0.5,
+            gpu: false,
+            pixel_scale: 1,
+            palette_cycling: false,
+            gradient: None,
+            background_image: None,
+            background_gradient: None,
+            background_tint: false,
+            skyline: None,
+            skyline_mask: None,
+            skyline_height: 0.2,
+            skyline_lights: true,
+            stagger_events: false,
+            span_outputs: false,
+            stats_overlay: false,
+            quote_interval: 900.0,
+            quote_anchor: (0.5, 0.85),
+            sky_report_time: None,
+            hooks: Vec::new(),
+            hook_min_interval: 10.0,
+        }
+    }
+}
+
+// The layout of config files this build reads. Files from before there was a
+// `version` key are version 1.
+pub const CONFIG_VERSION: u32 = 1;
+
+// The flash limit of the accessibility profile: nothing goes from black to
+// white in less than a second
+pub const ACCESSIBLE_FLASH_LIMIT: f32 = 1.0;
+
+// The defaults as a config file, every key explained, for
+// --dump-default-config
+pub const DEFAULT_FILE: &str = include_str!("../assets/config.toml");
+
+// One change to the layout, from the version before: the new key and value for
+// a line, given its old ones, or None to keep the line as it is
+type Migration = fn(&str, &str) -> Option<(String, String)>;
+
+// Every change to the layout, oldest first: MIGRATIONS[0] takes a version 1
+// file to version 2, and so on. Add one here whenever a key is renamed or its
+// units change, and bump CONFIG_VERSION.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize - 1] = [];
+
+// $XDG_CONFIG_HOME/wl-starfield, falling back to ~/.config/wl-starfield
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("wl-starfield"))
+}
+
+impl Config {
+    // The user's config, or the defaults if there is none (or it's broken)
+    pub fn load() -> Self {
+        let Some(path) = config_dir().map(|dir| dir.join("config.toml")) else {
+            return Self::default();
+        };
+        Self::load_from(&path).unwrap_or_else(|err| {
+            eprintln!("wl-starfield: ignoring {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    fn load_from(path: &Path) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.to_string()),
+        };
+        let Some((old, migrated)) = migrate(&text, &MIGRATIONS)? else {
+            return Self::parse(&text);
+        };
+        // Only once it's known to be good, and keeping the original beside it
+        let config = Self::parse(&migrated)?;
+        let backup = path.with_extension(format!("toml.v{old}"));
+        match fs::write(&backup, &text).and_then(|()| fs::write(path, &migrated)) {
+            Ok(()) => eprintln!(
+                "wl-starfield: updated {} from version {old}, the original is in {}",
+                path.display(),
+                backup.display()
+            ),
+            Err(err) => eprintln!(
+                "wl-starfield: couldn't update {} from version {old}: {err}",
+                path.display()
+            ),
+        }
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        // Whether flash_limit was given, as the accessibility profile sets it
+        // otherwise, wherever in the file that is
+        let mut flash_limit = None;
+
+        for (number, line) in (1..).zip(text.lines()) {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {number}: expected `key = value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let at = |err: String| format!("line {number}: {err}");
+
+            match key {
+                "version" => {
+                    let version: u32 = number_value(key, value).map_err(at)?;
+                    if !(1..=CONFIG_VERSION).contains(&version) {
+                        return Err(at(format!(
+                            "`version` must be 1 to {CONFIG_VERSION}, got {version}"
+                        )));
+                    }
+                }
+                "star_count" => config.star_count = number_value(key, value).map_err(at)?,
+                "star_min_speed" => config.star_min_speed = number_value(key, value).map_err(at)?,
+                "star_max_speed" => config.star_max_speed = number_value(key, value).map_err(at)?,
+                "star_drag" => config.star_drag = number_value(key, value).map_err(at)?,
+                "star_min_size" => config.star_min_size = number_value(key, value).map_err(at)?,
+                "star_max_size" => config.star_max_size = number_value(key, value).map_err(at)?,
+                "diffraction_spikes" => {
+                    config.diffraction_spikes = bool_value(key, value).map_err(at)?
+                }
+                "spike_min_size" => config.spike_min_size = number_value(key, value).map_err(at)?,
+                "spike_length" => config.spike_length = number_value(key, value).map_err(at)?,
+                "bloom" => config.bloom = bool_value(key, value).map_err(at)?,
+                "bloom_threshold" => {
+                    config.bloom_threshold = number_value(key, value).map_err(at)?
+                }
+                "bloom_strength" => config.bloom_strength = number_value(key, value).map_err(at)?,
+                "star_trails" => config.star_trails = bool_value(key, value).map_err(at)?,
+                "trail_decay" => config.trail_decay = number_value(key, value).map_err(at)?,
+                "star_colors" => {
+                    let name = string_value(key, value).map_err(at)?;
+                    config.star_colors = name.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be \"blackbody\" or \"palette\", got `{value}`"
+                        ))
+                    })?;
+                }
+                "accent" => {
+                    let accent = string_value(key, value).map_err(at)?;
+                    config.accent = Some(accent.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be \"#rrggbb\", \"pywal\" or \"pywal:N\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "accent_strength" => {
+                    config.accent_strength = number_value(key, value).map_err(at)?
+                }
+                "quality" => {
+                    let name = string_value(key, value).map_err(at)?;
+                    config.quality = name.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be \"standard\", \"smooth\" or \"high\", got `{value}`"
+                        ))
+                    })?;
+                }
+                "drift_direction" => {
+                    config.drift_angle = if value.starts_with('"') {
+                        match string_value(key, value).map_err(at)?.as_str() {
+                            "auto" => None,
+                            "right" => Some(0.0),
+                            "up" => Some(90.0),
+                            "left" => Some(180.0),
+                            "diagonal" => Some(225.0),
+                            "down" => Some(270.0),
+                            _ => {
+                                return Err(at(format!(
+                                    "`{key}` must be an angle or \"left\", \"right\", \"up\", \"down\", \"diagonal\" or \"auto\", got `{value}`"
+                                )));
+                            }
+                        }
+                    } else {
+                        Some(number_value(key, value).map_err(at)?)
+                    };
+                }
+                "shooting_star_gravity" => {
+                    config.shooting_star_gravity = number_value(key, value).map_err(at)?
+                }
+                "comets_per_hour" => {
+                    config.comets_per_hour = number_value(key, value).map_err(at)?
+                }
+                "comet_catch" => config.comet_catch = bool_value(key, value).map_err(at)?,
+                "satellites_per_hour" => {
+                    config.satellites_per_hour = number_value(key, value).map_err(at)?
+                }
+                "satellite_brightness" => {
+                    config.satellite_brightness = number_value(key, value).map_err(at)?
+                }
+                "planets" => config.planets = number_value(key, value).map_err(at)?,
+                "supernova_chance" => {
+                    config.supernova_chance = number_value(key, value).map_err(at)?
+                }
+                "loudness_budget" => {
+                    config.loudness_budget = number_value(key, value).map_err(at)?
+                }
+                "fps_cap" => config.fps_cap = number_value(key, value).map_err(at)?,
+                "transition_seconds" => {
+                    config.transition_seconds = number_value(key, value).map_err(at)?
+                }
+                "intro" => config.intro = bool_value(key, value).map_err(at)?,
+                "timeline" => {
+                    let timeline = string_value(key, value).map_err(at)?;
+                    config.timeline = Some(
+                        timeline
+                            .parse()
+                            .map_err(|err| at(format!("`{key}`: {err}")))?,
+                    );
+                }
+                "adaptive_detail" => config.adaptive_detail = bool_value(key, value).map_err(at)?,
+                "auto_tune" => config.auto_tune = bool_value(key, value).map_err(at)?,
+                "haze" => config.haze = number_value(key, value).map_err(at)?,
+                "clouds" => config.clouds = bool_value(key, value).map_err(at)?,
+                "cloud_wind_speed" => {
+                    config.cloud_wind_speed = number_value(key, value).map_err(at)?
+                }
+                "cloud_wind_direction" => {
+                    config.cloud_wind_direction = number_value(key, value).map_err(at)?
+                }
+                "milky_way" => config.milky_way = bool_value(key, value).map_err(at)?,
+                "milky_way_angle" => {
+                    config.milky_way_angle = number_value(key, value).map_err(at)?
+                }
+                "milky_way_brightness" => {
+                    config.milky_way_brightness = number_value(key, value).map_err(at)?
+                }
+                "moon" => config.moon = bool_value(key, value).map_err(at)?,
+                "moon_phase" => config.moon_phase = Some(number_value(key, value).map_err(at)?),
+                "nebula" => config.nebula = bool_value(key, value).map_err(at)?,
+                "nebula_hue" => config.nebula_hue = number_value(key, value).map_err(at)?,
+                "nebula_density" => config.nebula_density = number_value(key, value).map_err(at)?,
+                "nebula_drift_speed" => {
+                    config.nebula_drift_speed = number_value(key, value).map_err(at)?
+                }
+                "breathing" => config.breathing = bool_value(key, value).map_err(at)?,
+                "breaths_per_minute" => {
+                    config.breaths_per_minute = number_value(key, value).map_err(at)?
+                }
+                "constellations" => config.constellations = bool_value(key, value).map_err(at)?,
+                "interactive" => config.interactive = bool_value(key, value).map_err(at)?,
+                "telescope" => config.telescope = bool_value(key, value).map_err(at)?,
+                "warp" => config.warp = bool_value(key, value).map_err(at)?,
+                "perspective" => config.perspective = bool_value(key, value).map_err(at)?,
+                "camera_speed" => config.camera_speed = number_value(key, value).map_err(at)?,
+                "parallax" => config.parallax = bool_value(key, value).map_err(at)?,
+                "warp_center" => config.warp_center = point_value(key, value).map_err(at)?,
+                "scene" => {
+                    let name = string_value(key, value).map_err(at)?;
+                    config.scene = name.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be \"starfield\", \"orrery\" or \"tunnel\", got `{value}`"
+                        ))
+                    })?;
+                }
+                "orrery_days_per_second" => {
+                    config.orrery_days_per_second = number_value(key, value).map_err(at)?
+                }
+                "scene_schedule" => {
+                    let schedule = string_value(key, value).map_err(at)?;
+                    config.scene_schedule = Some(
+                        schedule
+                            .parse()
+                            .map_err(|err| at(format!("`{key}`: {err}")))?,
+                    );
+                }
+                "scene_fade_seconds" => {
+                    config.scene_fade_seconds = number_value(key, value).map_err(at)?
+                }
+                "latitude" => config.latitude = Some(number_value(key, value).map_err(at)?),
+                "longitude" => config.longitude = Some(number_value(key, value).map_err(at)?),
+                "render_threads" => config.render_threads = number_value(key, value).map_err(at)?,
+                "accessibility" => config.accessibility = bool_value(key, value).map_err(at)?,
+                "sparkles" => config.sparkles = bool_value(key, value).map_err(at)?,
+                "flash_limit" => {
+                    config.flash_limit = number_value(key, value).map_err(at)?;
+                    flash_limit = Some(config.flash_limit);
+                }
+                "exposure_seconds" => {
+                    config.exposure_seconds = number_value(key, value).map_err(at)?
+                }
+                "capture_crop" => {
+                    let crop = string_value(key, value).map_err(at)?;
+                    config.capture_crop = Some(crop.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be like \"1080x1080\" or \"1080x1080+420+0\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "capture_scale" => config.capture_scale = number_value(key, value).map_err(at)?,
+                "record_seconds" => config.record_seconds = number_value(key, value).map_err(at)?,
+                "record_fps" => config.record_fps = number_value(key, value).map_err(at)?,
+                "record_scale" => config.record_scale = number_value(key, value).map_err(at)?,
+                "capture_dir" => {
+                    config.capture_dir = Some(string_value(key, value).map_err(at)?.into())
+                }
+                "frame_export" => {
+                    config.frame_export = Some(string_value(key, value).map_err(at)?.into())
+                }
+                "pipewire_source" => config.pipewire_source = bool_value(key, value).map_err(at)?,
+                "pipewire_size" => {
+                    let size = string_value(key, value).map_err(at)?;
+                    let parsed = size.split_once('x').and_then(|(width, height)| {
+                        let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+                        (width > 0 && height > 0).then_some((width, height))
+                    });
+                    config.pipewire_size = Some(parsed.ok_or_else(|| {
+                        at(format!(
+                            "`{key}` must be a size like \"1280x720\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "pipewire_fps" => config.pipewire_fps = number_value(key, value).map_err(at)?,
+                "gpu" => config.gpu = bool_value(key, value).map_err(at)?,
+                "pixel_scale" => config.pixel_scale = number_value(key, value).map_err(at)?,
+                "palette_cycling" => config.palette_cycling = bool_value(key, value).map_err(at)?,
+                "gradient" => {
+                    let gradient = string_value(key, value).map_err(at)?;
+                    config.gradient = Some(gradient.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be two or three colors like \"#0b1030, #40e0ff\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "background_image" => {
+                    config.background_image = Some(string_value(key, value).map_err(at)?.into())
+                }
+                "background_gradient" => {
+                    let gradient = string_value(key, value).map_err(at)?;
+                    config.background_gradient = Some(gradient.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be two or three colors like \"#0b1030, #000000\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "background_tint" => config.background_tint = bool_value(key, value).map_err(at)?,
+                "skyline" => {
+                    let name = string_value(key, value).map_err(at)?;
+                    config.skyline = Some(name.parse().map_err(|()| {
+                        at(format!(
+                            "`{key}` must be \"mountains\", \"trees\" or \"city\", got `{value}`"
+                        ))
+                    })?);
+                }
+                "skyline_mask" => {
+                    config.skyline_mask = Some(string_value(key, value).map_err(at)?.into())
+                }
+                "skyline_height" => config.skyline_height = number_value(key, value).map_err(at)?,
+                "skyline_lights" => config.skyline_lights = bool_value(key, value).map_err(at)?,
+                "stagger_events" => config.stagger_events = bool_value(key, value).map_err(at)?,
+                "span_outputs" => config.span_outputs = bool_value(key, value).map_err(at)?,
+                "stats_overlay" => config.stats_overlay = bool_value(key, value).map_err(at)?,
+                "quote_interval" => config.quote_interval = number_value(key, value).map_err(at)?,
+                "quote_anchor" => config.quote_anchor = point_value(key, value).map_err(at)?,
+                "sky_report_time" => {
+                    let time = string_value(key, value).map_err(at)?;
+                    config.sky_report_time = Some(schedule::time_of_day(&time).map_err(at)?);
+                }
+                "hook_min_interval" => {
+                    config.hook_min_interval = number_value(key, value).map_err(at)?
+                }
+                _ if key.starts_with("scene_schedule.") => {
+                    let output = &key["scene_schedule.".len()..];
+                    let schedule = string_value(key, value).map_err(at)?;
+                    let schedule = schedule
+                        .parse()
+                        .map_err(|err| at(format!("`{key}`: {err}")))?;
+                    config
+                        .output_scene_schedules
+                        .retain(|(name, _)| name != output);
+                    config
+                        .output_scene_schedules
+                        .push((output.to_string(), schedule));
+                }
+                _ => {
+                    let event = key
+                        .strip_prefix("on_")
+                        .and_then(|name| SkyEvent::ALL.into_iter().find(|e| e.name() == name))
+                        .ok_or_else(|| at(format!("unknown key `{key}`")))?;
+                    let command = string_value(key, value).map_err(at)?;
+                    config.hooks.retain(|(e, _)| *e != event);
+                    config.hooks.push((event, command));
+                }
+            }
+        }
+
+        if config.accessibility {
+            config.flash_limit = flash_limit.unwrap_or(ACCESSIBLE_FLASH_LIMIT);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    // The scene schedule for the output of this name, or for them all
+    pub fn scene_schedule_for(&self, output: Option<&str>) -> Option<Schedule> {
+        output
+            .and_then(|output| {
+                self.output_scene_schedules
+                    .iter()
+                    .find(|(name, _)| name == output)
+            })
+            .map(|(_, schedule)| schedule)
+            .or(self.scene_schedule.as_ref())
+            .cloned()
+    }
+
+    // Reject ranges the star spawner can't sample from
+    fn validate(&self) -> Result<(), String> {
+        if !(self.star_min_speed >= 0.0 && self.star_min_speed < self.star_max_speed) {
+            return Err("star_min_speed must be at least 0 and below star_max_speed".into());
+        }
+        if !(self.star_drag >= 0.0 && self.star_drag.is_finite()) {
+            return Err("star_drag must be 0 (none) or more".into());
+        }
+        if self.star_min_size == 0 || self.star_min_size > self.star_max_size {
+            return Err("star_min_size must be at least 1 and at most star_max_size".into());
+        }
+        if !self.shooting_star_gravity.is_finite() {
+            return Err("shooting_star_gravity must be a number".into());
+        }
+        if !(self.comets_per_hour >= 0.0 && self.comets_per_hour.is_finite()) {
+            return Err("comets_per_hour must be 0 (none) or more".into());
+        }
+        if !(self.satellites_per_hour >= 0.0 && self.satellites_per_hour.is_finite()) {
+            return Err("satellites_per_hour must be 0 (none) or more".into());
+        }
+        if !(0.0..=1.0).contains(&self.satellite_brightness) {
+            return Err("satellite_brightness must be between 0 and 1".into());
+        }
+        if self.planets > MAX_PLANETS {
+            return Err(format!("planets must be at most {MAX_PLANETS}"));
+        }
+        if !(0.0..=1.0).contains(&self.supernova_chance) {
+            return Err("supernova_chance must be between 0 (none) and 1".into());
+        }
+        if !(self.loudness_budget >= 0.0 && self.loudness_budget.is_finite()) {
+            return Err("loudness_budget must be 0 (no limit) or more".into());
+        }
+        if !(self.fps_cap >= 0.0 && self.fps_cap.is_finite()) {
+            return Err("fps_cap must be 0 (no limit) or more".into());
+        }
+        if self.spike_min_size == 0 {
+            return Err("spike_min_size must be at least 1".into());
+        }
+        if !(0.0..=20.0).contains(&self.spike_length) {
+            return Err("spike_length must be between 0 and 20".into());
+        }
+        if !(0.0..1.0).contains(&self.bloom_threshold) {
+            return Err("bloom_threshold must be at least 0 and under 1".into());
+        }
+        if !(0.0..=4.0).contains(&self.bloom_strength) {
+            return Err("bloom_strength must be between 0 and 4".into());
+        }
+        if !(self.trail_decay > 0.0 && self.trail_decay <= 1.0) {
+            return Err("trail_decay must be over 0 and at most 1".into());
+        }
+        if !(self.skyline_height > 0.0 && self.skyline_height <= 1.0) {
+            return Err("skyline_height must be over 0 and at most 1".into());
+        }
+        if !(self.flash_limit >= 0.0 && self.flash_limit.is_finite()) {
+            return Err("flash_limit must be 0 (no limit) or more".into());
+        }
+        if !(0.0..=1.0).contains(&self.haze) {
+            return Err("haze must be between 0 and 1".into());
+        }
+        if !(self.cloud_wind_speed.is_finite() && self.cloud_wind_direction.is_finite()) {
+            return Err("cloud_wind_speed and cloud_wind_direction must be numbers".into());
+        }
+        if !self.milky_way_angle.is_finite() {
+            return Err("milky_way_angle must be a number".into());
+        }
+        if !(0.0..=1.0).contains(&self.milky_way_brightness) {
+            return Err("milky_way_brightness must be between 0 and 1".into());
+        }
+        if self
+            .moon_phase
+            .is_some_and(|phase| !(0.0..=1.0).contains(&phase))
+        {
+            return Err("moon_phase must be between 0 and 1".into());
+        }
+        if !self.nebula_hue.is_finite() {
+            return Err("nebula_hue must be a number".into());
+        }
+        if !(0.0..=1.0).contains(&self.nebula_density) {
+            return Err("nebula_density must be between 0 and 1".into());
+        }
+        if !(self.nebula_drift_speed >= 0.0 && self.nebula_drift_speed.is_finite()) {
+            return Err("nebula_drift_speed must be 0 or more".into());
+        }
+        if self.drift_angle.is_some_and(|angle| !angle.is_finite()) {
+            return Err("drift_direction must be a number of degrees".into());
+        }
+        if !(self.camera_speed >= 0.0 && self.camera_speed <= 1.0) {
+            return Err("camera_speed must be between 0 and 1".into());
+        }
+        let (x, y) = self.warp_center;
+        if !((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)) {
+            return Err("warp_center must be between 0 and 1 across and down".into());
+        }
+        if !(self.orrery_days_per_second >= 0.0 && self.orrery_days_per_second.is_finite()) {
+            return Err("orrery_days_per_second must be 0 (real time) or more".into());
+        }
+        if !(self.scene_fade_seconds >= 0.0 && self.scene_fade_seconds.is_finite()) {
+            return Err("scene_fade_seconds must be 0 (no fade) or more".into());
+        }
+        if self.latitude.is_some() != self.longitude.is_some() {
+            return Err("latitude and longitude must be set together".into());
+        }
+        if self
+            .latitude
+            .is_some_and(|latitude| !(-90.0..=90.0).contains(&latitude))
+        {
+            return Err("latitude must be between -90 and 90".into());
+        }
+        if self
+            .longitude
+            .is_some_and(|longitude| !(-180.0..=180.0).contains(&longitude))
+        {
+            return Err("longitude must be between -180 and 180".into());
+        }
+        if !(self.exposure_seconds > 0.0 && self.exposure_seconds.is_finite()) {
+            return Err("exposure_seconds must be above 0".into());
+        }
+        if !(1..=16).contains(&self.pixel_scale) {
+            return Err("pixel_scale must be between 1 and 16".into());
+        }
+        if !(self.capture_scale > 0.0 && self.capture_scale <= 8.0) {
+            return Err("capture_scale must be above 0 and at most 8".into());
+        }
+        if !(self.transition_seconds >= 0.0 && self.transition_seconds <= 60.0) {
+            return Err("transition_seconds must be between 0 and 60".into());
+        }
+        if !(0.0..=1.0).contains(&self.accent_strength) {
+            return Err("accent_strength must be between 0 and 1".into());
+        }
+        if !(self.record_seconds > 0.0 && self.record_seconds <= 600.0) {
+            return Err("record_seconds must be above 0 and at most 600".into());
+        }
+        if !(1..=240).contains(&self.pipewire_fps) {
+            return Err("pipewire_fps must be between 1 and 240".into());
+        }
+        if self.gpu && !cfg!(feature = "gpu") {
+            return Err("gpu needs wl-starfield built with `--features gpu`".into());
+        }
+        if !(self.record_fps >= 1.0 && self.record_fps <= 60.0) {
+            return Err("record_fps must be between 1 and 60".into());
+        }
+        if !(self.record_scale > 0.0 && self.record_scale <= 1.0) {
+            return Err("record_scale must be above 0 and at most 1".into());
+        }
+        if !(self.breaths_per_minute > 0.0 && self.breaths_per_minute <= 60.0) {
+            return Err("breaths_per_minute must be above 0 and at most 60".into());
+        }
+        if !(self.hook_min_interval >= 0.0 && self.hook_min_interval.is_finite()) {
+            return Err("hook_min_interval must be 0 or more".into());
+        }
+        if !(self.quote_interval >= QUOTE_SHOW_TIME && self.quote_interval.is_finite()) {
+            return Err(format!(
+                "quote_interval must be at least {QUOTE_SHOW_TIME}, the seconds each quote shows"
+            ));
+        }
+        let (x, y) = self.quote_anchor;
+        if !((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)) {
+            return Err("quote_anchor must be between 0 and 1 across and down".into());
+        }
+        Ok(())
+    }
+}
+
+// The version a config file was written for, 1 if it doesn't say
+fn file_version(text: &str) -> Result<u32, String> {
+    for line in text.lines() {
+        if let Some((key, value)) = strip_comment(line).split_once('=')
+            && key.trim() == "version"
+        {
+            let version = number_value(key.trim(), value.trim())?;
+            if version == 0 {
+                return Err("`version` must be 1 or more".into());
+            }
+            return Ok(version);
+        }
+    }
+    Ok(1)
+}
+
+// Bring a file written for an older layout up to date, one version at a time,
+// leaving comments and untouched lines as they are. Gives the version it was
+// written for and the updated text, or None if it's current already.
+fn migrate(text: &str, migrations: &[Migration]) -> Result<Option<(u32, String)>, String> {
+    let current = migrations.len() as u32 + 1;
+    let old = file_version(text)?;
+    if old > current {
+        return Err(format!(
+            "written for a newer wl-starfield (version {old}, this one reads up to {current})"
+        ));
+    }
+    if old == current {
+        return Ok(None);
+    }
+    let steps = &migrations[old as usize - 1..];
+    let mut versioned = false;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let code = strip_comment(line);
+        // Whatever follows the value, spacing and all
+        let comment = &line[code.trim_end().len()..];
+        let Some((key, value)) = code.split_once('=') else {
+            lines.push(line.to_string());
+            continue;
+        };
+        let (mut key, mut value) = (key.trim().to_string(), value.trim().to_string());
+        if key == "version" {
+            lines.push(format!("version = {current}{comment}"));
+            versioned = true;
+            continue;
+        }
+        let mut changed = false;
+        for step in steps {
+            if let Some((new_key, new_value)) = step(&key, &value) {
+                (key, value, changed) = (new_key, new_value, true);
+            }
+        }
+        lines.push(match changed {
+            true => format!("{key} = {value}{comment}"),
+            false => line.to_string(),
+        });
+    }
+    if !versioned {
+        lines.insert(0, format!("version = {current}"));
+    }
+    Ok(Some((old, lines.join("\n") + "\n")))
+}
+
+// TOML numbers may use underscores as digit separators
+fn number_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("`{key}` must be a number, got `{value}`"))
+}
+
+// A point as a quoted pair of fractions, e.g. "0.5,0.5"
+fn point_value(key: &str, value: &str) -> Result<(f32, f32), String> {
+    string_value(key, value)?
+        .split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| format!("`{key}` must be like \"0.5,0.5\", got `{value}`"))
+}
+
+// A double-quoted TOML basic string, with \" and \\ escapes
+fn string_value(key: &str, value: &str) -> Result<String, String> {
+    let bad = || format!("`{key}` must be a \"quoted string\", got `{value}`");
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(bad)?;
+    let mut string = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('"' | '\\')) => string.push(escaped),
+                _ => return Err(bad()),
+            },
+            '"' => return Err(bad()),
+            c => string.push(c),
+        }
+    }
+    Ok(string)
+}
+
+// Everything before a `#` that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn bool_value(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("`{key}` must be true or false, got `{value}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_keys_and_keeps_defaults() {
+        let config = Config::parse(
+            "# denser sky\n\
+             star_count = 12_000\n\
+             star_max_speed = 40   # faster\n\
+             \n\
+             shooting_star_gravity = 0\n\
+             clouds = true\n\
+             interactive = false\n\
+             warp_center = \"0.25, 0.5\"\n\
+             drift_direction = \"down\"\n\
+             sky_report_time = \"21:30\"\n\
+             capture_dir = \"/tmp/sky\"\n\
+             accent = \"pywal:4\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                star_count: 12_000,
+                star_max_speed: 40.0,
+                shooting_star_gravity: 0.0,
+                clouds: true,
+                interactive: false,
+                warp_center: (0.25, 0.5),
+                drift_angle: Some(270.0),
+                sky_report_time: Some(21 * 3600 + 30 * 60),
+                capture_dir: Some("/tmp/sky".into()),
+                accent: Some(Accent::Pywal(4)),
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn the_accessibility_profile_limits_flashes_unless_told_otherwise() {
+        assert_eq!(Config::default().flash_limit, 0.0);
+        let gentle = Config::parse("accessibility = true").unwrap();
+        assert_eq!(gentle.flash_limit, ACCESSIBLE_FLASH_LIMIT);
+        // A limit of its own, before or after, wins
+        for text in [
+            "flash_limit = 0.5\naccessibility = true",
+            "accessibility = true\nflash_limit = 0.5",
+        ] {
+            assert_eq!(Config::parse(text).unwrap().flash_limit, 0.5);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(Config::parse("star_count = lots").is_err());
+        assert!(Config::parse("star_colour = 3").is_err());
+        assert!(Config::parse("star_count").is_err());
+        assert!(Config::parse("version = 2").is_err());
+        assert!(Config::parse("star_min_speed = 30").is_err());
+        assert!(Config::parse("star_drag = -1").is_err());
+        assert!(Config::parse("star_min_size = 0").is_err());
+        assert!(Config::parse("spike_min_size = 0").is_err());
+        assert!(Config::parse("spike_length = 50").is_err());
+        assert!(Config::parse("bloom_threshold = 1").is_err());
+        assert!(Config::parse("bloom_strength = -1").is_err());
+        assert!(Config::parse("trail_decay = 0").is_err());
+        assert!(Config::parse("flash_limit = -2").is_err());
+        assert!(Config::parse("accessibility = sometimes").is_err());
+        assert!(Config::parse("interactive = 0").is_err());
+        assert!(Config::parse("clouds = yes").is_err());
+        assert!(Config::parse("adaptive_detail = 1").is_err());
+        assert!(Config::parse("parallax = off").is_err());
+        assert!(Config::parse("capture_crop = \"square\"").is_err());
+        assert!(Config::parse("capture_scale = 0").is_err());
+        assert!(Config::parse("record_fps = 0").is_err());
+        assert!(Config::parse("accent = \"orange\"").is_err());
+        assert!(Config::parse("accent_strength = 2").is_err());
+        assert!(Config::parse("transition_seconds = -1").is_err());
+        assert_eq!(
+            Config::parse("pipewire_size = \"1280x720\"").map(|config| config.pipewire_size),
+            Ok(Some((1280, 720)))
+        );
+        assert!(Config::parse("pipewire_size = \"1280\"").is_err());
+        assert!(Config::parse("pipewire_fps = 0").is_err());
+        assert_eq!(Config::parse("gpu = true").is_ok(), cfg!(feature = "gpu"));
+        assert!(Config::parse("timeline = \"brightness 0->1 over 10s\"").is_ok());
+        assert!(Config::parse("timeline = \"brightness 0->1 over ages\"").is_err());
+        assert!(Config::parse("gradient = \"#000000\"").is_err());
+        assert!(Config::parse("background_gradient = \"navy, black\"").is_err());
+        assert!(Config::parse("skyline = \"volcano\"").is_err());
+        assert!(Config::parse("skyline_height = 0").is_err());
+        assert!(Config::parse("record_scale = 2").is_err());
+        assert!(Config::parse("pixel_scale = 0").is_err());
+        assert!(Config::parse("pixel_scale = 2.5").is_err());
+        assert!(Config::parse("breaths_per_minute = 0").is_err());
+        assert!(Config::parse("planets = 5").is_err());
+        assert!(Config::parse("supernova_chance = 2").is_err());
+        assert!(Config::parse("scene = \"solar_system\"").is_err());
+        assert!(Config::parse("star_colors = \"rainbow\"").is_err());
+        assert!(Config::parse("quality = \"ultra\"").is_err());
+        assert!(Config::parse("scene_schedule = \"every 10s\"").is_err());
+        assert!(Config::parse("scene_fade_seconds = -1").is_err());
+        assert!(Config::parse("warp_center = \"middle\"").is_err());
+        assert!(Config::parse("drift_direction = \"sideways\"").is_err());
+        assert!(Config::parse("drift_direction = nan").is_err());
+        assert!(Config::parse("camera_speed = 2").is_err());
+        assert!(Config::parse("sky_report_time = \"9pm\"").is_err());
+        assert!(Config::parse("warp_center = \"0.5,1.5\"").is_err());
+        assert!(Config::parse("quote_interval = 5").is_err());
+        assert!(Config::parse("quote_anchor = \"0.5\"").is_err());
+        assert!(Config::parse("quote_anchor = \"-0.1,0.5\"").is_err());
+        assert!(Config::parse("latitude = 52.2").is_err());
+        assert!(Config::parse("latitude = 95\nlongitude = 0").is_err());
+        assert!(Config::parse("on_theme_changed = \"true\"").is_err());
+        assert!(Config::parse("on_comet = notify-send").is_err());
+        assert!(Config::parse("on_comet = \"say \"hi\"\"").is_err());
+    }
+
+    #[test]
+    fn parses_event_hooks() {
+        let config = Config::parse(
+            r##"on_comet = "notify-send 'A comet!' \"#1\""  # tell me
+                on_focus_end = "paplay ding.oga"
+                hook_min_interval = 30"##,
+        )
+        .unwrap();
+        assert_eq!(
+            config.hooks,
+            [
+                (
+                    SkyEvent::Comet,
+                    r##"notify-send 'A comet!' "#1""##.to_string()
+                ),
+                (SkyEvent::FocusEnd, "paplay ding.oga".to_string()),
+            ]
+        );
+        assert_eq!(config.hook_min_interval, 30.0);
+
+        // A later line for the same event replaces the earlier one
+        let config = Config::parse("on_comet = \"a\"\non_comet = \"b\"").unwrap();
+        assert_eq!(config.hooks, [(SkyEvent::Comet, "b".to_string())]);
+    }
+
+    #[test]
+    fn picks_scene_schedules_by_output() {
+        let config = Config::parse(
+            "scene_schedule = \"every 2h\"\n\
+             scene_schedule.DP-1 = \"07:00 starfield, 19:30 tunnel\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.scene_schedule_for(Some("DP-1")),
+            Some(Schedule::Timetable(vec![
+                (7 * 3600, Scene::Starfield),
+                (19 * 3600 + 1800, Scene::Tunnel),
+            ]))
+        );
+        assert_eq!(
+            config.scene_schedule_for(Some("HDMI-A-1")),
+            Some(Schedule::Every(2 * 3600))
+        );
+        assert_eq!(config.scene_schedule_for(None), Some(Schedule::Every(7200)));
+    }
+
+    #[test]
+    fn default_file_holds_the_defaults_shown_in_the_readme() {
+        assert_eq!(Config::parse(DEFAULT_FILE), Ok(Config::default()));
+        // Source trees trimmed for packaging leave the README out
+        let Ok(readme) = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))
+        else {
+            return;
+        };
+        let shown = readme
+            .split("```toml\n")
+            .nth(1)
+            .and_then(|block| block.split("```").next())
+            .unwrap();
+        assert!(DEFAULT_FILE.contains(shown));
+    }
+
+    #[test]
+    fn migrates_old_files_a_version_at_a_time() {
+        // Version 2 renamed `stars`; version 3 took the frame rate cap from a
+        // frame time in milliseconds to frames a second
+        let migrations: [Migration; 2] = [
+            |key, value| (key == "stars").then(|| ("star_count".into(), value.into())),
+            |key, value| {
+                let ms: f32 = value.parse().ok().filter(|_| key == "frame_ms")?;
+                Some(("fps_cap".into(), (1000.0 / ms).to_string()))
+            },
+        ];
+        let old = "# my sky\nstars = 800  # sparse\nframe_ms = 20\nclouds = true\n";
+        assert_eq!(
+            migrate(old, &migrations),
+            Ok(Some((
+                1,
+                "version = 3\n# my sky\nstar_count = 800  # sparse\nfps_cap = 50\n\
+                 clouds = true\n"
+                    .into()
+            )))
+        );
+        let from_2 = "version = 2 # keep me\nframe_ms = 40\nstars = 5\n";
+        assert_eq!(
+            migrate(from_2, &migrations),
+            Ok(Some((
+                2,
+                "version = 3 # keep me\nfps_cap = 25\nstars = 5\n".into()
+            )))
+        );
+        assert_eq!(migrate("version = 3\nstars = 5", &migrations), Ok(None));
+        assert!(migrate("version = 4", &migrations).is_err());
+        assert!(migrate("version = 0", &migrations).is_err());
+        assert_eq!(migrate("star_count = 5", &MIGRATIONS), Ok(None));
+    }
+}
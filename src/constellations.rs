@@ -0,0 +1,297 @@
+// Constellation overlay: faint stick figures joining groups of the brightest
+// stars. The sky is made up as it goes, so there's no fixed catalog to draw
+// them from; figures are found among the near, bright stars instead. Each is
+// a handful that lie close together and drift at nearly the same speed, so
+// they keep their shape for a while, joined by the shortest lines that link
+// them all. A figure fades away once its stars have drifted too far apart,
+// ends at once if one of them wraps around, and new ones form from whatever
+// bright stars are free.
+
+use crate::ScreenDetails;
+use crate::render::Target;
+use crate::stars::Stars;
+
+// Stars nearer than this are bright enough to be in a figure
+const BRIGHT_DEPTH: f32 = 0.7;
+// Stars joined in a figure are at most LINK_RADIUS pixels apart, and drift
+// within DRIFT_MATCH pixels a second of the first one
+const LINK_RADIUS: f32 = 140.0;
+const DRIFT_MATCH: f32 = 1.5;
+const MIN_STARS: usize = 4;
+const MAX_STARS: usize = 7;
+// About one figure for every this many square pixels of sky
+const SKY_PER_FIGURE: f32 = 250_000.0;
+// A figure breaks up once any of its lines has stretched this much
+const MAX_STRETCH: f32 = 1.4;
+// Seconds between looks for new figures, and to fade one (or the whole
+// overlay) in or out
+const FORM_INTERVAL: f32 = 2.0;
+const FADE_TIME: f32 = 2.0;
+const LINE_COLOR: (u8, u8, u8) = (140, 170, 230);
+const LINE_ALPHA: f32 = 0.25;
+// Lines stop this many pixels short of their stars
+const STAR_GAP: f32 = 5.0;
+
+struct Figure {
+    // Member stars by index and seed, and where their centers are now
+    stars: Vec<(usize, u64)>,
+    points: Vec<(f32, f32)>,
+    // Lines as pairs of members, with each one's length when it formed
+    lines: Vec<(usize, usize, f32)>,
+    // 0..1, rising as it forms and falling once it breaks up
+    opacity: f32,
+    breaking: bool,
+}
+
+impl Figure {
+    // Follow the member stars, fading in, or out once it breaks up
+    fn update(&mut self, fade: f32, stars: &Stars) {
+        for (point, &(i, seed)) in self.points.iter_mut().zip(&self.stars) {
+            match stars.seed.get(i) {
+                Some(&now) if now == seed => *point = stars.center(i),
+                _ => {
+                    self.breaking = true;
+                    self.opacity = 0.0;
+                    return;
+                }
+            }
+        }
+        let points = &self.points;
+        self.breaking |= self
+            .lines
+            .iter()
+            .any(|&(a, b, length)| distance(points[a], points[b]) > length * MAX_STRETCH);
+        let fade = if self.breaking { -fade } else { fade };
+        self.opacity = (self.opacity + fade).clamp(0.0, 1.0);
+    }
+
+    fn is_gone(&self) -> bool {
+        self.breaking && self.opacity <= 0.0
+    }
+}
+
+pub struct Constellations {
+    pub enabled: bool,
+    // How far the overlay as a whole is shown, 0..1, easing after `enabled`
+    shown: f32,
+    figures: Vec<Figure>,
+    form_in: f32,
+}
+
+impl Constellations {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            shown: 0.0,
+            figures: Vec::new(),
+            form_in: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, stars: &Stars, screen_details: &ScreenDetails) {
+        let fade = dt / FADE_TIME;
+        self.shown = if self.enabled {
+            (self.shown + fade).min(1.0)
+        } else {
+            (self.shown - fade).max(0.0)
+        };
+        // Nothing is kept while it's hidden
+        if self.shown <= 0.0 {
+            self.figures.clear();
+            self.form_in = 0.0;
+            return;
+        }
+
+        for figure in &mut self.figures {
+            figure.update(fade, stars);
+        }
+        self.figures.retain(|figure| !figure.is_gone());
+        self.form_in -= dt;
+        if self.form_in <= 0.0 {
+            self.form_in = FORM_INTERVAL;
+            self.form(stars, screen_details);
+        }
+    }
+
+    // Make new figures from bright stars that aren't in one yet, until the
+    // sky has its share of them
+    fn form(&mut self, stars: &Stars, screen_details: &ScreenDetails) {
+        let area = screen_details.width as f32 * screen_details.height as f32;
+        let wanted = (area / SKY_PER_FIGURE).round().max(1.0) as usize;
+        if self.figures.len() >= wanted {
+            return;
+        }
+        let taken = |i: usize| {
+            self.figures
+                .iter()
+                .any(|figure| figure.stars.iter().any(|&(j, _)| j == i))
+        };
+        // Stars still flying out to their places don't count yet
+        let free: Vec<usize> = (0..stars.len())
+            .filter(|&i| {
+                stars.depth[i] < BRIGHT_DEPTH && stars.intro_progress[i] >= 1.0 && !taken(i)
+            })
+            .collect();
+        let drift = |i: usize| stars.cruise_speed[free[i]] * stars.depth[free[i]];
+        let center = |i: usize| stars.center(free[i]);
+
+        let mut used = vec![false; free.len()];
+        for root in 0..free.len() {
+            if self.figures.len() >= wanted {
+                break;
+            }
+            if used[root] {
+                continue;
+            }
+            // Grow out from the root, each time joining on the free star
+            // nearest to any member, which makes the lines a minimum
+            // spanning tree
+            let mut members = vec![root];
+            let mut lines = Vec::new();
+            while members.len() < MAX_STARS {
+                let nearest = (0..free.len())
+                    .filter(|&i| !used[i] && !members.contains(&i))
+                    .filter(|&i| (drift(i) - drift(root)).abs() <= DRIFT_MATCH)
+                    .flat_map(|i| {
+                        members
+                            .iter()
+                            .enumerate()
+                            .map(move |(m, &member)| (i, m, distance(center(member), center(i))))
+                    })
+                    .filter(|&(_, _, length)| length <= LINK_RADIUS)
+                    .min_by(|a, b| a.2.total_cmp(&b.2));
+                let Some((i, m, length)) = nearest else {
+                    break;
+                };
+                lines.push((m, members.len(), length));
+                members.push(i);
+            }
+            if members.len() < MIN_STARS {
+                continue;
+            }
+            for &i in &members {
+                used[i] = true;
+            }
+            self.figures.push(Figure {
+                stars: members
+                    .iter()
+                    .map(|&i| (free[i], stars.seed[free[i]]))
+                    .collect(),
+                points: members.iter().map(|&i| center(i)).collect(),
+                lines,
+                opacity: 0.0,
+                breaking: false,
+            });
+        }
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        for figure in &self.figures {
+            let alpha = LINE_ALPHA * self.shown * figure.opacity;
+            if alpha <= 0.0 {
+                continue;
+            }
+            for &(a, b, _) in &figure.lines {
+                let (from, to) = (figure.points[a], figure.points[b]);
+                let length = distance(from, to);
+                if length <= 2.0 * STAR_GAP {
+                    continue;
+                }
+                let gap = (
+                    (to.0 - from.0) / length * STAR_GAP,
+                    (to.1 - from.1) / length * STAR_GAP,
+                );
+                target.line(
+                    (from.0 + gap.0, from.1 + gap.1),
+                    (to.0 - gap.0, to.1 - gap.1),
+                    LINE_COLOR,
+                    alpha,
+                );
+            }
+        }
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, MockScreen};
+
+    // Five near stars in a loose row drifting together, and one far too fast
+    // to stay with them
+    fn cluster(screen: &mut MockScreen) -> Stars {
+        let (width, height) = (screen.details.width, screen.details.height);
+        let mut stars = Stars::default();
+        for (i, (x, y)) in [
+            (400.0, 300.0),
+            (480.0, 320.0),
+            (560.0, 300.0),
+            (640.0, 340.0),
+            (700.0, 300.0),
+        ]
+        .into_iter()
+        .chain([(520.0, 360.0)])
+        .enumerate()
+        {
+            stars.spawn(&mut screen.rng, &screen.config, width, height);
+            (stars.x[i], stars.y[i]) = (x, y);
+            stars.depth[i] = 0.6;
+            stars.size[i] = 2;
+            stars.cruise_speed[i] = if i == 5 { 20.0 } else { 10.0 };
+            stars.speed[i] = stars.cruise_speed[i];
+        }
+        stars
+    }
+
+    #[test]
+    fn figures_join_close_stars_drifting_together() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = cluster(&mut screen);
+        let mut constellations = Constellations::new(true);
+        for dt in FixedClock::fps(30.0).ticks(FADE_TIME) {
+            constellations.update(dt, &stars, &screen.details);
+        }
+        assert_eq!(constellations.figures.len(), 1);
+        let figure = &constellations.figures[0];
+        assert_eq!(figure.stars.len(), 5);
+        assert_eq!(figure.lines.len(), 4);
+
+        // A faint line runs between the first two stars, but not onto them
+        let mut frame = Frame::new(&screen.details);
+        constellations.draw(&mut frame.target());
+        let (from, to) = (stars.center(0), stars.center(1));
+        let middle = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+        let lit = (-1..=1)
+            .map(|dy| frame.brightness(middle.0 as u32, (middle.1 as i32 + dy) as u32))
+            .max()
+            .unwrap();
+        assert!((20..100).contains(&lit), "{lit}");
+        frame.assert_dark(from.0 as u32, from.1 as u32);
+
+        // It ends with a star that wraps around as another, and the rest are
+        // too far apart to make one without it
+        stars.seed[2] ^= 1;
+        stars.x[2] = 1900.0;
+        constellations.update(0.1, &stars, &screen.details);
+        assert!(constellations.figures.is_empty());
+    }
+
+    #[test]
+    fn hidden_constellations_fade_out_and_forget_their_figures() {
+        let mut screen = MockScreen::landscape();
+        let stars = cluster(&mut screen);
+        let mut constellations = Constellations::new(true);
+        for dt in FixedClock::fps(30.0).ticks(FADE_TIME) {
+            constellations.update(dt, &stars, &screen.details);
+        }
+        constellations.enabled = false;
+        constellations.update(FADE_TIME / 2.0, &stars, &screen.details);
+        assert_eq!(constellations.figures.len(), 1);
+        constellations.update(FADE_TIME / 2.0, &stars, &screen.details);
+        assert!(constellations.figures.is_empty());
+    }
+}
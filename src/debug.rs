@@ -0,0 +1,184 @@
+// Debug overlays for working on effects: bounding boxes and velocity vectors
+// for each kind of object, plus where new objects come from and how long
+// until they may. Each kind can be switched on separately, since the star
+// overlay alone covers the screen.
+
+use crate::render::{Rect, Target, blend};
+use crate::text;
+use std::str::FromStr;
+
+// Velocity vectors show how far an object will travel in this many seconds
+const VECTOR_TIME: f32 = 0.5;
+const PANEL_SCALE: i32 = 1;
+const PANEL_PADDING: i32 = 6;
+
+pub(crate) const STAR_COLOR: (u8, u8, u8) = (90, 200, 255);
+pub(crate) const SHOOTING_STAR_COLOR: (u8, u8, u8) = (255, 170, 60);
+pub(crate) const COMET_COLOR: (u8, u8, u8) = (120, 255, 140);
+pub(crate) const SATELLITE_COLOR: (u8, u8, u8) = (230, 230, 90);
+const SPAWN_COLOR: (u8, u8, u8) = (255, 80, 160);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DebugOverlays {
+    pub stars: bool,
+    pub shooting_stars: bool,
+    pub comet: bool,
+    pub satellites: bool,
+    // Spawn zones, spawn rates and cooldown timers
+    pub spawning: bool,
+    // A heat map of star density and light in place of the sky
+    pub heatmap: bool,
+}
+
+impl DebugOverlays {
+    pub fn any(&self) -> bool {
+        self.stars
+            || self.shooting_stars
+            || self.comet
+            || self.satellites
+            || self.spawning
+            || self.heatmap
+    }
+}
+
+// A comma-separated list of overlays, e.g. "stars,comet", or "all" for every
+// one drawn over the sky
+impl FromStr for DebugOverlays {
+    type Err = String;
+
+    fn from_str(list: &str) -> Result<Self, String> {
+        let mut overlays = Self::default();
+        for name in list.split(',').map(str::trim) {
+            match name {
+                "stars" => overlays.stars = true,
+                "shooting-stars" => overlays.shooting_stars = true,
+                "comet" => overlays.comet = true,
+                "satellites" => overlays.satellites = true,
+                "spawning" => overlays.spawning = true,
+                "heatmap" => overlays.heatmap = true,
+                "all" => {
+                    overlays = Self {
+                        stars: true,
+                        shooting_stars: true,
+                        comet: true,
+                        satellites: true,
+                        spawning: true,
+                        heatmap: overlays.heatmap,
+                    }
+                }
+                _ => return Err(format!("unknown debug overlay `{name}`")),
+            }
+        }
+        Ok(overlays)
+    }
+}
+
+// An object's bounding box, and a line from `position` showing where
+// `velocity` takes it
+pub(crate) fn mark(
+    target: &mut Target,
+    bounds: Rect,
+    (position, velocity): ((f32, f32), (f32, f32)),
+    color: (u8, u8, u8),
+) {
+    outline(target, bounds, color);
+    let (x, y) = position;
+    let end = (x + velocity.0 * VECTOR_TIME, y + velocity.1 * VECTOR_TIME);
+    line(target, position, end, color);
+}
+
+fn outline(target: &mut Target, rect: Rect, color: (u8, u8, u8)) {
+    if !target.overlaps(rect) {
+        return;
+    }
+    for (y, left, row) in target.span_rows(rect) {
+        let edge_row = y == rect.y0 || y == rect.y1 - 1;
+        for (x, pixel) in (left..).zip(row) {
+            if edge_row || x == rect.x0 || x == rect.x1 - 1 {
+                blend(pixel, color, 0.8);
+            }
+        }
+    }
+}
+
+fn line(target: &mut Target, from: (f32, f32), to: (f32, f32), color: (u8, u8, u8)) {
+    let reach = Rect::new(
+        from.0.min(to.0).floor() as i32,
+        from.1.min(to.1).floor() as i32,
+        from.0.max(to.0).ceil() as i32 + 1,
+        from.1.max(to.1).ceil() as i32 + 1,
+    );
+    if !target.overlaps(reach) {
+        return;
+    }
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        target.blend(x as i32, y as i32, color, 0.8);
+    }
+}
+
+// Shaded area where new objects appear
+pub(crate) fn zone(target: &mut Target, rect: Rect) {
+    if !target.overlaps(rect) {
+        return;
+    }
+    for (_, _, row) in target.span_rows(rect) {
+        for pixel in row {
+            blend(pixel, SPAWN_COLOR, 0.3);
+        }
+    }
+    outline(target, rect, SPAWN_COLOR);
+}
+
+// Translucent box of text in the top-left corner
+pub(crate) fn panel<S: AsRef<str>>(target: &mut Target, lines: &[S]) {
+    let (w, h) = text::measure(lines, PANEL_SCALE);
+    let rect = Rect::new(0, 0, w + PANEL_PADDING * 2, h + PANEL_PADDING * 2);
+    if !target.overlaps(rect) {
+        return;
+    }
+    for (_, _, row) in target.span_rows(rect) {
+        for pixel in row {
+            blend(pixel, (0, 0, 0), 0.7);
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let y = PANEL_PADDING + i as i32 * text::line_height(PANEL_SCALE);
+        text::draw_line(
+            target,
+            PANEL_PADDING,
+            y,
+            line.as_ref(),
+            SPAWN_COLOR,
+            1.0,
+            PANEL_SCALE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_overlay_lists() {
+        assert_eq!(
+            "stars, comet".parse(),
+            Ok(DebugOverlays {
+                stars: true,
+                comet: true,
+                ..DebugOverlays::default()
+            })
+        );
+        let all: DebugOverlays = "all".parse().unwrap();
+        assert!(all.stars && all.shooting_stars && all.comet && all.satellites && all.spawning);
+        // The heat map hides the sky, so it's only on when asked for
+        assert!(!all.heatmap);
+        assert!("all,heatmap".parse::<DebugOverlays>().unwrap().heatmap);
+        assert!("stars,planets".parse::<DebugOverlays>().is_err());
+        assert!("".parse::<DebugOverlays>().is_err());
+    }
+}
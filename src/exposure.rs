@@ -0,0 +1,294 @@
+// Long-exposure "astro photo" capture. Rendered frames are added to a float
+// buffer weighted by how long each stayed on screen, then developed through a
+// saturating film response, so drifting stars draw trails and meteors leave
+// streaks however fast frames were coming. A capture can keep just part of
+// the screen, and be scaled up or down as it's developed.
+
+use crate::ScreenDetails;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// How quickly light builds up: a full-brightness pixel lit for 1/GAIN
+// seconds develops to about 63% white
+const GAIN: f32 = 3.0;
+
+// The part of the screen a capture keeps: `width` x `height` pixels with
+// their top left at `position`, or in the middle of the screen without one.
+// Written `WxH` or `WxH+X+Y`, like an X11 geometry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    pub width: u32,
+    pub height: u32,
+    pub position: Option<(u32, u32)>,
+}
+
+impl FromStr for Crop {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, ()> {
+        let number = |text: &str| text.parse::<u32>().map_err(drop);
+        let (size, position) = match text.split_once('+') {
+            Some((size, position)) => {
+                let (x, y) = position.split_once('+').ok_or(())?;
+                (size, Some((number(x)?, number(y)?)))
+            }
+            None => (text, None),
+        };
+        let (width, height) = size.split_once('x').ok_or(())?;
+        let (width, height) = (number(width)?, number(height)?);
+        if width == 0 || height == 0 {
+            return Err(());
+        }
+        Ok(Self {
+            width,
+            height,
+            position,
+        })
+    }
+}
+
+pub struct Exposure {
+    screen_details: ScreenDetails,
+    // Top left and size of the part of the screen being gathered
+    left: usize,
+    top: usize,
+    region: ScreenDetails,
+    // How much to scale the region by when developing it
+    scale: f32,
+    // Light gathered per pixel of the region, RGB, in brightness x seconds
+    light: Vec<f32>,
+    remaining: f32,
+}
+
+impl Exposure {
+    pub fn new(
+        screen_details: ScreenDetails,
+        seconds: f32,
+        crop: Option<Crop>,
+        scale: f32,
+    ) -> Self {
+        // A crop that doesn't fit is pulled back onto the screen
+        let (width, height) = match crop {
+            Some(crop) => (
+                crop.width.min(screen_details.width),
+                crop.height.min(screen_details.height),
+            ),
+            None => (screen_details.width, screen_details.height),
+        };
+        let (left, top) = crop.and_then(|crop| crop.position).unwrap_or((
+            (screen_details.width - width) / 2,
+            (screen_details.height - height) / 2,
+        ));
+        let region = ScreenDetails { width, height };
+        Self {
+            screen_details,
+            left: left.min(screen_details.width - width) as usize,
+            top: top.min(screen_details.height - height) as usize,
+            region,
+            scale,
+            light: vec![0.0; width as usize * height as usize * 3],
+            remaining: seconds,
+        }
+    }
+
+    // The screen being captured
+    pub fn screen_details(&self) -> ScreenDetails {
+        self.screen_details
+    }
+
+    // The size of the finished picture
+    pub fn size(&self) -> ScreenDetails {
+        let scaled = |pixels: u32| ((pixels as f32 * self.scale).round() as u32).max(1);
+        ScreenDetails {
+            width: scaled(self.region.width),
+            height: scaled(self.region.height),
+        }
+    }
+
+    // Add an RGBA8 frame of the whole screen that was on screen for `dt`
+    // seconds. Returns true once the exposure has gathered all the time it
+    // was opened for.
+    pub fn add(&mut self, frame: &[u8], dt: f32) -> bool {
+        let dt = dt.min(self.remaining);
+        let stride = self.screen_details.width as usize * 4;
+        let width = self.region.width as usize;
+        for (row, light) in self.light.chunks_exact_mut(width * 3).enumerate() {
+            let start = (self.top + row) * stride + self.left * 4;
+            let pixels = &frame[start..start + width * 4];
+            for (light, pixel) in light.chunks_exact_mut(3).zip(pixels.chunks_exact(4)) {
+                for (light, &value) in light.iter_mut().zip(pixel) {
+                    *light += value as f32 / 255.0 * dt;
+                }
+            }
+        }
+        self.remaining -= dt;
+        self.remaining <= 0.0
+    }
+
+    // The finished picture as RGBA8, `size()` pixels across. Scaling down
+    // averages the light that fell on each picture pixel; scaling up repeats
+    // pixels.
+    pub fn develop(&self) -> Vec<u8> {
+        let size = self.size();
+        let (width, height) = (self.region.width as usize, self.region.height as usize);
+        // The region pixels from `i` of `n` picture pixels across `across`
+        let span = |i: usize, n: usize, across: usize| {
+            let start = i * across / n;
+            start..((i + 1) * across / n).max(start + 1)
+        };
+        let mut image = Vec::with_capacity(size.width as usize * size.height as usize * 4);
+        for y in 0..size.height as usize {
+            let rows = span(y, size.height as usize, height);
+            for x in 0..size.width as usize {
+                let columns = span(x, size.width as usize, width);
+                let mut sum = [0.0; 3];
+                for row in rows.clone() {
+                    let light =
+                        &self.light[(row * width + columns.start) * 3..][..columns.len() * 3];
+                    for pixel in light.chunks_exact(3) {
+                        for (sum, &channel) in sum.iter_mut().zip(pixel) {
+                            *sum += channel;
+                        }
+                    }
+                }
+                let count = (rows.len() * columns.len()) as f32;
+                for channel in sum {
+                    let channel = channel / count;
+                    image.push(((1.0 - (-channel * GAIN).exp()) * 255.0).round() as u8);
+                }
+                image.push(255);
+            }
+        }
+        image
+    }
+}
+
+// A fresh file name starting with `name`, in `dir` if given, otherwise in
+// $XDG_PICTURES_DIR, ~/Pictures, or failing those the working directory
+pub fn capture_path(dir: Option<&Path>, name: &str) -> PathBuf {
+    let dir = dir.map(Path::to_path_buf).unwrap_or_else(|| {
+        env::var_os("XDG_PICTURES_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join("Pictures")))
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_default()
+    });
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    dir.join(format!("{name}-{stamp}.png"))
+}
+
+pub fn save_png(path: &Path, screen_details: ScreenDetails, rgba: &[u8]) -> io::Result<()> {
+    write_png(BufWriter::new(File::create(path)?), screen_details, rgba)
+}
+
+pub fn write_png(out: impl Write, screen_details: ScreenDetails, rgba: &[u8]) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(out, screen_details.width, screen_details.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: ScreenDetails = ScreenDetails {
+        width: 40,
+        height: 1,
+    };
+
+    // A white dot sliding right at `speed` pixels per second
+    fn expose(fps: usize, speed: usize) -> Vec<u8> {
+        let mut exposure = Exposure::new(SCREEN, 2.0, None, 1.0);
+        let mut frame = vec![0; 40 * 4];
+        for i in 0.. {
+            frame.fill(0);
+            let x = (i * speed / fps).min(39);
+            frame[x * 4..x * 4 + 4].fill(255);
+            if exposure.add(&frame, 1.0 / fps as f32) {
+                break;
+            }
+        }
+        exposure.develop()
+    }
+
+    #[test]
+    fn moving_lights_leave_trails_whatever_the_frame_rate() {
+        let image = expose(60, 10);
+        // Twenty pixels of trail, none of the untouched sky
+        let lit = image.chunks(4).filter(|px| px[0] > 40).count();
+        assert_eq!(lit, 20);
+        assert_eq!(image[25 * 4], 0);
+
+        let slow = expose(20, 10);
+        for (a, b) in image.iter().zip(&slow) {
+            assert!(a.abs_diff(*b) <= 2, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn steady_light_saturates() {
+        let mut exposure = Exposure::new(SCREEN, 5.0, None, 1.0);
+        assert!(exposure.add(&[255; 40 * 4], 5.0));
+        assert!(exposure.develop().iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn crops_keep_their_part_of_the_screen_scaled() {
+        assert_eq!(
+            "4x2+1+1".parse(),
+            Ok(Crop {
+                width: 4,
+                height: 2,
+                position: Some((1, 1)),
+            })
+        );
+        assert_eq!("0x2".parse::<Crop>(), Err(()));
+        assert_eq!("4x2+1".parse::<Crop>(), Err(()));
+
+        // A 6x4 screen lit only in the crop's left half
+        let screen = ScreenDetails {
+            width: 6,
+            height: 4,
+        };
+        let mut frame = vec![0; 6 * 4 * 4];
+        for y in 1..3 {
+            frame[(y * 6 + 1) * 4..(y * 6 + 3) * 4].fill(255);
+        }
+        let crop = "4x2+1+1".parse().ok();
+        let mut exposure = Exposure::new(screen, 1.0, crop, 0.5);
+        assert!(exposure.add(&frame, 1.0));
+        assert_eq!(
+            exposure.size(),
+            ScreenDetails {
+                width: 2,
+                height: 1
+            }
+        );
+        let image = exposure.develop();
+        assert_eq!(image.len(), 2 * 4);
+        assert!(image[0] > 240);
+        assert_eq!(image[4], 0);
+
+        // Too big a crop is the whole screen, whatever its position
+        let crop = Some(Crop {
+            width: 10,
+            height: 10,
+            position: Some((3, 3)),
+        });
+        let mut exposure = Exposure::new(screen, 1.0, crop, 1.0);
+        exposure.add(&frame, 1.0);
+        assert_eq!(exposure.size(), screen);
+        assert!(exposure.develop()[(6 + 1) * 4] > 240);
+    }
+}
@@ -0,0 +1,173 @@
+// The flash limiter, for anyone sensitive to flashing light: no part of the
+// screen may get brighter or darker faster than `flash_limit` a second, as a
+// share of the whole way from black to white. Each block of BLOCK_SIZE pixels
+// square is compared with what was last shown there, and where its mean
+// brightness has changed by more than the time since allows, the block is
+// held partway back toward the last frame. A supernova or a sparkle swells
+// and fades rather than flashing, and the limit holds whatever caused the
+// change.
+
+use crate::render::{Pixel, Rect, Target, blend, luma};
+
+// Small enough that a flash a few pixels across shows in its block's mean,
+// and big enough that a star moving about inside one doesn't
+const BLOCK_SIZE: i32 = 8;
+
+pub(crate) struct FlashLimiter {
+    // Most a tile's brightness, 0..1, may change by in a second
+    limit: f32,
+    // Seconds since the last frame was shown
+    elapsed: f32,
+    // The last frame as it was shown
+    previous: Vec<Pixel>,
+}
+
+impl FlashLimiter {
+    pub fn new(limit: f32) -> Self {
+        Self {
+            limit,
+            elapsed: 0.0,
+            previous: Vec::new(),
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    // Hold back each block of the part of a frame covering `view` that the
+    // target holds, if it has changed too fast since the last
+    pub fn apply(&self, target: &mut Target, view: Rect) {
+        let width = (view.x1 - view.x0) as usize;
+        if self.previous.len() != width * (view.y1 - view.y0) as usize {
+            return;
+        }
+        let allowed = self.limit * self.elapsed;
+        let clip = target.clip();
+        // Blocks are laid out from the corner of the view, so they're the
+        // same whichever tile they're drawn in
+        let first = |from: i32, origin: i32| from - (from - origin).rem_euclid(BLOCK_SIZE);
+        for top in (first(clip.y0, view.y0)..clip.y1).step_by(BLOCK_SIZE as usize) {
+            for left in (first(clip.x0, view.x0)..clip.x1).step_by(BLOCK_SIZE as usize) {
+                let block = Rect::new(left, top, left + BLOCK_SIZE, top + BLOCK_SIZE);
+                let last = |y: i32, x: i32| {
+                    &self.previous[(y - view.y0) as usize * width + (x - view.x0) as usize..]
+                };
+                let (mut now, mut before, mut count) = (0.0, 0.0, 0);
+                for (y, x, row) in target.span_rows(block) {
+                    for (&pixel, &last) in row.iter().zip(last(y, x)) {
+                        now += luma(pixel);
+                        before += luma(last);
+                    }
+                    count += row.len();
+                }
+                let change = (now - before).abs() / (count.max(1) as f32 * 255.0);
+                if change <= allowed {
+                    continue;
+                }
+                let hold = 1.0 - allowed / change;
+                for (y, x, row) in target.span_rows(block) {
+                    for (pixel, &[r, g, b, _]) in row.iter_mut().zip(last(y, x)) {
+                        blend(pixel, (r, g, b), hold);
+                    }
+                }
+            }
+        }
+    }
+
+    // Keep the frame as shown to hold the next to
+    pub fn keep(&mut self, pixels: &[Pixel]) {
+        self.previous.clear();
+        self.previous.extend_from_slice(pixels);
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render;
+    use crate::workers::Workers;
+
+    #[test]
+    fn a_sudden_flash_swells_and_fades_at_the_limit() {
+        let view = Rect::new(0, 0, 256, 128);
+        let mut frame = vec![0; 256 * 128 * 4];
+        let workers = Workers::new(2);
+        // A white square the size of a tile comes and goes, a tenth of a
+        // second a frame
+        let mut show = |limiter: &mut FlashLimiter, lit: bool| {
+            for (i, pixel) in frame.as_chunks_mut::<4>().0.iter_mut().enumerate() {
+                let (x, y) = (i % 256, i / 256);
+                let on = lit && (64..128).contains(&x) && y < 64;
+                *pixel = if on { [255; 4] } else { [0, 0, 0, 255] };
+            }
+            render::for_each_tile(&mut frame, view, &workers, |_, target| {
+                limiter.apply(target, view)
+            });
+            let (pixels, _) = frame.as_chunks::<4>();
+            limiter.keep(pixels);
+            limiter.advance(0.1);
+            (pixels[96][0], pixels[0][0])
+        };
+
+        let mut limiter = FlashLimiter::new(1.0);
+        show(&mut limiter, false);
+        let rising: Vec<_> = (0..12).map(|_| show(&mut limiter, true).0).collect();
+        assert!((20..30).contains(&rising[0]), "{rising:?}");
+        assert!(
+            rising
+                .windows(2)
+                .all(|pair| pair[0] < pair[1] || pair[1] == 255)
+        );
+        assert_eq!(rising[11], 255);
+        // Tiles around it are left alone
+        assert_eq!(show(&mut limiter, true).1, 0);
+        let (falling, _) = show(&mut limiter, false);
+        assert!((225..235).contains(&falling), "{falling}");
+
+        // Slow enough, and it's left alone
+        let mut limiter = FlashLimiter::new(20.0);
+        show(&mut limiter, false);
+        assert_eq!(show(&mut limiter, true).0, 255);
+    }
+
+    #[test]
+    fn even_a_small_flash_is_slowed() {
+        let view = Rect::new(0, 0, 64, 64);
+        let mut frame = vec![0; 64 * 64 * 4];
+        let workers = Workers::new(1);
+        // A white dot three pixels square, and a star somewhere else, at 60
+        // frames a second
+        let mut show = |limiter: &mut FlashLimiter, flash: bool, star: usize| {
+            for (i, pixel) in frame.as_chunks_mut::<4>().0.iter_mut().enumerate() {
+                let (x, y) = (i % 64, i / 64);
+                let on = flash && (33..36).contains(&x) && (33..36).contains(&y);
+                *pixel = if on || (x, y) == (star, 3) {
+                    [255; 4]
+                } else {
+                    [0, 0, 0, 255]
+                };
+            }
+            render::for_each_tile(&mut frame, view, &workers, |_, target| {
+                limiter.apply(target, view)
+            });
+            let (pixels, _) = frame.as_chunks::<4>();
+            limiter.keep(pixels);
+            limiter.advance(1.0 / 60.0);
+            (pixels[34 * 64 + 34][0], pixels[3 * 64 + star][0])
+        };
+
+        let mut limiter = FlashLimiter::new(1.0);
+        show(&mut limiter, false, 1);
+        let rising: Vec<_> = (0..60).map(|_| show(&mut limiter, true, 1).0).collect();
+        // Over several frames, not all at once
+        assert!(rising[0] < 64 && rising[5] < 255, "{rising:?}");
+        assert!(rising.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(rising[59], 255);
+
+        // A star moving within its block isn't held back
+        assert_eq!(show(&mut limiter, true, 2).1, 255);
+        assert_eq!(show(&mut limiter, true, 3).1, 255);
+    }
+}
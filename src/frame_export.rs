@@ -0,0 +1,143 @@
+// Sharing frames as they're drawn, so OBS and other tools can take the sky as
+// a source without capturing the whole desktop. With `frame_export` set,
+// every frame of the first output is written over the last in a file, best
+// kept on a RAM-backed filesystem such as /dev/shm, that readers map into
+// memory. The file is a HEADER_LEN header then RGBA rows, top to bottom:
+//
+//   0  "WLSF"
+//   4  format version, u32
+//   8  width, u32
+//  12  height, u32
+//  16  frame sequence number, u64, odd while a frame is being written
+//  24  reserved
+//
+// all little-endian. A reader copies the pixels out between two reads of an
+// even, unchanged sequence number to be sure of a whole frame.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use wl_starfield::ScreenDetails;
+
+const MAGIC: &[u8; 4] = b"WLSF";
+const VERSION: u32 = 1;
+const HEADER_LEN: u64 = 32;
+const SEQUENCE_AT: u64 = 16;
+
+pub struct FrameExport {
+    file: File,
+    path: PathBuf,
+    size: Option<ScreenDetails>,
+    sequence: u64,
+}
+
+impl FrameExport {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            size: None,
+            sequence: 0,
+        })
+    }
+
+    // Put `frame` in the file in place of the last one
+    pub fn publish(&mut self, frame: &[u8], screen_details: ScreenDetails) -> io::Result<()> {
+        if self.size != Some(screen_details) {
+            self.resize(screen_details)?;
+        }
+        self.sequence += 1;
+        self.file
+            .write_all_at(&self.sequence.to_le_bytes(), SEQUENCE_AT)?;
+        self.file.write_all_at(frame, HEADER_LEN)?;
+        self.sequence += 1;
+        self.file
+            .write_all_at(&self.sequence.to_le_bytes(), SEQUENCE_AT)
+    }
+
+    // A new header, and room for frames of the new size
+    fn resize(&mut self, screen_details: ScreenDetails) -> io::Result<()> {
+        let ScreenDetails { width, height } = screen_details;
+        let mut header = [0; HEADER_LEN as usize];
+        header[0..4].copy_from_slice(MAGIC);
+        header[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        header[8..12].copy_from_slice(&width.to_le_bytes());
+        header[12..16].copy_from_slice(&height.to_le_bytes());
+        header[16..24].copy_from_slice(&self.sequence.to_le_bytes());
+        self.file
+            .set_len(HEADER_LEN + width as u64 * height as u64 * 4)?;
+        self.file.write_all_at(&header, 0)?;
+        self.size = Some(screen_details);
+        Ok(())
+    }
+}
+
+impl Drop for FrameExport {
+    // Readers see the source go away rather than a frozen last frame
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// Start exporting to `path`, saying so if it can't be made
+pub fn start(path: Option<&Path>) -> Option<FrameExport> {
+    let path = path?;
+    match FrameExport::new(path) {
+        Ok(export) => {
+            eprintln!("wl-starfield: exporting frames to {}", path.display());
+            Some(export)
+        }
+        Err(err) => {
+            eprintln!(
+                "wl-starfield: can't export frames to {}: {err}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn frames_are_written_over_in_place() {
+        let path = std::env::temp_dir().join(format!("wl-starfield-export-{}", std::process::id()));
+        let mut export = FrameExport::new(&path).unwrap();
+        let screen_details = ScreenDetails {
+            width: 2,
+            height: 1,
+        };
+        export.publish(&[1; 8], screen_details).unwrap();
+        export.publish(&[2; 8], screen_details).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[0..4], MAGIC);
+        assert_eq!(bytes[8..16], [2, 0, 0, 0, 1, 0, 0, 0]);
+        // Two frames, each written between an odd and an even number
+        assert_eq!(bytes[16..24], 4u64.to_le_bytes());
+        assert_eq!(&bytes[32..], &[2; 8]);
+
+        export
+            .publish(
+                &[3; 4],
+                ScreenDetails {
+                    width: 1,
+                    height: 1,
+                },
+            )
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap().len(), 36);
+        drop(export);
+        assert!(!path.exists());
+    }
+}
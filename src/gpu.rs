@@ -0,0 +1,197 @@
+// GPU sprites for the window backend: stars and shooting-star blobs are
+// uploaded as one instance each and drawn as quads by a small shader, over the
+// frame `pixels` has already put on screen. Everything else is still drawn on
+// the CPU by `Starfield::render_with_sprites`.
+
+use pixels::{PixelsContext, wgpu};
+use wl_starfield::{ScreenDetails, Sprite};
+
+const SHADER: &str = "
+struct Instance {
+    @location(0) center: vec2<f32>,
+    @location(1) half_size: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) soft: f32,
+}
+
+struct Varyings {
+    @builtin(position) position: vec4<f32>,
+    // -1..1 across the quad
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) soft: f32,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, sprite: Instance) -> Varyings {
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0;
+    var out: Varyings;
+    out.position = vec4<f32>(sprite.center + corner * sprite.half_size, 0.0, 1.0);
+    out.local = corner;
+    out.color = sprite.color;
+    out.soft = sprite.soft;
+    return out;
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    var alpha = in.color.a;
+    if in.soft > 0.5 {
+        // Same soft circular falloff as the CPU shooting-star points
+        let rim = 1.0 - clamp(length(in.local), 0.0, 1.0);
+        alpha = alpha * rim * rim;
+    }
+    return vec4<f32>(in.color.rgb, alpha);
+}
+";
+
+// center, half size, color, soft: nine floats per sprite
+const INSTANCE_SIZE: usize = 9 * 4;
+const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4, 3 => Float32];
+// Room for the default star count plus shooting stars before the first regrow
+const INITIAL_CAPACITY: usize = 8192;
+
+pub struct SpriteRenderer {
+    pipeline: wgpu::RenderPipeline,
+    instances: wgpu::Buffer,
+    // Blending happens in linear light on sRGB targets, so colors are
+    // converted on the way in
+    srgb: bool,
+    // Instance data, rebuilt every frame without reallocating
+    bytes: Vec<u8>,
+    // What `Starfield::render_with_sprites` fills in for this frame
+    pub sprites: Vec<Sprite>,
+}
+
+impl SpriteRenderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wl-starfield sprites"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wl-starfield sprites"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("wl-starfield sprites"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_SIZE as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            instances: instance_buffer(device, INITIAL_CAPACITY),
+            srgb: format.is_srgb(),
+            bytes: Vec::with_capacity(INITIAL_CAPACITY * INSTANCE_SIZE),
+            sprites: Vec::with_capacity(INITIAL_CAPACITY),
+        }
+    }
+
+    // Draw this frame's sprites on top of whatever `target` already holds
+    pub fn draw(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        context: &PixelsContext,
+        screen_details: ScreenDetails,
+    ) {
+        if self.sprites.is_empty() {
+            return;
+        }
+
+        // Pixels to clip space, with y pointing up
+        let (sx, sy) = (
+            2.0 / screen_details.width as f32,
+            2.0 / screen_details.height as f32,
+        );
+        let channel = |c: u8| {
+            let c = c as f32 / 255.0;
+            if self.srgb { srgb_to_linear(c) } else { c }
+        };
+        self.bytes.clear();
+        for sprite in &self.sprites {
+            let (r, g, b) = sprite.color;
+            let (x, y) = sprite.center;
+            let instance = [
+                x * sx - 1.0,
+                1.0 - y * sy,
+                sprite.radius * sx,
+                sprite.radius * sy,
+                channel(r),
+                channel(g),
+                channel(b),
+                sprite.alpha,
+                if sprite.soft { 1.0 } else { 0.0 },
+            ];
+            for value in instance {
+                self.bytes.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        if self.bytes.len() as u64 > self.instances.size() {
+            let count = self.sprites.len() + self.sprites.len() / 2;
+            self.instances = instance_buffer(&context.device, count);
+        }
+        context.queue.write_buffer(&self.instances, 0, &self.bytes);
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("wl-starfield sprites"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.instances.slice(..self.bytes.len() as u64));
+        pass.draw(0..4, 0..self.sprites.len() as u32);
+    }
+}
+
+fn instance_buffer(device: &wgpu::Device, count: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wl-starfield sprites"),
+        size: (count * INSTANCE_SIZE) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,97 @@
+// Gradient-mapped monochrome. As the last pass over the sky, every pixel is
+// brought down to its luminance and that is looked up along a gradient of two
+// or three colors, black sky at the first and the brightest stars at the
+// last, for a duotone look such as navy through to cyan.
+
+use crate::accent::hex_color;
+use crate::render::{Pixel, Target, pack};
+use std::str::FromStr;
+
+// The colors along a gradient, darkest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(u8, u8, u8)>,
+}
+
+impl FromStr for Gradient {
+    type Err = ();
+
+    // Two or three "#rrggbb" colors, separated by commas
+    fn from_str(text: &str) -> Result<Self, ()> {
+        let stops = text
+            .split(',')
+            .map(|stop| hex_color(stop.trim()).ok_or(()))
+            .collect::<Result<Vec<_>, ()>>()?;
+        if !(2..=3).contains(&stops.len()) {
+            return Err(());
+        }
+        Ok(Self { stops })
+    }
+}
+
+impl Gradient {
+    // The color `along` of the way from the first stop to the last, 0..1
+    pub(crate) fn color_at(&self, along: f32) -> (u8, u8, u8) {
+        let segments = (self.stops.len() - 1) as f32;
+        // Where along the gradient, and between which two stops
+        let along = along.clamp(0.0, 1.0) * segments;
+        let from = (along as usize).min(self.stops.len() - 2);
+        let t = along - from as f32;
+        let (a, b) = (self.stops[from], self.stops[from + 1]);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+    }
+}
+
+// A gradient ready to draw with: what each luminance shows
+pub(crate) struct GradientMap {
+    lookup: [Pixel; 256],
+}
+
+impl GradientMap {
+    pub fn new(gradient: &Gradient) -> Self {
+        let lookup = std::array::from_fn(|luma| pack(gradient.color_at(luma as f32 / 255.0)));
+        Self { lookup }
+    }
+
+    pub fn apply(&self, target: &mut Target) {
+        for pixel in target.pixels() {
+            let [r, g, b, _] = pixel.map(u32::from);
+            // Rec. 709 weights, in 256ths
+            let luma = (54 * r + 183 * g + 19 * b) >> 8;
+            *pixel = self.lookup[luma as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Rect;
+
+    fn mapped(gradient: &str, color: (u8, u8, u8)) -> Pixel {
+        let map = GradientMap::new(&gradient.parse().unwrap());
+        let mut pixels = [pack(color)];
+        let mut target = Target::new(&mut pixels, 1, (0, 0), Rect::new(0, 0, 1, 1));
+        map.apply(&mut target);
+        pixels[0]
+    }
+
+    #[test]
+    fn luminance_runs_along_the_stops() {
+        let duotone = "#000020, #00e0ff";
+        assert_eq!(mapped(duotone, (0, 0, 0)), pack((0, 0, 0x20)));
+        assert_eq!(mapped(duotone, (255, 255, 255)), pack((0, 0xe0, 0xff)));
+        // Any hue of the same brightness lands on the same color
+        let grey = mapped(duotone, (128, 128, 128));
+        assert_eq!(grey[0], 0);
+        assert!(grey[1] > 100 && grey[1] < 124, "{grey:?}");
+
+        // With three stops, middle grey is at the middle one
+        let three = "#000000, #ff0000, #ffffff";
+        assert_eq!(mapped(three, (128, 128, 128)), pack((255, 1, 1)));
+
+        assert_eq!("#000000".parse::<Gradient>(), Err(()));
+        assert_eq!("#000000, navy".parse::<Gradient>(), Err(()));
+    }
+}
@@ -0,0 +1,47 @@
+// `wl-starfield render`: the sky with no window, drawn on the CPU a frame at
+// a time at a fixed step and saved as PNGs. With a seed the frames come out
+// the same on every run, for golden-image tests, previews and static
+// wallpapers made on machines with no display.
+
+use crate::cli::RenderArgs;
+use rand::SeedableRng;
+use std::fs;
+use std::io::{self, BufWriter};
+use wl_starfield::config::Config;
+use wl_starfield::exposure;
+use wl_starfield::rng::FastRng;
+use wl_starfield::{ScreenDetails, Starfield};
+
+pub fn render(args: &RenderArgs) -> io::Result<()> {
+    let seed = args.seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        eprintln!("wl-starfield: sky seed {seed} (pass --seed {seed} to see it again)");
+        seed
+    });
+    let (width, height) = args.size;
+    let screen_details = ScreenDetails { width, height };
+    let mut starfield =
+        Starfield::new(FastRng::seed_from_u64(seed), screen_details, Config::load());
+    starfield.set_fixed_clock(true);
+
+    let dt = 1.0 / args.fps;
+    for _ in 0..(args.after * args.fps).round() as u32 {
+        starfield.update(dt);
+    }
+    let to_stdout = args.output.as_os_str() == "-";
+    if !to_stdout {
+        fs::create_dir_all(&args.output)?;
+    }
+    let mut frame = vec![0; width as usize * height as usize * 4];
+    for number in 1..=args.frames {
+        starfield.update(dt);
+        starfield.render(&mut frame);
+        if to_stdout {
+            exposure::write_png(BufWriter::new(io::stdout().lock()), screen_details, &frame)?;
+        } else {
+            let path = args.output.join(format!("frame-{number:04}.png"));
+            exposure::save_png(&path, screen_details, &frame)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+// The heat map debug view, drawn in place of the sky: how densely stars are
+// packed and how much light everything gives off, cell by cell, blurred so
+// clusters and gaps stand out. Density is blue and light orange, each scaled
+// to its own peak, so wherever both run high comes out white. Events weigh in
+// by what they add to the loudness that holds new ones back.
+
+use crate::render::{Rect, Target};
+
+// Pixels to a side of each cell
+const HEAT_CELL: usize = 16;
+// Weights of the blur, out from the middle, applied across then down
+const HEAT_BLUR: [f32; 3] = [6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+const DENSITY_COLOR: (f32, f32, f32) = (40.0, 110.0, 255.0);
+const LIGHT_COLOR: (f32, f32, f32) = (255.0, 140.0, 30.0);
+
+#[derive(Default)]
+pub(crate) struct HeatMap {
+    cols: usize,
+    rows: usize,
+    // Stars, and light in pixels' worth at full brightness, in each cell
+    density: Vec<f32>,
+    light: Vec<f32>,
+    scratch: Vec<f32>,
+    // The greatest of each, blurred, to scale the colors to
+    peaks: (f32, f32),
+    // The greatest and mean of each before blurring, for the panel
+    stats: [(f32, f32); 2],
+}
+
+impl HeatMap {
+    // Start afresh over a view `width` by `height` pixels
+    pub fn clear(&mut self, width: u32, height: u32) {
+        self.cols = (width as usize).div_ceil(HEAT_CELL);
+        self.rows = (height as usize).div_ceil(HEAT_CELL);
+        let cells = self.cols * self.rows;
+        for field in [&mut self.density, &mut self.light, &mut self.scratch] {
+            field.clear();
+            field.resize(cells, 0.0);
+        }
+    }
+
+    // Add `stars` and `light` at `(x, y)` in the view, if it's in it
+    pub fn add(&mut self, (x, y): (f32, f32), stars: f32, light: f32) {
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+        let (col, row) = (x as usize / HEAT_CELL, y as usize / HEAT_CELL);
+        if col < self.cols && row < self.rows {
+            self.density[row * self.cols + col] += stars;
+            self.light[row * self.cols + col] += light;
+        }
+    }
+
+    // Done adding: take the figures, then blur both
+    pub fn finish(&mut self) {
+        let cells = self.density.len().max(1) as f32;
+        for (stats, field) in self.stats.iter_mut().zip([&self.density, &self.light]) {
+            let peak = field.iter().copied().fold(0.0, f32::max);
+            *stats = (peak, field.iter().sum::<f32>() / cells);
+        }
+        blur(&mut self.density, &mut self.scratch, self.cols);
+        blur(&mut self.light, &mut self.scratch, self.cols);
+        let peak = |field: &[f32]| field.iter().copied().fold(0.0, f32::max);
+        self.peaks = (peak(&self.density), peak(&self.light));
+    }
+
+    // Lines for the debug panel
+    pub fn describe(&self) -> [String; 2] {
+        let [(stars, mean_stars), (light, mean_light)] = self.stats;
+        [
+            format!("density: peak {stars:.0}, mean {mean_stars:.1} stars per {HEAT_CELL} px cell"),
+            format!("light: peak {light:.0}, mean {mean_light:.1} per cell"),
+        ]
+    }
+
+    pub fn draw(&self, target: &mut Target, view: Rect) {
+        let scale = |value: f32, peak: f32| {
+            if peak > 0.0 {
+                // Square-rooted, so sparse parts still show
+                (value / peak).sqrt()
+            } else {
+                0.0
+            }
+        };
+        // Smoothly between the cells' centers
+        let place = |at: i32, cells: usize| {
+            let at = (at as f32 + 0.5) / HEAT_CELL as f32 - 0.5;
+            let before = at.floor();
+            let last = cells as i32 - 1;
+            (
+                (before as i32).clamp(0, last) as usize,
+                (before as i32 + 1).clamp(0, last) as usize,
+                at - before,
+            )
+        };
+        let sample = |field: &[f32], (above, below, ty), (before, after, tx)| {
+            let (above, below) = (above * self.cols, below * self.cols);
+            let top = field[above + before] + (field[above + after] - field[above + before]) * tx;
+            let bottom =
+                field[below + before] + (field[below + after] - field[below + before]) * tx;
+            top + (bottom - top) * ty
+        };
+        for (y, left, row) in target.span_rows(view) {
+            let row_at = place(y - view.y0, self.rows);
+            for (x, pixel) in (left..).zip(row) {
+                let at = place(x - view.x0, self.cols);
+                let density = scale(sample(&self.density, row_at, at), self.peaks.0);
+                let light = scale(sample(&self.light, row_at, at), self.peaks.1);
+                let mix = |d: f32, l: f32| (d * density + l * light).min(255.0) as u8;
+                *pixel = [
+                    mix(DENSITY_COLOR.0, LIGHT_COLOR.0),
+                    mix(DENSITY_COLOR.1, LIGHT_COLOR.1),
+                    mix(DENSITY_COLOR.2, LIGHT_COLOR.2),
+                    255,
+                ];
+            }
+        }
+    }
+}
+
+// Blur a field `cols` cells wide across into `scratch`, then down back into it
+fn blur(field: &mut [f32], scratch: &mut [f32], cols: usize) {
+    let rows = field.len() / cols.max(1);
+    let reach = HEAT_BLUR.len() - 1;
+    for y in 0..rows {
+        for x in 0..cols {
+            scratch[y * cols + x] = (x.saturating_sub(reach)..=(x + reach).min(cols - 1))
+                .map(|from| field[y * cols + from] * HEAT_BLUR[from.abs_diff(x)])
+                .sum();
+        }
+    }
+    for y in 0..rows {
+        for x in 0..cols {
+            field[y * cols + x] = (y.saturating_sub(reach)..=(y + reach).min(rows - 1))
+                .map(|from| scratch[from * cols + x] * HEAT_BLUR[from.abs_diff(y)])
+                .sum();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    #[test]
+    fn clusters_and_light_show_in_their_own_colors() {
+        let mut heat = HeatMap::default();
+        heat.clear(1920, 1080);
+        // A tight cluster of faint stars top left, a lone bright light bottom right
+        for i in 0..50 {
+            heat.add((100.0 + i as f32, 100.0), 1.0, 0.1);
+        }
+        heat.add((1500.0, 800.0), 0.0, 50.0);
+        heat.add((-5.0, 2000.0), 1.0, 1.0);
+        heat.finish();
+        assert_eq!(heat.stats[0].0, 16.0);
+
+        let mut frame = Frame::new(&LANDSCAPE);
+        heat.draw(&mut frame.target(), Rect::new(0, 0, 1920, 1080));
+        let [r, _, b, _] = frame.pixel(120, 100);
+        assert!(b > 200 && r < b, "{r} {b}");
+        let [r, _, b, _] = frame.pixel(1500, 800);
+        assert!(r > 200 && b < r, "{r} {b}");
+        // Blurred out to the next cells, but not far
+        frame.assert_brighter_than(1500, 830, 20);
+        frame.assert_dark(900, 500);
+    }
+}
@@ -0,0 +1,109 @@
+// Event hooks: shell commands from the config's `on_<event>` keys, run when
+// that event happens in any output's sky. They run in the background through
+// `sh -c`, with the event's name in $WL_STARFIELD_EVENT, and each one runs at
+// most once every `hook_min_interval` seconds so a busy sky can't flood the
+// system with notifications or sounds.
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use wl_starfield::SkyEvent;
+use wl_starfield::config::Config;
+
+struct Hook {
+    event: SkyEvent,
+    command: String,
+    last_run: Option<Instant>,
+}
+
+pub struct Hooks {
+    hooks: Vec<Hook>,
+    min_interval: Duration,
+}
+
+impl Hooks {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            hooks: config
+                .hooks
+                .iter()
+                .map(|(event, command)| Hook {
+                    event: *event,
+                    command: command.clone(),
+                    last_run: None,
+                })
+                .collect(),
+            min_interval: Duration::from_secs_f32(config.hook_min_interval),
+        }
+    }
+
+    // Run the hooks for whatever happened in a sky's last update
+    pub fn fire(&mut self, events: &[SkyEvent]) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        for &event in events {
+            if let Some(command) = self.due(event, now) {
+                run(event, command);
+            }
+        }
+    }
+
+    // The command to run for `event` at `now`, if it has one and it hasn't
+    // run too recently
+    fn due(&mut self, event: SkyEvent, now: Instant) -> Option<&str> {
+        let min_interval = self.min_interval;
+        let hook = self.hooks.iter_mut().find(|hook| hook.event == event)?;
+        if hook
+            .last_run
+            .is_some_and(|last| now.duration_since(last) < min_interval)
+        {
+            return None;
+        }
+        hook.last_run = Some(now);
+        Some(&hook.command)
+    }
+}
+
+fn run(event: SkyEvent, command: &str) {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("WL_STARFIELD_EVENT", event.name())
+        .stdin(Stdio::null())
+        .spawn();
+    match child {
+        // Reap it off the render thread whenever it finishes
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => eprintln!("wl-starfield: can't run the {} hook: {err}", event.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_are_rate_limited_per_event() {
+        let config = Config {
+            hooks: vec![
+                (SkyEvent::Comet, "comet".into()),
+                (SkyEvent::Sparkle, "sparkle".into()),
+            ],
+            hook_min_interval: 10.0,
+            ..Config::default()
+        };
+        let mut hooks = Hooks::new(&config);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(hooks.due(SkyEvent::Comet, at(0)), Some("comet"));
+        assert_eq!(hooks.due(SkyEvent::Sparkle, at(1)), Some("sparkle"));
+        assert_eq!(hooks.due(SkyEvent::Comet, at(5)), None);
+        assert_eq!(hooks.due(SkyEvent::Comet, at(10)), Some("comet"));
+        assert_eq!(hooks.due(SkyEvent::ShootingStar, at(20)), None);
+    }
+}
@@ -0,0 +1,60 @@
+// A few lines of faint text in the bottom-left corner, for whatever the
+// embedder wants kept in view, such as lifetime statistics.
+
+use crate::ScreenDetails;
+use crate::render::{Rect, Target};
+use crate::text;
+
+const HUD_SCALE: i32 = 2;
+const HUD_MARGIN: i32 = 16;
+const HUD_COLOR: (u8, u8, u8) = (200, 210, 235);
+const HUD_ALPHA: f32 = 0.5;
+
+pub(crate) fn draw<S: AsRef<str>>(
+    target: &mut Target,
+    screen_details: &ScreenDetails,
+    lines: &[S],
+) {
+    let (w, h) = text::measure(lines, HUD_SCALE);
+    let bottom = screen_details.height as i32 - HUD_MARGIN;
+    let rect = Rect::new(HUD_MARGIN, bottom - h, HUD_MARGIN + w, bottom);
+    if !target.overlaps(rect) {
+        return;
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let y = rect.y0 + i as i32 * text::line_height(HUD_SCALE);
+        text::draw_line(
+            target,
+            rect.x0,
+            y,
+            line.as_ref(),
+            HUD_COLOR,
+            HUD_ALPHA,
+            HUD_SCALE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    #[test]
+    fn lines_sit_in_the_bottom_left_corner() {
+        let mut frame = Frame::new(&LANDSCAPE);
+        draw(&mut frame.target(), &LANDSCAPE, &["3 comets", "1h 5m"]);
+        assert!(frame.lit(40) > 50);
+        let (w, h) = text::measure(&["3 comets", "1h 5m"], HUD_SCALE);
+        for y in (0..1080).step_by(4) {
+            for x in (0..1920).step_by(4) {
+                let inside = x >= HUD_MARGIN as u32
+                    && x < (HUD_MARGIN + w) as u32
+                    && y >= 1080 - (HUD_MARGIN + h) as u32;
+                if !inside {
+                    frame.assert_dark(x, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+// Lightweight translations for on-screen text. Each locale is a flat table of
+// key -> template; missing keys fall back to English. Templates may contain
+// `{name}` placeholders, filled in by `Locale::format`.
+
+use std::env;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Locale {
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+const EN: &[(&str, &str)] = &[
+    ("star.class", "Class {class}, {distance} light-years"),
+    ("lore.0", "Sailors once steered by its glow."),
+    ("lore.1", "Orbited by a lonely ice giant."),
+    ("lore.2", "Its light left before the pyramids."),
+    ("lore.3", "A faint companion hides in its glare."),
+    ("lore.4", "Flares every few decades."),
+    ("lore.5", "Named in a forgotten star chart."),
+    ("lore.6", "Home to a dusty debris disc."),
+    ("lore.7", "Slowly shedding its outer layers."),
+    ("lore.8", "Surveyed once, then ignored."),
+    ("lore.9", "Hosts three rocky worlds."),
+    ("lore.10", "Older than the Sun by a billion years."),
+    ("lore.11", "Quietly burning through its hydrogen."),
+    ("moon.0", "New moon"),
+    ("moon.1", "Waxing crescent"),
+    ("moon.2", "First quarter"),
+    ("moon.3", "Waxing gibbous"),
+    ("moon.4", "Full moon"),
+    ("moon.5", "Waning gibbous"),
+    ("moon.6", "Last quarter"),
+    ("moon.7", "Waning crescent"),
+    ("moon.lit", "{phase} ({lit}% lit)"),
+    ("planet.mercury", "Mercury"),
+    ("planet.venus", "Venus"),
+    ("planet.mars", "Mars"),
+    ("planet.jupiter", "Jupiter"),
+    ("planet.saturn", "Saturn"),
+    ("report.title", "Tonight's sky"),
+    (
+        "report.shower",
+        "The {name} peak tonight, up to {rate} meteors an hour",
+    ),
+    ("report.all_night", "{planets} all night"),
+    ("report.evening", "{planets} in the evening"),
+    ("report.morning", "{planets} before dawn"),
+    ("catch.caught", "caught! {caught} of {tries}"),
+    ("catch.missed", "missed {caught} of {tries}"),
+    ("stats.shooting_stars.one", "{n} shooting star wished on"),
+    ("stats.shooting_stars.other", "{n} shooting stars wished on"),
+    ("stats.comets.one", "{n} comet"),
+    ("stats.comets.other", "{n} comets"),
+    ("stats.satellites.one", "{n} satellite"),
+    ("stats.satellites.other", "{n} satellites"),
+    ("stats.supernovae.one", "{n} supernova"),
+    ("stats.supernovae.other", "{n} supernovae"),
+    ("stats.sparkles.one", "{n} sparkle"),
+    ("stats.sparkles.other", "{n} sparkles"),
+    ("stats.focus_sessions.one", "{n} focus session"),
+    ("stats.focus_sessions.other", "{n} focus sessions"),
+    ("stats.uptime", "{time} under the stars"),
+    ("time.days", "{d}d {h}h {m}m"),
+    ("time.hours", "{h}h {m}m"),
+    ("time.minutes", "{m}m"),
+    ("status.focus", "Focus session"),
+    ("status.breathing", "Breathing"),
+    ("status.comet", "A comet is crossing"),
+    ("status.satellites.one", "{n} satellite overhead"),
+    ("status.satellites.other", "{n} satellites overhead"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("star.class", "Klasse {class}, {distance} Lichtjahre"),
+    ("lore.0", "Seefahrer navigierten einst nach ihm."),
+    ("lore.1", "Umkreist von einem einsamen Eisriesen."),
+    ("lore.2", "Sein Licht ist älter als die Pyramiden."),
+    ("lore.3", "Ein schwacher Begleiter versteckt sich."),
+    ("lore.4", "Flackert alle paar Jahrzehnte auf."),
+    ("lore.5", "Benannt in einer vergessenen Sternkarte."),
+    ("lore.6", "Umgeben von einer Trümmerscheibe."),
+    ("lore.7", "Stößt langsam seine Hülle ab."),
+    ("lore.8", "Einmal vermessen, dann vergessen."),
+    ("lore.9", "Beherbergt drei Gesteinsplaneten."),
+    ("lore.10", "Eine Milliarde Jahre älter als die Sonne."),
+    ("lore.11", "Verbrennt leise seinen Wasserstoff."),
+    ("moon.0", "Neumond"),
+    ("moon.1", "Zunehmende Sichel"),
+    ("moon.2", "Erstes Viertel"),
+    ("moon.3", "Zunehmender Mond"),
+    ("moon.4", "Vollmond"),
+    ("moon.5", "Abnehmender Mond"),
+    ("moon.6", "Letztes Viertel"),
+    ("moon.7", "Abnehmende Sichel"),
+    ("moon.lit", "{phase} ({lit} % beleuchtet)"),
+    ("planet.mercury", "Merkur"),
+    ("planet.venus", "Venus"),
+    ("planet.mars", "Mars"),
+    ("planet.jupiter", "Jupiter"),
+    ("planet.saturn", "Saturn"),
+    ("report.title", "Der Himmel heute Nacht"),
+    (
+        "report.shower",
+        "Die {name} erreichen heute Nacht ihr Maximum, bis zu {rate} Meteore pro Stunde",
+    ),
+    ("report.all_night", "{planets} die ganze Nacht"),
+    ("report.evening", "{planets} am Abend"),
+    ("report.morning", "{planets} vor der Dämmerung"),
+    ("catch.caught", "gefangen! {caught} von {tries}"),
+    ("catch.missed", "verfehlt, {caught} von {tries}"),
+    ("stats.shooting_stars.one", "{n} Sternschnuppe mit Wunsch"),
+    (
+        "stats.shooting_stars.other",
+        "{n} Sternschnuppen mit Wunsch",
+    ),
+    ("stats.comets.one", "{n} Komet"),
+    ("stats.comets.other", "{n} Kometen"),
+    ("stats.satellites.one", "{n} Satellit"),
+    ("stats.satellites.other", "{n} Satelliten"),
+    ("stats.supernovae.one", "{n} Supernova"),
+    ("stats.supernovae.other", "{n} Supernovae"),
+    ("stats.sparkles.one", "{n} Funkeln"),
+    ("stats.sparkles.other", "{n} Funkeln"),
+    ("stats.focus_sessions.one", "{n} Fokussitzung"),
+    ("stats.focus_sessions.other", "{n} Fokussitzungen"),
+    ("stats.uptime", "{time} unter den Sternen"),
+    ("time.days", "{d}T {h}h {m}min"),
+    ("time.hours", "{h}h {m}min"),
+    ("time.minutes", "{m}min"),
+    ("status.focus", "Fokussitzung"),
+    ("status.breathing", "Atmen"),
+    ("status.comet", "Ein Komet zieht vorbei"),
+    ("status.satellites.one", "{n} Satellit am Himmel"),
+    ("status.satellites.other", "{n} Satelliten am Himmel"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("star.class", "Classe {class}, {distance} années-lumière"),
+    ("lore.0", "Les marins se guidaient sur son éclat."),
+    ("lore.1", "Une géante de glace solitaire l'entoure."),
+    ("lore.2", "Sa lumière précède les pyramides."),
+    ("lore.3", "Un faible compagnon se cache dans son éclat."),
+    ("lore.4", "S'embrase toutes les quelques décennies."),
+    ("lore.5", "Nommée dans une carte céleste oubliée."),
+    ("lore.6", "Entourée d'un disque de débris."),
+    ("lore.7", "Perd lentement ses couches externes."),
+    ("lore.8", "Observée une fois, puis oubliée."),
+    ("lore.9", "Abrite trois mondes rocheux."),
+    (
+        "lore.10",
+        "Plus vieille que le Soleil d'un milliard d'années.",
+    ),
+    ("lore.11", "Brûle tranquillement son hydrogène."),
+    ("moon.0", "Nouvelle lune"),
+    ("moon.1", "Premier croissant"),
+    ("moon.2", "Premier quartier"),
+    ("moon.3", "Gibbeuse croissante"),
+    ("moon.4", "Pleine lune"),
+    ("moon.5", "Gibbeuse décroissante"),
+    ("moon.6", "Dernier quartier"),
+    ("moon.7", "Dernier croissant"),
+    ("moon.lit", "{phase} (éclairée à {lit} %)"),
+    ("planet.mercury", "Mercure"),
+    ("planet.venus", "Vénus"),
+    ("planet.mars", "Mars"),
+    ("planet.jupiter", "Jupiter"),
+    ("planet.saturn", "Saturne"),
+    ("report.title", "Le ciel ce soir"),
+    (
+        "report.shower",
+        "Pic des {name} cette nuit, jusqu'à {rate} météores par heure",
+    ),
+    ("report.all_night", "{planets} toute la nuit"),
+    ("report.evening", "{planets} en soirée"),
+    ("report.morning", "{planets} avant l'aube"),
+    ("catch.caught", "attrapée ! {caught} sur {tries}"),
+    ("catch.missed", "manquée, {caught} sur {tries}"),
+    ("stats.shooting_stars.one", "{n} vœu sur une étoile filante"),
+    (
+        "stats.shooting_stars.other",
+        "{n} vœux sur des étoiles filantes",
+    ),
+    ("stats.comets.one", "{n} comète"),
+    ("stats.comets.other", "{n} comètes"),
+    ("stats.satellites.one", "{n} satellite"),
+    ("stats.satellites.other", "{n} satellites"),
+    ("stats.supernovae.one", "{n} supernova"),
+    ("stats.supernovae.other", "{n} supernovae"),
+    ("stats.sparkles.one", "{n} scintillement"),
+    ("stats.sparkles.other", "{n} scintillements"),
+    ("stats.focus_sessions.one", "{n} séance de concentration"),
+    ("stats.focus_sessions.other", "{n} séances de concentration"),
+    ("stats.uptime", "{time} sous les étoiles"),
+    ("time.days", "{d}j {h}h {m}min"),
+    ("time.hours", "{h}h {m}min"),
+    ("time.minutes", "{m}min"),
+    ("status.focus", "Séance de concentration"),
+    ("status.breathing", "Respiration"),
+    ("status.comet", "Une comète passe"),
+    ("status.satellites.one", "{n} satellite dans le ciel"),
+    ("status.satellites.other", "{n} satellites dans le ciel"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("star.class", "Clase {class}, {distance} años luz"),
+    ("lore.0", "Los marineros se guiaban por su brillo."),
+    ("lore.1", "La orbita un gigante de hielo solitario."),
+    ("lore.2", "Su luz partió antes de las pirámides."),
+    ("lore.3", "Un tenue compañero se oculta en su brillo."),
+    ("lore.4", "Estalla cada pocas décadas."),
+    ("lore.5", "Nombrada en una carta estelar olvidada."),
+    ("lore.6", "Rodeada por un disco de escombros."),
+    ("lore.7", "Pierde lentamente sus capas externas."),
+    ("lore.8", "Estudiada una vez y luego ignorada."),
+    ("lore.9", "Alberga tres mundos rocosos."),
+    ("lore.10", "Mil millones de años más vieja que el Sol."),
+    ("lore.11", "Quema en silencio su hidrógeno."),
+    ("moon.0", "Luna nueva"),
+    ("moon.1", "Luna creciente"),
+    ("moon.2", "Cuarto creciente"),
+    ("moon.3", "Creciente gibosa"),
+    ("moon.4", "Luna llena"),
+    ("moon.5", "Menguante gibosa"),
+    ("moon.6", "Cuarto menguante"),
+    ("moon.7", "Luna menguante"),
+    ("moon.lit", "{phase} (iluminada al {lit} %)"),
+    ("planet.mercury", "Mercurio"),
+    ("planet.venus", "Venus"),
+    ("planet.mars", "Marte"),
+    ("planet.jupiter", "Júpiter"),
+    ("planet.saturn", "Saturno"),
+    ("report.title", "El cielo esta noche"),
+    (
+        "report.shower",
+        "Las {name} alcanzan su máximo esta noche, hasta {rate} meteoros por hora",
+    ),
+    ("report.all_night", "{planets} toda la noche"),
+    ("report.evening", "{planets} al anochecer"),
+    ("report.morning", "{planets} antes del amanecer"),
+    ("catch.caught", "atrapado: {caught} de {tries}"),
+    ("catch.missed", "fallado: {caught} de {tries}"),
+    ("stats.shooting_stars.one", "{n} estrella fugaz con deseo"),
+    (
+        "stats.shooting_stars.other",
+        "{n} estrellas fugaces con deseo",
+    ),
+    ("stats.comets.one", "{n} cometa"),
+    ("stats.comets.other", "{n} cometas"),
+    ("stats.satellites.one", "{n} satélite"),
+    ("stats.satellites.other", "{n} satélites"),
+    ("stats.supernovae.one", "{n} supernova"),
+    ("stats.supernovae.other", "{n} supernovas"),
+    ("stats.sparkles.one", "{n} destello"),
+    ("stats.sparkles.other", "{n} destellos"),
+    ("stats.focus_sessions.one", "{n} sesión de concentración"),
+    (
+        "stats.focus_sessions.other",
+        "{n} sesiones de concentración",
+    ),
+    ("stats.uptime", "{time} bajo las estrellas"),
+    ("time.days", "{d}d {h}h {m}min"),
+    ("time.hours", "{h}h {m}min"),
+    ("time.minutes", "{m}min"),
+    ("status.focus", "Sesión de concentración"),
+    ("status.breathing", "Respiración"),
+    ("status.comet", "Un cometa está pasando"),
+    ("status.satellites.one", "{n} satélite en el cielo"),
+    ("status.satellites.other", "{n} satélites en el cielo"),
+];
+
+impl Locale {
+    // First of LC_ALL, LC_MESSAGES, LANG that names a supported language
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find_map(|tag| Self::from_tag(&tag))
+            .unwrap_or(Locale::En)
+    }
+
+    // Parse POSIX-style tags such as "de_DE.UTF-8" or "fr"
+    fn from_tag(tag: &str) -> Option<Self> {
+        let lang = tag.split(['_', '.', '@', '-']).next()?;
+        match lang.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::De => DE,
+            Locale::Fr => FR,
+            Locale::Es => ES,
+        }
+    }
+
+    pub fn tr(self, key: &str) -> &'static str {
+        let lookup = |table: &'static [(&str, &'static str)]| {
+            table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+        };
+        lookup(self.table()).or_else(|| lookup(EN)).unwrap_or("?")
+    }
+
+    // Translate `{key}.one` or `{key}.other` by how many there are, with the
+    // count in place of `{n}`
+    pub fn plural(self, key: &str, n: u64) -> String {
+        // French counts none as one
+        let one = match self {
+            Locale::Fr => n <= 1,
+            _ => n == 1,
+        };
+        let form = if one { "one" } else { "other" };
+        self.format(&format!("{key}.{form}"), &[("n", &n.to_string())])
+    }
+
+    // Translate `key` and substitute `{name}` placeholders
+    pub fn format(self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.tr(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_posix_locale_tags() {
+        assert_eq!(Locale::from_tag("de_DE.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::from_tag("fr"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("C.UTF-8"), None);
+    }
+
+    #[test]
+    fn every_locale_covers_the_english_keys() {
+        for locale in [Locale::De, Locale::Fr, Locale::Es] {
+            for (key, _) in EN {
+                assert!(
+                    locale.table().iter().any(|(k, _)| k == key),
+                    "{locale:?} is missing {key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn plurals_follow_the_language() {
+        assert_eq!(Locale::En.plural("stats.comets", 0), "0 comets");
+        assert_eq!(Locale::En.plural("stats.comets", 1), "1 comet");
+        assert_eq!(Locale::Fr.plural("stats.comets", 0), "0 comète");
+        assert_eq!(Locale::De.plural("stats.comets", 3), "3 Kometen");
+    }
+}
@@ -0,0 +1,679 @@
+// Wallpaper mode: draw on the wlr-layer-shell background layer, beneath every
+// window, the way sway/Hyprland/river expect a live wallpaper to. Each output
+// gets its own surface and star population. Frames are rendered on the CPU
+// straight into shared-memory buffers and paced by the compositor's frame
+// callbacks, so a hidden output costs nothing. During a focus session each
+// surface also holds an idle inhibitor, so the screen stays on. There is no
+// keyboard on the wallpaper, so it is driven by `--commands` instead.
+
+use smithay_client_toolkit::{
+    default_environment,
+    environment::Environment,
+    environment::SimpleGlobal,
+    new_default_environment,
+    output::{OutputInfo, with_output_info},
+    reexports::{
+        client::protocol::{
+            wl_output::{Transform, WlOutput},
+            wl_shm,
+            wl_surface::WlSurface,
+        },
+        client::{Attached, Main},
+        protocols::unstable::idle_inhibit::v1::client::{
+            zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+            zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+        },
+        protocols::wlr::unstable::layer_shell::v1::client::{
+            zwlr_layer_shell_v1::{Layer, ZwlrLayerShellV1},
+            zwlr_layer_surface_v1::{Anchor, Event as LayerEvent, ZwlrLayerSurfaceV1},
+        },
+    },
+    shm::AutoMemPool,
+};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::{Duration, Instant};
+use wl_starfield::config::Config;
+use wl_starfield::exposure::Exposure;
+use wl_starfield::recording::Recording;
+use wl_starfield::{ScreenDetails, Starfield};
+
+use crate::accent_watch::AccentWatch;
+use crate::cli::Args;
+use crate::commands::Command;
+use crate::frame_export::{self, FrameExport};
+use crate::hooks::Hooks;
+use crate::pipewire::PipewireSource;
+use crate::save_capture;
+use crate::sky_report::SkyReport;
+use crate::stagger::Stagger;
+use crate::stats::StatsKeeper;
+use crate::status::StatusWriter;
+use crate::{Recorder, Sky, save_recording};
+
+default_environment!(Env,
+    fields = [
+        layer_shell: SimpleGlobal<ZwlrLayerShellV1>,
+        idle_inhibit: SimpleGlobal<ZwpIdleInhibitManagerV1>,
+    ],
+    singles = [
+        ZwlrLayerShellV1 => layer_shell,
+        ZwpIdleInhibitManagerV1 => idle_inhibit
+    ],
+);
+
+#[derive(Debug)]
+pub enum Error {
+    // Not running under Wayland at all
+    NoWayland,
+    // The compositor doesn't speak wlr-layer-shell (e.g. GNOME)
+    NoLayerShell,
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoWayland => write!(f, "no Wayland compositor to connect to"),
+            Error::NoLayerShell => write!(f, "the compositor doesn't support wlr-layer-shell"),
+            Error::Io(err) => write!(f, "lost the Wayland connection: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+// One background-layer surface and its own starfield
+struct Surface {
+    // The output the surface covers, and sctk's id for it
+    output: WlOutput,
+    output_id: u32,
+    // Sky number handed to `new_starfield`, so every output looks different
+    index: u64,
+    surface: WlSurface,
+    layer_surface: Main<ZwlrLayerSurfaceV1>,
+    pool: AutoMemPool,
+    configured: Rc<Cell<Option<(u32, u32)>>>,
+    closed: Rc<Cell<bool>>,
+    frame_due: Rc<Cell<bool>>,
+    // Not every compositor offers idle inhibition
+    idle_inhibit: Option<Attached<ZwpIdleInhibitManagerV1>>,
+    // Held for as long as the starfield is in a focus session
+    inhibitor: Option<Main<ZwpIdleInhibitorV1>>,
+    // Where the surface sits in the sky all outputs share, if they do
+    span: Option<Span>,
+    // Built once the compositor has told us the surface size
+    starfield: Option<Starfield>,
+    // Long-exposure capture in progress
+    exposure: Option<Exposure>,
+    // Animated recording in progress, and the file it goes to
+    recording: Option<(Recording, PathBuf)>,
+    // Where its frames are shared, on the first output
+    export: Option<FrameExport>,
+    pipewire: Option<PipewireSource>,
+    // Screen pixels across each of the sky's, and the sky's own frame when it
+    // can't be drawn straight into the buffer: chunky or on a turned output
+    pixel_scale: u32,
+    small: Vec<u8>,
+    last_frame: Instant,
+}
+
+impl Surface {
+    fn new(
+        env: &Environment<Env>,
+        layer_shell: &Attached<ZwlrLayerShellV1>,
+        idle_inhibit: Option<&Attached<ZwpIdleInhibitManagerV1>>,
+        output: &WlOutput,
+        output_id: u32,
+        index: u64,
+        pixel_scale: u32,
+    ) -> io::Result<Self> {
+        let surface = env.create_surface().detach();
+        let layer_surface = layer_shell.get_layer_surface(
+            &surface,
+            Some(output),
+            Layer::Background,
+            "wl-starfield".to_string(),
+        );
+        // Size 0 on anchored edges means "as big as the output"
+        layer_surface.set_anchor(Anchor::all());
+        layer_surface.set_size(0, 0);
+        // Extend under panels rather than being pushed aside by them
+        layer_surface.set_exclusive_zone(-1);
+
+        let configured = Rc::new(Cell::new(None));
+        let closed = Rc::new(Cell::new(false));
+        {
+            let (configured, closed) = (configured.clone(), closed.clone());
+            layer_surface.quick_assign(move |layer_surface, event, _| match event {
+                LayerEvent::Configure {
+                    serial,
+                    width,
+                    height,
+                } => {
+                    layer_surface.ack_configure(serial);
+                    configured.set(Some((width, height)));
+                }
+                LayerEvent::Closed => closed.set(true),
+                _ => {}
+            });
+        }
+        surface.commit();
+
+        Ok(Self {
+            output: output.clone(),
+            output_id,
+            index,
+            surface,
+            layer_surface,
+            pool: env.create_auto_pool()?,
+            configured,
+            closed,
+            frame_due: Rc::new(Cell::new(true)),
+            idle_inhibit: idle_inhibit.cloned(),
+            inhibitor: None,
+            span: None,
+            starfield: None,
+            exposure: None,
+            recording: None,
+            export: None,
+            pipewire: None,
+            pixel_scale,
+            small: Vec::new(),
+            last_frame: Instant::now(),
+        })
+    }
+
+    // Top left of the output in the compositor's layout, which follows
+    // monitors being rearranged
+    fn position(&self) -> (i32, i32) {
+        with_output_info(&self.output, |info| info.location).unwrap_or((0, 0))
+    }
+
+    // When this surface may draw next, if it's waiting to
+    fn next_frame(&self, frame_interval: Option<Duration>) -> Option<Instant> {
+        if self.starfield.is_some() && !self.frame_due.get() {
+            return None;
+        }
+        Some(self.last_frame + frame_interval.unwrap_or_default())
+    }
+
+    fn draw(
+        &mut self,
+        new_starfield: &impl Fn(ScreenDetails, Sky, Option<&str>) -> Starfield,
+        recorder: &Recorder,
+    ) -> io::Result<()> {
+        // Nothing can be drawn until the compositor says how big the surface is
+        let Some((width, height)) = self.configured.get() else {
+            return Ok(());
+        };
+        let screen_details = ScreenDetails { width, height }.scaled_down(self.pixel_scale);
+        let (index, span) = (self.index, self.span);
+        let starfield = self.starfield.get_or_insert_with(|| match span {
+            Some(span) => {
+                let mut starfield = new_starfield(span.space, Sky::Spanning(span.outputs), None);
+                starfield.set_view(span.origin, screen_details);
+                starfield
+            }
+            None => {
+                let name = with_output_info(&self.output, |info| info.name.clone());
+                new_starfield(screen_details, Sky::Own(index), name.as_deref())
+            }
+        });
+        // Later configures follow mode changes and output rotation
+        if starfield.screen_details() != screen_details {
+            starfield.resize(screen_details);
+            if self.exposure.take().is_some() {
+                eprintln!("wl-starfield: output resized, capture abandoned");
+            }
+            if let Some(recording) = self.recording.take() {
+                save_recording(recording);
+            }
+        }
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        starfield.update(dt);
+        self.last_frame = now;
+
+        // Keep the output awake exactly while a focus session runs
+        match (starfield.is_focused(), &self.inhibitor, &self.idle_inhibit) {
+            (true, None, Some(manager)) => {
+                self.inhibitor = Some(manager.create_inhibitor(&self.surface));
+            }
+            (false, Some(inhibitor), _) => {
+                inhibitor.destroy();
+                self.inhibitor = None;
+            }
+            _ => {}
+        }
+
+        // The buffer is laid out the way the output's panel is mounted
+        let transform =
+            with_output_info(&self.output, |info| info.transform).unwrap_or(Transform::Normal);
+        let (w, h) = buffer_size(transform, (width as i32, height as i32));
+        let (canvas, buffer) = self.pool.buffer(w, h, w * 4, wl_shm::Format::Xrgb8888)?;
+        // Chunky pixels are drawn small and then blown up to fill the canvas,
+        // and a turned output's frame is turned to fit its buffer
+        let scale = self.pixel_scale as usize;
+        let direct = scale == 1 && transform == Transform::Normal;
+        let frame: &mut [u8] = if !direct {
+            let (small_width, small_height) = (screen_details.width, screen_details.height);
+            self.small
+                .resize(small_width as usize * small_height as usize * 4, 0);
+            &mut self.small
+        } else {
+            &mut *canvas
+        };
+        starfield.render(frame);
+        if let Some(exposure) = &mut self.exposure
+            && exposure.add(frame, dt)
+        {
+            save_capture(self.exposure.take().unwrap(), recorder.dir.clone());
+        }
+        if let Some((recording, _)) = &mut self.recording
+            && recording.add(frame, dt)
+        {
+            save_recording(self.recording.take().unwrap());
+        }
+        if let Some(export) = &mut self.export
+            && let Err(err) = export.publish(frame, screen_details)
+        {
+            eprintln!("wl-starfield: frame export stopped: {err}");
+            self.export = None;
+        }
+        if let Some(pipewire) = &mut self.pipewire
+            && let Err(err) = pipewire.publish(frame, screen_details)
+        {
+            eprintln!("wl-starfield: PipeWire source stopped: {err}");
+            self.pipewire = None;
+        }
+        if !direct {
+            present(
+                &self.small,
+                screen_details.width as usize,
+                canvas,
+                (width as usize, height as usize),
+                scale,
+                transform,
+            );
+        }
+        // RGBA -> little-endian XRGB, which is BGRX in memory
+        for pixel in canvas.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+
+        self.frame_due.set(false);
+        let due = self.frame_due.clone();
+        self.surface
+            .frame()
+            .quick_assign(move |_, _, _| due.set(true));
+        self.surface.attach(Some(&buffer), 0, 0);
+        self.surface.set_buffer_transform(transform);
+        self.surface.damage_buffer(0, 0, w, h);
+        self.surface.commit();
+        Ok(())
+    }
+}
+
+// Buffers go to the compositor already turned and mirrored the way the
+// output's panel is mounted, and the surface says so, so it can put them up as
+// they are instead of transforming every frame itself. The stars themselves
+// stay in the surface's logical space, which is the way the output is viewed.
+// A quarter turn swaps the buffer's width and height.
+fn buffer_size<T>(transform: Transform, (width, height): (T, T)) -> (T, T) {
+    match transform {
+        Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270 => {
+            (height, width)
+        }
+        _ => (width, height),
+    }
+}
+
+// Which pixel of the `width` x `height` surface lands on buffer pixel (x, y)
+// under `transform`. The flipped variants mirror left to right first and then
+// rotate counterclockwise, as wl_output lays them out.
+fn surface_pixel(
+    transform: Transform,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+) -> (usize, usize) {
+    let (right, bottom) = (width - 1, height - 1);
+    match transform {
+        Transform::_90 => (right - y, x),
+        Transform::_180 => (right - x, bottom - y),
+        Transform::_270 => (y, bottom - x),
+        Transform::Flipped => (right - x, y),
+        Transform::Flipped90 => (y, x),
+        Transform::Flipped180 => (x, bottom - y),
+        Transform::Flipped270 => (right - y, bottom - x),
+        _ => (x, y),
+    }
+}
+
+// Fill the buffer from the sky's frame, turned by `transform`: each frame
+// pixel blown up into a `scale` square of the surface, stretching the last row
+// and column over whatever is left over
+fn present(
+    frame: &[u8],
+    frame_width: usize,
+    canvas: &mut [u8],
+    surface: (usize, usize),
+    scale: usize,
+    transform: Transform,
+) {
+    let frame_height = frame.len() / 4 / frame_width;
+    let (buffer_width, _) = buffer_size(transform, surface);
+    for (y, row) in canvas.chunks_exact_mut(buffer_width * 4).enumerate() {
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let (sx, sy) = surface_pixel(transform, (x, y), surface);
+            let i = (sy / scale).min(frame_height - 1) * frame_width
+                + (sx / scale).min(frame_width - 1);
+            pixel.copy_from_slice(&frame[i * 4..i * 4 + 4]);
+        }
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        if let Some(inhibitor) = &self.inhibitor {
+            inhibitor.destroy();
+        }
+        self.layer_surface.destroy();
+        self.surface.destroy();
+    }
+}
+
+// A surface's place in the sky shared by every output: the size of the
+// layout space they cover, where the surface's top left sits in it, and how
+// many outputs there are
+#[derive(Clone, Copy, PartialEq)]
+struct Span {
+    space: ScreenDetails,
+    origin: (i32, i32),
+    outputs: usize,
+}
+
+// Lay one sky across every configured surface, as their outputs are laid out.
+// A surface's sky starts over whenever its place in it changes, so they all
+// do when outputs come, go, move or change size.
+fn span(surfaces: &mut [Surface], pixel_scale: u32) {
+    let placed = |s: &Surface| Some((s.position(), s.configured.get()?));
+    let (mut min, mut max) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+    let mut outputs = 0;
+    for ((x, y), (width, height)) in surfaces.iter().filter_map(placed) {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x + width as i32), max.1.max(y + height as i32));
+        outputs += 1;
+    }
+    let space = ScreenDetails {
+        width: max.0.saturating_sub(min.0).max(0) as u32,
+        height: max.1.saturating_sub(min.1).max(0) as u32,
+    }
+    .scaled_down(pixel_scale);
+    let scale = pixel_scale as i32;
+    for surface in surfaces {
+        let span = placed(surface).map(|((x, y), _)| Span {
+            space,
+            origin: ((x - min.0) / scale, (y - min.1) / scale),
+            outputs,
+        });
+        if surface.span != span {
+            surface.span = span;
+            surface.starfield = None;
+        }
+    }
+}
+
+// Cover every output (or just the --monitor one, by name or index) with a
+// background-layer surface, following outputs as they come and go, and
+// animate them until the connection drops or a quit command comes in
+pub fn run(
+    args: &Args,
+    frame_interval: Option<Duration>,
+    config: &Config,
+    commands: &Receiver<Command>,
+    hooks: &mut Hooks,
+    stagger: &mut Stagger,
+    new_starfield: impl Fn(ScreenDetails, Sky, Option<&str>) -> Starfield,
+) -> Result<(), Error> {
+    let exposure_seconds = config.exposure_seconds;
+    let (env, display, mut queue) = new_default_environment!(
+        Env,
+        fields = [
+            layer_shell: SimpleGlobal::new(),
+            idle_inhibit: SimpleGlobal::new(),
+        ]
+    )
+    .map_err(|_| Error::NoWayland)?;
+    let layer_shell = env
+        .get_global::<ZwlrLayerShellV1>()
+        .ok_or(Error::NoLayerShell)?;
+    let idle_inhibit = env.get_global::<ZwpIdleInhibitManagerV1>();
+
+    let surfaces = Rc::new(RefCell::new(Vec::<Surface>::new()));
+    let monitor = args.monitor.as_deref();
+    let wanted = monitor.map(str::to_string);
+    let pixel_scale = config.pixel_scale;
+    let mut next_index = 0;
+    let mut add_output = {
+        let (env, surfaces) = (env.clone(), surfaces.clone());
+        move |output: WlOutput, info: &OutputInfo, position: Option<usize>| {
+            if info.obsolete {
+                surfaces.borrow_mut().retain(|s| s.output_id != info.id);
+                output.release();
+                return;
+            }
+            if let Some(wanted) = &wanted {
+                let by_index = position.is_some_and(|i| i.to_string() == *wanted);
+                if info.name != *wanted && !by_index {
+                    return;
+                }
+            }
+            match Surface::new(
+                &env,
+                &layer_shell,
+                idle_inhibit.as_ref(),
+                &output,
+                info.id,
+                next_index,
+                pixel_scale,
+            ) {
+                Ok(surface) => surfaces.borrow_mut().push(surface),
+                Err(err) => eprintln!("wl-starfield: can't cover {}: {err}", info.name),
+            }
+            next_index += 1;
+        }
+    };
+
+    for (i, output) in env.get_all_outputs().into_iter().enumerate() {
+        if let Some(info) = with_output_info(&output, Clone::clone) {
+            add_output(output, &info, Some(i));
+        }
+    }
+    if let Some(wanted) = monitor
+        && surfaces.borrow().is_empty()
+    {
+        eprintln!("wl-starfield: no monitor named {wanted} yet, waiting for it");
+    }
+    let _listener = env.listen_for_outputs(move |output, info, _| add_output(output, info, None));
+    let mut status = StatusWriter::new();
+    let mut stats = StatsKeeper::new(config.stats_overlay);
+    let recorder = Recorder::new(config);
+    let mut record = args.record;
+    let mut export = frame_export::start(config.frame_export.as_deref());
+    let mut pipewire = PipewireSource::new(config, frame_interval);
+    let mut sky_report = SkyReport::new(config);
+    let mut accent = AccentWatch::new(config);
+    let mut layout = Vec::new();
+
+    loop {
+        surfaces.borrow_mut().retain(|s| !s.closed.get());
+        for command in commands.try_iter() {
+            let mut surfaces = surfaces.borrow_mut();
+            match command {
+                Command::Quit => return Ok(()),
+                Command::Capture => {
+                    eprintln!("wl-starfield: exposing for {exposure_seconds} s");
+                    for surface in surfaces.iter_mut() {
+                        let Some(starfield) = &surface.starfield else {
+                            continue;
+                        };
+                        let screen_details = starfield.screen_details();
+                        surface.exposure.get_or_insert_with(|| {
+                            Exposure::new(
+                                screen_details,
+                                exposure_seconds,
+                                config.capture_crop,
+                                config.capture_scale,
+                            )
+                        });
+                    }
+                }
+                command => {
+                    let command = match surfaces.iter().find_map(|s| s.starfield.as_ref()) {
+                        Some(sky) => command.settle(sky),
+                        None => command,
+                    };
+                    for starfield in surfaces.iter_mut().filter_map(|s| s.starfield.as_mut()) {
+                        command.apply(starfield);
+                    }
+                }
+            }
+        }
+
+        // Sleep until the earliest surface may draw, then draw all that are ready
+        let next = surfaces
+            .borrow()
+            .iter()
+            .filter_map(|s| s.next_frame(frame_interval))
+            .min();
+        if let Some(next) = next {
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+        let now = Instant::now();
+        let mut waiting = false;
+        if config.span_outputs {
+            span(&mut surfaces.borrow_mut(), config.pixel_scale);
+        }
+        if let Some(color) = accent.check() {
+            for surface in surfaces.borrow_mut().iter_mut() {
+                if let Some(starfield) = &mut surface.starfield {
+                    starfield.set_accent(Some(color));
+                }
+            }
+        }
+        layout.clear();
+        layout.extend(surfaces.borrow().iter().map(|s| (s.index, s.position().0)));
+        for (i, surface) in surfaces.borrow_mut().iter_mut().enumerate() {
+            match surface.next_frame(frame_interval) {
+                Some(at) if at <= now => {
+                    surface.draw(&new_starfield, &recorder)?;
+                    let Some(starfield) = &mut surface.starfield else {
+                        continue;
+                    };
+                    // --record starts on the first output as soon as it has a sky
+                    if i == 0
+                        && let Some(seconds) = record.take()
+                    {
+                        surface.recording =
+                            recorder.start(starfield.screen_details(), Some(seconds));
+                    }
+                    if i == 0 && export.is_some() {
+                        surface.export = export.take();
+                    }
+                    if i == 0 && pipewire.is_some() {
+                        surface.pipewire = pipewire.take();
+                    }
+                    stagger.frame(surface.index, starfield, &layout, now);
+                    hooks.fire(starfield.events());
+                    // Frame rate and state for status bars, and statistics, as
+                    // seen on the first output
+                    if i == 0 {
+                        status.frame(starfield);
+                        sky_report.check();
+                        stats.frame(starfield);
+                    }
+                    stats.show_on(starfield);
+                }
+                Some(_) => waiting = true,
+                None => {}
+            }
+        }
+
+        display.flush()?;
+        if waiting {
+            queue.dispatch_pending(&mut (), |_, _, _| {})?;
+        } else {
+            queue.dispatch(&mut (), |_, _, _| {})?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSFORMS: [Transform; 8] = [
+        Transform::Normal,
+        Transform::_90,
+        Transform::_180,
+        Transform::_270,
+        Transform::Flipped,
+        Transform::Flipped90,
+        Transform::Flipped180,
+        Transform::Flipped270,
+    ];
+
+    // A 3x2 frame whose pixels are numbered in reading order
+    fn numbered() -> Vec<u8> {
+        (0..6).flat_map(|n| [n, 0, 0, 255]).collect()
+    }
+
+    fn turned(transform: Transform, scale: usize) -> Vec<u8> {
+        let surface = (3 * scale, 2 * scale);
+        let mut canvas = vec![0; surface.0 * surface.1 * 4];
+        present(&numbered(), 3, &mut canvas, surface, scale, transform);
+        canvas.chunks_exact(4).map(|pixel| pixel[0]).collect()
+    }
+
+    #[test]
+    fn every_transform_shows_each_surface_pixel_exactly_once() {
+        for transform in TRANSFORMS {
+            let mut seen = turned(transform, 1);
+            seen.sort();
+            assert_eq!(seen, [0, 1, 2, 3, 4, 5], "{transform:?}");
+        }
+    }
+
+    #[test]
+    fn buffers_are_laid_out_the_way_the_panel_is_mounted() {
+        // Surface:  0 1 2
+        //           3 4 5
+        assert_eq!(turned(Transform::Normal, 1), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(turned(Transform::Flipped, 1), [2, 1, 0, 5, 4, 3]);
+        assert_eq!(turned(Transform::_180, 1), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(turned(Transform::Flipped180, 1), [3, 4, 5, 0, 1, 2]);
+        // Quarter turns are two pixels wide and three tall
+        assert_eq!(turned(Transform::_90, 1), [2, 5, 1, 4, 0, 3]);
+        assert_eq!(turned(Transform::_270, 1), [3, 0, 4, 1, 5, 2]);
+        assert_eq!(turned(Transform::Flipped90, 1), [0, 3, 1, 4, 2, 5]);
+        assert_eq!(turned(Transform::Flipped270, 1), [5, 2, 4, 1, 3, 0]);
+    }
+
+    #[test]
+    fn chunky_pixels_are_blown_up_before_turning() {
+        let buffer = turned(Transform::Flipped90, 2);
+        // Four pixels across, six down: each source pixel a 2x2 block
+        assert_eq!(&buffer[..8], [0, 0, 3, 3, 0, 0, 3, 3]);
+        assert_eq!(&buffer[16..], [2, 2, 5, 5, 2, 2, 5, 5]);
+    }
+}
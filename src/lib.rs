@@ -0,0 +1,3315 @@
+// The starfield simulation and CPU renderer. Embedders create a Starfield
+// for their surface size, call `update` with the frame time and `render` into
+// an RGBA8 buffer; windowing and input are left to them.
+
+pub mod accent;
+#[cfg(feature = "alloc-audit")]
+mod alloc_audit;
+mod backdrop;
+mod blackbody;
+mod bloom;
+mod clouds;
+mod comet;
+mod comet_catch;
+pub mod config;
+mod constellations;
+pub mod debug;
+pub mod exposure;
+mod flash_limit;
+pub mod gradient;
+mod heatmap;
+mod hud;
+pub mod i18n;
+mod lod;
+mod lore;
+mod milky_way;
+mod moon;
+mod nebula;
+mod orrery;
+mod palette;
+mod particles;
+mod planet;
+mod planetarium;
+pub mod profile;
+pub mod quote;
+pub mod recording;
+mod render;
+pub mod report;
+pub mod rng;
+mod satellite;
+pub mod schedule;
+pub mod skyline;
+mod stars;
+mod supernova;
+mod telescope;
+#[cfg(test)]
+mod test_support;
+mod text;
+mod tooltip;
+mod trails;
+mod transform;
+mod tunnel;
+pub mod tween;
+mod workers;
+
+use accent::Tint;
+use backdrop::{Backdrop, Image, Source};
+use bloom::Bloom;
+use clouds::Clouds;
+use comet::Comet;
+use comet_catch::CometCatch;
+use config::{Config, Quality};
+use constellations::Constellations;
+use debug::DebugOverlays;
+use flash_limit::FlashLimiter;
+use gradient::GradientMap;
+use heatmap::HeatMap;
+use i18n::Locale;
+use lod::Lod;
+use lore::LoreCache;
+use milky_way::MilkyWay;
+use moon::Moon;
+use nebula::Nebula;
+use orrery::Orrery;
+use palette::Palette;
+use particles::{Emitter, Kind, PARTICLE_CAP, Particles};
+use planet::Planet;
+use planetarium::Planetarium;
+use profile::{Profile, Stopwatch};
+use quote::QuoteOverlay;
+use rand::{Rng, SeedableRng};
+use render::{BACKGROUND, Pixel, Rect, Target, TileBins, blend, pack};
+use rng::FastRng;
+use satellite::Satellite;
+use skyline::{Outline, Skyline};
+use stars::{Glare, Stars};
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use supernova::Supernova;
+use telescope::Telescope;
+use tooltip::Tooltip;
+use trails::Trails;
+use tunnel::Tunnel;
+use tween::{Animation, Param, Timeline, Tweens};
+use workers::Workers;
+
+// Shooting-star trails are sampled every TRAIL_SPACING pixels of travel and
+// cover roughly TRAIL_DURATION seconds of flight, whatever the frame rate
+const TRAIL_SPACING: f32 = 4.0;
+const TRAIL_DURATION: f32 = 1.2;
+const TRAIL_MIN_SAMPLES: usize = 16;
+const TRAIL_MAX_SAMPLES: usize = 160;
+// Shooting stars that come down into the lowest ENTRY_ZONE of the screen hit
+// thicker air. They heat up over ENTRY_HEAT_TIME seconds, less the deeper
+// they are, turning toward ENTRY_COLOR and flaring at the head while they
+// shed sparks, then burn out over BURN_OUT_TIME.
+const ENTRY_ZONE: f32 = 0.15;
+const ENTRY_HEAT_TIME: f32 = 0.8;
+const BURN_OUT_TIME: f32 = 0.4;
+const ENTRY_COLOR: (u8, u8, u8) = (255, 140, 50);
+const ENTRY_FLARE: f32 = 16.0;
+// Sparks shed a second at full heat, left behind as the shooting star
+// plunges on and thrown off to the sides
+const SPARK_RATE: f32 = 60.0;
+const SPARKS: Kind = Kind {
+    inherit: 0.3,
+    speed: (24.0, 80.0),
+    life: 0.5,
+    color: (255, 190, 90),
+    alpha: 0.9,
+    size: 2,
+    gravity: 1.0,
+};
+// Average shooting stars per second: about one every 3-4 seconds
+const SHOOTING_STAR_RATE: f64 = 0.3;
+// Shooting stars (and spare trail buffers) to reserve room for up front
+const SHOOTING_STAR_POOL: usize = 16;
+// Room for this many satellites overhead before their list has to grow
+const SATELLITE_POOL: usize = 4;
+// Seconds the opening flight of the stars out from the center takes
+const INTRO_TIME: f32 = 2.0;
+// With blackbody colors, how much of a star's size comes from its heat
+// rather than chance, and how much dimmer the coolest stars are
+const HEAT_SIZE: f32 = 0.6;
+const HEAT_GLOW: f32 = 0.3;
+// How close (in pixels) the pointer must be to a star to hover it
+const HOVER_RADIUS: f32 = 8.0;
+// In warp, stars go this many times their drift speed at the vanishing point,
+// gaining as much again every WARP_FALLOFF of the shorter side they get out
+const WARP_SPEED: f32 = 8.0;
+const WARP_FALLOFF: f32 = 0.1;
+// Stars come out again within this fraction of the shorter side of the
+// vanishing point, and streak back along this many seconds of their path
+const WARP_SPAWN: f32 = 0.3;
+const WARP_STREAK: f32 = 0.08;
+const WARP_STREAK_ALPHA: f32 = 0.5;
+// In perspective, stars are this near when they pass the camera, and fade in
+// over this much of the way from the far end (at 1). The focal length is
+// this fraction of the shorter side, and stars show their own size this
+// far away, growing up to PERSPECTIVE_MAX_SIZE.
+const PERSPECTIVE_NEAR: f32 = 0.05;
+const PERSPECTIVE_FADE: f32 = 0.1;
+const PERSPECTIVE_FOCAL: f32 = 0.5;
+const PERSPECTIVE_SIZE_AT: f32 = 0.3;
+const PERSPECTIVE_MAX_SIZE: f32 = 8.0;
+// In high quality, a star's splat spreads SPLAT_SIGMA of its size either
+// side, and is drawn out to SPLAT_REACH spreads from its center
+const SPLAT_SIGMA: f32 = 0.45;
+const SPLAT_REACH: f32 = 3.0;
+// In smooth quality, stars at least this many pixels across get a round
+// rim; smaller ones are all rim already
+const SMOOTH_ROUND_SIZE: u32 = 3;
+// With diffraction spikes, big stars also glow with a Gaussian halo spreading
+// GLARE_SIGMA of their size either side, drawn out to GLARE_REACH spreads,
+// and their spikes start at GLARE_SPIKE_ALPHA and fade out towards the tips
+const GLARE_SIGMA: f32 = 0.9;
+const GLARE_REACH: f32 = 3.0;
+const GLARE_ALPHA: f32 = 0.35;
+const GLARE_SPIKE_ALPHA: f32 = 0.8;
+// Columns of a splat worked out up front; wider ones work the rest out as
+// they go
+const SPLAT_COLUMNS: usize = 32;
+// Pixels a star at depth 1 shifts by with the pointer at the edge of the
+// output, and how quickly (per second) the shift follows the pointer
+const PARALLAX_SHIFT: f32 = 12.0;
+const PARALLAX_EASE: f32 = 4.0;
+// A frame that runs the sky on by this many times an average frame's worth
+// counts as a time jump, whether it stalled or the sky sped up
+const ECHO_JUMP_FACTOR: f32 = 3.0;
+// Skipped seconds of sky time needed for a full-strength echo
+const ECHO_FULL_SKIP: f32 = 0.5;
+const ECHO_FADE_TIME: f32 = 0.3;
+// Stars deeper than HAZE_NEAR start to fade into the haze, fully by HAZE_FAR
+const HAZE_NEAR: f32 = 0.5;
+const HAZE_FAR: f32 = 4.0;
+// At the bottom of each breath the sky is dimmed by this much
+const BREATH_DEPTH: f32 = 0.45;
+// Seconds to ease breathing in or out when it's switched on or off
+const BREATH_FADE_TIME: f32 = 3.0;
+// The brightest stars now and then flash one frame of chromatic sparkle:
+// red, green and blue ghosts pulled SPARKLE_SPREAD pixels apart vertically.
+// Only stars whose unclamped brightness reaches SPARKLE_BRIGHTNESS qualify,
+// sparkles come about SPARKLE_RATE times a second, and never closer together
+// than SPARKLE_MIN_GAP seconds.
+const SPARKLE_BRIGHTNESS: f32 = 300.0;
+const SPARKLE_RATE: f64 = 0.4;
+const SPARKLE_MIN_GAP: f32 = 1.5;
+const SPARKLE_SPREAD: i32 = 2;
+const SPARKLE_ALPHA: f32 = 0.7;
+// Blue bends most toward the zenith, red least
+const SPARKLE_GHOSTS: [(i32, (u8, u8, u8)); 3] = [
+    (-SPARKLE_SPREAD, (120, 150, 255)),
+    (0, (150, 255, 150)),
+    (SPARKLE_SPREAD, (255, 130, 120)),
+];
+// Three clicks on a star, each within TRIPLE_CLICK_GAP seconds of the last,
+// set it off as a supernova, at most once every CLICK_SUPERNOVA_COOLDOWN
+// seconds
+const TRIPLE_CLICK_GAP: f32 = 0.5;
+const CLICK_SUPERNOVA_COOLDOWN: f32 = 5.0;
+// A focus session dims the sky by this much, fading over FOCUS_FADE_TIME seconds
+const FOCUS_DIM: f32 = 0.6;
+const FOCUS_FADE_TIME: f32 = 1.5;
+// Light from shooting stars, comets and sparkles is averaged over
+// LOUDNESS_WINDOW seconds, counted in fresh shooting stars on screen. Past
+// THROTTLE_START of the configured budget new events are held back more and
+// more, until none start at the budget itself.
+const LOUDNESS_WINDOW: f32 = 10.0;
+const THROTTLE_START: f32 = 0.75;
+const COMET_LOUDNESS: f32 = 1.0;
+const SATELLITE_LOUDNESS: f32 = 0.2;
+const SUPERNOVA_LOUDNESS: f32 = 1.5;
+// A sparkle's single frame counts as this many seconds of a shooting star
+const SPARKLE_LOUDNESS: f32 = 0.5;
+// In the heat map, a whole unit of loudness counts as this many pixels' worth
+// of light at full brightness
+const HEAT_EVENT_LIGHT: f32 = 50.0;
+// Sky time between rolls for new events, and the most that can be owed after
+// a stall, so a long one doesn't set off a backlog of them at once
+const EVENT_TICK: f32 = 0.1;
+const EVENT_BACKLOG: f32 = 1.0;
+// The sky is simulated in steps of this many seconds whatever the frame rate,
+// and at most this many times a frame; further behind, after a stall or in a
+// timelapse, the steps lengthen instead
+const SIM_STEP: f32 = 1.0 / 120.0;
+const MAX_STEPS: u32 = 64;
+
+// What the sky is up to, for status bars
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SkyStatus {
+    pub focused: bool,
+    pub breathing: bool,
+    // The moon shown, or today's if there isn't one: 0 new, 0.5 full
+    pub moon_phase: f32,
+    pub comet: bool,
+    pub satellites: usize,
+}
+
+// Something that happened in the sky during an update, for event hooks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkyEvent {
+    ShootingStar,
+    Comet,
+    Satellite,
+    Sparkle,
+    Supernova,
+    FocusStart,
+    FocusEnd,
+}
+
+impl SkyEvent {
+    pub const ALL: [SkyEvent; 7] = [
+        SkyEvent::ShootingStar,
+        SkyEvent::Comet,
+        SkyEvent::Satellite,
+        SkyEvent::Sparkle,
+        SkyEvent::Supernova,
+        SkyEvent::FocusStart,
+        SkyEvent::FocusEnd,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkyEvent::ShootingStar => "shooting_star",
+            SkyEvent::Comet => "comet",
+            SkyEvent::Satellite => "satellite",
+            SkyEvent::Sparkle => "sparkle",
+            SkyEvent::Supernova => "supernova",
+            SkyEvent::FocusStart => "focus_start",
+            SkyEvent::FocusEnd => "focus_end",
+        }
+    }
+}
+
+// What the sky shows
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Scene {
+    // Drifting stars, or the real sky in planetarium mode
+    #[default]
+    Starfield,
+    // The planets going round the sun, seen from above
+    Orrery,
+    // Rings of stars rushing past at warp speed
+    Tunnel,
+}
+
+impl Scene {
+    pub const ALL: [Scene; 3] = [Scene::Starfield, Scene::Orrery, Scene::Tunnel];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scene::Starfield => "starfield",
+            Scene::Orrery => "orrery",
+            Scene::Tunnel => "tunnel",
+        }
+    }
+
+    // The one after this, round to the first again
+    pub fn next(self) -> Scene {
+        let i = Scene::ALL
+            .iter()
+            .position(|&scene| scene == self)
+            .unwrap_or(0);
+        Scene::ALL[(i + 1) % Scene::ALL.len()]
+    }
+}
+
+impl FromStr for Scene {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        Scene::ALL
+            .into_iter()
+            .find(|scene| scene.name() == name)
+            .ok_or(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenDetails {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Orientation {
+    Landscape,
+    Portrait,
+}
+
+impl ScreenDetails {
+    // The size to simulate at for pixels `scale` screen pixels across
+    pub fn scaled_down(self, scale: u32) -> ScreenDetails {
+        ScreenDetails {
+            width: (self.width / scale).max(1),
+            height: (self.height / scale).max(1),
+        }
+    }
+
+    // Both backends simulate in the output's logical, already transformed
+    // space, the way it is viewed; wallpaper mode turns and mirrors only the
+    // finished buffer to match the panel. So a rotated output is simply a sky
+    // taller than it is wide, and everything that cares about direction asks
+    // this.
+    fn orientation(&self) -> Orientation {
+        if self.height > self.width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+
+    // Which way the stars drift, as a step of length 1 in screen pixels: at
+    // `angle` degrees counterclockwise from rightward, or along the long axis
+    // with no angle set
+    fn drift(&self, angle: Option<f32>) -> (f32, f32) {
+        match (angle, self.orientation()) {
+            (Some(angle), _) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                // Straight left, down and so on exactly, not off by a hair
+                let snap = |c: f32| if c.abs() < 1e-6 { 0.0 } else { c };
+                (snap(cos), snap(-sin))
+            }
+            (None, Orientation::Landscape) => (-1.0, 0.0),
+            (None, Orientation::Portrait) => (0.0, 1.0),
+        }
+    }
+}
+
+// A star or shooting-star blob handed to a GPU renderer instead of being
+// drawn into the frame. `center` and `radius` are in frame pixels; hard
+// sprites fill their square, soft ones fade out toward the rim like the CPU
+// shooting-star points. Colors are final, with haze and dimming applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    pub center: (f32, f32),
+    pub radius: f32,
+    pub color: (u8, u8, u8),
+    pub alpha: f32,
+    pub soft: bool,
+}
+
+// Everything an object needs to advance by one frame
+struct UpdateContext<'a> {
+    dt: f32,
+    // The sky's clock: the time of day, unless a timelapse is running it fast
+    now: SystemTime,
+    rng: &'a mut FastRng,
+    screen_details: &'a ScreenDetails,
+    config: &'a Config,
+    // Where anything that sheds sparks or debris puts them
+    particles: &'a mut Particles,
+}
+
+// Common trait for all celestial objects
+trait CelestialObject {
+    fn update(&mut self, ctx: &mut UpdateContext);
+    fn draw(&self, target: &mut Target);
+    fn bounds(&self) -> Rect;
+    fn is_alive(&self, screen_details: &ScreenDetails) -> bool;
+    // Where the object is and how fast it's going, in pixels and pixels per
+    // second, for the debug overlays
+    fn motion(&self, screen_details: &ScreenDetails) -> ((f32, f32), (f32, f32));
+}
+
+struct ShootingStar {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    life: f32,
+    max_life: f32,
+    trail: VecDeque<(f32, f32)>,
+    trail_max_len: usize,
+    // Trail samples drawn, one in this many, from the level of detail
+    trail_step: usize,
+    // Where it was before the last step, and how far back towards there it
+    // is drawn, as for stars
+    last: (f32, f32),
+    lag: (f32, f32),
+    // The sky's accent color, if it has one
+    tint: Option<Tint>,
+    entry: Entry,
+    // Shedding sparks on entry
+    sparks: Emitter,
+}
+
+// How far a shooting star has got into the thick air near the ground
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Entry {
+    // High up, as they all start
+    Cruising,
+    // Heating up, 0..1
+    Heating(f32),
+    // Burning out, with this much of it left, 1 down to 0
+    BurningOut(f32),
+}
+
+impl CelestialObject for ShootingStar {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        let dt = ctx.dt;
+        // Update physics
+        self.x += self.vx * dt;
+        self.vy += ctx.config.shooting_star_gravity * dt;
+        self.y += self.vy * dt;
+        self.life += dt;
+
+        self.record_trail();
+
+        // How far into the entry zone it is, 0 at the top and 1 at the bottom
+        let height = ctx.screen_details.height as f32;
+        let depth = (self.y / height - (1.0 - ENTRY_ZONE)) / ENTRY_ZONE;
+        self.entry = match self.entry {
+            Entry::Cruising if depth > 0.0 => Entry::Heating(0.0),
+            Entry::Heating(heat) => {
+                let heat = heat + dt * (1.0 + depth.clamp(0.0, 1.0)) / ENTRY_HEAT_TIME;
+                if heat >= 1.0 {
+                    Entry::BurningOut(1.0)
+                } else {
+                    Entry::Heating(heat)
+                }
+            }
+            Entry::BurningOut(left) => Entry::BurningOut((left - dt / BURN_OUT_TIME).max(0.0)),
+            entry => entry,
+        };
+
+        if let Entry::Heating(heat) = self.entry {
+            self.sparks.trickle(
+                ctx.particles,
+                dt,
+                SPARK_RATE * heat,
+                (self.x, self.y),
+                (self.vx, self.vy),
+            );
+        }
+    }
+
+    fn draw(&self, target: &mut Target) {
+        self.points(|x, y, color, alpha, size| target.soft_point(x, y, color, alpha, size));
+    }
+
+    fn bounds(&self) -> Rect {
+        let (lag_x, lag_y) = self.lag;
+        let head = Rect::around(
+            self.x + lag_x,
+            self.y + lag_y,
+            3.0 + self.heat() * ENTRY_FLARE / 2.0,
+        );
+        self.trail.iter().fold(head, |rect, &(tx, ty)| {
+            rect.union(&Rect::around(tx + lag_x, ty + lag_y, 2.0))
+        })
+    }
+
+    fn is_alive(&self, screen_details: &ScreenDetails) -> bool {
+        self.life < self.max_life
+            && self.entry != Entry::BurningOut(0.0)
+            && self.x > -200.0
+            && self.x < screen_details.width as f32 + 200.0
+            && self.y > -200.0
+            && self.y < screen_details.height as f32 + 200.0
+    }
+
+    fn motion(&self, _: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        ((self.x, self.y), (self.vx, self.vy))
+    }
+}
+
+impl ShootingStar {
+    // `trail` is a recycled buffer; whatever it holds is discarded
+    fn new(start_x: f32, start_y: f32, vx: f32, vy: f32, mut trail: VecDeque<(f32, f32)>) -> Self {
+        let max_life = 3.0;
+        trail.clear();
+        trail.reserve(TRAIL_MAX_SAMPLES);
+        trail.push_back((start_x, start_y));
+        Self {
+            x: start_x,
+            y: start_y,
+            vx,
+            vy,
+            life: 0.0,
+            max_life,
+            trail,
+            trail_max_len: Self::trail_len_for(vx, vy),
+            trail_step: 1,
+            last: (start_x, start_y),
+            lag: (0.0, 0.0),
+            tint: None,
+            entry: Entry::Cruising,
+            sparks: Emitter::new(
+                SPARKS,
+                (start_x.to_bits() as u64) << 32 | start_y.to_bits() as u64,
+            ),
+        }
+    }
+
+    // Brightness left as it burns out, 1 down to 0
+    fn fade(&self) -> f32 {
+        let burning = match self.entry {
+            Entry::BurningOut(left) => left,
+            _ => 1.0,
+        };
+        (1.0 - self.life / self.max_life).clamp(0.0, 1.0) * burning
+    }
+
+    // How hot entry has made it, 0 until it reaches the thick air
+    fn heat(&self) -> f32 {
+        match self.entry {
+            Entry::Cruising => 0.0,
+            Entry::Heating(heat) => heat,
+            Entry::BurningOut(_) => 1.0,
+        }
+    }
+
+    // Every blob the shooting star is drawn as, tail first: center, color,
+    // opacity and diameter
+    fn points(&self, mut point: impl FnMut(f32, f32, (u8, u8, u8), f32, i32)) {
+        let alpha = self.fade();
+        let (lag_x, lag_y) = self.lag;
+        let heat = self.heat();
+        // Toward orange as it heats up
+        let tinted = |color| {
+            let mut pixel = pack(color);
+            if heat > 0.0 {
+                blend(&mut pixel, ENTRY_COLOR, heat);
+            }
+            let color = (pixel[0], pixel[1], pixel[2]);
+            match self.tint {
+                Some(tint) => tint.apply(color),
+                None => color,
+            }
+        };
+
+        // Draw trail using stored positions, counting samples back from the
+        // head so it keeps its place when only some are drawn
+        let len = self.trail.len();
+        for (i, &(tx, ty)) in self.trail.iter().enumerate() {
+            if !(len - 1 - i).is_multiple_of(self.trail_step) {
+                continue;
+            }
+            let trail_progress = i as f32 / self.trail.len() as f32;
+            let trail_alpha = alpha * trail_progress * trail_progress; // Quadratic falloff
+
+            if trail_alpha < 0.01 {
+                continue; // Skip nearly invisible segments
+            }
+
+            // Color gradient: white/yellow at head to orange/red at tail
+            let r = (255.0 * (0.8 + 0.2 * trail_progress)) as u8;
+            let g = (255.0 * (0.6 + 0.4 * trail_progress)) as u8;
+            let b = (100.0 + 155.0 * (1.0 - trail_progress)) as u8;
+
+            // Variable width: thicker at head, thinner at tail, and wider to
+            // cover the gaps when samples are skipped
+            let width = ((1.0 + 3.0 * trail_progress) * self.trail_step as f32) as i32;
+
+            point(
+                tx + lag_x,
+                ty + lag_y,
+                tinted((r, g, b)),
+                trail_alpha,
+                width,
+            );
+        }
+
+        // Draw bright head, brighter still and flaring in a haze as it
+        // heats up
+        if alpha > 0.01 {
+            let bright = (alpha * (1.0 + heat)).min(1.0);
+            if heat > 0.0 {
+                point(
+                    self.x + lag_x,
+                    self.y + lag_y,
+                    tinted((255, 255, 220)),
+                    bright * heat,
+                    (6.0 + heat * ENTRY_FLARE) as i32,
+                );
+            }
+            let head_size = 6;
+            point(
+                self.x + lag_x,
+                self.y + lag_y,
+                tinted((255, 255, 220)),
+                bright,
+                head_size,
+            );
+        }
+    }
+
+    // Faster stars get proportionally more samples so the trail spans the same
+    // stretch of flight time
+    fn trail_len_for(vx: f32, vy: f32) -> usize {
+        let samples = (vx.hypot(vy) * TRAIL_DURATION / TRAIL_SPACING) as usize;
+        samples.clamp(TRAIL_MIN_SAMPLES, TRAIL_MAX_SAMPLES)
+    }
+
+    // Lay samples every TRAIL_SPACING pixels from the last one toward the head.
+    // A long frame adds several samples, a short one may add none.
+    fn record_trail(&mut self) {
+        let Some(&(last_x, last_y)) = self.trail.back() else {
+            self.trail.push_back((self.x, self.y));
+            return;
+        };
+
+        let (dx, dy) = (self.x - last_x, self.y - last_y);
+        let dist = dx.hypot(dy);
+        let steps = (dist / TRAIL_SPACING) as usize;
+        for step in 1..=steps {
+            // Trim before pushing so the buffer never outgrows its reservation
+            if self.trail.len() >= self.trail_max_len {
+                self.trail.pop_front();
+            }
+            let t = step as f32 * TRAIL_SPACING / dist;
+            self.trail.push_back((last_x + dx * t, last_y + dy * t));
+        }
+    }
+
+    // The stretch of edge, just off-screen, that shooting stars enter along:
+    // the top 40% of the right edge on landscape outputs, and the right half
+    // of the top edge on portrait ones
+    fn spawn_zone(screen_details: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        match screen_details.orientation() {
+            Orientation::Landscape => ((width + 50.0, 50.0), (width + 50.0, height * 0.4)),
+            Orientation::Portrait => ((width * 0.5, -50.0), (width - 50.0, -50.0)),
+        }
+    }
+
+    fn spawn(
+        rng: &mut impl Rng,
+        screen_details: &ScreenDetails,
+        trail: VecDeque<(f32, f32)>,
+    ) -> Self {
+        let ((x0, y0), (x1, y1)) = Self::spawn_zone(screen_details);
+        let t: f32 = rng.r#gen();
+        let (start_x, start_y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+
+        match screen_details.orientation() {
+            Orientation::Landscape => {
+                // Mostly horizontal
+                let vx = -rng.gen_range(200.0..400.0); // Faster horizontal speed
+                let vy = rng.gen_range(10.0..50.0); // Moderate downward speed
+                Self::new(start_x, start_y, vx, vy, trail)
+            }
+            Orientation::Portrait => {
+                // Falling steeply so the streak uses the tall axis instead of
+                // crossing the narrow one
+                let vx = -rng.gen_range(60.0..150.0);
+                let vy = rng.gen_range(200.0..350.0);
+                Self::new(start_x, start_y, vx, vy, trail)
+            }
+        }
+    }
+}
+
+// Tracks abrupt jumps in simulated time so skipped motion is smeared
+// instead of stars visibly teleporting
+struct TimeEcho {
+    avg_dt: Option<f32>,
+    // The time scale the last frame ran at
+    timelapse: f32,
+    strength: f32,
+}
+
+impl TimeEcho {
+    fn new() -> Self {
+        Self {
+            avg_dt: None,
+            timelapse: 1.0,
+            strength: 0.0,
+        }
+    }
+
+    // Returns true when this frame, of `dt` seconds with the sky running
+    // `timelapse` times as fast, is a jump that should start a new echo:
+    // either the frame stalled or the time scale leapt up
+    fn observe(&mut self, dt: f32, timelapse: f32) -> bool {
+        let avg = *self.avg_dt.get_or_insert(dt);
+        let last = std::mem::replace(&mut self.timelapse, timelapse);
+        // Sky time gone by beyond a few ordinary frames' worth at the old speed
+        let skipped = dt * timelapse - avg * last * ECHO_JUMP_FACTOR;
+
+        // Leave stalls out of the average so back-to-back hitches still register
+        if dt <= avg * ECHO_JUMP_FACTOR {
+            self.avg_dt = Some(avg + (dt - avg) * 0.1);
+        }
+        if skipped > 0.0 {
+            self.strength = (skipped / ECHO_FULL_SKIP).min(1.0);
+            return true;
+        }
+        self.strength = (self.strength - dt / ECHO_FADE_TIME).max(0.0);
+        false
+    }
+}
+
+// Global gain envelope for the relaxation mode. The sky brightens while
+// breathing in and dims while breathing out, on a raised cosine so neither
+// end of a breath has a hard edge.
+struct Breath {
+    enabled: bool,
+    // Position within the current breath, 0..1, starting fully exhaled
+    phase: f32,
+    // Eases toward 1 while enabled and back to 0 after, so toggling never pops
+    strength: f32,
+}
+
+impl Breath {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            phase: 0.0,
+            strength: if enabled { 1.0 } else { 0.0 },
+        }
+    }
+
+    fn update(&mut self, dt: f32, breaths_per_minute: f32) {
+        let step = dt / BREATH_FADE_TIME;
+        self.strength = if self.enabled {
+            (self.strength + step).min(1.0)
+        } else {
+            (self.strength - step).max(0.0)
+        };
+        if self.strength > 0.0 {
+            self.phase = (self.phase + dt * breaths_per_minute / 60.0).fract();
+        } else {
+            // Start the next session from a fresh exhale
+            self.phase = 0.0;
+        }
+    }
+
+    // How far to fade the sky toward the background this frame
+    fn dimming(&self) -> f32 {
+        let exhaled = 0.5 + 0.5 * (self.phase * std::f32::consts::TAU).cos();
+        let strength = self.strength * self.strength * (3.0 - 2.0 * self.strength);
+        exhaled * strength * BREATH_DEPTH
+    }
+}
+
+// How busy the sky's passing events have been lately, so that stacked ones
+// don't turn the scene chaotic
+struct Loudness {
+    level: f32,
+}
+
+impl Loudness {
+    fn new() -> Self {
+        Self { level: 0.0 }
+    }
+
+    // `glow` is the light events are giving off now, `flashes` the one-frame
+    // bursts that went off this frame
+    fn observe(&mut self, dt: f32, glow: f32, flashes: f32) {
+        self.level += (glow - self.level) * (1.0 - (-dt / LOUDNESS_WINDOW).exp());
+        self.level += flashes / LOUDNESS_WINDOW;
+    }
+
+    // Share of their usual rate new events may start at, 0..1. A budget of 0
+    // never holds anything back.
+    fn allowance(&self, budget: f32) -> f64 {
+        if budget <= 0.0 {
+            return 1.0;
+        }
+        let headroom = (budget - self.level) / (budget * (1.0 - THROTTLE_START));
+        headroom.clamp(0.0, 1.0) as f64
+    }
+}
+
+// How far back towards `last` something now at `now` is drawn, `behind` of
+// a step short of it; not at all if it jumped there, wrapping around
+fn lag_behind(
+    last: (f32, f32),
+    now: (f32, f32),
+    behind: f32,
+    screen_details: &ScreenDetails,
+) -> (f32, f32) {
+    let (dx, dy) = (last.0 - now.0, last.1 - now.1);
+    if dx.abs() > screen_details.width as f32 / 2.0 || dy.abs() > screen_details.height as f32 / 2.0
+    {
+        return (0.0, 0.0);
+    }
+    (dx * behind, dy * behind)
+}
+
+// Helper function to draw the binned objects overlapping one tile
+fn draw_objects<T: CelestialObject>(
+    objects: &[T],
+    bins: &TileBins,
+    tile: usize,
+    target: &mut Target,
+) {
+    for i in bins.get(tile) {
+        objects[i].draw(target);
+    }
+}
+
+// What makes up a scene: the drifting stars, or whatever takes their place
+struct Scenery {
+    stars: Stars,
+    planetarium: Option<Planetarium>,
+    orrery: Option<Orrery>,
+    tunnel: Option<Tunnel>,
+}
+
+impl Scenery {
+    fn new(rng: &mut FastRng, screen_details: &ScreenDetails, config: &Config) -> Self {
+        let scene = config.scene;
+        let planetarium = config
+            .latitude
+            .zip(config.longitude)
+            .filter(|_| scene == Scene::Starfield)
+            .map(|(latitude, longitude)| {
+                Planetarium::new(rng, latitude, longitude, config.star_count)
+            });
+        let orrery = (scene == Scene::Orrery).then(|| {
+            Orrery::new(
+                rng,
+                screen_details,
+                config.orrery_days_per_second,
+                SystemTime::now(),
+            )
+        });
+        let tunnel = (scene == Scene::Tunnel).then(|| Tunnel::new(rng));
+        let drifting = if scene == Scene::Starfield && planetarium.is_none() {
+            config.star_count
+        } else {
+            0
+        };
+        let mut stars = Stars::default();
+        for _ in 0..drifting {
+            stars.spawn(rng, config, screen_details.width, screen_details.height);
+        }
+        stars.glare = config.diffraction_spikes.then_some(Glare {
+            min_size: config.spike_min_size,
+            length: config.spike_length,
+        });
+        stars.additive = config.background_image.is_some() || config.background_gradient.is_some();
+        if config.intro {
+            stars.expand_from(
+                screen_details.width as f32 / 2.0,
+                screen_details.height as f32 / 2.0,
+            );
+        }
+        Self {
+            stars,
+            planetarium,
+            orrery,
+            tunnel,
+        }
+    }
+}
+
+// Everything that lives on screen, plus the scratch state needed to draw it
+pub struct Starfield {
+    // The whole sky, and the part of it this output's frames show: all of it
+    // unless the sky spans several outputs
+    screen_details: ScreenDetails,
+    view: Rect,
+    config: Config,
+    rng: FastRng,
+    // Seeds for each tick's event rolls, and sky time since the last one
+    event_rng: FastRng,
+    event_clock: f32,
+    // What time it is in the sky, and how many times faster than real time
+    // it passes
+    clock: SystemTime,
+    timelapse: f32,
+    // Advance the clock by frame times alone, even in real time
+    fixed_clock: bool,
+    // Updates leave everything as it is while paused
+    paused: bool,
+    // Sky time not yet simulated, less than a step
+    backlog: f32,
+    // Settings being eased to new values, and how bright the sky is, 0..1
+    tweens: Tweens,
+    brightness: f32,
+    // Drifting stars, or none when the planetarium shows the real sky or
+    // another scene is on instead
+    stars: Stars,
+    planetarium: Option<Planetarium>,
+    orrery: Option<Orrery>,
+    tunnel: Option<Tunnel>,
+    // A scene to change to at the next frame, and the change under way:
+    // the last frame of the old scene, dissolving into the new one 0..1
+    next_scene: Option<Scene>,
+    crossfade: Option<(Vec<Pixel>, f32)>,
+    // Whether any frame has been drawn yet, to fade from
+    drawn: bool,
+    // Seconds local time is ahead of UTC, for scene schedules, and the scene
+    // the schedule last called for
+    utc_offset: i64,
+    scheduled: Option<Scene>,
+    shooting_stars: Vec<ShootingStar>,
+    // Trail buffers of dead shooting stars, reused by new ones
+    spare_trails: Vec<VecDeque<(f32, f32)>>,
+    echo: TimeEcho,
+    breath: Breath,
+    loudness: Loudness,
+    // Star flashing a chromatic sparkle this frame, and seconds until another may
+    sparkle: Option<usize>,
+    sparkle_cooldown: f32,
+    focused: bool,
+    // Shooting stars and comets only start when triggered from outside
+    following: bool,
+    // Focus state as of the last update, to notice it changing
+    was_focused: bool,
+    // What happened during the last update
+    events: Vec<SkyEvent>,
+    // How far into focus dimming the sky is, 0..1
+    focus_fade: f32,
+    star_bins: TileBins,
+    echo_bins: TileBins,
+    shooting_star_bins: TileBins,
+    // Sparks and debris, whatever shed them
+    particles: Particles,
+    milky_way: Option<MilkyWay>,
+    nebula: Option<Nebula>,
+    clouds: Option<Clouds>,
+    // The indexed palette pixel art is brought down to, if it cycles
+    palette: Option<Palette>,
+    // The gradient everything is mapped through in monochrome mode
+    gradient: Option<GradientMap>,
+    // What the sky is drawn on, if not plain background
+    backdrop: Option<Backdrop>,
+    // Light from the brightest things spread into the sky around them
+    bloom: Option<Bloom>,
+    // The last sky, faded, for this one to be drawn over in star trails mode
+    trails: Option<Trails>,
+    // The ground, in front of the whole sky
+    skyline: Option<Skyline>,
+    // Holds back any part of the frame changing brightness too fast
+    flash_limiter: Option<FlashLimiter>,
+    // At most one comet is in the sky at a time
+    comet: Option<Comet>,
+    // The comet catch toy, if it's being played
+    comet_catch: Option<CometCatch>,
+    satellites: Vec<Satellite>,
+    planets: Vec<Planet>,
+    // A star going supernova, and then its remnant
+    supernova: Option<Supernova>,
+    moon: Option<Moon>,
+    telescope: Option<Telescope>,
+    constellations: Constellations,
+    // Kept from frame to frame to draw them with
+    workers: Workers,
+    // Level of detail, and time spent on the frame so far
+    lod: Lod,
+    work: Duration,
+    // Time spent in each part since last taken, while profiling
+    profile: Option<Profile>,
+    // The accent color stars and shooting stars are tinted towards
+    tint: Option<Tint>,
+    pub quotes: Option<QuoteOverlay>,
+    // Lines of text for a corner of the screen, set by the embedder
+    pub hud: Vec<String>,
+    // Pointer position on the output, for hovering stars and parallax
+    pub cursor: Option<(f32, f32)>,
+    // Where the pointer was last clicked, until the next update takes it up
+    click: Option<(f32, f32)>,
+    // The star clicked on last, by seed, how many times in a row, and
+    // seconds since; and seconds until clicks may set off another supernova
+    clicks: Option<(u64, u32)>,
+    since_click: f32,
+    click_cooldown: f32,
+    // Where the parallax has eased to, -1..1 across and down the output
+    parallax: (f32, f32),
+    lore: LoreCache,
+    // Tooltip for the hovered star, keyed by that star's seed
+    tooltip: Option<(u64, Tooltip)>,
+    pub debug: DebugOverlays,
+    heat_map: HeatMap,
+}
+
+impl Starfield {
+    pub fn new(mut rng: FastRng, screen_details: ScreenDetails, config: Config) -> Self {
+        let Scenery {
+            stars,
+            planetarium,
+            orrery,
+            tunnel,
+        } = Scenery::new(&mut rng, &screen_details, &config);
+        let mut tweens = Tweens::default();
+        if let Some(timeline) = &config.timeline {
+            tweens.play(timeline);
+        }
+        let tint = config
+            .accent
+            .and_then(|accent| accent.color())
+            .map(|color| Tint::new(color, config.accent_strength));
+        let milky_way = config.milky_way.then(|| {
+            MilkyWay::new(
+                &mut rng,
+                &screen_details,
+                config.milky_way_angle,
+                config.milky_way_brightness,
+            )
+        });
+        let nebula = config.nebula.then(|| {
+            Nebula::new(
+                &mut rng,
+                &screen_details,
+                config.nebula_hue,
+                config.nebula_density,
+                config.nebula_drift_speed,
+            )
+        });
+        let clouds = config.clouds.then(|| {
+            Clouds::new(
+                &mut rng,
+                &screen_details,
+                config.cloud_wind_speed,
+                config.cloud_wind_direction,
+            )
+        });
+        let palette = (config.palette_cycling && config.pixel_scale > 1).then(Palette::new);
+        let gradient = config.gradient.as_ref().map(GradientMap::new);
+        // An image that won't load leaves the gradient, if there is one
+        let image = config.background_image.as_ref().and_then(|path| {
+            Image::load(path)
+                .inspect_err(|err| eprintln!("wl-starfield: can't load {}: {err}", path.display()))
+                .ok()
+        });
+        let backdrop = image
+            .map(Source::Image)
+            .or_else(|| config.background_gradient.clone().map(Source::Gradient))
+            .map(|source| Backdrop::new(source, config.background_tint));
+        let bloom = config
+            .bloom
+            .then(|| Bloom::new(config.bloom_threshold, config.bloom_strength));
+        let trails = config.star_trails.then(|| Trails::new(config.trail_decay));
+        let flash_limiter =
+            (config.flash_limit > 0.0).then(|| FlashLimiter::new(config.flash_limit));
+        let moon = (config.moon && config.scene == Scene::Starfield)
+            .then(|| Moon::new(&mut rng, &screen_details, config.moon_phase));
+        let telescope = config
+            .telescope
+            .then(|| Telescope::new(&mut rng, &screen_details));
+        let locale = Locale::detect();
+        let comet_catch = config
+            .comet_catch
+            .then(|| CometCatch::new(&mut rng, &screen_details, locale));
+        let breath = Breath::new(config.breathing);
+        let constellations = Constellations::new(config.constellations);
+        let event_rng = FastRng::seed_from_u64(rng.r#gen());
+        let planets = match config.planets {
+            0 => Vec::new(),
+            count => Planet::spawn(&mut rng, &config, &screen_details, count),
+        };
+        // A mask that won't load leaves the shape, if there is one
+        let mask = config.skyline_mask.as_ref().and_then(|path| {
+            Image::load(path)
+                .inspect_err(|err| eprintln!("wl-starfield: can't load {}: {err}", path.display()))
+                .ok()
+        });
+        let skyline = mask
+            .map(Outline::Mask)
+            .or_else(|| config.skyline.map(Outline::Shape))
+            .map(|outline| {
+                Skyline::new(
+                    outline,
+                    config.skyline_height,
+                    config.skyline_lights,
+                    rng.r#gen(),
+                )
+            });
+        let render_threads = match config.render_threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+
+        Self {
+            screen_details,
+            view: Rect::new(
+                0,
+                0,
+                screen_details.width as i32,
+                screen_details.height as i32,
+            ),
+            config,
+            rng,
+            event_rng,
+            event_clock: 0.0,
+            clock: SystemTime::now(),
+            timelapse: 1.0,
+            fixed_clock: false,
+            paused: false,
+            backlog: 0.0,
+            tweens,
+            brightness: 1.0,
+            stars,
+            planetarium,
+            orrery,
+            tunnel,
+            next_scene: None,
+            crossfade: None,
+            drawn: false,
+            utc_offset: 0,
+            scheduled: None,
+            shooting_stars: Vec::with_capacity(SHOOTING_STAR_POOL),
+            spare_trails: (0..SHOOTING_STAR_POOL)
+                .map(|_| VecDeque::with_capacity(TRAIL_MAX_SAMPLES))
+                .collect(),
+            echo: TimeEcho::new(),
+            breath,
+            loudness: Loudness::new(),
+            sparkle: None,
+            sparkle_cooldown: SPARKLE_MIN_GAP,
+            focused: false,
+            following: false,
+            was_focused: false,
+            // Room for every kind at once, so updates never allocate
+            events: Vec::with_capacity(SkyEvent::ALL.len()),
+            focus_fade: 0.0,
+            star_bins: TileBins::new(),
+            echo_bins: TileBins::new(),
+            shooting_star_bins: TileBins::new(),
+            particles: Particles::new(),
+            milky_way,
+            nebula,
+            clouds,
+            palette,
+            gradient,
+            backdrop,
+            bloom,
+            trails,
+            skyline,
+            flash_limiter,
+            comet: None,
+            comet_catch,
+            satellites: Vec::with_capacity(SATELLITE_POOL),
+            planets,
+            supernova: None,
+            moon,
+            telescope,
+            constellations,
+            workers: Workers::new(render_threads),
+            lod: Lod::default(),
+            work: Duration::ZERO,
+            profile: None,
+            tint,
+            quotes: None,
+            hud: Vec::new(),
+            cursor: None,
+            click: None,
+            clicks: None,
+            since_click: 0.0,
+            click_cooldown: 0.0,
+            parallax: (0.0, 0.0),
+            lore: LoreCache::new(locale),
+            tooltip: None,
+            debug: DebugOverlays::default(),
+            heat_map: HeatMap::default(),
+        }
+    }
+
+    // Size of the frames it renders
+    pub fn screen_details(&self) -> ScreenDetails {
+        ScreenDetails {
+            width: (self.view.x1 - self.view.x0) as u32,
+            height: (self.view.y1 - self.view.y0) as u32,
+        }
+    }
+
+    // Make this output one of several windows onto a sky spanning them all:
+    // frames show just the `size` part of it at `origin`. Copies of the same
+    // sky (same seed, size and config) on each output keep in step, so stars
+    // and shooting stars line up where the outputs meet.
+    pub fn set_view(&mut self, (x, y): (i32, i32), size: ScreenDetails) {
+        self.view = Rect::new(x, y, x + size.width as i32, y + size.height as i32);
+        if let Some(milky_way) = &mut self.milky_way {
+            milky_way.show(self.view);
+        }
+    }
+
+    // How long each frame's updating and drawing may take before the
+    // costliest effects are simplified, or none to keep full detail
+    pub fn set_frame_budget(&mut self, budget: Option<Duration>) {
+        self.lod.set_budget(budget);
+    }
+
+    // Time the busiest parts of each frame, for benchmarking
+    pub fn set_profiling(&mut self, on: bool) {
+        self.profile = on.then(Profile::default);
+    }
+
+    // The time spent in each part since this was last called, if profiling
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.as_mut().map(std::mem::take)
+    }
+
+    // Tint stars and shooting stars towards this color, by accent_strength,
+    // or stop
+    pub fn set_accent(&mut self, color: Option<(u8, u8, u8)>) {
+        self.tint = color.map(|color| Tint::new(color, self.config.accent_strength));
+        self.stars.tint = self.tint;
+        for shooting_star in &mut self.shooting_stars {
+            shooting_star.tint = self.tint;
+        }
+    }
+
+    // Switch the breathing relaxation mode, easing in or out of it
+    pub fn set_breathing(&mut self, enabled: bool) {
+        self.breath.enabled = enabled;
+    }
+
+    pub fn is_breathing(&self) -> bool {
+        self.breath.enabled
+    }
+
+    // Run the sky `speed` times faster than real time, to see hours of it go
+    // by in minutes. Drift, events and the moon all speed up; breathing and
+    // fades don't.
+    pub fn set_timelapse(&mut self, speed: f32) {
+        self.tweens.cancel(Param::Speed);
+        self.timelapse = speed;
+    }
+
+    pub fn timelapse(&self) -> f32 {
+        self.timelapse
+    }
+
+    // Keep the sky's clock to the frame times it's given rather than the
+    // time of day, for rendering frames at a fixed step however long each
+    // takes to make
+    pub fn set_fixed_clock(&mut self, fixed: bool) {
+        self.fixed_clock = fixed;
+    }
+
+    // Freeze the frame: updates leave everything, breathing and fades too,
+    // just as it is until the sky resumes
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Add or take away drifting stars, live, to have `count` of them. Other
+    // scenes and the planetarium keep the count for when drifting stars are
+    // back.
+    pub fn set_star_count(&mut self, count: usize) {
+        self.tweens.cancel(Param::Stars);
+        self.change_star_count(count);
+    }
+
+    fn change_star_count(&mut self, count: usize) {
+        self.config.star_count = count;
+        if self.config.scene != Scene::Starfield || self.planetarium.is_some() {
+            return;
+        }
+        let (width, height) = (self.screen_details.width, self.screen_details.height);
+        let rng = &mut self.rng;
+        let config = &self.config;
+        self.stars.truncate(count);
+        for _ in self.stars.len()..count {
+            self.stars.spawn(rng, config, width, height);
+        }
+        // It may have been one of those taken away
+        self.sparkle = None;
+    }
+
+    pub fn star_count(&self) -> usize {
+        self.config.star_count
+    }
+
+    // Dim the whole sky, 1 for full brightness and 0 for none
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.tweens.cancel(Param::Brightness);
+        self.brightness = brightness.clamp(0.0, 1.0);
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    // Ease a setting to a new value, over transition_seconds unless the
+    // animation says otherwise
+    pub fn animate(&mut self, animation: &Animation) {
+        let param = animation.param;
+        let from = animation.from.unwrap_or(match param {
+            Param::Speed => self.timelapse,
+            Param::Brightness => self.brightness,
+            Param::Stars => self.config.star_count as f32,
+        });
+        let duration = animation.seconds.unwrap_or(self.config.transition_seconds);
+        self.tweens
+            .start(param, from, animation.to, duration, animation.easing);
+    }
+
+    // Play a timeline of animations one after another, in place of any
+    // playing; an empty one stops it
+    pub fn play(&mut self, timeline: &Timeline) {
+        self.tweens.play(timeline);
+    }
+
+    // Switch to another scene, which starts afresh: drifting stars burst out
+    // from the middle again and the planets start from where they are today
+    pub fn set_scene(&mut self, scene: Scene) {
+        if scene == self.config.scene {
+            return;
+        }
+        self.config.scene = scene;
+        Scenery {
+            stars: self.stars,
+            planetarium: self.planetarium,
+            orrery: self.orrery,
+            tunnel: self.tunnel,
+        } = Scenery::new(&mut self.rng, &self.screen_details, &self.config);
+        self.moon = match scene {
+            Scene::Starfield if self.config.moon => self.moon.take().or_else(|| {
+                Some(Moon::new(
+                    &mut self.rng,
+                    &self.screen_details,
+                    self.config.moon_phase,
+                ))
+            }),
+            _ => None,
+        };
+        // Whatever belonged to the old stars goes with them
+        self.sparkle = None;
+        self.supernova = None;
+        self.particles.clear();
+        self.tooltip = None;
+    }
+
+    pub fn scene(&self) -> Scene {
+        self.config.scene
+    }
+
+    // Like `set_scene`, but dissolving from the old scene's last frame into
+    // the new one over `scene_fade_seconds`
+    pub fn fade_to(&mut self, scene: Scene) {
+        if self.config.scene_fade_seconds > 0.0 {
+            self.next_scene = Some(scene);
+        } else {
+            self.set_scene(scene);
+        }
+    }
+
+    // Where the local time zone is, in seconds ahead of UTC, so scene
+    // schedules follow the local clock
+    pub fn set_utc_offset(&mut self, seconds: i64) {
+        self.utc_offset = seconds;
+    }
+
+    // Show or hide the constellation figures, fading them in or out
+    pub fn set_constellations(&mut self, shown: bool) {
+        self.constellations.enabled = shown;
+    }
+
+    pub fn showing_constellations(&self) -> bool {
+        self.constellations.enabled
+    }
+
+    // Switch hyperspace on or off: stars streak out from `warp_center`, or go
+    // back to drifting from wherever they've got to
+    pub fn set_warp(&mut self, warping: bool) {
+        self.config.warp = warping;
+    }
+
+    pub fn is_warping(&self) -> bool {
+        self.config.warp
+    }
+
+    // Wipe away the trails left so far in star trails mode
+    pub fn clear_trails(&mut self) {
+        if let Some(trails) = &mut self.trails {
+            trails.clear();
+        }
+    }
+
+    // Start or end a focus session: the sky dims, shooting stars stop coming
+    // and hovering stars no longer pops up their lore. Embedders should also
+    // keep the screen from idling while `is_focused`.
+    pub fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    // Leave starting shooting stars and comets to whoever calls `trigger`, so
+    // several outputs can share one sequence of them
+    pub fn set_following(&mut self, following: bool) {
+        self.following = following;
+    }
+
+    // Start a shooting star, or a comet if there isn't one already, right
+    // now. Other events can't be started on demand, and a focus session
+    // holds these back as usual.
+    pub fn trigger(&mut self, event: SkyEvent) {
+        if self.focused {
+            return;
+        }
+        match event {
+            SkyEvent::ShootingStar => {
+                let trail = self.spare_trails.pop().unwrap_or_default();
+                self.shooting_stars.push(ShootingStar::spawn(
+                    &mut self.rng,
+                    &self.screen_details,
+                    trail,
+                ));
+            }
+            SkyEvent::Comet if self.comet.is_none() => {
+                self.comet = Some(Comet::spawn(&mut self.rng, &self.screen_details));
+            }
+            _ => return,
+        }
+        self.events.push(event);
+    }
+
+    // A click of the pointer where it is. Three in quick succession on the
+    // same star set it off as a supernova, in place of any going already;
+    // playing comet catch, it drops an attractor instead.
+    pub fn click(&mut self) {
+        self.click = self.cursor;
+    }
+
+    // Count a click at `(x, y)` on the output, setting off the star there
+    // on the third
+    fn take_click(&mut self, (x, y): (f32, f32)) {
+        let (left, top) = (self.view.x0 as f32, self.view.y0 as f32);
+        let moon = self.moon.as_ref();
+        let clicked = self.stars.hovered((x + left, y + top)).filter(|&i| {
+            !self.focused && !moon.is_some_and(|moon| moon.covers(self.stars.center(i)))
+        });
+        let Some(i) = clicked else {
+            self.clicks = None;
+            return;
+        };
+        let seed = self.stars.seed[i];
+        let count = match self.clicks {
+            Some((last, count)) if last == seed && self.since_click <= TRIPLE_CLICK_GAP => {
+                count + 1
+            }
+            _ => 1,
+        };
+        self.clicks = Some((seed, count));
+        self.since_click = 0.0;
+        if count < 3 || self.click_cooldown > 0.0 {
+            return;
+        }
+        self.clicks = None;
+        self.click_cooldown = CLICK_SUPERNOVA_COOLDOWN;
+        let stars = &self.stars;
+        self.supernova = Some(Supernova::new(i, seed, stars.center(i), stars.color[i]));
+        self.events.push(SkyEvent::Supernova);
+    }
+
+    // What happened during the last update
+    pub fn events(&self) -> &[SkyEvent] {
+        &self.events
+    }
+
+    pub fn status(&self) -> SkyStatus {
+        SkyStatus {
+            focused: self.focused,
+            breathing: self.is_breathing(),
+            moon_phase: self
+                .moon
+                .as_ref()
+                .map_or_else(|| moon::phase_at(self.clock), Moon::phase),
+            comet: self.comet.is_some(),
+            satellites: self.satellites.len(),
+        }
+    }
+
+    // Follow a change in surface size, stretching the sky so the stars stay
+    // spread across all of it
+    pub fn resize(&mut self, screen_details: ScreenDetails) {
+        let scale_x = screen_details.width as f32 / self.screen_details.width as f32;
+        let scale_y = screen_details.height as f32 / self.screen_details.height as f32;
+        self.stars.rescale(scale_x, scale_y);
+        for planet in &mut self.planets {
+            planet.rescale(scale_x, scale_y);
+        }
+        if let Some(milky_way) = &mut self.milky_way {
+            milky_way.resize(&screen_details);
+        }
+        if let Some(nebula) = &mut self.nebula {
+            nebula.resize(&screen_details);
+        }
+        if let Some(moon) = &mut self.moon {
+            moon.resize(&screen_details);
+        }
+        if let Some(clouds) = &mut self.clouds {
+            clouds.resize(&screen_details);
+        }
+        self.screen_details = screen_details;
+        self.view = Rect::new(
+            0,
+            0,
+            screen_details.width as i32,
+            screen_details.height as i32,
+        );
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.paused {
+            self.events.clear();
+            self.click = None;
+            return;
+        }
+        let started = Instant::now();
+        self.tweens.wait(dt);
+        while let Some(animation) = self.tweens.next_due(self.config.transition_seconds) {
+            self.animate(&animation);
+        }
+        for param in Param::ALL {
+            let Some(value) = self.tweens.step(param, dt) else {
+                continue;
+            };
+            match param {
+                Param::Speed => self.timelapse = value,
+                Param::Brightness => self.brightness = value.clamp(0.0, 1.0),
+                Param::Stars => {
+                    let count = value.round().max(0.0) as usize;
+                    if count != self.config.star_count {
+                        self.change_star_count(count);
+                    }
+                }
+            }
+        }
+        let jumped = self.echo.observe(dt, self.timelapse);
+        self.breath.update(dt, self.config.breaths_per_minute);
+        if let Some(trails) = &mut self.trails {
+            trails.advance(dt);
+        }
+        if let Some(skyline) = &mut self.skyline {
+            skyline.update(dt);
+        }
+        if let Some(limiter) = &mut self.flash_limiter {
+            limiter.advance(dt);
+        }
+        if let Some(palette) = &mut self.palette {
+            palette.update(dt);
+        }
+        let focus_step = dt / FOCUS_FADE_TIME;
+        self.focus_fade = if self.focused {
+            (self.focus_fade + focus_step).min(1.0)
+        } else {
+            (self.focus_fade - focus_step).max(0.0)
+        };
+        self.events.clear();
+        if self.focused != self.was_focused {
+            self.was_focused = self.focused;
+            self.events.push(if self.focused {
+                SkyEvent::FocusStart
+            } else {
+                SkyEvent::FocusEnd
+            });
+        }
+        if let Some((x, y)) = self.click.take() {
+            match &mut self.comet_catch {
+                Some(game) if !self.focused => {
+                    game.attract((x + self.view.x0 as f32, y + self.view.y0 as f32))
+                }
+                Some(_) => {}
+                None => self.take_click((x, y)),
+            }
+        }
+        self.since_click += dt;
+        self.click_cooldown -= dt;
+        // A sparkle lasts exactly one frame
+        self.sparkle = None;
+        // Keep to the scene schedule, one change at a time. A scene picked by
+        // hand stays until the schedule calls for another.
+        if self.next_scene.is_none() && self.crossfade.is_none() {
+            let local = self.local_time();
+            let due = self
+                .config
+                .scene_schedule
+                .as_ref()
+                .map(|schedule| schedule.scene_at(local));
+            if let Some(due) = due.filter(|&due| Some(due) != self.scheduled) {
+                self.scheduled = Some(due);
+                if due != self.config.scene {
+                    self.fade_to(due);
+                }
+            }
+        }
+        if let Some((_, progress)) = &mut self.crossfade {
+            *progress += dt / self.config.scene_fade_seconds;
+            if *progress >= 1.0 {
+                self.crossfade = None;
+            }
+        }
+
+        // A timelapse runs the sky itself faster; breathing and fades stay in
+        // real time
+        let sky_dt = dt * self.timelapse;
+        self.clock = if self.timelapse == 1.0 && !self.fixed_clock {
+            SystemTime::now()
+        } else {
+            self.clock + Duration::from_secs_f32(sky_dt)
+        };
+        // Steps of a fixed length, so motion and gravity come out the same at
+        // any frame rate, with what's left over carried to the next update.
+        // Moving things are drawn that far short of where the last step put
+        // them, between it and the one before, so they glide between steps.
+        self.backlog += sky_dt;
+        // Within a hair of a whole step counts, so rounding doesn't hold one
+        // back a frame
+        let (steps, step_dt) = match (self.backlog / SIM_STEP + 1e-3) as u32 {
+            steps if steps > MAX_STEPS => (MAX_STEPS, self.backlog / MAX_STEPS as f32),
+            steps => (steps, SIM_STEP),
+        };
+        for step in 0..steps {
+            self.step(step_dt, jumped && step == 0);
+        }
+        self.backlog = (self.backlog - step_dt * steps as f32).max(0.0);
+        let behind = 1.0 - (self.backlog / SIM_STEP).min(1.0);
+        self.stars.lag_behind(behind, &self.screen_details);
+        for shooting_star in &mut self.shooting_stars {
+            let now = (shooting_star.x, shooting_star.y);
+            shooting_star.lag = lag_behind(shooting_star.last, now, behind, &self.screen_details);
+        }
+        self.particles.lag_behind(behind * step_dt);
+
+        self.follow_pointer(dt);
+        let (left, top) = (self.view.x0 as f32, self.view.y0 as f32);
+        let cursor = self
+            .cursor
+            .filter(|_| !self.focused)
+            .map(|(x, y)| (x + left, y + top));
+        match cursor.and_then(|pos| self.stars.hovered(pos)) {
+            Some(i) => {
+                let (stars, seed) = (&self.stars, self.stars.seed[i]);
+                match &mut self.tooltip {
+                    Some((shown, tip)) if *shown == seed => tip.move_to(stars.center(i)),
+                    _ => {
+                        let lore = self.lore.get(seed, stars.color[i], stars.depth[i]);
+                        let mut lines = vec![lore.name.clone()];
+                        lines.extend(lore.lines.iter().cloned());
+                        self.tooltip = Some((seed, Tooltip::new(lines, stars.center(i))));
+                    }
+                }
+            }
+            None => self.tooltip = None,
+        }
+        self.work += started.elapsed();
+    }
+
+    // Ease the parallax toward the pointer, or back to rest without one, and
+    // shift the stars by it, the deeper the further
+    fn follow_pointer(&mut self, dt: f32) {
+        let (width, height) = (
+            (self.view.x1 - self.view.x0) as f32,
+            (self.view.y1 - self.view.y0) as f32,
+        );
+        let target = match self.cursor {
+            Some((x, y)) if self.config.parallax => (
+                (x / width * 2.0 - 1.0).clamp(-1.0, 1.0),
+                (y / height * 2.0 - 1.0).clamp(-1.0, 1.0),
+            ),
+            _ => (0.0, 0.0),
+        };
+        let ease = 1.0 - (-PARALLAX_EASE * dt).exp();
+        let step = |from: f32, to: f32| {
+            let eased = from + (to - from) * ease;
+            // Settle rather than creep forever
+            if (to - eased).abs() < 1e-3 { to } else { eased }
+        };
+        let parallax = (
+            step(self.parallax.0, target.0),
+            step(self.parallax.1, target.1),
+        );
+        // Every star is shifted each frame it's away from rest, so ones
+        // respawned at a new depth keep up
+        if parallax == (0.0, 0.0) && self.parallax == parallax {
+            return;
+        }
+        self.parallax = parallax;
+        self.stars.shift(parallax);
+    }
+
+    // Advance everything in the sky by `dt` of sky time
+    fn step(&mut self, dt: f32, jumped: bool) {
+        let screen_details = &self.screen_details;
+        // Hold back new events while the sky is already busy
+        let allowance = self.loudness.allowance(self.config.loudness_budget);
+        // Those already out move on before any more are shed
+        self.particles.update(dt, self.config.shooting_star_gravity);
+        let mut ctx = UpdateContext {
+            dt,
+            now: self.clock,
+            rng: &mut self.rng,
+            screen_details,
+            config: &self.config,
+            particles: &mut self.particles,
+        };
+
+        let star_update = Stopwatch::new(self.profile.is_some());
+        star_update.time(|| {
+            if jumped {
+                self.stars.mark_echo();
+            }
+            self.stars.tint = self.tint;
+            self.stars.mark_last();
+            self.stars.update(&mut ctx);
+        });
+        if let Some(profile) = &mut self.profile {
+            profile.star_update += star_update.total();
+        }
+        if let Some(planetarium) = &mut self.planetarium {
+            planetarium.update(&mut ctx);
+        }
+        if let Some(orrery) = &mut self.orrery {
+            orrery.update(&mut ctx);
+        }
+        if let Some(tunnel) = &mut self.tunnel {
+            tunnel.update(&mut ctx);
+        }
+
+        self.sparkle_cooldown -= dt;
+
+        let detail = self.lod.detail();
+        let planets: &[Planet] = match self.config.scene {
+            Scene::Starfield => &self.planets,
+            _ => &[],
+        };
+        for shooting_star in &mut self.shooting_stars {
+            shooting_star.trail_step = detail.trail_step;
+            shooting_star.tint = self.tint;
+            shooting_star.last = (shooting_star.x, shooting_star.y);
+            let (ax, ay) = Planet::pull(planets, shooting_star.x, shooting_star.y);
+            shooting_star.vx += ax * dt;
+            shooting_star.vy += ay * dt;
+            shooting_star.update(&mut ctx);
+        }
+        for dead in self
+            .shooting_stars
+            .extract_if(.., |s| !s.is_alive(screen_details))
+        {
+            self.spare_trails.push(dead.trail);
+        }
+        if let Some(comet) = &mut self.comet {
+            comet.update(&mut ctx);
+            if !comet.is_alive(screen_details) {
+                self.comet = None;
+            }
+        }
+        if let Some(game) = &mut self.comet_catch {
+            game.update(ctx.rng, screen_details, dt);
+        }
+        for satellite in &mut self.satellites {
+            satellite.update(&mut ctx);
+        }
+        self.satellites.retain(|s| s.is_alive(screen_details));
+        if self.config.scene == Scene::Starfield {
+            for planet in &mut self.planets {
+                planet.tint = self.tint;
+                planet.update(&mut ctx);
+            }
+        }
+        if let Some(supernova) = &mut self.supernova {
+            // Its star comes back as another once it wraps around
+            let (i, seed) = supernova.star();
+            let star = (self.stars.seed.get(i) == Some(&seed)).then(|| self.stars.center(i));
+            supernova.follow(star);
+            supernova.update(&mut ctx);
+            if !supernova.is_alive(screen_details) {
+                self.supernova = None;
+            }
+        }
+        if let Some(moon) = &mut self.moon {
+            moon.update(&mut ctx);
+        }
+        if let Some(nebula) = &mut self.nebula {
+            nebula.update(&ctx, detail);
+        }
+        if let Some(clouds) = &mut self.clouds {
+            clouds.update(&ctx, detail);
+        }
+        if let Some(telescope) = &mut self.telescope {
+            telescope.update(&mut ctx, &self.stars);
+        }
+        self.constellations
+            .update(dt, &self.stars, &self.screen_details);
+        if let Some(quotes) = &mut self.quotes {
+            quotes.update(&mut ctx);
+        }
+        self.roll_events(dt, allowance);
+
+        let glow = self
+            .shooting_stars
+            .iter()
+            .map(ShootingStar::fade)
+            .sum::<f32>()
+            + self
+                .comet
+                .as_ref()
+                .map_or(0.0, |comet| comet.brightness() * COMET_LOUDNESS)
+            + self
+                .satellites
+                .iter()
+                .map(|s| s.shine() * SATELLITE_LOUDNESS)
+                .sum::<f32>()
+            + self
+                .supernova
+                .as_ref()
+                .map_or(0.0, |supernova| supernova.brightness() * SUPERNOVA_LOUDNESS);
+        let flashes = if self.sparkle.is_some() {
+            SPARKLE_LOUDNESS
+        } else {
+            0.0
+        };
+        self.loudness.observe(dt, glow, flashes);
+    }
+
+    pub fn render(&mut self, frame: &mut [u8]) {
+        self.composite(frame, true);
+    }
+
+    // Like `render`, but stars and shooting stars are left out of the frame and
+    // listed in `sprites` for a GPU renderer to draw over it. Being on top,
+    // they are no longer veiled by clouds or dimming, so both are folded into
+    // the sprite colors; overlays can end up under a star.
+    pub fn render_with_sprites(&mut self, frame: &mut [u8], sprites: &mut Vec<Sprite>) {
+        self.composite(frame, false);
+
+        let dimming = self.dimming();
+        let dim = |color| {
+            let mut pixel = pack(color);
+            blend(&mut pixel, BACKGROUND, dimming);
+            (pixel[0], pixel[1], pixel[2])
+        };
+        // Sprites are placed on the frame, not in the sky
+        let (left, top) = (self.view.x0 as f32, self.view.y0 as f32);
+        sprites.clear();
+        for i in 0..self.stars.len() {
+            // Cover exactly the pixels the CPU would fill
+            let rect = self.stars.body(i);
+            if rect.intersect(&self.view).is_none() {
+                continue;
+            }
+            let center = (
+                (rect.x0 + rect.x1) as f32 / 2.0,
+                (rect.y0 + rect.y1) as f32 / 2.0,
+            );
+            // The moon is drawn into the frame, beneath the sprites
+            if self.moon.as_ref().is_some_and(|moon| moon.covers(center)) {
+                continue;
+            }
+            sprites.push(Sprite {
+                center: (center.0 - left, center.1 - top),
+                radius: self.stars.size[i] as f32 / 2.0,
+                color: dim(self.stars.shade(i)),
+                alpha: 1.0,
+                soft: false,
+            });
+        }
+        if let Some(i) = self.sparkle {
+            for (rect, color) in self.stars.sparkle_ghosts(i) {
+                sprites.push(Sprite {
+                    center: (
+                        (rect.x0 + rect.x1) as f32 / 2.0 - left,
+                        (rect.y0 + rect.y1) as f32 / 2.0 - top,
+                    ),
+                    radius: (rect.x1 - rect.x0) as f32 / 2.0,
+                    color: dim(color),
+                    alpha: SPARKLE_ALPHA,
+                    soft: false,
+                });
+            }
+        }
+        for shooting_star in &self.shooting_stars {
+            shooting_star.points(|x, y, color, alpha, size| {
+                sprites.push(Sprite {
+                    center: (x.floor() + 0.5 - left, y.floor() + 0.5 - top),
+                    radius: size as f32 / 2.0,
+                    color: dim(color),
+                    alpha,
+                    soft: true,
+                })
+            });
+        }
+    }
+
+    // Start new events. They are rolled for once per EVENT_TICK of sky time
+    // rather than once a frame, each tick with its own seed, so copies of a
+    // sky spanning several outputs start the same ones at the same moments
+    // whatever their frame rates.
+    fn roll_events(&mut self, dt: f32, allowance: f64) {
+        self.event_clock = (self.event_clock + dt).min(EVENT_BACKLOG);
+        while self.event_clock >= EVENT_TICK {
+            self.event_clock -= EVENT_TICK;
+            // Arrivals are caught up to the tick they started on
+            let mut ctx = UpdateContext {
+                dt: self.event_clock,
+                now: self.clock,
+                rng: &mut FastRng::seed_from_u64(self.event_rng.r#gen()),
+                screen_details: &self.screen_details,
+                config: &self.config,
+                particles: &mut self.particles,
+            };
+            let chance = |per_second: f64| EVENT_TICK as f64 * per_second * allowance;
+            // Every roll is made whether or not it can come to anything, so
+            // one coming out differently can't upset the others
+            let [sparkle, shooting_star, comet, satellite, supernova]: [f64; 5] = ctx.rng.r#gen();
+            // Other scenes are worlds of their own, without meteors
+            let quiet = self.focused || self.config.scene != Scene::Starfield;
+
+            // Look at a few random stars and let the first bright enough one
+            // flash
+            if self.config.sparkles
+                && !quiet
+                && self.sparkle_cooldown <= 0.0
+                && sparkle < chance(SPARKLE_RATE)
+            {
+                let moon = self.moon.as_ref();
+                self.sparkle = (0..8)
+                    .map(|_| ctx.rng.gen_range(0..self.stars.len().max(1)))
+                    .find(|&i| {
+                        i < self.stars.len()
+                            && self.stars.brightness(i) >= SPARKLE_BRIGHTNESS
+                            && !moon.is_some_and(|moon| moon.covers(self.stars.center(i)))
+                    });
+                if self.sparkle.is_some() {
+                    self.sparkle_cooldown = SPARKLE_MIN_GAP;
+                    self.events.push(SkyEvent::Sparkle);
+                }
+            }
+
+            if !quiet && !self.following && shooting_star < chance(SHOOTING_STAR_RATE) {
+                let trail = self.spare_trails.pop().unwrap_or_default();
+                let mut shooting_star = ShootingStar::spawn(ctx.rng, ctx.screen_details, trail);
+                shooting_star.update(&mut ctx);
+                self.shooting_stars.push(shooting_star);
+                self.events.push(SkyEvent::ShootingStar);
+            }
+
+            if self.comet.is_none()
+                && !quiet
+                && !self.following
+                && comet < chance(self.config.comets_per_hour as f64 / 3600.0)
+            {
+                let mut comet = Comet::spawn(ctx.rng, ctx.screen_details);
+                comet.update(&mut ctx);
+                self.comet = Some(comet);
+                self.events.push(SkyEvent::Comet);
+            }
+
+            if !quiet && satellite < chance(self.config.satellites_per_hour as f64 / 3600.0) {
+                let brightness = self.config.satellite_brightness;
+                let mut satellite = Satellite::spawn(ctx.rng, ctx.screen_details, brightness);
+                satellite.update(&mut ctx);
+                self.satellites.push(satellite);
+                self.events.push(SkyEvent::Satellite);
+            }
+
+            // Any star but one behind the moon may go, one at a time
+            if self.supernova.is_none()
+                && !quiet
+                && supernova < chance(self.config.supernova_chance as f64 / 60.0)
+            {
+                let moon = self.moon.as_ref();
+                let i = ctx.rng.gen_range(0..self.stars.len().max(1));
+                let stars = &self.stars;
+                if i < stars.len() && !moon.is_some_and(|moon| moon.covers(stars.center(i))) {
+                    let mut supernova =
+                        Supernova::new(i, stars.seed[i], stars.center(i), stars.color[i]);
+                    supernova.update(&mut ctx);
+                    self.supernova = Some(supernova);
+                    self.events.push(SkyEvent::Supernova);
+                }
+            }
+        }
+    }
+
+    // Seconds since the epoch on the sky's clock, in local time
+    fn local_time(&self) -> u64 {
+        let utc = self
+            .clock
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() as i64);
+        (utc + self.utc_offset).max(0) as u64
+    }
+
+    // How far the sky is faded toward the background by breathing, focus and
+    // brightness
+    fn dimming(&self) -> f32 {
+        let focus_dim =
+            self.focus_fade * self.focus_fade * (3.0 - 2.0 * self.focus_fade) * FOCUS_DIM;
+        1.0 - (1.0 - self.breath.dimming()) * (1.0 - focus_dim) * self.brightness
+    }
+
+    fn composite(&mut self, frame: &mut [u8], with_stars: bool) {
+        let started = Instant::now();
+        // Change scenes between frames, keeping the last one to fade from
+        if let Some(scene) = self.next_scene.take() {
+            if self.drawn {
+                let (pixels, _) = frame.as_chunks::<4>();
+                self.crossfade = Some((pixels.to_vec(), 0.0));
+            }
+            self.set_scene(scene);
+        }
+        self.drawn = true;
+        // Bin everything by tile, then composite tile by tile
+        let view = self.view;
+        let echoing = self.echo.strength > 0.0;
+        let dimming = self.dimming();
+        // Warp streaks and glare are drawn here even when the stars are GPU
+        // sprites
+        let glaring = self.stars.glare.is_some();
+        if with_stars || self.config.warp || glaring {
+            let bounds = match self.config.quality {
+                Quality::Standard => Stars::bounds,
+                Quality::Smooth => Stars::smooth_bounds,
+                Quality::High => Stars::splat_bounds,
+            };
+            let stars = &self.stars;
+            self.star_bins
+                .rebuild(view, (0..stars.len()).map(|i| bounds(stars, i)));
+        }
+        if with_stars {
+            self.shooting_star_bins
+                .rebuild(view, self.shooting_stars.iter().map(ShootingStar::bounds));
+        }
+        if echoing {
+            let stars = &self.stars;
+            self.echo_bins
+                .rebuild(view, (0..stars.len()).map(|i| stars.echo_bounds(i)));
+        }
+        if let Some(planetarium) = &mut self.planetarium {
+            planetarium.bin(view);
+        }
+        if let Some(tunnel) = &mut self.tunnel {
+            tunnel.bin(view);
+        }
+
+        let shades = self.palette.as_ref().map(Palette::shades);
+        let local = self.local_time();
+        if let Some(backdrop) = &mut self.backdrop {
+            let (width, height) = (view.x1 - view.x0, view.y1 - view.y0);
+            backdrop.prepare(width as usize, height as usize, local);
+        }
+        if let Some(skyline) = &mut self.skyline {
+            let (width, height) = (view.x1 - view.x0, view.y1 - view.y0);
+            skyline.prepare(width as usize, height as usize);
+        }
+        if self.debug.heatmap {
+            self.build_heat_map();
+        }
+        // The game's score goes under the embedder's lines while it shows
+        let banner = self.comet_catch.as_ref().and_then(CometCatch::banner);
+        let hud_lines: Vec<&str> = match banner {
+            Some(banner) => self
+                .hud
+                .iter()
+                .map(String::as_str)
+                .chain([banner])
+                .collect(),
+            None => Vec::new(),
+        };
+        let mut debug_panel = if self.debug.spawning {
+            self.spawn_panel()
+        } else {
+            Vec::new()
+        };
+        if self.debug.heatmap {
+            debug_panel.extend(self.heat_map.describe());
+            if !self.debug.spawning {
+                debug_panel.push(format!("loudness: {:.2}", self.loudness.level));
+            }
+        }
+
+        // Out of the way of the drawing, which borrows the rest of the sky
+        let mut bloom = self.bloom.take();
+        let mut trails = self.trails.take();
+        let last_sky = trails.as_ref();
+        let profiling = self.profile.is_some();
+        let (star_draw, shooting_star_draw) =
+            (Stopwatch::new(profiling), Stopwatch::new(profiling));
+        let occlusion = self
+            .skyline
+            .as_ref()
+            .and_then(|skyline| skyline.occlusion(view));
+        // The sky itself, then what's done over all of it
+        let sky = |tile: usize, target: &mut Target| {
+            match &self.backdrop {
+                Some(backdrop) => backdrop.draw(target, view),
+                None => target.fill(pack(BACKGROUND)),
+            }
+            if let Some(trails) = last_sky {
+                trails.draw(target, view);
+            }
+            if let Some(milky_way) = &self.milky_way {
+                milky_way.draw(tile, target);
+            }
+            if let Some(nebula) = &self.nebula {
+                nebula.draw(target);
+            }
+            if let Some(orrery) = &self.orrery {
+                orrery.draw(target);
+            }
+            // Behind everything that moves, so stars sit on top of their lines
+            self.constellations.draw(target);
+            // Everything that shines, held back behind the ground so none of
+            // it glows through into the bloom or the trails
+            let emissive = |target: &mut Target| {
+                if echoing {
+                    for i in self.echo_bins.get(tile) {
+                        self.stars.draw_echo(i, target, self.echo.strength);
+                    }
+                }
+                // Comets stay on the CPU even when stars are GPU sprites
+                if let Some(comet) = &self.comet
+                    && target.overlaps(comet.bounds())
+                {
+                    comet.draw(target);
+                }
+                if with_stars {
+                    let draw = match self.config.quality {
+                        Quality::Standard => Stars::draw,
+                        Quality::Smooth => Stars::draw_smooth,
+                        Quality::High => Stars::draw_splat,
+                    };
+                    star_draw.time(|| {
+                        for i in self.star_bins.get(tile) {
+                            draw(&self.stars, i, target);
+                        }
+                    });
+                } else if self.config.warp || glaring {
+                    for i in self.star_bins.get(tile) {
+                        self.stars.draw_streak(i, target);
+                        self.stars.draw_glare(i, target);
+                    }
+                }
+                // Always on the CPU, being fixed to the sky rather than sprites
+                if let Some(planetarium) = &self.planetarium {
+                    planetarium.draw(tile, target);
+                }
+                if let Some(tunnel) = &self.tunnel {
+                    tunnel.draw(tile, target);
+                }
+                // Over its star, which stays beneath the flare
+                if let Some(supernova) = &self.supernova
+                    && target.overlaps(supernova.bounds())
+                {
+                    supernova.draw(target);
+                }
+                // Steady among the stars, on the CPU even when they're sprites
+                if self.config.scene == Scene::Starfield {
+                    for planet in &self.planets {
+                        if target.overlaps(planet.bounds()) {
+                            planet.draw(target);
+                        }
+                    }
+                }
+                // In front of the stars, behind shooting stars and clouds
+                if let Some(moon) = &self.moon {
+                    moon.draw(target);
+                }
+                // In orbit, so in front of the moon; tiny enough to skip binning
+                for satellite in &self.satellites {
+                    if target.overlaps(satellite.bounds()) {
+                        satellite.draw(target);
+                    }
+                }
+                if with_stars {
+                    if let Some(i) = self.sparkle {
+                        self.stars.draw_sparkle(i, target);
+                    }
+                    shooting_star_draw.time(|| {
+                        draw_objects(&self.shooting_stars, &self.shooting_star_bins, tile, target)
+                    });
+                }
+                // Over whatever shed them, on the CPU like comets; tiny enough
+                // to skip binning, which would grow with every burst
+                self.particles.draw(target);
+            };
+            match &occlusion {
+                Some(occlusion) => target.occluded(occlusion, emissive),
+                None => emissive(target),
+            }
+            if let Some(clouds) = &self.clouds {
+                clouds.draw(target, &self.screen_details);
+            }
+            if let Some(game) = &self.comet_catch {
+                game.draw(target);
+            }
+        };
+        // In front of the sky and the light bloom spreads through it, and
+        // not kept for trails, as the ground stays put
+        let foreground = |target: &mut Target| {
+            if let Some(skyline) = &self.skyline {
+                skyline.draw(target, view);
+            }
+        };
+        let finish = |target: &mut Target| {
+            // The sky breathes and dims for focus; overlays on top of it stay
+            // steady and readable
+            if dimming > 0.0 {
+                target.fade(dimming);
+            }
+            if let Some((from, progress)) = &self.crossfade
+                && from.len() == (view.x1 - view.x0) as usize * (view.y1 - view.y0) as usize
+            {
+                target.dissolve(from, view, 1.0 - progress);
+            }
+            if let (Some(palette), Some(shades)) = (&self.palette, &shades) {
+                palette.apply(target, shades);
+            }
+            if let Some(gradient) = &self.gradient {
+                gradient.apply(target);
+            }
+            if let Some(telescope) = &self.telescope {
+                telescope.draw(target, &self.screen_details, &self.stars);
+            }
+            if let Some(quotes) = &self.quotes {
+                quotes.draw(target, &self.screen_details);
+            }
+            if !hud_lines.is_empty() {
+                hud::draw(target, &self.screen_details, &hud_lines);
+            } else if !self.hud.is_empty() {
+                hud::draw(target, &self.screen_details, &self.hud);
+            }
+            if let Some((_, tip)) = &self.tooltip {
+                tip.draw(target, &self.screen_details);
+            }
+            if self.debug.any() {
+                self.draw_debug(target, &debug_panel);
+            }
+            // Last, so it holds back whatever changed
+            if let Some(limiter) = &self.flash_limiter {
+                limiter.apply(target, view);
+            }
+        };
+        // Layer by layer: the sky, bloom over it, the ground in front, then
+        // what's done over all of it. Bloom needs the whole sky drawn before
+        // it can spread its light, and trails keep it before anything goes
+        // over it.
+        let workers = &self.workers;
+        if self.debug.heatmap {
+            let heat_map = &self.heat_map;
+            render::for_each_tile(frame, view, workers, |_, target| {
+                heat_map.draw(target, view);
+                self.draw_debug(target, &debug_panel);
+            });
+        } else if bloom.is_some() || trails.is_some() {
+            render::for_each_tile(frame, view, workers, sky);
+            let (pixels, _) = frame.as_chunks::<4>();
+            if let Some(trails) = &mut trails {
+                trails.keep(pixels);
+            }
+            if let Some(bloom) = &mut bloom {
+                bloom.gather(pixels, (view.x1 - view.x0) as usize, workers);
+            }
+            let bloom = bloom.as_ref();
+            render::for_each_tile(frame, view, workers, |_, target| {
+                if let Some(bloom) = bloom {
+                    bloom.draw(target, view);
+                }
+                foreground(target);
+                finish(target);
+            });
+        } else {
+            render::for_each_tile(frame, view, workers, |tile, target| {
+                sky(tile, target);
+                foreground(target);
+                finish(target);
+            });
+        }
+        self.bloom = bloom;
+        self.trails = trails;
+        if let Some(limiter) = &mut self.flash_limiter {
+            let (pixels, _) = frame.as_chunks::<4>();
+            limiter.keep(pixels);
+        }
+        if let Some(profile) = &mut self.profile {
+            profile.star_draw += star_draw.total();
+            profile.shooting_star_draw += shooting_star_draw.total();
+        }
+        self.work += started.elapsed();
+        self.lod.record(std::mem::take(&mut self.work));
+    }
+
+    fn draw_debug(&self, target: &mut Target, panel: &[String]) {
+        let screen_details = &self.screen_details;
+        let mark = |target: &mut Target, object: &dyn CelestialObject, color| {
+            debug::mark(
+                target,
+                object.bounds(),
+                object.motion(screen_details),
+                color,
+            );
+        };
+        if self.debug.stars {
+            for i in 0..self.stars.len() {
+                let (bounds, motion) = (self.stars.bounds(i), self.stars.motion(i));
+                debug::mark(target, bounds, motion, debug::STAR_COLOR);
+            }
+        }
+        if self.debug.shooting_stars {
+            for shooting_star in &self.shooting_stars {
+                mark(target, shooting_star, debug::SHOOTING_STAR_COLOR);
+            }
+        }
+        if self.debug.comet
+            && let Some(comet) = &self.comet
+        {
+            mark(target, comet, debug::COMET_COLOR);
+        }
+        if self.debug.satellites {
+            for satellite in &self.satellites {
+                mark(target, satellite, debug::SATELLITE_COLOR);
+            }
+        }
+        if self.debug.spawning {
+            // The zone itself is off-screen, so shade the edge it's beyond
+            let ((x0, y0), (x1, y1)) = ShootingStar::spawn_zone(screen_details);
+            let (w, h) = (screen_details.width as i32, screen_details.height as i32);
+            let clamp = |x: f32, y: f32| ((x as i32).clamp(0, w - 1), (y as i32).clamp(0, h - 1));
+            let ((x0, y0), (x1, y1)) = (clamp(x0, y0), clamp(x1, y1));
+            debug::zone(target, Rect::new(x0 - 3, y0 - 3, x1 + 4, y1 + 4));
+        }
+        if !panel.is_empty() {
+            debug::panel(target, panel);
+        }
+    }
+
+    // Add up star density and light across the view for the heat map.
+    // Events count for what they add to the loudness, in pixels' worth.
+    fn build_heat_map(&mut self) {
+        let view = self.view;
+        let heat_map = &mut self.heat_map;
+        heat_map.clear((view.x1 - view.x0) as u32, (view.y1 - view.y0) as u32);
+        let mut add = |(x, y): (f32, f32), stars, light| {
+            heat_map.add((x - view.x0 as f32, y - view.y0 as f32), stars, light)
+        };
+        let stars = &self.stars;
+        for i in 0..stars.len() {
+            let size = stars.size[i] as f32;
+            let shown = stars.brightness(i).min(255.0) / 255.0;
+            add(stars.center(i), 1.0, shown * size * size);
+        }
+        for shooting_star in &self.shooting_stars {
+            let light = shooting_star.fade() * HEAT_EVENT_LIGHT;
+            add((shooting_star.x, shooting_star.y), 0.0, light);
+        }
+        if let Some(comet) = &self.comet {
+            let ((position, _), light) = (comet.motion(&self.screen_details), comet.brightness());
+            add(position, 0.0, light * COMET_LOUDNESS * HEAT_EVENT_LIGHT);
+        }
+        for satellite in &self.satellites {
+            let (position, _) = satellite.motion(&self.screen_details);
+            add(
+                position,
+                0.0,
+                satellite.shine() * SATELLITE_LOUDNESS * HEAT_EVENT_LIGHT,
+            );
+        }
+        if let Some(supernova) = &self.supernova {
+            let Rect { x0, y0, x1, y1 } = supernova.bounds();
+            let center = ((x0 + x1) as f32 / 2.0, (y0 + y1) as f32 / 2.0);
+            add(
+                center,
+                0.0,
+                supernova.brightness() * SUPERNOVA_LOUDNESS * HEAT_EVENT_LIGHT,
+            );
+        }
+        heat_map.finish();
+    }
+
+    // Spawn rates and timers for the debug overlay
+    fn spawn_panel(&self) -> Vec<String> {
+        let sparkle = if !self.config.sparkles {
+            "off".to_string()
+        } else if self.sparkle_cooldown > 0.0 {
+            format!("{:.1} s", self.sparkle_cooldown)
+        } else {
+            "ready".to_string()
+        };
+        let comet = match &self.comet {
+            Some(comet) => format!("age {:.0} s", comet.age()),
+            None => format!("none, {} per hour", self.config.comets_per_hour),
+        };
+        let supernova = match &self.supernova {
+            Some(_) => "going off".to_string(),
+            None => format!(
+                "none, {:.0}% chance per minute",
+                self.config.supernova_chance * 100.0
+            ),
+        };
+        let paused = if self.focused { " (paused)" } else { "" };
+        let budget = self.config.loudness_budget;
+        let loudness = if budget > 0.0 {
+            let allowance = self.loudness.allowance(budget) * 100.0;
+            format!(
+                "{:.2} of {budget}, spawning at {allowance:.0}%",
+                self.loudness.level
+            )
+        } else {
+            format!("{:.2}, no limit", self.loudness.level)
+        };
+        vec![
+            format!(
+                "shooting stars: {}, {SHOOTING_STAR_RATE} per s{paused}",
+                self.shooting_stars.len()
+            ),
+            format!("comet: {comet}{paused}"),
+            format!(
+                "satellites: {}, {} per hour{paused}",
+                self.satellites.len(),
+                self.config.satellites_per_hour
+            ),
+            format!("supernova: {supernova}{paused}"),
+            format!("sparkle cooldown: {sparkle}{paused}"),
+            format!("particles: {} of {PARTICLE_CAP}", self.particles.len()),
+            format!("loudness: {loudness}"),
+            format!("detail: {}", self.lod.describe()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skyline::SkylineShape;
+    use crate::test_support::{FixedClock, Frame, LANDSCAPE as SCREEN, MockScreen};
+    use rand::SeedableRng;
+
+    #[test]
+    fn shooting_stars_enter_along_the_long_axis() {
+        let mut rng = FastRng::seed_from_u64(3);
+        let portrait = crate::test_support::PORTRAIT;
+
+        for _ in 0..100 {
+            let star = ShootingStar::spawn(&mut rng, &SCREEN, VecDeque::new());
+            assert!(star.x > SCREEN.width as f32 && star.vx.abs() > star.vy.abs());
+
+            let star = ShootingStar::spawn(&mut rng, &portrait, VecDeque::new());
+            assert!(star.y < 0.0 && star.vy > star.vx.abs());
+        }
+    }
+
+    #[test]
+    fn trail_length_is_independent_of_frame_rate() {
+        let mut screen = MockScreen::landscape();
+        let mut fly = |fps: f32| {
+            let mut star = ShootingStar::new(2000.0, 100.0, -300.0, 20.0, VecDeque::new());
+            screen.run(&mut star, FixedClock::fps(fps), 2.0);
+            let (tail_x, tail_y) = star.trail[0];
+            (star.x - tail_x).hypot(star.y - tail_y)
+        };
+
+        let slow = fly(20.0);
+        let fast = fly(144.0);
+        assert!(
+            (slow - fast).abs() < TRAIL_SPACING * 2.0,
+            "{slow} vs {fast}"
+        );
+    }
+
+    #[test]
+    fn shooting_stars_burn_out_and_leave_a_fading_trail() {
+        let mut screen = MockScreen::landscape();
+        let mut star = ShootingStar::new(1500.0, 300.0, -200.0, 0.0, VecDeque::new());
+        screen.run(&mut star, FixedClock::fps(60.0), 1.0);
+
+        let mut frame = Frame::new(&SCREEN);
+        frame.draw(&star);
+        let (head_x, head_y) = (star.x as u32, star.y as u32);
+        frame.assert_brighter_than(head_x, head_y, 150);
+        // Dimmer back along the trail, and nothing ahead of the head
+        assert!(frame.brightness(head_x + 60, head_y) < frame.brightness(head_x, head_y));
+        frame.assert_dark(head_x - 20, head_y);
+
+        // Well on screen the whole time, so only its lifetime ends it
+        let lived = screen.run(&mut star, FixedClock::fps(60.0), 10.0);
+        assert!((lived + 1.0 - star.max_life).abs() < 0.05, "{lived}");
+    }
+
+    #[test]
+    fn shooting_stars_heat_up_and_burn_out_low_in_the_sky() {
+        let mut screen = MockScreen::landscape();
+        // Diving into the bottom of the screen
+        let mut star = ShootingStar::new(1500.0, 700.0, -200.0, 150.0, VecDeque::new());
+        screen.run(&mut star, FixedClock::fps(60.0), 1.0);
+        assert_eq!(star.entry, Entry::Cruising);
+        assert_eq!(screen.particles.len(), 0);
+
+        for _ in 0..60 {
+            screen.run(&mut star, FixedClock::fps(60.0), 1.0 / 60.0);
+            if star.heat() > 0.5 {
+                break;
+            }
+        }
+        assert!(matches!(star.entry, Entry::Heating(_)), "{:?}", star.entry);
+        assert!(screen.particles.len() >= 3);
+        let mut frame = Frame::new(&SCREEN);
+        frame.draw(&star);
+        // Orange rather than white at the head, and flaring around it
+        let [r, g, b, _] = frame.pixel(star.x as u32, star.y as u32);
+        assert!(r > g && g > b, "{r} {g} {b}");
+        frame.assert_brighter_than(star.x as u32, star.y as u32 - 3, 20);
+
+        // Gone well before its time is up
+        let lived = screen.run(&mut star, FixedClock::fps(60.0), 10.0);
+        assert!(lived < 1.0, "{lived}");
+        assert_eq!(star.entry, Entry::BurningOut(0.0));
+    }
+
+    #[test]
+    fn trails_thin_out_at_lower_detail() {
+        let mut screen = MockScreen::landscape();
+        let mut star = ShootingStar::new(1500.0, 300.0, -200.0, 0.0, VecDeque::new());
+        screen.run(&mut star, FixedClock::fps(60.0), 0.5);
+        let points = |star: &ShootingStar| {
+            let mut points = Vec::new();
+            star.points(|x, y, _, _, size| points.push((x, y, size)));
+            points
+        };
+        let full = points(&star);
+        star.trail_step = 2;
+        let thinned = points(&star);
+
+        // About half as many blobs, wider, ending at the same head
+        assert!(
+            thinned.len() < full.len() * 2 / 3,
+            "{} {}",
+            thinned.len(),
+            full.len()
+        );
+        assert_eq!(thinned.last(), full.last());
+        let widest =
+            |points: &[(f32, f32, i32)]| points[..points.len() - 1].iter().map(|p| p.2).max();
+        assert!(widest(&thinned) > widest(&full));
+    }
+
+    #[test]
+    fn resizing_spreads_stars_over_the_new_screen() {
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(17), SCREEN, Config::default());
+        starfield.resize(crate::test_support::PORTRAIT);
+
+        let stars = &starfield.stars;
+        let max_x = stars.x.iter().fold(0.0f32, |max, &x| max.max(x));
+        let max_y = stars.y.iter().fold(0.0f32, |max, &y| max.max(y));
+        assert!(max_x <= 1080.0 && max_x > 1000.0, "{max_x}");
+        assert!(max_y <= 1920.0 && max_y > 1800.0, "{max_y}");
+    }
+
+    #[test]
+    fn time_jumps_echo_by_how_much_was_skipped_then_fade() {
+        let mut echo = TimeEcho::new();
+        let dt = 1.0 / 60.0;
+        for _ in 0..30 {
+            assert!(!echo.observe(dt, 1.0));
+        }
+        assert_eq!(echo.strength, 0.0);
+
+        // A short hitch makes a faint echo, a long one a full one
+        assert!(echo.observe(dt + 0.25, 1.0));
+        let faint = echo.strength;
+        assert!(faint > 0.3 && faint < 0.6, "{faint}");
+        assert!(echo.observe(2.0, 1.0));
+        assert_eq!(echo.strength, 1.0);
+
+        // Then it fades away over ECHO_FADE_TIME of ordinary frames
+        let frames = (ECHO_FADE_TIME / dt).round() as u32;
+        for _ in 0..frames / 2 {
+            assert!(!echo.observe(dt, 1.0));
+        }
+        assert!((echo.strength - 0.5).abs() < 0.05, "{}", echo.strength);
+        for _ in frames / 2..frames {
+            echo.observe(dt, 1.0);
+        }
+        assert!(echo.strength < 1e-3, "{}", echo.strength);
+    }
+
+    #[test]
+    fn speeding_time_up_echoes_the_stars() {
+        let config = Config {
+            star_count: 200,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(4), SCREEN, config);
+        let dt = 1.0 / 60.0;
+        for _ in 0..30 {
+            starfield.update(dt);
+        }
+        assert_eq!(starfield.echo.strength, 0.0);
+
+        // As from the Up hotkey or a `speed` command: a leap in the time scale
+        // skips as much sky time as a long stall
+        starfield.set_timelapse(50.0);
+        starfield.update(dt);
+        assert_eq!(starfield.echo.strength, 1.0);
+
+        // Keeping up that speed, or slowing down again, doesn't
+        for _ in 0..30 {
+            starfield.update(dt);
+        }
+        assert_eq!(starfield.echo.strength, 0.0);
+        starfield.set_timelapse(1.0);
+        starfield.update(dt);
+        assert_eq!(starfield.echo.strength, 0.0);
+    }
+
+    #[test]
+    fn breathing_eases_in_and_cycles_at_the_set_rate() {
+        let mut breath = Breath::new(false);
+        breath.enabled = true;
+        let dt = 1.0 / 60.0;
+        let mut last = breath.dimming();
+        // Switching on ramps the envelope up instead of jumping
+        for _ in 0..(BREATH_FADE_TIME / dt) as u32 {
+            breath.update(dt, 6.0);
+            assert!((breath.dimming() - last).abs() < 0.01);
+            last = breath.dimming();
+        }
+
+        // At 6 breaths a minute a full breath takes ten seconds
+        breath.phase = 0.0;
+        assert!((breath.dimming() - BREATH_DEPTH).abs() < 1e-4);
+        for _ in 0..300 {
+            breath.update(dt, 6.0);
+        }
+        assert!(breath.dimming() < 1e-4, "not fully inhaled halfway through");
+        for _ in 0..300 {
+            breath.update(dt, 6.0);
+        }
+        assert!((breath.dimming() - BREATH_DEPTH).abs() < 1e-3);
+    }
+
+    #[test]
+    fn focus_sessions_hold_back_shooting_stars_and_tooltips() {
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(5), SCREEN, Config::default());
+        starfield.set_focus(true);
+        // Keep the pointer on a star the whole time
+        for _ in 0..60 * 30 {
+            starfield.update(1.0 / 60.0);
+            starfield.cursor = Some(starfield.stars.center(0));
+        }
+        assert!(starfield.shooting_stars.is_empty());
+        assert!(starfield.tooltip.is_none());
+
+        starfield.set_focus(false);
+        starfield.update(1.0 / 60.0);
+        assert!(starfield.tooltip.is_some());
+    }
+
+    #[test]
+    fn stars_start_in_place_without_the_intro() {
+        let flying = Starfield::new(FastRng::seed_from_u64(5), SCREEN, Config::default());
+        assert!(flying.stars.intro_progress.iter().all(|&p| p < 1.0));
+        let config = Config {
+            intro: false,
+            ..Config::default()
+        };
+        let settled = Starfield::new(FastRng::seed_from_u64(5), SCREEN, config);
+        assert!(settled.stars.intro_progress.iter().all(|&p| p == 1.0));
+    }
+
+    #[test]
+    fn triple_clicking_a_star_sets_it_off() {
+        let config = Config {
+            supernova_chance: 0.0,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(5), SCREEN, config);
+        starfield.update(INTRO_TIME + 0.1);
+        let click = |starfield: &mut Starfield, star: usize, after: f32| {
+            starfield.cursor = Some(starfield.stars.center(star));
+            starfield.click();
+            starfield.update(after);
+            starfield.events().contains(&SkyEvent::Supernova)
+        };
+
+        // Too slow to count as a triple click
+        for _ in 0..3 {
+            assert!(!click(&mut starfield, 0, 1.0));
+        }
+        assert!(starfield.supernova.is_none());
+
+        assert!(!click(&mut starfield, 0, 0.1));
+        assert!(!click(&mut starfield, 0, 0.1));
+        assert!(click(&mut starfield, 0, 0.1));
+        let seed = starfield.stars.seed[0];
+        assert_eq!(starfield.supernova.as_ref().unwrap().star(), (0, seed));
+
+        // Another star straight after is held back by the cooldown, but goes
+        // once it's over
+        for _ in 0..3 {
+            assert!(!click(&mut starfield, 1, 0.1));
+        }
+        starfield.update(CLICK_SUPERNOVA_COOLDOWN);
+        let fired = (0..3)
+            .map(|_| click(&mut starfield, 1, 0.1))
+            .collect::<Vec<_>>();
+        assert_eq!(fired, [false, false, true]);
+        assert_eq!(starfield.supernova.as_ref().unwrap().star().0, 1);
+    }
+
+    #[test]
+    fn stars_shift_away_from_the_pointer_by_depth() {
+        let shifts = |parallax| {
+            let config = Config {
+                parallax,
+                ..Config::default()
+            };
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(8), SCREEN, config);
+            // Held at the right edge long enough to settle
+            starfield.cursor = Some((SCREEN.width as f32, SCREEN.height as f32 / 2.0));
+            for _ in 0..60 * 3 {
+                starfield.update(1.0 / 60.0);
+            }
+            let stars = starfield.stars;
+            stars
+                .depth
+                .into_iter()
+                .zip(stars.parallax)
+                .collect::<Vec<_>>()
+        };
+        for (depth, (x, y)) in shifts(true) {
+            assert!(
+                (x + PARALLAX_SHIFT * depth).abs() < 0.1,
+                "{x} at depth {depth}"
+            );
+            assert_eq!(y, 0.0);
+        }
+        assert!(shifts(false).iter().all(|&(_, shift)| shift == (0.0, 0.0)));
+    }
+
+    #[test]
+    fn stars_come_and_go_live_and_pausing_stops_them() {
+        let config = Config {
+            star_count: 300,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(9), SCREEN, config);
+        starfield.update(1.0 / 60.0);
+        starfield.set_star_count(100);
+        assert_eq!(starfield.stars.len(), 100);
+        starfield.set_star_count(400);
+        assert_eq!(starfield.stars.len(), 400);
+        assert_eq!(starfield.star_count(), 400);
+
+        starfield.set_paused(true);
+        let centers = |stars: &Stars| {
+            (0..stars.len())
+                .map(|i| stars.center(i))
+                .collect::<Vec<_>>()
+        };
+        let before = centers(&starfield.stars);
+        for _ in 0..60 {
+            starfield.update(1.0 / 60.0);
+        }
+        assert_eq!(centers(&starfield.stars), before);
+        starfield.set_paused(false);
+        starfield.update(1.0 / 60.0);
+        assert_ne!(centers(&starfield.stars), before);
+    }
+
+    #[test]
+    fn an_accent_pulls_stars_towards_its_hue() {
+        let config = Config {
+            star_count: 300,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(3), SCREEN, config);
+        starfield.update(1.0 / 60.0);
+        let redness = |starfield: &Starfield| {
+            let stars = &starfield.stars;
+            (0..stars.len())
+                .map(|i| {
+                    let (r, g, b) = stars.shade(i);
+                    r as f32 - (g as f32 + b as f32) / 2.0
+                })
+                .sum::<f32>()
+        };
+        let before = redness(&starfield);
+        starfield.set_accent(Some((255, 40, 40)));
+        assert!(redness(&starfield) > before + 1000.0);
+        starfield.set_accent(None);
+        assert_eq!(redness(&starfield), before);
+    }
+
+    #[test]
+    fn the_sky_moves_the_same_at_any_frame_rate() {
+        let config = Config {
+            star_count: 200,
+            shooting_star_gravity: 400.0,
+            ..Config::default()
+        };
+        let run = |fps: u32| {
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(8), SCREEN, config.clone());
+            starfield.set_fixed_clock(true);
+            starfield.shooting_stars.push(ShootingStar::new(
+                1500.0,
+                100.0,
+                -300.0,
+                0.0,
+                VecDeque::new(),
+            ));
+            for _ in 0..fps * 2 {
+                starfield.update(1.0 / fps as f32);
+            }
+            starfield
+        };
+        let (slow, fast) = (run(30), run(144));
+        let (a, b) = (&slow.shooting_stars[0], &fast.shooting_stars[0]);
+        assert!((a.x - b.x).abs() < 0.5 && (a.y - b.y).abs() < 0.5);
+        for i in 0..slow.stars.len() {
+            let (a, b) = (&slow.stars, &fast.stars);
+            assert!((a.x[i] - b.x[i]).abs() < 0.5 && (a.y[i] - b.y[i]).abs() < 0.5);
+        }
+
+        // Between steps, things are drawn between where they were and are
+        let mut starfield = run(60);
+        starfield.update(SIM_STEP * 1.5);
+        let stars = &starfield.stars;
+        let (x, _) = stars.position(0);
+        assert!(x < stars.last[0].0.max(stars.x[0]) && x > stars.last[0].0.min(stars.x[0]));
+    }
+
+    #[test]
+    fn animated_changes_ease_in_over_the_transition() {
+        let config = Config {
+            star_count: 400,
+            transition_seconds: 1.0,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(5), SCREEN, config);
+        starfield.animate(&Animation::to(Param::Stars, 200.0));
+        starfield.animate(&Animation::to(Param::Brightness, 0.5));
+        starfield.update(0.5);
+        assert!(starfield.stars.len() < 400 && starfield.stars.len() > 200);
+        assert!(starfield.brightness() < 1.0 && starfield.brightness() > 0.5);
+        starfield.update(0.5);
+        assert_eq!(starfield.stars.len(), 200);
+        assert_eq!(starfield.brightness(), 0.5);
+
+        // Setting it outright stops the easing there
+        starfield.animate(&Animation::to(Param::Stars, 400.0));
+        starfield.update(0.25);
+        starfield.set_star_count(300);
+        starfield.update(1.0);
+        assert_eq!(starfield.stars.len(), 300);
+    }
+
+    #[test]
+    fn updates_report_what_happened() {
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(41), SCREEN, Config::default());
+        let mut seen = Vec::new();
+        let step = |starfield: &mut Starfield, seen: &mut Vec<SkyEvent>| {
+            starfield.update(1.0 / 60.0);
+            seen.extend_from_slice(starfield.events());
+        };
+        let count = |seen: &[SkyEvent], event| seen.iter().filter(|&&e| e == event).count();
+        for _ in 0..60 * 30 {
+            step(&mut starfield, &mut seen);
+        }
+        // About 0.3 a second, and each reported only as it appears
+        let shooting_stars = count(&seen, SkyEvent::ShootingStar);
+        assert!((3..=20).contains(&shooting_stars), "{shooting_stars}");
+        assert_eq!(count(&seen, SkyEvent::FocusStart), 0);
+
+        // A focus session is reported once as it starts and once as it ends
+        starfield.set_focus(true);
+        step(&mut starfield, &mut seen);
+        step(&mut starfield, &mut seen);
+        starfield.set_focus(false);
+        step(&mut starfield, &mut seen);
+        assert_eq!(count(&seen, SkyEvent::FocusStart), 1);
+        assert_eq!(count(&seen, SkyEvent::FocusEnd), 1);
+    }
+
+    #[test]
+    fn busy_skies_hold_back_new_events_until_they_calm() {
+        let mut loudness = Loudness::new();
+        for _ in 0..600 {
+            loudness.observe(0.05, 3.0, 0.0);
+        }
+        assert_eq!(loudness.allowance(2.0), 0.0);
+        assert_eq!(loudness.allowance(0.0), 1.0, "a budget of 0 is no limit");
+        for _ in 0..1200 {
+            loudness.observe(0.05, 0.0, 0.0);
+        }
+        assert_eq!(loudness.allowance(2.0), 1.0);
+
+        // A burst of shooting stars well over budget: nothing new starts
+        // while they burn out, however long that takes
+        let config = Config {
+            loudness_budget: 0.5,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(37), SCREEN, config);
+        for i in 0..12 {
+            let y = 200.0 + i as f32 * 50.0;
+            let star = ShootingStar::new(1500.0, y, -100.0, 0.0, VecDeque::new());
+            starfield.shooting_stars.push(star);
+        }
+        let (mut spawned, mut held) = (0, 0);
+        for frame in 0..60 * 40 {
+            let busy = starfield.loudness.allowance(0.5) == 0.0;
+            let before = starfield.shooting_stars.len();
+            starfield.update(1.0 / 60.0);
+            if starfield.shooting_stars.len() > before {
+                spawned += 1;
+                assert!(!busy, "spawned while busy at frame {frame}");
+            } else if busy {
+                held += 1;
+            }
+        }
+        assert!(held > 60 * 5, "only held back for {held} frames");
+        assert!(spawned > 0, "never calmed down");
+    }
+
+    #[test]
+    fn skies_spanning_outputs_stay_in_step() {
+        let config = Config {
+            star_count: 300,
+            ..Config::default()
+        };
+        let space = ScreenDetails {
+            width: 800,
+            height: 300,
+        };
+        let half = ScreenDetails {
+            width: 400,
+            height: 300,
+        };
+        let sky = || Starfield::new(FastRng::seed_from_u64(46), space, config.clone());
+        let (mut whole, mut left, mut right) = (sky(), sky(), sky());
+        left.set_view((0, 0), half);
+        right.set_view((400, 0), half);
+        assert_eq!(right.screen_details(), half);
+
+        // Outputs running at different rates see the same stars, wrapping
+        // into the same new lives, and the same shooting stars
+        for _ in 0..1203 {
+            whole.update(1.0 / 60.0);
+            left.update(1.0 / 60.0);
+        }
+        for _ in 0..802 {
+            right.update(1.0 / 40.0);
+        }
+        assert_eq!(left.stars.seed, whole.stars.seed);
+        let wrapped = sky()
+            .stars
+            .seed
+            .iter()
+            .zip(&right.stars.seed)
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(wrapped > 50, "{wrapped}");
+        assert_eq!(left.stars.seed, right.stars.seed);
+        for i in 0..left.stars.len() {
+            let (a, b) = (&left.stars, &right.stars);
+            assert!((a.x[i] - b.x[i]).abs() < 1.0 && (a.y[i] - b.y[i]).abs() < 1.0);
+        }
+        assert!(!left.shooting_stars.is_empty());
+        assert_eq!(left.shooting_stars.len(), right.shooting_stars.len());
+        for (a, b) in left.shooting_stars.iter().zip(&right.shooting_stars) {
+            assert!((a.x - b.x).abs() < 1.0 && (a.y - b.y).abs() < 1.0);
+        }
+
+        // And each frame is exactly its part of the whole sky
+        let mut full = vec![0; 800 * 300 * 4];
+        let mut part = vec![0; 400 * 300 * 4];
+        whole.render(&mut full);
+        left.render(&mut part);
+        for (y, row) in part.chunks_exact(400 * 4).enumerate() {
+            assert_eq!(row, &full[y * 800 * 4..][..400 * 4], "row {y}");
+        }
+    }
+
+    #[test]
+    fn timelapses_run_the_sky_and_its_clock_fast() {
+        let config = Config {
+            star_count: 300,
+            ..Config::default()
+        };
+        let sky = || Starfield::new(FastRng::seed_from_u64(47), SCREEN, config.clone());
+        let (mut fast, mut slow) = (sky(), sky());
+        fast.set_timelapse(60.0);
+        let start = fast.clock;
+
+        // A second at 60x ends up where a minute at the normal rate does, give
+        // or take the coarser steps
+        for _ in 0..60 {
+            fast.update(1.0 / 60.0);
+        }
+        for _ in 0..3600 {
+            slow.update(1.0 / 60.0);
+        }
+        let elapsed = fast.clock.duration_since(start).unwrap().as_secs_f32();
+        assert!((elapsed - 60.0).abs() < 0.01, "{elapsed}");
+        assert_eq!(fast.stars.seed, slow.stars.seed);
+        for i in 0..fast.stars.len() {
+            let (a, b) = (&fast.stars, &slow.stars);
+            assert!((a.x[i] - b.x[i]).abs() < 2.0 && (a.y[i] - b.y[i]).abs() < 2.0);
+        }
+        // Breathing keeps to real time
+        fast.set_breathing(true);
+        fast.update(1.0 / 60.0);
+        assert!(fast.breath.strength < 0.1);
+    }
+
+    #[test]
+    fn sprites_stand_in_for_the_stars_they_replace() {
+        let config = Config {
+            sparkles: false,
+            ..Config::default()
+        };
+        let new = || Starfield::new(FastRng::seed_from_u64(29), SCREEN, config.clone());
+        let (mut cpu, mut gpu) = (new(), new());
+        for _ in 0..180 {
+            cpu.update(1.0 / 60.0);
+            gpu.update(1.0 / 60.0);
+        }
+        let mut cpu_frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+        let mut gpu_frame = cpu_frame.clone();
+        let mut sprites = Vec::new();
+        cpu.render(&mut cpu_frame);
+        gpu.render_with_sprites(&mut gpu_frame, &mut sprites);
+
+        // Only empty sky is left for the CPU to draw
+        assert!(
+            gpu_frame
+                .as_chunks::<4>()
+                .0
+                .iter()
+                .all(|&px| px == pack(BACKGROUND))
+        );
+        let stars: Vec<_> = sprites.iter().filter(|s| !s.soft).collect();
+        assert_eq!(stars.len(), gpu.stars.len());
+
+        // The last star drawn is on top on the CPU, so its pixels must match
+        let last = stars.last().unwrap();
+        let (x, y) = (last.center.0 as usize, last.center.1 as usize);
+        let i = (y * SCREEN.width as usize + x) * 4;
+        let (r, g, b) = last.color;
+        assert_eq!(cpu_frame[i..i + 3], [r, g, b]);
+    }
+
+    #[test]
+    fn sparkles_are_single_frames_and_rate_capped() {
+        let sparkle_frames = |sparkles| {
+            let config = Config {
+                sparkles,
+                ..Config::default()
+            };
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(31), SCREEN, config);
+            // Make every star bright enough to qualify
+            starfield.stars.depth.fill(0.1);
+            let mut frames = Vec::new();
+            for frame in 0..60 * 60 {
+                starfield.update(1.0 / 60.0);
+                if starfield.sparkle.is_some() {
+                    frames.push(frame);
+                }
+            }
+            frames
+        };
+
+        let frames = sparkle_frames(true);
+        assert!(!frames.is_empty());
+        assert!(frames.len() <= (60.0 / SPARKLE_MIN_GAP) as usize);
+        let min_gap = SPARKLE_MIN_GAP * 60.0;
+        assert!(
+            frames
+                .windows(2)
+                .all(|w| (w[1] - w[0]) as f32 >= min_gap - 1.0)
+        );
+        assert!(sparkle_frames(false).is_empty());
+    }
+
+    #[test]
+    fn scenes_switch_at_runtime() {
+        let config = Config {
+            star_count: 200,
+            moon: true,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(3), SCREEN, config);
+        starfield.update(1.0 / 60.0);
+        starfield.set_scene(Scene::Tunnel);
+        assert!(starfield.stars.len() == 0 && starfield.moon.is_none());
+        assert!(starfield.tunnel.is_some());
+        let mut frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+        starfield.update(1.0 / 60.0);
+        starfield.render(&mut frame);
+
+        // Round to stars again, which burst out anew, and the moon with them
+        starfield.set_scene(Scene::Tunnel.next());
+        assert_eq!(starfield.scene(), Scene::Starfield);
+        assert_eq!(starfield.stars.len(), 200);
+        assert!(starfield.tunnel.is_none() && starfield.moon.is_some());
+        starfield.update(1.0 / 60.0);
+        starfield.render(&mut frame);
+    }
+
+    #[test]
+    fn scene_changes_crossfade() {
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(5), SCREEN, Config::default());
+        let mut frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+        for _ in 0..60 {
+            starfield.update(1.0 / 60.0);
+        }
+        starfield.render(&mut frame);
+        let before = frame.clone();
+
+        // The first frame after the change is still the old scene's
+        starfield.fade_to(Scene::Tunnel);
+        assert_eq!(starfield.scene(), Scene::Starfield);
+        starfield.render(&mut frame);
+        assert_eq!(starfield.scene(), Scene::Tunnel);
+        assert_eq!(frame, before);
+
+        let fade = Config::default().scene_fade_seconds;
+        for _ in 0..(fade * 60.0) as usize + 1 {
+            starfield.update(1.0 / 60.0);
+        }
+        assert!(starfield.crossfade.is_none());
+        starfield.render(&mut frame);
+        assert_ne!(frame, before);
+    }
+
+    #[test]
+    fn same_seed_renders_the_same_frames() {
+        let screen = ScreenDetails {
+            width: 480,
+            height: 270,
+        };
+        let config = Config {
+            clouds: true,
+            ..Config::default()
+        };
+        let frame_after_two_seconds = |seed| {
+            let mut starfield =
+                Starfield::new(FastRng::seed_from_u64(seed), screen, config.clone());
+            for _ in 0..120 {
+                starfield.update(1.0 / 60.0);
+            }
+            let mut frame = vec![0; (screen.width * screen.height * 4) as usize];
+            starfield.render(&mut frame);
+            frame
+        };
+
+        assert!(frame_after_two_seconds(1) == frame_after_two_seconds(1));
+        assert!(frame_after_two_seconds(1) != frame_after_two_seconds(2));
+    }
+
+    #[test]
+    fn frames_are_identical_however_many_threads_draw_them() {
+        let frame_with = |render_threads| {
+            let config = Config {
+                render_threads,
+                clouds: true,
+                ..Config::default()
+            };
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(3), SCREEN, config);
+            for _ in 0..30 {
+                starfield.update(1.0 / 60.0);
+            }
+            let mut frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+            starfield.render(&mut frame);
+            frame
+        };
+
+        let single = frame_with(1);
+        assert!(single == frame_with(3));
+        assert!(single == frame_with(64));
+    }
+
+    #[cfg(feature = "alloc-audit")]
+    #[test]
+    fn steady_state_frames_do_not_allocate() {
+        // Render threads are kept from frame to frame, so handing them the
+        // tiles doesn't allocate either
+        let config = Config {
+            render_threads: 4,
+            ..Config::default()
+        };
+        let mut starfield = Starfield::new(FastRng::seed_from_u64(11), SCREEN, config);
+        let mut frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+        let mut step = || {
+            starfield.update(1.0 / 60.0);
+            starfield.render(&mut frame);
+        };
+
+        // Let pools and bins grow to their working size first
+        for _ in 0..600 {
+            step();
+        }
+
+        let before = alloc_audit::allocations();
+        for _ in 0..1200 {
+            step();
+        }
+        assert_eq!(alloc_audit::allocations() - before, 0);
+    }
+
+    #[test]
+    fn planets_bend_shooting_stars_that_pass_close() {
+        let config = Config {
+            star_count: 100,
+            planets: 1,
+            shooting_star_gravity: 0.0,
+            ..Config::default()
+        };
+        let heading = |planets: bool| {
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(3), SCREEN, config.clone());
+            let ((x, y), _) = starfield.planets[0].motion(&SCREEN);
+            if !planets {
+                starfield.planets.clear();
+            }
+            // Aimed to pass just above it
+            let star = ShootingStar::new(x + 200.0, y - 15.0, -400.0, 0.0, VecDeque::new());
+            starfield.shooting_stars.push(star);
+            for _ in 0..60 {
+                starfield.update(1.0 / 60.0);
+            }
+            let star = &starfield.shooting_stars[0];
+            star.vy.atan2(star.vx)
+        };
+        assert_eq!(heading(false), std::f32::consts::PI);
+        let bent = (heading(true) - std::f32::consts::PI).abs();
+        assert!(bent > 0.5, "{bent}");
+    }
+
+    #[test]
+    fn nothing_shines_through_the_ground() {
+        let render = |skyline: Option<SkylineShape>, shooting_stars: bool| {
+            let config = Config {
+                star_count: 0,
+                bloom: true,
+                skyline,
+                skyline_height: 0.5,
+                ..Config::default()
+            };
+            let mut starfield = Starfield::new(FastRng::seed_from_u64(2), SCREEN, config);
+            let mut frame = vec![0; (SCREEN.width * SCREEN.height * 4) as usize];
+            starfield.render(&mut frame);
+            if shooting_stars {
+                // A bunch just below the ridge, bright enough together to
+                // bloom and close enough for it to reach the sky above
+                let middle = SCREEN.width as i32 / 2;
+                let view = Rect::new(0, 0, SCREEN.width as i32, SCREEN.height as i32);
+                let ridge = match starfield.skyline.as_ref().and_then(|s| s.occlusion(view)) {
+                    Some(ground) => (middle - 8..middle + 8)
+                        .filter_map(|x| (ground.rect.y0..).find(|&y| ground.row(y, x)[0] == 255))
+                        .max()
+                        .unwrap(),
+                    None => SCREEN.height as i32 / 2,
+                };
+                for (dx, dy) in (-2..=2).flat_map(|dx| (0..5).map(move |dy| (dx, dy))) {
+                    let (x, y) = ((middle + dx) as f32, (ridge + 3 + dy) as f32);
+                    let star = ShootingStar::new(x, y, -300.0, 0.0, VecDeque::new());
+                    starfield.shooting_stars.push(star);
+                }
+            }
+            starfield.render(&mut frame);
+            frame
+        };
+        assert_ne!(render(None, true), render(None, false));
+        // Behind the hills, neither they nor their glow show
+        let hills = Some(SkylineShape::Mountains);
+        assert_eq!(render(hills, true), render(hills, false));
+    }
+}
@@ -0,0 +1,153 @@
+// Level of detail: when a sky's frames take too long to make, the costliest
+// effects are made more cheaply instead of being switched off. Shooting-star
+// trails are drawn from every other sample in wider strokes, the nebula is
+// resampled every other frame, and then the nebula and clouds lose their
+// finest octave of noise. The embedder sets a budget for each frame's work,
+// and the time each frame actually took moves the sky a level at a time
+// between full and least detail.
+
+use std::time::Duration;
+
+// Weight of each frame in the running average of frame times
+const SMOOTHING: f32 = 0.1;
+// Detail drops when frames average more than SIMPLIFY_AT of the budget, and
+// comes back once they are under RESTORE_AT of it
+const SIMPLIFY_AT: f32 = 0.75;
+const RESTORE_AT: f32 = 0.4;
+// Frames to wait after a change of level before another, so the average can
+// catch up with it
+const SETTLE_FRAMES: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Detail {
+    // Shooting-star trails are drawn from every this many samples
+    pub trail_step: usize,
+    // The nebula is resampled every this many frames
+    pub nebula_every: u32,
+    // Finest noise octaves left out of the nebula and clouds
+    pub octaves_dropped: u32,
+}
+
+impl Detail {
+    pub const FULL: Detail = Detail {
+        trail_step: 1,
+        nebula_every: 1,
+        octaves_dropped: 0,
+    };
+}
+
+const LEVELS: [Detail; 3] = [
+    Detail::FULL,
+    Detail {
+        trail_step: 2,
+        nebula_every: 2,
+        octaves_dropped: 0,
+    },
+    Detail {
+        trail_step: 2,
+        nebula_every: 2,
+        octaves_dropped: 1,
+    },
+];
+
+#[derive(Debug, Default)]
+pub(crate) struct Lod {
+    // Seconds each frame's work may take, or none for full detail always
+    budget: Option<f32>,
+    // Running average of frame times in seconds
+    load: f32,
+    // Index into LEVELS
+    level: usize,
+    settling: u32,
+}
+
+impl Lod {
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget
+            .map(|budget| budget.as_secs_f32())
+            .filter(|&budget| budget > 0.0);
+        if self.budget.is_none() {
+            self.level = 0;
+        }
+    }
+
+    // Count one frame's work
+    pub fn record(&mut self, work: Duration) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        self.load += (work.as_secs_f32() - self.load) * SMOOTHING;
+        if self.settling > 0 {
+            self.settling -= 1;
+            return;
+        }
+        let level = if self.load > budget * SIMPLIFY_AT {
+            (self.level + 1).min(LEVELS.len() - 1)
+        } else if self.load < budget * RESTORE_AT {
+            self.level.saturating_sub(1)
+        } else {
+            self.level
+        };
+        if level != self.level {
+            self.level = level;
+            self.settling = SETTLE_FRAMES;
+        }
+    }
+
+    pub fn detail(&self) -> Detail {
+        LEVELS[self.level]
+    }
+
+    // For the debug overlay: the level, 0 for full detail, and the average
+    // frame time as a share of the budget
+    pub fn describe(&self) -> String {
+        match self.budget {
+            Some(budget) => format!(
+                "level {} of {}, frames at {:.0}% of budget",
+                self.level,
+                LEVELS.len() - 1,
+                self.load / budget * 100.0
+            ),
+            None => "full, no budget".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn detail_steps_down_under_load_and_back() {
+        let mut lod = Lod::default();
+        // Without a budget nothing changes however slow it gets
+        for _ in 0..100 {
+            lod.record(BUDGET * 4);
+        }
+        assert_eq!(lod.detail(), Detail::FULL);
+
+        let mut lod = Lod::default();
+        lod.set_budget(Some(BUDGET));
+        let mut levels = Vec::new();
+        for _ in 0..200 {
+            lod.record(BUDGET);
+            levels.push(lod.level);
+        }
+        // One level at a time, to the least detail
+        assert_eq!(levels.iter().max(), Some(&(LEVELS.len() - 1)));
+        assert!(levels.windows(2).all(|pair| pair[1] <= pair[0] + 1));
+        assert_eq!(lod.detail().trail_step, 2);
+
+        // Half the budget is between the thresholds, so it stays put
+        for _ in 0..200 {
+            lod.record(BUDGET / 2);
+        }
+        assert_eq!(lod.level, LEVELS.len() - 1);
+        for _ in 0..200 {
+            lod.record(BUDGET / 10);
+        }
+        assert_eq!(lod.detail(), Detail::FULL);
+    }
+}
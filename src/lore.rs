@@ -0,0 +1,125 @@
+// Procedural star designations and flavour text. Everything is derived from
+// the star's seed, so a star keeps its name and story for as long as it lives.
+
+use crate::i18n::Locale;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// Forget everything once the cache grows past this; regenerating is cheap
+const CACHE_LIMIT: usize = 256;
+
+const CATALOGS: [&str; 5] = ["HD", "HIP", "Gliese", "TYC", "Kepler"];
+const SYLLABLES: [&str; 16] = [
+    "al", "nar", "ith", "ve", "sha", "dor", "mir", "zan", "ka", "rel", "thu", "on", "bel", "ques",
+    "ra", "lyn",
+];
+// Lore phrases are looked up as lore.0 .. lore.{LORE_COUNT - 1}
+const LORE_COUNT: usize = 12;
+
+pub struct Lore {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+// Rough spectral class from the star's tint
+fn spectral_class((r, g, b): (u8, u8, u8)) -> &'static str {
+    if b > r {
+        "B"
+    } else if r == g && g == b {
+        "A"
+    } else if g > 240 {
+        "G"
+    } else if g > 200 {
+        "K"
+    } else {
+        "M"
+    }
+}
+
+fn designation(rng: &mut StdRng) -> String {
+    match *CATALOGS.choose(rng).unwrap() {
+        "HD" => format!("HD {}", rng.gen_range(1..360_000)),
+        "HIP" => format!("HIP {}", rng.gen_range(1..120_000)),
+        "Gliese" => format!("Gliese {}", rng.gen_range(1..1_000)),
+        "TYC" => format!(
+            "TYC {}-{}-1",
+            rng.gen_range(1..9_600),
+            rng.gen_range(1..2_000)
+        ),
+        catalog => format!("{catalog}-{}", rng.gen_range(1..2_000)),
+    }
+}
+
+fn proper_name(rng: &mut StdRng) -> String {
+    let mut name: String = (0..rng.gen_range(2..=3))
+        .map(|_| *SYLLABLES.choose(rng).unwrap())
+        .collect();
+    name[..1].make_ascii_uppercase();
+    name
+}
+
+pub fn generate(seed: u64, color: (u8, u8, u8), depth: f32, locale: Locale) -> Lore {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let catalog = designation(&mut rng);
+
+    // A few stars are famous enough to have a proper name
+    let name = if rng.gen_bool(0.1) {
+        format!("{} ({catalog})", proper_name(&mut rng))
+    } else {
+        catalog
+    };
+
+    let distance = depth * rng.gen_range(40.0..400.0);
+    let class = format!("{}{}", spectral_class(color), rng.gen_range(0..10));
+    let phrase = format!("lore.{}", rng.gen_range(0..LORE_COUNT));
+    Lore {
+        name,
+        lines: vec![
+            locale.format(
+                "star.class",
+                &[("class", &class), ("distance", &format!("{distance:.0}"))],
+            ),
+            locale.tr(&phrase).to_string(),
+        ],
+    }
+}
+
+// Generated lore keyed by star seed
+pub struct LoreCache {
+    entries: HashMap<u64, Lore>,
+    locale: Locale,
+}
+
+impl LoreCache {
+    pub fn new(locale: Locale) -> Self {
+        Self {
+            entries: HashMap::new(),
+            locale,
+        }
+    }
+
+    pub fn get(&mut self, seed: u64, color: (u8, u8, u8), depth: f32) -> &Lore {
+        if self.entries.len() >= CACHE_LIMIT && !self.entries.contains_key(&seed) {
+            self.entries.clear();
+        }
+        self.entries
+            .entry(seed)
+            .or_insert_with(|| generate(seed, color, depth, self.locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lore_is_stable_per_seed() {
+        let a = generate(42, (255, 255, 255), 1.0, Locale::En);
+        let b = generate(42, (255, 255, 255), 1.0, Locale::En);
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.lines, b.lines);
+        assert!(a.lines[0].starts_with("Class A"));
+    }
+}
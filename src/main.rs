@@ -1,370 +1,890 @@
+mod accent_watch;
+mod bench;
+mod cli;
+mod commands;
+mod completions;
+mod frame_export;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod headless;
+mod hooks;
+mod layer_shell;
+mod pipewire;
+mod sky_report;
+mod stagger;
+mod stats;
+mod status;
+mod tuning;
+mod video;
+
+use accent_watch::AccentWatch;
+use cli::Args;
+use commands::Command;
+use frame_export::FrameExport;
+#[cfg(feature = "gpu")]
+use gpu::SpriteRenderer;
+use hooks::Hooks;
+use pipewire::PipewireSource;
 use pixels::{Error, Pixels, SurfaceTexture};
-use rand::Rng;
-use std::time::Instant;
+use rand::SeedableRng;
+use sky_report::SkyReport;
+use stagger::Stagger;
+use stats::StatsKeeper;
+use status::StatusWriter;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::{Duration, Instant};
 use winit::{
     dpi::PhysicalSize,
-    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window, WindowBuilder},
 };
-
+use wl_starfield::config::{self, Config};
+use wl_starfield::exposure::{self, Exposure};
+use wl_starfield::quote::QuoteOverlay;
+use wl_starfield::recording::Recording;
+use wl_starfield::rng::FastRng;
+use wl_starfield::{ScreenDetails, Starfield};
+
+// Window size when there's no monitor to take it from
 const WIDTH: u32 = 1920;
 const HEIGHT: u32 = 1080;
-const STAR_COUNT: usize = 5000;
-const SHOOTING_STAR_GRAVITY: f32 = 30.0;
-const STAR_MIN_SIZE: u32 = 1;
-const STAR_MAX_SIZE: u32 = 4;
-const STAR_MIN_SPEED: f32 = 5.0;
-const STAR_MAX_SPEED: f32 = 25.0;
-
-struct ScreenDetails {
-    width: u32,
-    height: u32,
-}
-
-// Common trait for all celestial objects
-trait CelestialObject {
-    fn update(&mut self, dt: f32, elapsed: f32, rng: &mut impl Rng, screen_details: &ScreenDetails);
-    fn draw(&self, frame: &mut [u8], screen_details: &ScreenDetails);
-    fn is_alive(&self, screen_details: &ScreenDetails) -> bool;
-}
-
-struct Star {
-    x: f32,
-    y: f32,
-    speed: f32,
-    twinkle_phase: f32,
-    twinkle_speed: f32,
-    can_twinkle: bool,
-    depth: f32,
-    color: (u8, u8, u8),
-    size: u32,
-}
-
-impl CelestialObject for Star {
-    fn update(
-        &mut self,
-        dt: f32,
-        _elapsed: f32,
-        rng: &mut impl Rng,
-        screen_details: &ScreenDetails,
-    ) {
-        self.speed *= 0.999_f32.powf(dt * 60.0);
-        self.x -= self.speed * self.depth * dt;
+// The up and down arrows double and halve how fast the sky goes, within these
+const MIN_SPEED: f32 = 1.0 / 16.0;
+const MAX_SPEED: f32 = 64.0;
+// Frame budget for the level of detail when the frame rate isn't capped
+const UNCAPPED_BUDGET: Duration = Duration::from_nanos(16_666_667);
 
-        if self.x < 0.0 {
-            self.x = screen_details.width as f32;
-            self.y = rng.gen_range(0.0..screen_details.height as f32);
-            self.depth = rng.gen_range(0.5..2.0);
-            self.twinkle_phase = rng.gen_range(0.0..std::f32::consts::TAU);
-            self.twinkle_speed = rng.gen_range(0.5..3.14); // Max 1 blink every 2 seconds
-            self.speed = rng.gen_range(STAR_MIN_SPEED..STAR_MAX_SPEED);
-            self.size = rng.gen_range(STAR_MIN_SIZE..=STAR_MAX_SIZE);
-        }
+fn main() -> Result<(), Error> {
+    let args = Args::parse();
+    let mut config = Config::load();
+    let fps_cap = args.fps_cap.unwrap_or(config.fps_cap);
+    let frame_interval = (fps_cap > 0.0).then(|| Duration::from_secs_f32(1.0 / fps_cap));
+    tuning::apply(
+        &mut config,
+        frame_interval.unwrap_or(UNCAPPED_BUDGET),
+        args.retune,
+    );
+    if let Some(star_count) = args.star_count {
+        config.star_count = star_count;
     }
-
-    fn draw(&self, frame: &mut [u8], screen_details: &ScreenDetails) {
-        // We need elapsed time for twinkling, but we can calculate it from the phase
-        // For now, let's use a simple approach - we'll pass elapsed through context later if needed
-        let twinkle = (self.twinkle_phase).sin() * 0.5 + 0.5;
-        let intensity = (twinkle * 255.0 / self.depth).min(200.0) as u8;
-
-        let (base_r, base_g, base_b) = self.color;
-        let r = ((base_r as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
-        let g = ((base_g as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
-        let b = ((base_b as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
-
-        for dx in 0..self.size {
-            for dy in 0..self.size {
-                let ix = self.x as i32 + dx as i32;
-                let iy = self.y as i32 + dy as i32;
-                if ix >= 0
-                    && ix < screen_details.width as i32
-                    && iy >= 0
-                    && iy < screen_details.height as i32
-                {
-                    let idx = ((iy as u32 * screen_details.width + ix as u32) * 4) as usize;
-                    frame[idx] = r;
-                    frame[idx + 1] = g;
-                    frame[idx + 2] = b;
-                    frame[idx + 3] = 255;
-                }
-            }
-        }
+    if let Some(accent) = args.accent {
+        config.accent = Some(accent);
     }
-
-    fn is_alive(&self, _: &ScreenDetails) -> bool {
-        true // Stars are always alive, they just wrap around
+    if let Some(path) = &args.export_frames {
+        config.frame_export = Some(path.clone());
     }
-}
-
-impl Star {
-    fn new(rng: &mut impl Rng, width: u32, height: u32) -> Self {
-        let palette = [
-            (180, 200, 255), // blue
-            (255, 255, 255), // white
-            (255, 255, 200), // yellow
-            (255, 220, 180), // orange
-            (255, 180, 180), // red
-        ];
-        let color = palette[rng.gen_range(0..palette.len())];
+    config.pipewire_source |= args.pipewire;
+    if let Some(scene) = args.scene {
+        config.scene = scene;
+    }
+    if let Some((latitude, longitude)) = args.location {
+        (config.latitude, config.longitude) = (Some(latitude), Some(longitude));
+    }
+    let seed = args.seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        eprintln!("wl-starfield: sky seed {seed} (pass --seed {seed} to see it again)");
+        seed
+    });
+    let utc_offset = utc_offset();
+    let frame_budget = config
+        .adaptive_detail
+        .then(|| frame_interval.unwrap_or(UNCAPPED_BUDGET));
+    // Each output gets its own sky, derived from the one seed, or a copy of
+    // the one sky they all share, with stars enough to fill every output.
+    // An output with its own sky may have its own scene schedule too.
+    let new_starfield = |screen_details, sky: Sky, name: Option<&str>| {
+        let (output, outputs) = match sky {
+            Sky::Own(output) => (output, 1),
+            Sky::Spanning(outputs) => (0, outputs),
+        };
+        let sky_config = Config {
+            star_count: config.star_count * outputs,
+            scene_schedule: config.scene_schedule_for(name),
+            ..config.clone()
+        };
+        let rng = FastRng::seed_from_u64(seed.wrapping_add(output));
+        let mut starfield = Starfield::new(rng, screen_details, sky_config);
+        starfield.set_utc_offset(utc_offset);
+        starfield.set_frame_budget(frame_budget);
+        starfield.set_focus(args.focus);
+        if let Some(speed) = args.timelapse {
+            starfield.set_timelapse(speed);
+        }
+        starfield.debug = args.debug;
+        starfield.quotes = config::config_dir().and_then(|dir| {
+            QuoteOverlay::load(
+                &dir.join("quotes.txt"),
+                config.quote_interval,
+                config.quote_anchor,
+            )
+        });
+        starfield
+    };
 
-        Self {
-            x: rng.gen_range(0.0..width as f32),
-            y: rng.gen_range(0.0..height as f32),
-            speed: rng.gen_range(STAR_MIN_SPEED..STAR_MAX_SPEED),
-            can_twinkle: rng.gen_bool(0.15),
-            twinkle_phase: rng.gen_range(0.0..std::f32::consts::TAU),
-            twinkle_speed: rng.gen_range(0.5..3.14), // Max 1 blink every 2 seconds
-            depth: rng.gen_range(0.5..4.0),
-            color,
-            size: rng.gen_range(STAR_MIN_SIZE..=STAR_MAX_SIZE),
+    if let Some(path) = &args.record_video {
+        let (width, height) = args.windowed.unwrap_or((WIDTH, HEIGHT));
+        let seconds = args.record.unwrap_or(config.record_seconds);
+        // An uncapped frame rate has no fixed step to go by
+        let fps = if fps_cap > 0.0 { fps_cap } else { 60.0 };
+        let screen_details = ScreenDetails { width, height };
+        if let Err(err) = video::export(path, screen_details, seconds, fps, new_starfield) {
+            eprintln!("wl-starfield: {err}");
+            std::process::exit(1);
         }
+        return Ok(());
     }
 
-    fn update_twinkle(&mut self, elapsed: f32) {
-        if self.can_twinkle {
-            self.twinkle_phase = elapsed * self.twinkle_speed + self.twinkle_phase;
+    let commands = commands::listen(args.commands.as_deref());
+    let mut hooks = Hooks::new(&config);
+    // A shared sky already carries its events across outputs
+    let mut stagger = Stagger::new(config.stagger_events && !config.span_outputs);
+
+    // Prefer living on the wallpaper layer; fall back to a plain window
+    if args.windowed.is_none() {
+        match layer_shell::run(
+            &args,
+            frame_interval,
+            &config,
+            &commands,
+            &mut hooks,
+            &mut stagger,
+            new_starfield,
+        ) {
+            Ok(()) => return Ok(()),
+            Err(layer_shell::Error::NoWayland) => {}
+            Err(err @ layer_shell::Error::NoLayerShell) => {
+                eprintln!("wl-starfield: {err}, opening a fullscreen window instead");
+            }
+            Err(err) => {
+                eprintln!("wl-starfield: {err}");
+                std::process::exit(1);
+            }
         }
     }
+    run_window(
+        &args,
+        &config,
+        frame_interval,
+        commands,
+        hooks,
+        stagger,
+        new_starfield,
+    )
 }
 
-struct ShootingStar {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    life: f32,
-    max_life: f32,
-    trail: Vec<(f32, f32)>,
-    trail_max_len: usize,
+// Which sky `new_starfield` builds for an output
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sky {
+    // Its own, by sky number
+    Own(u64),
+    // A copy of the one sky shared by this many outputs
+    Spanning(usize),
 }
 
-impl CelestialObject for ShootingStar {
-    fn update(&mut self, dt: f32, _elapsed: f32, _rng: &mut impl Rng, _: &ScreenDetails) {
-        // Store current position in trail
-        self.trail.push((self.x, self.y));
-        if self.trail.len() > self.trail_max_len {
-            self.trail.remove(0);
-        }
-
-        // Update physics
-        self.x += self.vx * dt;
-        self.vy += SHOOTING_STAR_GRAVITY * dt;
-        self.y += self.vy * dt;
-        self.life += dt;
+// How far local time is ahead of UTC, in seconds, going by `date` since std
+// knows nothing of time zones. UTC if it can't be found out.
+fn utc_offset() -> i64 {
+    let output = std::process::Command::new("date").arg("+%z").output();
+    let Ok(output) = output else {
+        return 0;
+    };
+    // Like +0530 or -0800
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = text.trim();
+    let sign = if text.starts_with('-') { -1 } else { 1 };
+    let digits = text.trim_start_matches(['+', '-']);
+    match (digits.get(..2), digits.get(2..4)) {
+        (Some(hours), Some(minutes)) => match (hours.parse::<i64>(), minutes.parse::<i64>()) {
+            (Ok(hours), Ok(minutes)) => sign * (hours * 3600 + minutes * 60),
+            _ => 0,
+        },
+        _ => 0,
     }
+}
 
-    fn draw(&self, frame: &mut [u8], _: &ScreenDetails) {
-        let alpha = (1.0 - self.life / self.max_life).clamp(0.0, 1.0);
-
-        // Draw trail using stored positions
-        for (i, &(tx, ty)) in self.trail.iter().enumerate() {
-            let trail_progress = i as f32 / self.trail.len() as f32;
-            let trail_alpha = alpha * trail_progress * trail_progress; // Quadratic falloff
-
-            if trail_alpha < 0.01 {
-                continue; // Skip nearly invisible segments
-            }
-
-            // Color gradient: white/yellow at head to orange/red at tail
-            let r = (255.0 * (0.8 + 0.2 * trail_progress)) as u8;
-            let g = (255.0 * (0.6 + 0.4 * trail_progress)) as u8;
-            let b = (100.0 + 155.0 * (1.0 - trail_progress)) as u8;
-
-            // Variable width: thicker at head, thinner at tail
-            let width = (1.0 + 3.0 * trail_progress) as i32;
-
-            self.draw_point(frame, tx, ty, r, g, b, trail_alpha, width);
+// Develop and write out a finished capture off the render thread, since
+// encoding a large PNG takes a noticeable moment
+fn save_capture(exposure: Exposure, dir: Option<PathBuf>) {
+    thread::spawn(move || {
+        let path = exposure::capture_path(dir.as_deref(), "wl-starfield");
+        let image = exposure.develop();
+        match exposure::save_png(&path, exposure.size(), &image) {
+            Ok(()) => eprintln!("wl-starfield: saved {}", path.display()),
+            Err(err) => eprintln!("wl-starfield: can't save {}: {err}", path.display()),
         }
+    });
+}
 
-        // Draw bright head
-        if alpha > 0.01 {
-            let head_size = 6;
-            self.draw_point(frame, self.x, self.y, 255, 255, 220, alpha, head_size);
+// Write out a copy of a frame as it was drawn, likewise off the render thread
+fn save_screenshot(mut frame: Vec<u8>, size: ScreenDetails, dir: Option<PathBuf>) {
+    thread::spawn(move || {
+        // Whatever alpha the sky left behind, the picture is opaque
+        for pixel in frame.chunks_exact_mut(4) {
+            pixel[3] = 255;
         }
-    }
+        let path = exposure::capture_path(dir.as_deref(), "wl-starfield-screenshot");
+        match exposure::save_png(&path, size, &frame) {
+            Ok(()) => eprintln!("wl-starfield: saved {}", path.display()),
+            Err(err) => eprintln!("wl-starfield: can't save {}: {err}", path.display()),
+        }
+    });
+}
 
-    fn is_alive(&self, screen_details: &ScreenDetails) -> bool {
-        self.life < self.max_life
-            && self.x > -200.0
-            && self.x < screen_details.width as f32 + 200.0
-            && self.y > -200.0
-            && self.y < screen_details.height as f32 + 200.0
-    }
+// Starts animated recordings, with the settings from the config
+#[derive(Clone)]
+pub struct Recorder {
+    seconds: f32,
+    fps: f32,
+    scale: f32,
+    // Where recordings, captures and screenshots go
+    pub dir: Option<PathBuf>,
 }
 
-impl ShootingStar {
-    fn new(start_x: f32, start_y: f32, vx: f32, vy: f32) -> Self {
-        let max_life = 3.0;
+impl Recorder {
+    pub fn new(config: &Config) -> Self {
         Self {
-            x: start_x,
-            y: start_y,
-            vx,
-            vy,
-            life: 0.0,
-            max_life,
-            trail: Vec::new(),
-            trail_max_len: 80,
+            seconds: config.record_seconds,
+            fps: config.record_fps,
+            scale: config.record_scale,
+            dir: config.capture_dir.clone(),
         }
     }
 
-    fn draw_point(
+    // Record a screen to a fresh file, for `seconds` or by default
+    // record_seconds
+    pub fn start(
         &self,
-        frame: &mut [u8],
-        x: f32,
-        y: f32,
-        r: u8,
-        g: u8,
-        b: u8,
-        alpha: f32,
-        size: i32,
-    ) {
-        let center_x = x as i32;
-        let center_y = y as i32;
-
-        for dx in -size / 2..=size / 2 {
-            for dy in -size / 2..=size / 2 {
-                let px = center_x + dx;
-                let py = center_y + dy;
-
-                if px >= 0 && px < WIDTH as i32 && py >= 0 && py < HEIGHT as i32 {
-                    let idx = ((py as u32 * WIDTH + px as u32) * 4) as usize;
-
-                    // Soft circular falloff
-                    let dist = ((dx * dx + dy * dy) as f32).sqrt();
-                    let radius = size as f32 / 2.0;
-                    let falloff = (1.0 - (dist / radius).clamp(0.0, 1.0)).powf(2.0);
-                    let final_alpha = (alpha * falloff).clamp(0.0, 1.0);
-
-                    // Proper alpha blending
-                    let old_r = frame[idx] as f32 / 255.0;
-                    let old_g = frame[idx + 1] as f32 / 255.0;
-                    let old_b = frame[idx + 2] as f32 / 255.0;
-
-                    let new_r = r as f32 / 255.0;
-                    let new_g = g as f32 / 255.0;
-                    let new_b = b as f32 / 255.0;
-
-                    frame[idx] =
-                        ((old_r * (1.0 - final_alpha) + new_r * final_alpha) * 255.0) as u8;
-                    frame[idx + 1] =
-                        ((old_g * (1.0 - final_alpha) + new_g * final_alpha) * 255.0) as u8;
-                    frame[idx + 2] =
-                        ((old_b * (1.0 - final_alpha) + new_b * final_alpha) * 255.0) as u8;
-                    frame[idx + 3] = 255;
-                }
+        screen_details: ScreenDetails,
+        seconds: Option<f32>,
+    ) -> Option<(Recording, PathBuf)> {
+        let seconds = seconds.unwrap_or(self.seconds);
+        let path = exposure::capture_path(self.dir.as_deref(), "wl-starfield-recording");
+        match File::create(&path) {
+            Ok(file) => {
+                eprintln!("wl-starfield: recording for {seconds} s");
+                let recording = Recording::new(file, screen_details, seconds, self.fps, self.scale);
+                Some((recording, path))
+            }
+            Err(err) => {
+                eprintln!("wl-starfield: can't save {}: {err}", path.display());
+                None
             }
         }
     }
 }
 
-// Helper function to update and draw celestial objects
-fn update_and_draw_objects<T: CelestialObject>(
-    objects: &mut Vec<T>,
-    dt: f32,
-    elapsed: f32,
-    frame: &mut [u8],
-    rng: &mut impl Rng,
-    screen_details: &ScreenDetails,
-) {
-    objects.retain_mut(|obj| {
-        obj.update(dt, elapsed, rng, screen_details);
-        obj.draw(frame, screen_details);
-        obj.is_alive(screen_details)
+// Wait for a recording's encoder off the render thread, and clear away the
+// file if it was cut short
+pub fn save_recording((recording, path): (Recording, PathBuf)) {
+    thread::spawn(move || match recording.finish() {
+        Ok(()) => eprintln!("wl-starfield: saved {}", path.display()),
+        Err(err) => {
+            let _ = fs::remove_file(&path);
+            eprintln!("wl-starfield: recording abandoned: {err}");
+        }
     });
 }
 
-fn main() -> Result<(), Error> {
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title("wl-starfield")
-        .with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)))
-        .build(&event_loop)
-        .unwrap();
+// One winit window per output
+struct Output {
+    window: Window,
+    pixels: Pixels,
+    starfield: Starfield,
+    // Sky number, and the left edge of its monitor in the desktop layout
+    index: u64,
+    left: i32,
+    // Where it looks onto the sky that every output shares, if they do
+    view: Option<(i32, i32)>,
+    // Set when stars are drawn on the GPU
+    #[cfg(feature = "gpu")]
+    sprites: Option<SpriteRenderer>,
+    // Long-exposure capture in progress
+    exposure: Option<Exposure>,
+    // Animated recording in progress, and the file it goes to
+    recording: Option<(Recording, PathBuf)>,
+    // Where its frames are shared, on the first output
+    export: Option<FrameExport>,
+    pipewire: Option<PipewireSource>,
+    last_frame: Instant,
+}
 
-    // Get monitor resolution at startup
-    let size = window
-        .current_monitor()
-        .map(|m| m.size())
-        .unwrap_or(PhysicalSize::new(WIDTH, HEIGHT));
+// Draw a frame, with the stars as GPU sprites if they're drawn that way
+fn render(output: &mut Output) -> Result<(), pixels::Error> {
+    #[cfg(feature = "gpu")]
+    if let Some(rendered) = render_with_sprites(output) {
+        return rendered;
+    }
+    output.starfield.render(output.pixels.frame_mut());
+    output.pixels.render()
+}
 
-    let screen_details = ScreenDetails {
-        width: size.width,
-        height: size.height,
-    };
+// Captures and exports need the stars in the frame, so they take the CPU path
+#[cfg(feature = "gpu")]
+fn render_with_sprites(output: &mut Output) -> Option<Result<(), pixels::Error>> {
+    let renderer = output.sprites.as_mut().filter(|_| {
+        output.exposure.is_none() && output.export.is_none() && output.pipewire.is_none()
+    })?;
+    let frame = output.pixels.frame_mut();
+    output
+        .starfield
+        .render_with_sprites(frame, &mut renderer.sprites);
+    let screen_details = output.starfield.screen_details();
+    Some(output.pixels.render_with(|encoder, target, context| {
+        context.scaling_renderer.render(encoder, target);
+        renderer.draw(encoder, target, context, screen_details);
+        Ok(())
+    }))
+}
 
-    let surface_texture = SurfaceTexture::new(screen_details.width, screen_details.height, &window);
-    let mut pixels = Pixels::new(screen_details.width, screen_details.height, surface_texture)?;
+// Borderless fullscreen windows on every monitor (or just the --monitor one),
+// or with --windowed a single ordinary window
+fn run_window(
+    args: &Args,
+    config: &Config,
+    frame_interval: Option<Duration>,
+    commands: Receiver<Command>,
+    mut hooks: Hooks,
+    mut stagger: Stagger,
+    new_starfield: impl Fn(ScreenDetails, Sky, Option<&str>) -> Starfield,
+) -> Result<(), Error> {
+    let event_loop = EventLoop::new();
+    let monitors: Vec<Option<MonitorHandle>> = if args.windowed.is_some() {
+        vec![None]
+    } else if let Some(wanted) = args.monitor.as_deref() {
+        let found = event_loop
+            .available_monitors()
+            .enumerate()
+            .find(|(i, m)| m.name().as_deref() == Some(wanted) || i.to_string() == wanted)
+            .map(|(_, m)| m);
+        if found.is_none() {
+            eprintln!("wl-starfield: no monitor named {wanted}, using the current one");
+        }
+        vec![found]
+    } else {
+        let all: Vec<_> = event_loop.available_monitors().map(Some).collect();
+        if all.is_empty() { vec![None] } else { all }
+    };
 
-    let mut rng = rand::thread_rng();
-    let mut stars: Vec<Star> = (0..STAR_COUNT)
-        .map(|_| Star::new(&mut rng, screen_details.width, screen_details.height))
-        .collect();
-    let mut shooting_stars: Vec<ShootingStar> = Vec::new();
-    let start = Instant::now();
-    let mut last_frame = start;
+    // Skies are simulated this many times smaller than the screen and
+    // blown up with nearest-neighbor scaling
+    let pixel_scale = config.pixel_scale;
+    let interactive = config.interactive;
+    // Where a shared sky spans every monitor, the desktop space they cover
+    // and the top left of it
+    let span = (config.span_outputs && monitors.len() > 1).then(|| {
+        let (mut min, mut max) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+        for monitor in monitors.iter().flatten() {
+            let (position, size) = (monitor.position(), monitor.size());
+            min = (min.0.min(position.x), min.1.min(position.y));
+            max = (
+                max.0.max(position.x + size.width as i32),
+                max.1.max(position.y + size.height as i32),
+            );
+        }
+        let space = ScreenDetails {
+            width: (max.0 - min.0) as u32,
+            height: (max.1 - min.1) as u32,
+        };
+        (space.scaled_down(pixel_scale), min)
+    });
+    let sky_count = monitors.len();
+
+    let mut outputs = Vec::with_capacity(monitors.len());
+    for (index, monitor) in monitors.into_iter().enumerate() {
+        let position = monitor.as_ref().map_or((0, 0), |m| {
+            let position = m.position();
+            (position.x, position.y)
+        });
+        let name = monitor.as_ref().and_then(MonitorHandle::name);
+        let builder = WindowBuilder::new().with_title("wl-starfield");
+        let builder = if let Some((width, height)) = args.windowed {
+            builder.with_inner_size(PhysicalSize::new(width, height))
+        } else {
+            builder.with_fullscreen(Some(Fullscreen::Borderless(monitor)))
+        };
+        let window = builder.build(&event_loop).unwrap();
+
+        // Fill the window, or the monitor it went fullscreen on, at startup
+        let size = if args.windowed.is_some() {
+            window.inner_size()
+        } else {
+            window
+                .current_monitor()
+                .map(|m| m.size())
+                .unwrap_or(PhysicalSize::new(WIDTH, HEIGHT))
+        };
+        let screen_details = ScreenDetails {
+            width: size.width,
+            height: size.height,
+        }
+        .scaled_down(pixel_scale);
+
+        let surface_texture = SurfaceTexture::new(size.width, size.height, &window);
+        let pixels = Pixels::new(screen_details.width, screen_details.height, surface_texture)?;
+        // Chunky pixels are drawn on the CPU, where stars keep to the grid
+        #[cfg(feature = "gpu")]
+        let sprites = (config.gpu && pixel_scale == 1)
+            .then(|| SpriteRenderer::new(pixels.device(), pixels.render_texture_format()));
+        let view = span.map(|(_, (left, top))| {
+            let scale = pixel_scale as i32;
+            ((position.0 - left) / scale, (position.1 - top) / scale)
+        });
+        let mut starfield = match span {
+            Some((space, _)) => new_starfield(space, Sky::Spanning(sky_count), None),
+            None => new_starfield(screen_details, Sky::Own(index as u64), name.as_deref()),
+        };
+        if let Some(view) = view {
+            starfield.set_view(view, screen_details);
+        }
+        outputs.push(Output {
+            window,
+            pixels,
+            starfield,
+            index: index as u64,
+            left: position.0,
+            view,
+            #[cfg(feature = "gpu")]
+            sprites,
+            exposure: None,
+            recording: None,
+            export: None,
+            pipewire: None,
+            last_frame: Instant::now(),
+        });
+    }
+    outputs[0].export = frame_export::start(config.frame_export.as_deref());
+    outputs[0].pipewire = PipewireSource::new(config, frame_interval);
+    let recorder = Recorder::new(config);
+    if let Some(seconds) = args.record {
+        outputs[0].recording = recorder.start(outputs[0].starfield.screen_details(), Some(seconds));
+    }
 
+    let exposure_seconds = config.exposure_seconds;
+    let (capture_crop, capture_scale) = (config.capture_crop, config.capture_scale);
+    let new_exposure = move |screen_details| {
+        Exposure::new(
+            screen_details,
+            exposure_seconds,
+            capture_crop,
+            capture_scale,
+        )
+    };
+    // Frame rate and state for status bars, as seen on the first output
+    let first_output = outputs[0].window.id();
+    let mut status = StatusWriter::new();
+    let mut stats = StatsKeeper::new(config.stats_overlay);
+    let mut sky_report = SkyReport::new(config);
+    let mut accent = AccentWatch::new(config);
+    let layout: Vec<_> = outputs.iter().map(|o| (o.index, o.left)).collect();
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-
         match event {
-            Event::RedrawRequested(_) => {
+            Event::RedrawRequested(id) => {
+                let Some(output) = outputs.iter_mut().find(|o| o.window.id() == id) else {
+                    return;
+                };
                 let now = Instant::now();
-                let dt = (now - last_frame).as_secs_f32();
-                last_frame = now;
-
-                let elapsed = start.elapsed().as_secs_f32();
-                let frame = pixels.frame_mut();
-                frame.fill(0);
-
-                // Update stars with special handling for twinkling
-                for star in &mut stars {
-                    star.update(dt, elapsed, &mut rng, &screen_details);
-                    star.update_twinkle(elapsed);
-                    star.draw(frame, &screen_details);
+                let dt = (now - output.last_frame).as_secs_f32();
+                output.last_frame = now;
+
+                output.starfield.update(dt);
+                stagger.frame(output.index, &mut output.starfield, &layout, now);
+                hooks.fire(output.starfield.events());
+                if id == first_output {
+                    status.frame(&output.starfield);
+                    sky_report.check();
+                    stats.frame(&output.starfield);
                 }
+                stats.show_on(&mut output.starfield);
+                let rendered = render(output);
 
-                // Spawn shooting stars less frequently but more predictably
-                if rng.gen_bool(dt as f64 * 0.3) {
-                    // About 1 every 3-4 seconds
-                    let start_x = screen_details.width as f32 + 50.0; // Start off-screen
-                    let start_y = rng.gen_range(50.0..screen_details.height as f32 * 0.4);
-                    let vx = -rng.gen_range(200.0..400.0); // Faster horizontal speed
-                    let vy = rng.gen_range(10.0..50.0); // Moderate downward speed
-
-                    shooting_stars.push(ShootingStar::new(start_x, start_y, vx, vy));
+                if let Some(exposure) = &mut output.exposure
+                    && exposure.add(output.pixels.frame(), dt)
+                {
+                    save_capture(output.exposure.take().unwrap(), recorder.dir.clone());
+                }
+                if let Some((recording, _)) = &mut output.recording
+                    && recording.add(output.pixels.frame(), dt)
+                {
+                    save_recording(output.recording.take().unwrap());
                 }
 
-                // Update and draw shooting stars using the trait
-                update_and_draw_objects(
-                    &mut shooting_stars,
-                    dt,
-                    elapsed,
-                    frame,
-                    &mut rng,
-                    &screen_details,
-                );
+                if let Some(export) = &mut output.export
+                    && let Err(err) =
+                        export.publish(output.pixels.frame(), output.starfield.screen_details())
+                {
+                    eprintln!("wl-starfield: frame export stopped: {err}");
+                    output.export = None;
+                }
+                if let Some(pipewire) = &mut output.pipewire
+                    && let Err(err) =
+                        pipewire.publish(output.pixels.frame(), output.starfield.screen_details())
+                {
+                    eprintln!("wl-starfield: PipeWire source stopped: {err}");
+                    output.pipewire = None;
+                }
 
-                if pixels.render().is_err() {
+                if rendered.is_err() {
                     *control_flow = ControlFlow::Exit;
                 }
             }
             Event::MainEventsCleared => {
-                window.request_redraw();
+                if let Some(color) = accent.check() {
+                    for output in &mut outputs {
+                        output.starfield.set_accent(Some(color));
+                    }
+                }
+                for command in commands.try_iter() {
+                    match command {
+                        Command::Quit => *control_flow = ControlFlow::Exit,
+                        Command::Capture => {
+                            eprintln!("wl-starfield: exposing for {exposure_seconds} s");
+                            for output in &mut outputs {
+                                output.exposure.get_or_insert_with(|| {
+                                    new_exposure(output.starfield.screen_details())
+                                });
+                            }
+                        }
+                        command => {
+                            let command = match outputs.first() {
+                                Some(output) => command.settle(&output.starfield),
+                                None => command,
+                            };
+                            for output in &mut outputs {
+                                command.apply(&mut output.starfield);
+                            }
+                        }
+                    }
+                }
+                if *control_flow == ControlFlow::Exit {
+                    return;
+                }
+
+                let Some(interval) = frame_interval else {
+                    // Uncapped: redraw as fast as the outputs allow
+                    *control_flow = ControlFlow::Poll;
+                    outputs
+                        .iter()
+                        .for_each(|output| output.window.request_redraw());
+                    return;
+                };
+
+                // Redraw whatever is due, then sleep until the next output is
+                let now = Instant::now();
+                let mut next_frame = now + interval;
+                for output in &outputs {
+                    let due = output.last_frame + interval;
+                    if due <= now {
+                        output.window.request_redraw();
+                    } else {
+                        next_frame = next_frame.min(due);
+                    }
+                }
+                *control_flow = ControlFlow::WaitUntil(next_frame);
             }
-            Event::WindowEvent { event, .. } => {
-                if let WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            virtual_keycode: Some(VirtualKeyCode::Escape),
-                            state: ElementState::Pressed,
-                            ..
-                        },
-                    ..
-                } = event
-                {
-                    *control_flow = ControlFlow::Exit;
+            Event::WindowEvent { window_id, event } => {
+                let Some(output) = outputs.iter_mut().find(|o| o.window.id() == window_id) else {
+                    return;
+                };
+                match event {
+                    WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                        let screen_details = ScreenDetails {
+                            width: size.width,
+                            height: size.height,
+                        }
+                        .scaled_down(pixel_scale);
+                        if output
+                            .pixels
+                            .resize_surface(size.width, size.height)
+                            .is_err()
+                            || output
+                                .pixels
+                                .resize_buffer(screen_details.width, screen_details.height)
+                                .is_err()
+                        {
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                        // A shared sky stays the size of the whole desktop
+                        match output.view {
+                            Some(view) => output.starfield.set_view(view, screen_details),
+                            None => output.starfield.resize(screen_details),
+                        }
+                        if output
+                            .exposure
+                            .take_if(|e| e.screen_details() != screen_details)
+                            .is_some()
+                        {
+                            eprintln!("wl-starfield: window resized, capture abandoned");
+                        }
+                        if let Some(recording) = output
+                            .recording
+                            .take_if(|(r, _)| r.screen_details() != screen_details)
+                        {
+                            save_recording(recording);
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } if interactive => {
+                        let scale = pixel_scale as f32;
+                        output.starfield.cursor =
+                            Some((position.x as f32 / scale, position.y as f32 / scale));
+                    }
+                    WindowEvent::CursorLeft { .. } => {
+                        output.starfield.cursor = None;
+                    }
+                    WindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        button: MouseButton::Left,
+                        ..
+                    } if interactive => {
+                        output.starfield.click();
+                    }
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::Escape),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    // Focus sessions, like breathing, span every monitor
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::F),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let focused = !output.starfield.is_focused();
+                        for output in &mut outputs {
+                            output.starfield.set_focus(focused);
+                        }
+                    }
+                    // Start a long exposure of this output
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::P),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if output.exposure.is_none() => {
+                        eprintln!("wl-starfield: exposing for {exposure_seconds} s");
+                        output.exposure = Some(new_exposure(output.starfield.screen_details()));
+                    }
+                    // Save this output's frame as it is now
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::F12),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        save_screenshot(
+                            output.pixels.frame().to_vec(),
+                            output.starfield.screen_details(),
+                            recorder.dir.clone(),
+                        );
+                    }
+                    // Record this output, or abandon the recording under way
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::R),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => match output.recording.take() {
+                        Some(recording) => save_recording(recording),
+                        None => {
+                            output.recording =
+                                recorder.start(output.starfield.screen_details(), None);
+                        }
+                    },
+                    // F1-F6 switch the debug overlays for stars, shooting
+                    // stars, the comet, spawning and satellites, and the
+                    // heat map, on every monitor
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode:
+                                    Some(
+                                        key @ (VirtualKeyCode::F1
+                                        | VirtualKeyCode::F2
+                                        | VirtualKeyCode::F3
+                                        | VirtualKeyCode::F4
+                                        | VirtualKeyCode::F5
+                                        | VirtualKeyCode::F6),
+                                    ),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let mut debug = output.starfield.debug;
+                        let overlay = match key {
+                            VirtualKeyCode::F1 => &mut debug.stars,
+                            VirtualKeyCode::F2 => &mut debug.shooting_stars,
+                            VirtualKeyCode::F3 => &mut debug.comet,
+                            VirtualKeyCode::F4 => &mut debug.spawning,
+                            VirtualKeyCode::F5 => &mut debug.satellites,
+                            _ => &mut debug.heatmap,
+                        };
+                        *overlay = !*overlay;
+                        for output in &mut outputs {
+                            output.starfield.debug = debug;
+                        }
+                    }
+                    // Show constellations on every monitor, or none
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::C),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let shown = !output.starfield.showing_constellations();
+                        for output in &mut outputs {
+                            output.starfield.set_constellations(shown);
+                        }
+                    }
+                    // Jump to hyperspace on every monitor together, or drop out
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::W),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let warping = !output.starfield.is_warping();
+                        for output in &mut outputs {
+                            output.starfield.set_warp(warping);
+                        }
+                    }
+                    // Wipe the star trails clean everywhere
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::T),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        for output in &mut outputs {
+                            output.starfield.clear_trails();
+                        }
+                    }
+                    // Move every monitor on to the next scene
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::S),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let scene = output.starfield.scene().next();
+                        for output in &mut outputs {
+                            output.starfield.fade_to(scene);
+                        }
+                    }
+                    // Breathe on every monitor together, or none
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::B),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let breathing = !output.starfield.is_breathing();
+                        for output in &mut outputs {
+                            output.starfield.set_breathing(breathing);
+                        }
+                    }
+                    // A quarter more stars, or fewer, in every sky
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode:
+                                    Some(
+                                        key @ (VirtualKeyCode::Plus
+                                        | VirtualKeyCode::Equals
+                                        | VirtualKeyCode::NumpadAdd
+                                        | VirtualKeyCode::Minus
+                                        | VirtualKeyCode::NumpadSubtract),
+                                    ),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let more =
+                            !matches!(key, VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract);
+                        for output in &mut outputs {
+                            let count = output.starfield.star_count();
+                            let count = if more {
+                                (count + count / 4).max(count + 1)
+                            } else {
+                                count - count / 5
+                            };
+                            output.starfield.set_star_count(count);
+                        }
+                    }
+                    // Twice as fast, or half, on every monitor
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode:
+                                    Some(key @ (VirtualKeyCode::Up | VirtualKeyCode::Down)),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let factor = if key == VirtualKeyCode::Up { 2.0 } else { 0.5 };
+                        let speed =
+                            (output.starfield.timelapse() * factor).clamp(MIN_SPEED, MAX_SPEED);
+                        for output in &mut outputs {
+                            output.starfield.set_timelapse(speed);
+                        }
+                    }
+                    // Stop the sky where it is, or carry on
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(VirtualKeyCode::Space),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        let paused = !output.starfield.is_paused();
+                        for output in &mut outputs {
+                            output.starfield.set_paused(paused);
+                        }
+                    }
+                    _ => {}
                 }
             }
             _ => {}
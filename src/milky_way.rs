@@ -0,0 +1,250 @@
+// The Milky Way: a broad band of hazy light across the sky, thick with faint
+// stars and split along its length by a dark dust rift. It is the most
+// distant thing in the sky, so unlike the drifting stars in front of it, it
+// stays put.
+
+use crate::ScreenDetails;
+use crate::render::{GlowGrid, Rect, Target, TileBins};
+use crate::rng::{FastRng, value_noise};
+use rand::{Rng, SeedableRng};
+
+// Glow is sampled every BAND_CELL pixels and interpolated in between
+const BAND_CELL: usize = 12;
+// Width of the band's glow (one standard deviation either side of its center
+// line), as a share of the screen's diagonal
+const BAND_WIDTH: f32 = 0.09;
+// Light added along the center line at full brightness, per channel out of 255
+const MAX_GLOW: f32 = 70.0;
+// Glow color at the band's core and at its fringes
+const CORE_COLOR: (f32, f32, f32) = (255.0, 228.0, 195.0);
+const FRINGE_COLOR: (f32, f32, f32) = (165.0, 180.0, 255.0);
+// Size in pixels of the star clouds the band breaks up into
+const CLUMP_SIZE: f32 = 220.0;
+// How much of the light the dust rift blocks at its darkest, and its width as
+// a share of the band's
+const RIFT_DEPTH: f32 = 0.75;
+const RIFT_WIDTH: f32 = 0.3;
+// How far the rift wanders off the center line, as a share of the band width,
+// and over what distance along it
+const RIFT_WANDER: f32 = 0.6;
+const RIFT_WANDER_SIZE: f32 = 500.0;
+// Faint stars tried per million pixels of screen; the rift and sparse clumps
+// turn some of them away
+const STARS_PER_MEGAPIXEL: f32 = 9000.0;
+const STAR_MAX_ALPHA: f32 = 0.8;
+
+struct BandStar {
+    x: i32,
+    y: i32,
+    color: (u8, u8, u8),
+    alpha: f32,
+}
+
+// Where the band lies on the current screen
+#[derive(Clone, Copy)]
+struct Band {
+    seed: i64,
+    // Unit vector along the band, and a point on its center line
+    direction: (f32, f32),
+    center: (f32, f32),
+    // Spread of the glow either side of the center line, in pixels
+    sigma: f32,
+}
+
+impl Band {
+    // Distance of a point along the band from its center, and out to one side
+    fn coords(&self, x: f32, y: f32) -> (f32, f32) {
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        (
+            dx * self.direction.0 + dy * self.direction.1,
+            dy * self.direction.0 - dx * self.direction.1,
+        )
+    }
+
+    fn point(&self, along: f32, across: f32) -> (f32, f32) {
+        (
+            self.center.0 + self.direction.0 * along - self.direction.1 * across,
+            self.center.1 + self.direction.1 * along + self.direction.0 * across,
+        )
+    }
+
+    // How much of the band's light gets through at a point, 0..1: clumped
+    // into star clouds and darkened along the wandering dust rift
+    fn shade(&self, x: f32, y: f32) -> f32 {
+        let (along, across) = self.coords(x, y);
+        let clumps = value_noise(x / CLUMP_SIZE, y / CLUMP_SIZE, self.seed);
+        let wander = value_noise(along / RIFT_WANDER_SIZE, 0.5, self.seed.wrapping_add(1));
+        let offset = (wander * 2.0 - 1.0) * RIFT_WANDER * self.sigma;
+        let rift = ((across - offset) / (RIFT_WIDTH * self.sigma)).powi(2);
+        (0.45 + 0.55 * clumps) * (1.0 - RIFT_DEPTH * (-rift).exp())
+    }
+}
+
+pub struct MilkyWay {
+    seed: u64,
+    brightness: f32,
+    band: Band,
+    glow: GlowGrid,
+    stars: Vec<BandStar>,
+    // The stars never move, so they are binned once per size and view
+    bins: TileBins,
+}
+
+impl MilkyWay {
+    // `angle` is in degrees clockwise from rightwards, `brightness` 0..1
+    pub fn new(
+        rng: &mut impl Rng,
+        screen_details: &ScreenDetails,
+        angle: f32,
+        brightness: f32,
+    ) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let seed = rng.r#gen();
+        let mut milky_way = Self {
+            seed,
+            brightness,
+            band: Band {
+                seed: seed as i64,
+                direction: (cos, sin),
+                center: (0.0, 0.0),
+                sigma: 0.0,
+            },
+            glow: GlowGrid::new(BAND_CELL),
+            stars: Vec::new(),
+            bins: TileBins::new(),
+        };
+        milky_way.resize(screen_details);
+        milky_way
+    }
+
+    pub fn resize(&mut self, screen_details: &ScreenDetails) {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        // The same sky every time: rebuilt from the seed, not carried over
+        let mut rng = FastRng::seed_from_u64(self.seed);
+        let shift = rng.gen_range(-1.0..1.0) * width.min(height) / 6.0;
+        let band = Band {
+            center: (
+                width / 2.0 - self.band.direction.1 * shift,
+                height / 2.0 + self.band.direction.0 * shift,
+            ),
+            sigma: BAND_WIDTH * width.hypot(height),
+            ..self.band
+        };
+        self.band = band;
+
+        self.glow
+            .resize(screen_details.width, screen_details.height);
+        let glow = MAX_GLOW * self.brightness;
+        for (x, y, light) in self.glow.points_mut() {
+            let across = band.coords(x, y).1;
+            let profile = (-0.5 * (across / band.sigma).powi(2)).exp();
+            let (r, g, b) = mix(FRINGE_COLOR, CORE_COLOR, profile);
+            let level = profile * band.shade(x, y) * glow / 255.0;
+            *light = [r * level, g * level, b * level];
+        }
+
+        // Scatter stars across the band with a roughly normal spread, then
+        // keep them in proportion to how bright the band is where they land
+        let reach = width.hypot(height) / 2.0;
+        let tries = (STARS_PER_MEGAPIXEL * width * height / 1e6) as usize;
+        self.stars.clear();
+        for _ in 0..tries {
+            let along = rng.gen_range(-reach..reach);
+            // Sum of three uniforms: close enough to a normal distribution
+            let spread = rng.r#gen::<f32>() + rng.r#gen::<f32>() + rng.r#gen::<f32>() - 1.5;
+            let (x, y) = band.point(along, spread * 2.0 * band.sigma);
+            if x < 0.0 || y < 0.0 || x >= width || y >= height {
+                continue;
+            }
+            if rng.r#gen::<f32>() > band.shade(x, y) {
+                continue;
+            }
+            let color = if rng.gen_bool(0.5) {
+                (240, 240, 255)
+            } else {
+                (255, 238, 215)
+            };
+            let faintness: f32 = rng.r#gen();
+            self.stars.push(BandStar {
+                x: x as i32,
+                y: y as i32,
+                color,
+                alpha: STAR_MAX_ALPHA * self.brightness * (1.0 - faintness * faintness),
+            });
+        }
+        self.show(Rect::new(
+            0,
+            0,
+            screen_details.width as i32,
+            screen_details.height as i32,
+        ));
+    }
+
+    // Bin the band's stars for drawing frames covering `view` of the sky
+    pub fn show(&mut self, view: Rect) {
+        self.bins.rebuild(
+            view,
+            self.stars
+                .iter()
+                .map(|star| Rect::new(star.x, star.y, star.x + 1, star.y + 1)),
+        );
+    }
+
+    pub fn draw(&self, tile: usize, target: &mut Target) {
+        self.glow.draw(target);
+        for i in self.bins.get(tile) {
+            let star = &self.stars[i];
+            target.blend(star.x, star.y, star.color, star.alpha);
+        }
+    }
+}
+
+fn mix(a: (f32, f32, f32), b: (f32, f32, f32), t: f32) -> (f32, f32, f32) {
+    (
+        a.0 + (b.0 - a.0) * t,
+        a.1 + (b.1 - a.1) * t,
+        a.2 + (b.2 - a.2) * t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, seeded_rng};
+
+    #[test]
+    fn stars_and_light_crowd_into_the_band() {
+        let screen = ScreenDetails {
+            width: 1280,
+            height: 720,
+        };
+        let milky_way = MilkyWay::new(&mut seeded_rng(11), &screen, 30.0, 1.0);
+        let sigma = milky_way.band.sigma;
+        let across = |x: f32, y: f32| milky_way.band.coords(x, y).1;
+
+        let spread = |limit: f32| {
+            milky_way
+                .stars
+                .iter()
+                .filter(|s| across(s.x as f32, s.y as f32).abs() < limit)
+                .count()
+        };
+        let count = milky_way.stars.len();
+        assert!(spread(sigma) > count / 2);
+        assert!(spread(2.0 * sigma) > count * 19 / 20);
+
+        let mut frame = Frame::new(&screen);
+        milky_way.glow.draw(&mut frame.target());
+        let (mut near, mut far) = ((0.0, 0), (0.0, 0));
+        for (i, pixel) in frame.pixels().iter().enumerate() {
+            let level = pixel[..3].iter().map(|&c| c as f32).sum::<f32>();
+            let d = across((i % 1280) as f32, (i / 1280) as f32).abs();
+            if d < sigma {
+                near = (near.0 + level, near.1 + 1);
+            } else if d > 2.5 * sigma {
+                far = (far.0 + level, far.1 + 1);
+            }
+        }
+        assert!(near.0 / near.1 as f32 > 4.0 * far.0 / far.1.max(1) as f32);
+    }
+}
@@ -0,0 +1,223 @@
+// The Moon: a shaded disc showing the right phase, either today's or a fixed
+// one from the config, that arcs slowly across the sky the same way the stars
+// drift. It wraps back to the start once it has set, like the next night.
+
+use crate::render::{Rect, Target, blend};
+use crate::rng::value_noise;
+use crate::{CelestialObject, Orientation, ScreenDetails, UpdateContext};
+use rand::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Seconds to cross the sky from rising to setting
+const CROSSING_TIME: f32 = 1200.0;
+// Radius as a share of the screen's short side
+const RADIUS: f32 = 0.045;
+// Height of the arc's ends and top, as shares of the short axis from the
+// edge the sky drifts away from
+const ARC_LOW: f32 = 0.45;
+const ARC_HIGH: f32 = 0.15;
+const COLOR: (u8, u8, u8) = (238, 234, 220);
+// Faint earthshine on the unlit side
+const EARTHSHINE: f32 = 0.06;
+// Width of the terminator's soft edge, as a share of the radius
+const TERMINATOR_SOFTNESS: f32 = 0.08;
+// Size of the darker maria, as a share of the radius
+const MARIA_SIZE: f32 = 0.45;
+// A halo out to HALO_RADIUS radii, at most HALO_ALPHA strong at full moon
+const HALO_RADIUS: f32 = 3.0;
+const HALO_ALPHA: f32 = 0.12;
+// A new moon at 2000-01-06 18:14 UTC, and the mean length of a lunation
+const EPOCH_NEW_MOON: f64 = 947_182_440.0;
+const SYNODIC_MONTH: f64 = 29.530_588_853 * 86_400.0;
+
+#[derive(Clone, Copy)]
+pub struct Moon {
+    // Fixed phase from the config, or None to follow the date
+    fixed_phase: Option<f32>,
+    // 0 new, 0.25 first quarter, 0.5 full, 0.75 last quarter
+    phase: f32,
+    // How far across the sky it is, 0..1
+    progress: f32,
+    seed: i64,
+    center: (f32, f32),
+    radius: f32,
+}
+
+impl Moon {
+    pub fn new(rng: &mut impl Rng, screen_details: &ScreenDetails, phase: Option<f32>) -> Self {
+        let mut moon = Self {
+            fixed_phase: phase,
+            phase: phase.unwrap_or_else(|| phase_at(SystemTime::now())),
+            // Start somewhere already up
+            progress: rng.gen_range(0.2..0.8),
+            seed: rng.r#gen(),
+            center: (0.0, 0.0),
+            radius: 0.0,
+        };
+        moon.place(screen_details);
+        moon
+    }
+
+    fn place(&mut self, screen_details: &ScreenDetails) {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        self.radius = RADIUS * width.min(height);
+        let margin = self.radius * HALO_RADIUS;
+        let t = self.progress;
+        // 0 at either end of the arc, 1 at its top
+        let lift = 1.0 - (2.0 * t - 1.0).powi(2);
+        self.center = match screen_details.orientation() {
+            Orientation::Landscape => (
+                width + margin - t * (width + 2.0 * margin),
+                height * (ARC_LOW + (ARC_HIGH - ARC_LOW) * lift),
+            ),
+            Orientation::Portrait => (
+                width * (1.0 - ARC_LOW - (ARC_HIGH - ARC_LOW) * lift),
+                -margin + t * (height + 2.0 * margin),
+            ),
+        };
+    }
+
+    pub fn resize(&mut self, screen_details: &ScreenDetails) {
+        self.place(screen_details);
+    }
+
+    // Whether the disc hides whatever is behind it at this point
+    pub fn covers(&self, (x, y): (f32, f32)) -> bool {
+        (x - self.center.0).hypot(y - self.center.1) < self.radius
+    }
+
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    // Share of the disc that is lit, 0..1
+    fn illumination(&self) -> f32 {
+        (1.0 - (self.phase * std::f32::consts::TAU).cos()) / 2.0
+    }
+
+    // Brightness of the surface at (u, v), in radii from the center
+    fn surface(&self, u: f32, v: f32) -> f32 {
+        // Where the terminator crosses this row, from the right-hand limb
+        // through the middle (quarters) to the left-hand one
+        let half_width = (1.0 - v * v).max(0.0).sqrt();
+        let terminator = (self.phase * std::f32::consts::TAU).cos() * half_width;
+        // Waxing moons are lit from the right, waning ones from the left
+        let past = if self.phase < 0.5 {
+            u - terminator
+        } else {
+            -terminator - u
+        };
+        let lit = (past / TERMINATOR_SOFTNESS + 0.5).clamp(0.0, 1.0);
+
+        let maria = value_noise(u / MARIA_SIZE, v / MARIA_SIZE, self.seed);
+        let albedo = 0.7 + 0.3 * (1.0 - maria * maria);
+        albedo * (EARTHSHINE + (1.0 - EARTHSHINE) * lit)
+    }
+}
+
+impl CelestialObject for Moon {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        self.progress = (self.progress + ctx.dt / CROSSING_TIME) % 1.0;
+        if self.fixed_phase.is_none() {
+            self.phase = phase_at(ctx.now);
+        }
+        self.place(ctx.screen_details);
+    }
+
+    fn draw(&self, target: &mut Target) {
+        let rect = self.bounds();
+        if !target.overlaps(rect) {
+            return;
+        }
+        let (cx, cy) = self.center;
+        let halo = HALO_ALPHA * self.illumination();
+        for (py, left, row) in target.span_rows(rect) {
+            let v = (py as f32 + 0.5 - cy) / self.radius;
+            for (px, pixel) in (left..).zip(row) {
+                let u = (px as f32 + 0.5 - cx) / self.radius;
+                let distance = u.hypot(v);
+                if distance < 1.0 {
+                    // Opaque, so it hides the stars behind it
+                    let shade = self.surface(u, v);
+                    let (r, g, b) = COLOR;
+                    let scale = |c: u8| (c as f32 * shade) as u8;
+                    *pixel = [scale(r), scale(g), scale(b), 255];
+                    // Antialias the limb
+                    let edge = (1.0 - distance) * self.radius;
+                    if edge < 1.0 {
+                        let fade = 1.0 - edge;
+                        blend(pixel, (0, 0, 0), fade * 0.5);
+                    }
+                } else if distance < HALO_RADIUS {
+                    let falloff = (HALO_RADIUS - distance) / (HALO_RADIUS - 1.0);
+                    blend(pixel, COLOR, halo * falloff * falloff);
+                }
+            }
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::around(self.center.0, self.center.1, self.radius * HALO_RADIUS)
+    }
+
+    fn is_alive(&self, _: &ScreenDetails) -> bool {
+        true // It sets and rises again rather than going away
+    }
+
+    fn motion(&self, screen_details: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        let mut ahead = *self;
+        ahead.progress += 1.0 / CROSSING_TIME;
+        ahead.place(screen_details);
+        let velocity = (
+            ahead.center.0 - self.center.0,
+            ahead.center.1 - self.center.1,
+        );
+        (self.center, velocity)
+    }
+}
+
+// Phase of the moon at `time`, from the mean lunation since a known new moon.
+// Good to within about a day, which is all a disc this size can show.
+pub fn phase_at(time: SystemTime) -> f32 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64());
+    ((secs - EPOCH_NEW_MOON) / SYNODIC_MONTH).rem_euclid(1.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, seeded_rng};
+    use std::time::Duration;
+
+    fn lit_pixels(phase: f32) -> usize {
+        let screen = ScreenDetails {
+            width: 800,
+            height: 600,
+        };
+        let moon = Moon::new(&mut seeded_rng(2), &screen, Some(phase));
+        let mut frame = Frame::new(&screen);
+        frame.draw(&moon);
+        frame.lit(100)
+    }
+
+    #[test]
+    fn phases_follow_the_calendar_and_light_the_disc() {
+        let at = |unix: u64| phase_at(UNIX_EPOCH + Duration::from_secs(unix));
+        // New, full and last-quarter moons in early 2024
+        let new = at(1_704_974_220);
+        assert!(!(0.02..0.98).contains(&new), "{new}");
+        assert!((at(1_706_205_240) - 0.5).abs() < 0.02);
+        assert!((at(1_706_915_880) - 0.75).abs() < 0.02);
+
+        let full = lit_pixels(0.5);
+        let (first, last) = (lit_pixels(0.25), lit_pixels(0.75));
+        assert!(full > 1000);
+        assert!(lit_pixels(0.0) < full / 50);
+        for quarter in [first, last] {
+            let share = quarter as f32 / full as f32;
+            assert!((0.4..0.6).contains(&share), "{share}");
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Nebula layer: soft clouds of colored gas glowing faintly behind the stars.
+// Like the cloud layer it is a few octaves of value noise, but it adds light
+// rather than blocking it, and it drifts slowly the same way the stars do so
+// it reads as part of the distant sky.
+
+use crate::lod::Detail;
+use crate::render::{GlowGrid, Target};
+use crate::rng::value_noise;
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+
+// Glow is sampled every NEBULA_CELL pixels and interpolated in between
+const NEBULA_CELL: usize = 16;
+const OCTAVES: u32 = 4;
+// Size in pixels of the coarsest noise features
+const FEATURE_SIZE: f32 = 500.0;
+// Noise above the cover threshold glows, ramping up over SOFTNESS. The
+// threshold falls from COVER_SPARSE at density 0 to COVER_DENSE at density 1.
+const COVER_SPARSE: f32 = 0.85;
+const COVER_DENSE: f32 = 0.3;
+const SOFTNESS: f32 = 0.35;
+// Light added at the brightest point, per channel out of 255
+const MAX_GLOW: f32 = 70.0;
+const SATURATION: f32 = 0.65;
+// Degrees the hue wanders either side of the configured one, and the size of
+// the patches it wanders over
+const HUE_SPREAD: f32 = 40.0;
+const HUE_FEATURE_SIZE: f32 = 1200.0;
+
+pub struct Nebula {
+    seed: i64,
+    // Base hue in degrees
+    hue: f32,
+    cover: f32,
+    // Pixels per second
+    speed: f32,
+    // How far the nebula has drifted so far
+    offset: (f32, f32),
+    // Octaves of noise sampled, fewer when detail is low, and frames since
+    // the last sampling
+    octaves: u32,
+    stale: u32,
+    glow: GlowGrid,
+}
+
+impl Nebula {
+    // `hue` is in degrees, `density` 0..1, `speed` in pixels/s
+    pub fn new(
+        rng: &mut impl Rng,
+        screen_details: &ScreenDetails,
+        hue: f32,
+        density: f32,
+        speed: f32,
+    ) -> Self {
+        let mut nebula = Self {
+            seed: rng.r#gen(),
+            hue: hue.rem_euclid(360.0),
+            cover: COVER_SPARSE + (COVER_DENSE - COVER_SPARSE) * density,
+            speed,
+            offset: (0.0, 0.0),
+            octaves: OCTAVES,
+            stale: 0,
+            glow: GlowGrid::new(NEBULA_CELL),
+        };
+        nebula.resize(screen_details);
+        nebula
+    }
+
+    pub fn resize(&mut self, screen_details: &ScreenDetails) {
+        self.glow
+            .resize(screen_details.width, screen_details.height);
+        self.sample();
+    }
+
+    pub fn update(&mut self, ctx: &UpdateContext, detail: Detail) {
+        // The same way as the stars
+        let travel = self.speed * ctx.dt;
+        let (drift_x, drift_y) = ctx.screen_details.drift(ctx.config.drift_angle);
+        self.offset.0 -= drift_x * travel;
+        self.offset.1 -= drift_y * travel;
+        // It moves slowly enough to skip frames, catching up on the next
+        let octaves = OCTAVES - detail.octaves_dropped;
+        self.stale += 1;
+        if self.stale >= detail.nebula_every || octaves != self.octaves {
+            self.octaves = octaves;
+            self.stale = 0;
+            self.sample();
+        }
+    }
+
+    fn sample(&mut self) {
+        for (x, y, glow) in self.glow.points_mut() {
+            let (x, y) = (x + self.offset.0, y + self.offset.1);
+
+            let (mut total, mut weight) = (0.0, 0.0);
+            for octave in 0..self.octaves {
+                let frequency = (1 << octave) as f32 / FEATURE_SIZE;
+                let amplitude = 1.0 / (1 << octave) as f32;
+                let layer = self.seed.wrapping_add(octave as i64 * 0x5851_F42D);
+                total += amplitude * value_noise(x * frequency, y * frequency, layer);
+                weight += amplitude;
+            }
+            let cover = ((total / weight - self.cover) / SOFTNESS).clamp(0.0, 1.0);
+            if cover == 0.0 {
+                *glow = [0.0; 3];
+                continue;
+            }
+
+            let wander = value_noise(
+                x / HUE_FEATURE_SIZE,
+                y / HUE_FEATURE_SIZE,
+                self.seed.wrapping_sub(0x2545_F491),
+            );
+            let hue = self.hue + (wander * 2.0 - 1.0) * HUE_SPREAD;
+            let brightness = cover * cover * (3.0 - 2.0 * cover) * MAX_GLOW;
+            *glow = hue_to_rgb(hue, SATURATION).map(|c| c * brightness);
+        }
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        self.glow.draw(target);
+    }
+}
+
+// Fully bright color of the given hue (degrees) and saturation, 0..1 per channel
+fn hue_to_rgb(hue: f32, saturation: f32) -> [f32; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let ramp = 1.0 - (sector % 2.0 - 1.0).abs();
+    let (r, g, b) = match sector as u32 {
+        0 => (1.0, ramp, 0.0),
+        1 => (ramp, 1.0, 0.0),
+        2 => (0.0, 1.0, ramp),
+        3 => (0.0, ramp, 1.0),
+        4 => (ramp, 0.0, 1.0),
+        _ => (1.0, 0.0, ramp),
+    };
+    [r, g, b].map(|c| 1.0 - saturation * (1.0 - c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, MockScreen, seeded_rng};
+
+    const SCREEN: ScreenDetails = ScreenDetails {
+        width: 640,
+        height: 360,
+    };
+
+    fn render(nebula: &Nebula) -> Frame {
+        let mut frame = Frame::new(&SCREEN);
+        nebula.draw(&mut frame.target());
+        frame
+    }
+
+    #[test]
+    fn nebulae_drift_with_the_stars_and_thicken_with_density() {
+        let mut screen = MockScreen::new(SCREEN).with_seed(4);
+        let cell = NEBULA_CELL as f32;
+        let mut nebula = Nebula::new(&mut screen.rng, &SCREEN, 280.0, 0.5, cell);
+        let before = render(&nebula);
+        nebula.update(&screen.ctx(1.0), Detail::FULL);
+        let after = render(&nebula);
+
+        // One second moves everything exactly one cell to the left
+        let (width, cell) = (SCREEN.width as usize, NEBULA_CELL);
+        for (i, pixel) in after.pixels().iter().enumerate() {
+            if i % width < width - cell {
+                assert_eq!(*pixel, before.pixels()[i + cell]);
+            }
+        }
+
+        // Same gas, more of it glowing
+        let sparse = Nebula::new(&mut seeded_rng(5), &SCREEN, 280.0, 0.1, 0.0);
+        let dense = Nebula::new(&mut seeded_rng(5), &SCREEN, 280.0, 0.9, 0.0);
+        let (sparse, dense) = (render(&sparse).lit(0), render(&dense).lit(0));
+        assert!(sparse < dense);
+        assert!(dense > before.pixels().len() / 2);
+    }
+}
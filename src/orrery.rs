@@ -0,0 +1,370 @@
+// Orrery scene: a stylized top-down view of the solar system, the sun in the
+// middle and the eight planets circling it anticlockwise, as seen from above
+// the earth's north pole. Orbits are squeezed to fit the screen (their radii
+// go as the square root of the real ones) and the planets are drawn far
+// larger than to scale, but each keeps its real period against the others.
+// They start out where they really are, from their mean orbits plus the
+// first terms of the equation of center, which is good to a degree or so.
+// `orrery_days_per_second` sets how fast they then go round; at 0 they stay
+// where they really are, following the sky's clock.
+
+use crate::render::{Rect, Target, blend};
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+use std::f32::consts::TAU;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Neptune's orbit reaches this fraction of the shorter side out from the sun
+const OUTER_ORBIT: f32 = 0.46;
+// Sizes below are for a screen 1080 pixels on its shorter side, and scale
+const REFERENCE_SIZE: f32 = 1080.0;
+const SUN_RADIUS: f32 = 16.0;
+const SUN_COLOR: (u8, u8, u8) = (255, 214, 120);
+// The sun's glow reaches this many of its radii
+const SUN_GLOW: f32 = 4.0;
+const SUN_GLOW_ALPHA: f32 = 0.35;
+const ORBIT_COLOR: (u8, u8, u8) = (90, 110, 150);
+const ORBIT_ALPHA: f32 = 0.3;
+// Orbits are drawn as straight pieces about this many pixels long
+const ORBIT_SEGMENT: f32 = 6.0;
+// Saturn's rings, in its radii
+const RING_RADIUS: f32 = 1.9;
+const RING_COLOR: (u8, u8, u8) = (210, 190, 150);
+const RING_ALPHA: f32 = 0.7;
+// How much darker the side of a planet facing away from the sun is, 0..1
+const NIGHT_SIDE: f32 = 0.75;
+// Faint fixed stars behind it all, about one for this many square pixels
+const SKY_PER_STAR: f32 = 5000.0;
+const STAR_COLOR: (u8, u8, u8) = (200, 210, 255);
+const STAR_MAX_ALPHA: f32 = 0.5;
+// Unix time of the J2000.0 epoch, 2000-01-01 12:00 UTC
+const J2000: f64 = 946_728_000.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+struct Planet {
+    // Semi-major axis in AU, and period in days
+    axis: f32,
+    period: f64,
+    // Mean longitude at J2000 and longitude of perihelion, in degrees
+    mean_at_epoch: f64,
+    perihelion: f32,
+    eccentricity: f32,
+    // Size on screen in pixels
+    radius: f32,
+    color: (u8, u8, u8),
+}
+
+impl Planet {
+    const fn new(
+        (axis, period): (f32, f64),
+        (mean_at_epoch, perihelion, eccentricity): (f64, f32, f32),
+        radius: f32,
+        color: (u8, u8, u8),
+    ) -> Self {
+        Self {
+            axis,
+            period,
+            mean_at_epoch,
+            perihelion,
+            eccentricity,
+            radius,
+            color,
+        }
+    }
+}
+
+#[rustfmt::skip]
+const PLANETS: [Planet; 8] = [
+    // Mercury
+    Planet::new((0.387, 87.969), (252.251, 77.456, 0.2056), 3.0, (170, 160, 150)),
+    // Venus
+    Planet::new((0.723, 224.701), (181.980, 131.533, 0.0068), 5.0, (230, 210, 160)),
+    // Earth
+    Planet::new((1.000, 365.256), (100.464, 102.947, 0.0167), 5.0, (90, 150, 230)),
+    // Mars
+    Planet::new((1.524, 686.980), (355.453, 336.041, 0.0934), 4.0, (215, 110, 70)),
+    // Jupiter
+    Planet::new((5.203, 4332.59), (34.404, 14.728, 0.0489), 11.0, (220, 180, 140)),
+    // Saturn
+    Planet::new((9.537, 10759.22), (49.944, 92.432, 0.0555), 9.0, (225, 200, 140)),
+    // Uranus
+    Planet::new((19.19, 30688.5), (313.232, 170.964, 0.0463), 7.0, (160, 215, 225)),
+    // Neptune
+    Planet::new((30.07, 60182.0), (304.880, 44.971, 0.0090), 7.0, (90, 120, 220)),
+];
+const SATURN: usize = 5;
+
+pub struct Orrery {
+    days_per_second: f32,
+    // Days since J2000 in the orrery
+    days: f64,
+    center: (f32, f32),
+    // Sizes relative to REFERENCE_SIZE
+    scale: f32,
+    orbits: [f32; PLANETS.len()],
+    planets: [(f32, f32); PLANETS.len()],
+    // Background stars as fractions of the screen's width and height, with
+    // their brightness
+    stars: Vec<(f32, f32, f32)>,
+    size: (f32, f32),
+}
+
+impl Orrery {
+    pub fn new(
+        rng: &mut impl Rng,
+        screen_details: &ScreenDetails,
+        days_per_second: f32,
+        now: SystemTime,
+    ) -> Self {
+        let area = screen_details.width as f32 * screen_details.height as f32;
+        let stars = (0..(area / SKY_PER_STAR) as usize)
+            .map(|_| {
+                let alpha = rng.r#gen::<f32>().powi(3) * STAR_MAX_ALPHA;
+                (rng.r#gen(), rng.r#gen(), alpha)
+            })
+            .collect();
+        let mut orrery = Self {
+            days_per_second,
+            days: days_since_j2000(now),
+            center: (0.0, 0.0),
+            scale: 1.0,
+            orbits: [0.0; PLANETS.len()],
+            planets: [(0.0, 0.0); PLANETS.len()],
+            stars,
+            size: (0.0, 0.0),
+        };
+        orrery.place(screen_details);
+        orrery
+    }
+
+    pub fn update(&mut self, ctx: &mut UpdateContext) {
+        self.days = if self.days_per_second == 0.0 {
+            days_since_j2000(ctx.now)
+        } else {
+            self.days + (ctx.dt * self.days_per_second) as f64
+        };
+        self.place(ctx.screen_details);
+    }
+
+    // Lay the orbits out on the screen and put each planet on its own
+    fn place(&mut self, screen_details: &ScreenDetails) {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        self.size = (width, height);
+        self.center = (width / 2.0, height / 2.0);
+        self.scale = width.min(height) / REFERENCE_SIZE;
+        let outer = PLANETS[PLANETS.len() - 1].axis;
+        for (i, planet) in PLANETS.iter().enumerate() {
+            let radius = OUTER_ORBIT * width.min(height) * (planet.axis / outer).sqrt();
+            let (sin, cos) = longitude(i, self.days).sin_cos();
+            self.orbits[i] = radius;
+            // Ecliptic longitude 0 is to the right, and y runs down the screen
+            self.planets[i] = (self.center.0 + radius * cos, self.center.1 - radius * sin);
+        }
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        for &(x, y, alpha) in &self.stars {
+            target.blend(
+                (x * self.size.0) as i32,
+                (y * self.size.1) as i32,
+                STAR_COLOR,
+                alpha,
+            );
+        }
+        for &radius in &self.orbits {
+            circle(target, self.center, radius, ORBIT_COLOR, ORBIT_ALPHA);
+        }
+        self.draw_sun(target);
+        for (i, planet) in PLANETS.iter().enumerate() {
+            let radius = planet.radius * self.scale;
+            disc(target, self.planets[i], radius, planet.color, self.center);
+            if i == SATURN {
+                circle(
+                    target,
+                    self.planets[i],
+                    radius * RING_RADIUS,
+                    RING_COLOR,
+                    RING_ALPHA,
+                );
+            }
+        }
+    }
+
+    fn draw_sun(&self, target: &mut Target) {
+        let radius = SUN_RADIUS * self.scale;
+        let (cx, cy) = self.center;
+        let rect = Rect::around(cx, cy, radius * SUN_GLOW);
+        if !target.overlaps(rect) {
+            return;
+        }
+        for (py, left, row) in target.span_rows(rect) {
+            let v = (py as f32 + 0.5 - cy) / radius;
+            for (px, pixel) in (left..).zip(row) {
+                let u = (px as f32 + 0.5 - cx) / radius;
+                let distance = u.hypot(v);
+                if distance < SUN_GLOW {
+                    // Solid out to the rim, then a glow falling off beyond it
+                    let rim = ((1.0 - distance) * radius + 0.5).clamp(0.0, 1.0);
+                    let falloff = (SUN_GLOW - distance) / (SUN_GLOW - 1.0);
+                    let glow = SUN_GLOW_ALPHA * falloff.min(1.0).powi(2);
+                    blend(pixel, SUN_COLOR, rim + (1.0 - rim) * glow);
+                }
+            }
+        }
+    }
+}
+
+// Where planet `i` is along its orbit `days` after J2000, as an angle from
+// the vernal equinox in radians
+fn longitude(i: usize, days: f64) -> f32 {
+    let Planet {
+        period,
+        mean_at_epoch,
+        perihelion,
+        eccentricity,
+        ..
+    } = PLANETS[i];
+    let mean = (mean_at_epoch + 360.0 * days / period).rem_euclid(360.0) as f32;
+    let anomaly = (mean - perihelion).to_radians();
+    mean.to_radians()
+        + 2.0 * eccentricity * anomaly.sin()
+        + 1.25 * eccentricity * eccentricity * (2.0 * anomaly).sin()
+}
+
+// Where planet `i` is at `now`, in AU from the sun, taking its orbit as a
+// circle
+pub(crate) fn heliocentric(i: usize, now: SystemTime) -> (f32, f32) {
+    let angle = longitude(i, days_since_j2000(now));
+    let axis = PLANETS[i].axis;
+    (axis * angle.cos(), axis * angle.sin())
+}
+
+fn days_since_j2000(now: SystemTime) -> f64 {
+    let unix = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64());
+    (unix - J2000) / SECONDS_PER_DAY
+}
+
+// A thin circle, as a ring of short lines
+fn circle(target: &mut Target, (cx, cy): (f32, f32), radius: f32, color: (u8, u8, u8), alpha: f32) {
+    if !target.overlaps(Rect::around(cx, cy, radius + 1.0)) {
+        return;
+    }
+    let pieces = (TAU * radius / ORBIT_SEGMENT).ceil().max(12.0) as usize;
+    let point = |piece: usize| {
+        let (sin, cos) = (piece as f32 / pieces as f32 * TAU).sin_cos();
+        (cx + radius * cos, cy + radius * sin)
+    };
+    for piece in 0..pieces {
+        target.line(point(piece), point(piece + 1), color, alpha);
+    }
+}
+
+// A planet's disc, lit on the side facing the sun
+fn disc(
+    target: &mut Target,
+    (cx, cy): (f32, f32),
+    radius: f32,
+    color: (u8, u8, u8),
+    sun: (f32, f32),
+) {
+    let rect = Rect::around(cx, cy, radius + 1.0);
+    if !target.overlaps(rect) {
+        return;
+    }
+    let (dx, dy) = (sun.0 - cx, sun.1 - cy);
+    let length = dx.hypot(dy).max(f32::EPSILON);
+    let toward_sun = (dx / length, dy / length);
+    for (py, left, row) in target.span_rows(rect) {
+        let v = (py as f32 + 0.5 - cy) / radius;
+        for (px, pixel) in (left..).zip(row) {
+            let u = (px as f32 + 0.5 - cx) / radius;
+            let distance = u.hypot(v);
+            let coverage = ((1.0 - distance) * radius + 0.5).clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            let facing = u * toward_sun.0 + v * toward_sun.1;
+            let light = 1.0 - NIGHT_SIDE * (0.5 - 1.5 * facing).clamp(0.0, 1.0);
+            let (r, g, b) = color;
+            let shade = |c: u8| (c as f32 * light) as u8;
+            blend(pixel, (shade(r), shade(g), shade(b)), coverage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, MockScreen};
+    use std::time::Duration;
+
+    const EARTH: usize = 2;
+    const JUPITER: usize = 4;
+
+    fn at(unix: f64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs_f64(unix)
+    }
+
+    // Apart by at most `degrees`, either way round
+    fn near(a: f32, b: f32, degrees: f32) -> bool {
+        let apart = (a - b).rem_euclid(TAU);
+        apart.min(TAU - apart) < degrees.to_radians()
+    }
+
+    #[test]
+    fn planets_are_where_they_really_are() {
+        // At the March 2024 equinox the sun stood at longitude 0, so the
+        // earth was opposite it
+        let equinox = days_since_j2000(at(1_710_903_960.0));
+        assert!(near(longitude(EARTH, equinox), TAU / 2.0, 1.5));
+        // Jupiter was at opposition on 2023-11-03, in line with the earth
+        let opposition = days_since_j2000(at(1_698_969_600.0));
+        assert!(near(
+            longitude(EARTH, opposition),
+            longitude(JUPITER, opposition),
+            2.0
+        ));
+    }
+
+    #[test]
+    fn planets_go_round_at_their_own_speeds() {
+        let mut screen = MockScreen::landscape();
+        let details = screen.details;
+        let mut orrery = Orrery::new(&mut screen.rng, &details, 10.0, at(1_700_000_000.0));
+        let start = orrery.planets;
+        // A year in 36.5 seconds brings the earth back round, while Mercury
+        // has been round four times and then some
+        for dt in FixedClock::fps(30.0).ticks(36.5256) {
+            orrery.update(&mut screen.ctx(dt));
+        }
+        let moved = |i: usize| {
+            let (a, b) = (start[i], orrery.planets[i]);
+            (a.0 - b.0).hypot(a.1 - b.1)
+        };
+        assert!(moved(EARTH) < 1.0, "{}", moved(EARTH));
+        assert!(moved(0) > 20.0);
+
+        // The sun glows in the middle, and the earth is drawn where it is
+        let mut frame = Frame::new(&details);
+        orrery.draw(&mut frame.target());
+        frame.assert_brighter_than(960, 540, 200);
+        let (x, y) = orrery.planets[EARTH];
+        frame.assert_brighter_than(x as u32, y as u32, 60);
+    }
+
+    #[test]
+    fn a_still_orrery_follows_the_clock() {
+        let mut screen = MockScreen::landscape();
+        let details = screen.details;
+        let mut orrery = Orrery::new(&mut screen.rng, &details, 0.0, at(0.0));
+        let mut ctx = screen.ctx(1.0);
+        ctx.now = at(1_710_903_960.0);
+        orrery.update(&mut ctx);
+        assert_eq!(orrery.days, days_since_j2000(ctx.now));
+        // The earth sits left of the sun
+        let (x, y) = orrery.planets[EARTH];
+        assert!(x < 960.0 && (y - 540.0).abs() < 10.0, "{x}, {y}");
+    }
+}
@@ -0,0 +1,131 @@
+// Palette cycling for pixel-art mode. The sky is brought down to a small
+// indexed palette, the way old 8- and 16-bit art was drawn, and runs of that
+// palette rotate over time: everything drawn in one of them shimmers as its
+// shades roll through the run, with nothing in the sky itself moving. Faint
+// nebula glow lands in the purple run and blue glow in the watery one.
+
+use crate::render::{Pixel, Target, pack};
+
+const COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    // Stars: white, greys, and warm and cool tints
+    (255, 255, 255),
+    (170, 170, 185),
+    (85, 85, 100),
+    (255, 205, 150),
+    (255, 140, 90),
+    (165, 195, 255),
+    (95, 125, 220),
+    // Nebula purples, dark to light
+    (14, 6, 18),
+    (26, 10, 32),
+    (42, 16, 50),
+    (62, 24, 74),
+    // Water blues, dark to light
+    (8, 14, 30),
+    (15, 28, 55),
+    (25, 45, 85),
+    (40, 70, 120),
+];
+// Runs of the palette that rotate, as first index and length
+const CYCLES: [(usize, usize); 2] = [(8, 4), (12, 4)];
+// Steps each run turns per second
+const CYCLE_RATE: f32 = 4.0;
+// Bits of each channel the lookup goes by
+const LOOKUP_BITS: u32 = 5;
+
+pub(crate) struct Palette {
+    // Nearest palette index for every color, by its top LOOKUP_BITS bits of
+    // red, green and blue
+    lookup: Vec<u8>,
+    // Steps turned so far
+    turn: f32,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        let levels = 1 << LOOKUP_BITS;
+        let step = 256 / levels;
+        let mut lookup = Vec::with_capacity(levels * levels * levels);
+        for r in 0..levels {
+            for g in 0..levels {
+                for b in 0..levels {
+                    // The middle of the cell
+                    let color = [r, g, b].map(|c| (c * step + step / 2) as i32);
+                    let nearest = (0..COLORS.len())
+                        .min_by_key(|&i| {
+                            let (pr, pg, pb) = COLORS[i];
+                            let d = [pr, pg, pb]
+                                .into_iter()
+                                .zip(color)
+                                .map(|(p, c)| (p as i32 - c).pow(2));
+                            d.sum::<i32>()
+                        })
+                        .unwrap_or(0);
+                    lookup.push(nearest as u8);
+                }
+            }
+        }
+        Self { lookup, turn: 0.0 }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        // Wrapped at a turn every run comes full circle on
+        let whole = CYCLES.iter().map(|&(_, len)| len).product::<usize>() as f32;
+        self.turn = (self.turn + dt * CYCLE_RATE) % whole;
+    }
+
+    // What each index shows this frame, with the runs turned
+    pub fn shades(&self) -> [Pixel; COLORS.len()] {
+        let mut shades = COLORS.map(pack);
+        let turn = self.turn as usize;
+        for (start, len) in CYCLES {
+            for i in 0..len {
+                shades[start + i] = pack(COLORS[start + (i + turn) % len]);
+            }
+        }
+        shades
+    }
+
+    // Bring everything drawn so far down to the palette, shown in `shades`
+    pub fn apply(&self, target: &mut Target, shades: &[Pixel; COLORS.len()]) {
+        let shift = 8 - LOOKUP_BITS;
+        for pixel in target.pixels() {
+            let [r, g, b, _] = (*pixel).map(|c| (c >> shift) as usize);
+            let key = (r << (2 * LOOKUP_BITS)) | (g << LOOKUP_BITS) | b;
+            *pixel = shades[self.lookup[key] as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Rect;
+
+    fn shown(palette: &Palette, color: (u8, u8, u8)) -> Pixel {
+        let mut pixels = [pack(color)];
+        let mut target = Target::new(&mut pixels, 1, (0, 0), Rect::new(0, 0, 1, 1));
+        palette.apply(&mut target, &palette.shades());
+        pixels[0]
+    }
+
+    #[test]
+    fn runs_rotate_and_the_rest_stays_put() {
+        let mut palette = Palette::new();
+        assert_eq!(shown(&palette, (0, 0, 0)), pack((0, 0, 0)));
+        assert_eq!(shown(&palette, (250, 250, 250)), pack((255, 255, 255)));
+        let glow = (44, 17, 52);
+        assert_eq!(shown(&palette, glow), pack(COLORS[10]));
+
+        // A step along, the purple shows the next shade of its run
+        palette.update(1.0 / CYCLE_RATE);
+        assert_eq!(shown(&palette, glow), pack(COLORS[11]));
+        assert_eq!(shown(&palette, (250, 250, 250)), pack((255, 255, 255)));
+        palette.update(1.0 / CYCLE_RATE);
+        assert_eq!(shown(&palette, glow), pack(COLORS[8]));
+        // And back where it started after going all the way round
+        palette.update(2.0 / CYCLE_RATE);
+        assert_eq!(shown(&palette, glow), pack(COLORS[10]));
+    }
+}
@@ -0,0 +1,236 @@
+// Particles: small, short-lived specks of light thrown off by something
+// bigger, like the sparks a shooting star sheds as it burns up or the debris
+// a supernova blows out. Whatever sheds them does it through its own Emitter,
+// which keeps its own randomness, so they don't draw on the sky's, and hands
+// each a share of its velocity, so they carry on the way it was going as they
+// spread. From then on they fall, fade and go out on their own, outliving
+// what shed them. They all share one pool with room for PARTICLE_CAP, made up
+// front, so nothing allocates as they come and go; when it's full, new ones
+// are dropped rather than old ones cut short.
+
+use crate::render::{Rect, Target};
+use crate::rng::FastRng;
+use rand::{Rng, SeedableRng};
+
+// The most in the sky at once
+pub const PARTICLE_CAP: usize = 1024;
+
+// What an emitter sheds, and how
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Kind {
+    // Share of the velocity of what sheds them each keeps, and how fast
+    // they're thrown off on top of that, any way, in pixels a second
+    pub inherit: f32,
+    pub speed: (f32, f32),
+    // Seconds each glows for, fading out over them
+    pub life: f32,
+    pub color: (u8, u8, u8),
+    pub alpha: f32,
+    pub size: i32,
+    // Share of the sky's gravity they fall by
+    pub gravity: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    age: f32,
+    kind: Kind,
+}
+
+pub(crate) struct Particles {
+    live: Vec<Particle>,
+    // Seconds back along their paths they're drawn, to glide between steps
+    behind: f32,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            live: Vec::with_capacity(PARTICLE_CAP),
+            behind: 0.0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.live.clear();
+    }
+
+    // Move them on by `dt`, falling by `gravity`, and let the ones that have
+    // burned out go
+    pub fn update(&mut self, dt: f32, gravity: f32) {
+        for particle in &mut self.live {
+            particle.x += particle.vx * dt;
+            particle.vy += gravity * particle.kind.gravity * dt;
+            particle.y += particle.vy * dt;
+            particle.age += dt;
+        }
+        self.live
+            .retain(|particle| particle.age < particle.kind.life);
+    }
+
+    // Draw them `secs` back along their paths, as the sky is drawn that far
+    // short of its last step
+    pub fn lag_behind(&mut self, secs: f32) {
+        self.behind = secs;
+    }
+
+    fn place(&self, particle: &Particle) -> (f32, f32) {
+        (
+            particle.x - particle.vx * self.behind,
+            particle.y - particle.vy * self.behind,
+        )
+    }
+
+    pub fn bounds(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.live.iter().map(|particle| {
+            let (x, y) = self.place(particle);
+            Rect::around(x, y, particle.kind.size as f32 / 2.0 + 1.0)
+        })
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        for (particle, bounds) in self.live.iter().zip(self.bounds()) {
+            if !target.overlaps(bounds) {
+                continue;
+            }
+            let (x, y) = self.place(particle);
+            let kind = &particle.kind;
+            let glow = 1.0 - particle.age / kind.life;
+            target.soft_point(x, y, kind.color, kind.alpha * glow, kind.size);
+        }
+    }
+
+    fn push(&mut self, particle: Particle) {
+        if self.live.len() < PARTICLE_CAP {
+            self.live.push(particle);
+        }
+    }
+}
+
+pub(crate) struct Emitter {
+    kind: Kind,
+    // Particles due to be shed but not yet, a part of one at a time
+    due: f32,
+    rng: FastRng,
+}
+
+impl Emitter {
+    pub fn new(kind: Kind, seed: u64) -> Self {
+        Self {
+            kind,
+            due: 0.0,
+            rng: FastRng::seed_from_u64(seed),
+        }
+    }
+
+    // Shed `rate` a second for `dt` seconds from something at `at` going at
+    // `velocity`
+    pub fn trickle(
+        &mut self,
+        particles: &mut Particles,
+        dt: f32,
+        rate: f32,
+        at: (f32, f32),
+        velocity: (f32, f32),
+    ) {
+        self.due += dt * rate;
+        while self.due >= 1.0 {
+            self.due -= 1.0;
+            self.shed(particles, at, velocity);
+        }
+    }
+
+    // Shed `count` at once
+    pub fn burst(
+        &mut self,
+        particles: &mut Particles,
+        count: usize,
+        at: (f32, f32),
+        velocity: (f32, f32),
+    ) {
+        for _ in 0..count {
+            self.shed(particles, at, velocity);
+        }
+    }
+
+    fn shed(&mut self, particles: &mut Particles, (x, y): (f32, f32), (vx, vy): (f32, f32)) {
+        let kind = self.kind;
+        let angle = self.rng.gen_range(0.0..std::f32::consts::TAU);
+        let speed = self.rng.gen_range(kind.speed.0..kind.speed.1);
+        particles.push(Particle {
+            x,
+            y,
+            vx: vx * kind.inherit + angle.cos() * speed,
+            vy: vy * kind.inherit + angle.sin() * speed,
+            age: 0.0,
+            kind,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    const DUST: Kind = Kind {
+        inherit: 0.5,
+        speed: (1.0, 2.0),
+        life: 1.0,
+        color: (255, 200, 100),
+        alpha: 1.0,
+        size: 2,
+        gravity: 1.0,
+    };
+
+    #[test]
+    fn particles_carry_on_the_way_their_source_was_going_then_go_out() {
+        let mut particles = Particles::new();
+        let mut emitter = Emitter::new(DUST, 1);
+        // A little over half a second at ten a second is five, and half of one
+        // left over
+        emitter.trickle(&mut particles, 0.55, 10.0, (500.0, 500.0), (400.0, 0.0));
+        assert_eq!(particles.len(), 5);
+        particles.update(0.5, 0.0);
+        // Half its speed, give or take how hard they were thrown
+        for particle in &particles.live {
+            assert!((198.0..=202.0).contains(&particle.vx), "{}", particle.vx);
+            assert!((599.0..=601.0).contains(&particle.x), "{}", particle.x);
+        }
+
+        let mut frame = Frame::new(&LANDSCAPE);
+        let (x, y) = particles.place(&particles.live[0]);
+        particles.draw(&mut frame.target());
+        frame.assert_brighter_than(x as u32, y as u32, 50);
+
+        // They fall, and are gone when their time is up
+        let before = particles.live[0].y;
+        particles.update(0.4, 100.0);
+        assert!(particles.live[0].y > before + 10.0);
+        particles.update(0.2, 100.0);
+        assert_eq!(particles.len(), 0);
+    }
+
+    #[test]
+    fn the_pool_never_grows_past_the_cap() {
+        let mut particles = Particles::new();
+        let capacity = particles.live.capacity();
+        let mut emitter = Emitter::new(DUST, 2);
+        emitter.burst(&mut particles, PARTICLE_CAP - 1, (0.0, 0.0), (0.0, 0.0));
+        particles.update(0.5, 0.0);
+        emitter.burst(&mut particles, 10, (0.0, 0.0), (0.0, 0.0));
+        assert_eq!(particles.len(), PARTICLE_CAP);
+        assert_eq!(particles.live.capacity(), capacity);
+        // The older ones go out first, making room again
+        particles.update(0.6, 0.0);
+        assert_eq!(particles.len(), 1);
+    }
+}
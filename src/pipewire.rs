@@ -0,0 +1,180 @@
+// The sky as a PipeWire video source, for OBS, browsers and anything else
+// that takes a camera-like stream, with no screencast portal in between.
+// Frames of the first output are handed to GStreamer's pipewiresink, which
+// offers them as a Video/Source node named wl-starfield, scaled to
+// `pipewire_size` and resampled to `pipewire_fps` on the way. Frames the
+// pipeline can't keep up with are dropped rather than holding up the sky.
+
+use std::io::{self, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wl_starfield::ScreenDetails;
+use wl_starfield::config::Config;
+
+// Frames waiting for the pipeline at most
+const QUEUE: usize = 2;
+// The frame rate assumed for frames drawn as fast as possible
+const UNCAPPED_FPS: u32 = 60;
+
+pub struct PipewireSource {
+    size: Option<(u32, u32)>,
+    fps: u32,
+    // The rate frames arrive at
+    input_fps: u32,
+    pipeline: Option<Pipeline>,
+}
+
+struct Pipeline {
+    screen_details: ScreenDetails,
+    child: Child,
+    frames: SyncSender<Vec<u8>>,
+    writer: JoinHandle<()>,
+}
+
+impl PipewireSource {
+    // A source if the config asks for one, for frames `frame_interval` apart
+    // or as fast as they come
+    pub fn new(config: &Config, frame_interval: Option<Duration>) -> Option<Self> {
+        let input_fps = frame_interval.map_or(UNCAPPED_FPS, |interval| {
+            (1.0 / interval.as_secs_f32()).round().max(1.0) as u32
+        });
+        config.pipewire_source.then(|| Self {
+            size: config.pipewire_size,
+            fps: config.pipewire_fps,
+            input_fps,
+            pipeline: None,
+        })
+    }
+
+    // Pass `frame` on to the stream, starting the pipeline afresh if the
+    // frame size has changed
+    pub fn publish(&mut self, frame: &[u8], screen_details: ScreenDetails) -> io::Result<()> {
+        if self
+            .pipeline
+            .as_ref()
+            .is_none_or(|pipeline| pipeline.screen_details != screen_details)
+        {
+            self.stop();
+            self.pipeline = Some(self.start(screen_details)?);
+        }
+        let pipeline = self.pipeline.as_ref().expect("just started");
+        match pipeline.frames.try_send(frame.to_vec()) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => {
+                self.stop();
+                Err(io::Error::other("the pipeline closed"))
+            }
+        }
+    }
+
+    fn start(&self, screen_details: ScreenDetails) -> io::Result<Pipeline> {
+        let args = gst_args(screen_details, self.input_fps, self.size, self.fps);
+        let mut child = Command::new("gst-launch-1.0")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("can't run gst-launch-1.0: {err}"))
+            })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let (frames, queue) = mpsc::sync_channel(QUEUE);
+        let writer = thread::spawn(move || write_frames(stdin, queue));
+        eprintln!("wl-starfield: streaming to the PipeWire node wl-starfield");
+        Ok(Pipeline {
+            screen_details,
+            child,
+            frames,
+            writer,
+        })
+    }
+
+    fn stop(&mut self) {
+        if let Some(Pipeline {
+            mut child,
+            frames,
+            writer,
+            ..
+        }) = self.pipeline.take()
+        {
+            // Without waiting for it to drain, which it may never do
+            drop(frames);
+            let _ = child.kill();
+            let _ = writer.join();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for PipewireSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn write_frames(mut stdin: ChildStdin, queue: mpsc::Receiver<Vec<u8>>) {
+    for frame in queue {
+        if stdin.write_all(&frame).is_err() {
+            return;
+        }
+    }
+}
+
+// Raw RGBA frames in on stdin, out as a PipeWire source of the size and
+// frame rate asked for, or the frames' own size
+fn gst_args(
+    screen_details: ScreenDetails,
+    input_fps: u32,
+    size: Option<(u32, u32)>,
+    fps: u32,
+) -> Vec<String> {
+    let (width, height) = size.unwrap_or((screen_details.width, screen_details.height));
+    [
+        "-q".into(),
+        "fdsrc".into(),
+        "fd=0".into(),
+        "!".into(),
+        "rawvideoparse".into(),
+        format!("width={}", screen_details.width),
+        format!("height={}", screen_details.height),
+        "format=rgba".into(),
+        format!("framerate={input_fps}/1"),
+        "!".into(),
+        "videoconvert".into(),
+        "!".into(),
+        "videoscale".into(),
+        "!".into(),
+        "videorate".into(),
+        "!".into(),
+        format!("video/x-raw,width={width},height={height},framerate={fps}/1"),
+        "!".into(),
+        "pipewiresink".into(),
+        "mode=provide".into(),
+        "stream-properties=props,media.class=Video/Source,node.name=wl-starfield".into(),
+    ]
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_scales_to_the_size_asked_for() {
+        let screen_details = ScreenDetails {
+            width: 2560,
+            height: 1440,
+        };
+        let args = gst_args(screen_details, 60, Some((1280, 720)), 30);
+        assert!(args.contains(&"width=2560".to_string()));
+        assert!(args.contains(&"framerate=60/1".to_string()));
+        assert!(args.contains(&"video/x-raw,width=1280,height=720,framerate=30/1".to_string()));
+        assert_eq!(args.iter().filter(|arg| *arg == "!").count(), 6);
+
+        // Or keeps the frames' own
+        let args = gst_args(screen_details, 60, None, 30);
+        assert!(args.contains(&"video/x-raw,width=2560,height=1440,framerate=30/1".to_string()));
+    }
+}
@@ -0,0 +1,396 @@
+// Planets: a few bright wanderers among the stars. Each is a steady disc, as
+// big as one of the naked-eye planets and colored the way the stars are, that
+// never twinkles and drifts the way the stars do but at a pace of its own, slower
+// than most, so the stars slip past it. They wrap around like stars, coming
+// back in across the edge they drift away from.
+//
+// Shooting stars that pass close feel each one's pull, falling off with the
+// square of the distance, so a near miss swings them round in a curve or,
+// now and then, a loop or two about the disc before they fling free.
+//
+// Each is the root of a little transform hierarchy, so whatever it's made of
+// goes wherever it drifts. It has a moon or a few going round it, each a child
+// of its orbit, and each orbit a child of the planet squashed top to bottom:
+// tilted towards us, so a moon swings across the face of the disc on the near
+// side and slips behind it on the far one.
+
+use crate::accent::Tint;
+use crate::blackbody;
+use crate::config::{Config, StarColors};
+use crate::render::{Rect, Target, blend, haze};
+use crate::stars::PALETTE;
+use crate::transform::{Hierarchy, Node, Transform};
+use crate::{CelestialObject, ScreenDetails, UpdateContext};
+use rand::Rng;
+use std::f32::consts::TAU;
+
+// Radius in pixels of each there can be
+const RADII: [f32; 4] = [
+    3.5, // Venus
+    2.5, // Mars
+    3.0, // Jupiter
+    2.8, // Saturn
+];
+pub const MAX_PLANETS: usize = RADII.len();
+// Pixels a second they drift, each somewhere between these
+const PLANET_SPEED: (f32, f32) = (1.5, 5.0);
+// A faint glow around each, this many radii across
+const HALO_SIZE: f32 = 6.0;
+const HALO_ALPHA: f32 = 0.2;
+// Pull on a shooting star, in pixels a second squared, this much for every
+// pixel of radius at a pixel away
+const GRAVITY: f32 = 1_000_000.0;
+// and never more than this, however close it comes
+const MAX_PULL: f32 = 20_000.0;
+// Moons around each, and how far out they go, in radii of the planet
+const MOONS: (usize, usize) = (1, 3);
+const MOON_DISTANCE: (f32, f32) = (2.5, 5.0);
+// Radians a second the nearest go round; those further out are slower, by
+// Kepler's third law
+const MOON_SPEED: f32 = 1.2;
+// How far their orbits are tipped towards us, as the ratio of an orbit's
+// height to its width
+const MOON_TILT: (f32, f32) = (0.1, 0.4);
+// Radius in pixels, and how much grayer and fainter than their planet
+const MOON_RADIUS: (f32, f32) = (0.6, 1.0);
+const MOON_HAZE: f32 = 0.4;
+
+pub struct Planet {
+    // The planet and its parts, with it at the top
+    parts: Hierarchy,
+    body: Node,
+    speed: f32,
+    // Which way it last drifted, a step of length 1
+    drift: (f32, f32),
+    color: (u8, u8, u8),
+    radius: f32,
+    moons: Vec<Moonlet>,
+    // The sky's accent color, if it has one
+    pub tint: Option<Tint>,
+}
+
+// A moon going round a planet
+struct Moonlet {
+    // Where it is, on an orbit squashed to show its tilt
+    node: Node,
+    // Orbit radius, in pixels
+    distance: f32,
+    // How far round it is, in radians: on the near side from 0 to pi
+    angle: f32,
+    speed: f32,
+    radius: f32,
+}
+
+impl Moonlet {
+    fn spawn(rng: &mut impl Rng, parts: &mut Hierarchy, planet: Node, planet_radius: f32) -> Self {
+        let distance = planet_radius * rng.gen_range(MOON_DISTANCE.0..MOON_DISTANCE.1);
+        let tilt = rng.gen_range(MOON_TILT.0..MOON_TILT.1);
+        let orbit = parts.add(Some(planet), Transform::scale(1.0, tilt));
+        let node = parts.add(Some(orbit), Transform::translate(distance, 0.0));
+        let mut moon = Self {
+            node,
+            distance,
+            angle: 0.0,
+            speed: MOON_SPEED * (planet_radius * MOON_DISTANCE.0 / distance).powf(1.5),
+            radius: rng.gen_range(MOON_RADIUS.0..MOON_RADIUS.1),
+        };
+        moon.turn(parts, rng.gen_range(0.0..TAU));
+        moon
+    }
+
+    // Put it `angle` round its orbit
+    fn turn(&mut self, parts: &mut Hierarchy, angle: f32) {
+        self.angle = angle % TAU;
+        let (sin, cos) = self.angle.sin_cos();
+        parts.set(
+            self.node,
+            Transform::translate(self.distance * cos, self.distance * sin),
+        );
+    }
+
+    fn near(&self) -> bool {
+        self.angle.sin() > 0.0
+    }
+}
+
+impl Planet {
+    // `count` planets, no two the same size, somewhere on the screen
+    pub fn spawn(
+        rng: &mut impl Rng,
+        config: &Config,
+        screen_details: &ScreenDetails,
+        count: usize,
+    ) -> Vec<Self> {
+        let first = rng.gen_range(0..RADII.len());
+        (0..count.min(RADII.len()))
+            .map(|i| {
+                let radius = RADII[(first + i) % RADII.len()];
+                let color = match config.star_colors {
+                    StarColors::Palette => PALETTE[rng.gen_range(0..PALETTE.len())],
+                    StarColors::Blackbody => blackbody::color(blackbody::temperature(rng)),
+                };
+                let moons = rng.gen_range(MOONS.0..=MOONS.1);
+                let mut parts = Hierarchy::default();
+                let body = parts.add(
+                    None,
+                    Transform::translate(
+                        rng.gen_range(0.0..screen_details.width as f32),
+                        rng.gen_range(0.0..screen_details.height as f32),
+                    ),
+                );
+                Self {
+                    moons: (0..moons)
+                        .map(|_| Moonlet::spawn(rng, &mut parts, body, radius))
+                        .collect(),
+                    parts,
+                    body,
+                    speed: rng.gen_range(PLANET_SPEED.0..PLANET_SPEED.1),
+                    drift: (0.0, 0.0),
+                    color,
+                    radius,
+                    tint: None,
+                }
+            })
+            .collect()
+    }
+
+    // Follow a change in surface size, keeping its place on the sky
+    pub fn rescale(&mut self, scale_x: f32, scale_y: f32) {
+        let (x, y) = self.position();
+        self.place(x * scale_x, y * scale_y);
+    }
+
+    fn position(&self) -> (f32, f32) {
+        self.parts.world(self.body).origin()
+    }
+
+    // Move it to (`x`, `y`), and everything it's made of with it
+    fn place(&mut self, x: f32, y: f32) {
+        self.parts.set(self.body, Transform::translate(x, y));
+    }
+
+    // Pull of all of `planets` on something at (`x`, `y`), in pixels a second
+    // squared
+    pub fn pull(planets: &[Planet], x: f32, y: f32) -> (f32, f32) {
+        planets.iter().fold((0.0, 0.0), |(ax, ay), planet| {
+            let (px, py) = planet.position();
+            let (dx, dy) = (px - x, py - y);
+            let distance = dx.hypot(dy).max(planet.radius);
+            let pull = (GRAVITY * planet.radius / (distance * distance)).min(MAX_PULL);
+            (ax + pull * dx / distance, ay + pull * dy / distance)
+        })
+    }
+
+    fn reach(&self) -> f32 {
+        self.radius * HALO_SIZE / 2.0
+    }
+
+    // Its moons on one side of it, where they are on the screen
+    fn moons(&self, near: bool) -> impl Iterator<Item = ((f32, f32), &Moonlet)> {
+        self.moons
+            .iter()
+            .filter(move |moon| moon.near() == near)
+            .map(|moon| (self.parts.world(moon.node).origin(), moon))
+    }
+}
+
+impl CelestialObject for Planet {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        let config = ctx.config;
+        // Held still with the stars in perspective
+        let (dx, dy) = if config.perspective {
+            (0.0, 0.0)
+        } else {
+            ctx.screen_details.drift(config.drift_angle)
+        };
+        self.drift = (dx, dy);
+        let (x, y) = self.position();
+        self.place(x + dx * self.speed * ctx.dt, y + dy * self.speed * ctx.dt);
+        for moon in &mut self.moons {
+            moon.turn(&mut self.parts, moon.angle + moon.speed * ctx.dt);
+        }
+
+        // Back in across the edge it drifts away from, somewhere new along it
+        let (width, height) = (
+            ctx.screen_details.width as f32,
+            ctx.screen_details.height as f32,
+        );
+        let reach = self.reach();
+        let (x, y) = self.position();
+        if x < -reach || x > width + reach {
+            let x = if dx < 0.0 { width + reach } else { -reach };
+            self.place(x, ctx.rng.gen_range(0.0..height));
+        } else if y < -reach || y > height + reach {
+            let y = if dy > 0.0 { -reach } else { height + reach };
+            self.place(ctx.rng.gen_range(0.0..width), y);
+        }
+    }
+
+    fn draw(&self, target: &mut Target) {
+        let color = match self.tint {
+            Some(tint) => tint.apply(self.color),
+            None => self.color,
+        };
+        let moon_color = haze(color, MOON_HAZE);
+        let (x, y) = self.position();
+        // The far side first, for the disc to hide what it's in front of
+        for ((x, y), moon) in self.moons(false) {
+            disc(target, x, y, moon.radius, moon_color);
+        }
+        target.soft_point(x, y, color, HALO_ALPHA, (self.radius * HALO_SIZE) as i32);
+        disc(target, x, y, self.radius, color);
+        for ((x, y), moon) in self.moons(true) {
+            disc(target, x, y, moon.radius, moon_color);
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        let moons = self.moons.iter().map(|moon| moon.distance + moon.radius);
+        let (x, y) = self.position();
+        Rect::around(x, y, moons.fold(self.reach(), f32::max) + 1.0)
+    }
+
+    // They only ever wrap around
+    fn is_alive(&self, _: &ScreenDetails) -> bool {
+        true
+    }
+
+    fn motion(&self, _: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        let (dx, dy) = self.drift;
+        (self.position(), (dx * self.speed, dy * self.speed))
+    }
+}
+
+// An antialiased disc of `radius` pixels centered on (`x`, `y`)
+fn disc(target: &mut Target, x: f32, y: f32, radius: f32, color: (u8, u8, u8)) {
+    for (py, left, row) in target.span_rows(Rect::around(x, y, radius + 1.0)) {
+        let dy = py as f32 + 0.5 - y;
+        for (px, pixel) in (left..).zip(row) {
+            let distance = (px as f32 + 0.5 - x).hypot(dy);
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                blend(pixel, color, coverage);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, LANDSCAPE, MockScreen, seeded_rng};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn planets_shine_steadily_and_drift_at_their_own_pace() {
+        let mut screen = MockScreen::landscape();
+        let config = Config::default();
+        let mut planets = Planet::spawn(&mut seeded_rng(4), &config, &LANDSCAPE, MAX_PLANETS + 2);
+        assert_eq!(planets.len(), MAX_PLANETS);
+        let radii: Vec<_> = planets.iter().map(|p| p.radius).collect();
+        assert!((1..radii.len()).all(|i| !radii[..i].contains(&radii[i])));
+
+        let planet = &mut planets[0];
+        planet.place(960.0, 540.0);
+        let mut brightness = Vec::new();
+        for _ in 0..5 {
+            let mut frame = Frame::new(&LANDSCAPE);
+            frame.draw(&*planet);
+            let (x, y) = planet.position();
+            brightness.push(frame.brightness(x as u32, y as u32));
+            screen.run(planet, FixedClock::fps(60.0), 0.1);
+        }
+        // No twinkle: the same every time
+        assert!(
+            brightness.iter().all(|&b| b == brightness[0] && b > 200),
+            "{brightness:?}"
+        );
+        // Leftward on a landscape screen, at its own speed
+        let (x, y) = planet.position();
+        let moved = 960.0 - x;
+        assert!((moved - planet.speed * 0.5).abs() < 0.01, "{moved}");
+        assert_eq!(y, 540.0);
+    }
+
+    #[test]
+    fn planets_come_back_around_once_they_drift_off() {
+        let mut screen = MockScreen::landscape();
+        let mut planets = Planet::spawn(&mut seeded_rng(1), &Config::default(), &LANDSCAPE, 1);
+        let planet = &mut planets[0];
+        planet.place(-planet.reach() + 0.01, 540.0);
+        screen.run(planet, FixedClock::fps(60.0), 0.1);
+        let (x, y) = planet.position();
+        assert!(x > 1920.0, "{x}");
+        assert!((0.0..1080.0).contains(&y));
+        assert!(planet.is_alive(&LANDSCAPE));
+    }
+
+    #[test]
+    fn planets_keep_their_place_when_the_surface_resizes() {
+        let mut planets = Planet::spawn(&mut seeded_rng(2), &Config::default(), &LANDSCAPE, 1);
+        let planet = &mut planets[0];
+        planet.place(480.0, 270.0);
+        planet.rescale(0.5, 2.0);
+        assert_eq!(planet.position(), (240.0, 540.0));
+    }
+
+    #[test]
+    fn moons_go_round_their_planet_and_hide_behind_it() {
+        let mut screen = MockScreen::landscape();
+        let mut planets = Planet::spawn(&mut seeded_rng(5), &Config::default(), &LANDSCAPE, 1);
+        let planet = &mut planets[0];
+        assert!((MOONS.0..=MOONS.1).contains(&planet.moons.len()));
+        planet.place(960.5, 540.5);
+        // Just the one, on an orbit we know
+        let orbit = planet
+            .parts
+            .add(Some(planet.body), Transform::scale(1.0, 0.1));
+        let node = planet
+            .parts
+            .add(Some(orbit), Transform::translate(0.0, 0.0));
+        planet.moons = vec![Moonlet {
+            node,
+            distance: planet.radius * 3.0,
+            angle: 0.0,
+            speed: MOON_SPEED,
+            radius: MOON_RADIUS.1,
+        }];
+
+        let mut bare = Frame::new(&LANDSCAPE);
+        let moons = std::mem::take(&mut planet.moons);
+        bare.draw(&*planet);
+        planet.moons = moons;
+        // Brightness where the moon is with it `angle` round, and without it
+        let mut at = |angle: f32| {
+            planet.moons[0].turn(&mut planet.parts, angle);
+            let (x, y) = planet.parts.world(planet.moons[0].node).origin();
+            let (x, y) = (x as u32, y as u32);
+            let mut frame = Frame::new(&LANDSCAPE);
+            frame.draw(&*planet);
+            (frame.brightness(x, y), bare.brightness(x, y))
+        };
+        // Out to the side it shows on its own, across the near side it's
+        // grayer than the disc, and on the far side the disc hides it
+        let (beside, sky) = at(0.0);
+        assert!(beside > sky, "{beside} {sky}");
+        let (front, face) = at(FRAC_PI_2);
+        assert!(front < face, "{front} {face}");
+        let (behind, face) = at(-FRAC_PI_2);
+        assert_eq!(behind, face);
+
+        // Round it goes, and along with the planet as it drifts
+        planet.moons[0].turn(&mut planet.parts, 0.0);
+        let speed = planet.moons[0].speed;
+        screen.run(planet, FixedClock::fps(60.0), 0.5);
+        let angle = planet.moons[0].angle;
+        assert!((angle - speed * 0.5).abs() < 0.01, "{angle}");
+        let (x, y) = planet.position();
+        let (mx, my) = planet.parts.world(planet.moons[0].node).origin();
+        let distance = planet.moons[0].distance;
+        assert!((mx - (x + distance * angle.cos())).abs() < 0.01, "{mx}");
+        assert!(
+            (my - (y + distance * 0.1 * angle.sin())).abs() < 0.01,
+            "{my}"
+        );
+    }
+}
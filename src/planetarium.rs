@@ -0,0 +1,436 @@
+// Planetarium mode: the real sky over a given latitude and longitude, as it
+// stands at the sky's clock, turning about the celestial pole as the hours
+// pass. The brightest hundred or so stars come from a small built-in catalog;
+// the rest of the stars the config asks for are made up, scattered over the
+// celestial sphere, so the sky is as full as usual. The view faces the
+// equator (south from the northern hemisphere, north from the southern)
+// with the horizon along the bottom edge, through a stereographic
+// projection so constellations keep their shapes out to the sides.
+
+use crate::render::{Rect, TILE_SIZE, Target, TileBins};
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Degrees across the longer side of the screen
+const FIELD_OF_VIEW: f32 = 140.0;
+// Degrees of ground showing below the horizon
+const GROUND: f32 = 5.0;
+// Made-up stars range from magnitude 3.5 to the naked-eye limit, mostly faint
+const FAINTEST: f32 = 6.5;
+const BACKGROUND_BRIGHTEST: f32 = 3.5;
+// A magnitude 1 star shows at full brightness
+const FULL_MAGNITUDE: f32 = 1.0;
+// Stars fade into the murk this close to the horizon, in degrees
+const EXTINCTION: f32 = 8.0;
+// Unix time of the J2000.0 epoch, 2000-01-01 12:00 UTC
+const J2000: f64 = 946_728_000.0;
+
+// Name, right ascension (hours), declination (degrees), visual magnitude and
+// spectral class, for J2000
+#[rustfmt::skip]
+const CATALOG: [(&str, f32, f32, f32, char); 96] = [
+    ("Sirius", 6.752, -16.716, -1.46, 'A'),
+    ("Canopus", 6.399, -52.696, -0.74, 'F'),
+    ("Rigil Kentaurus", 14.660, -60.834, -0.27, 'G'),
+    ("Arcturus", 14.261, 19.182, -0.05, 'K'),
+    ("Vega", 18.616, 38.784, 0.03, 'A'),
+    ("Capella", 5.278, 45.998, 0.08, 'G'),
+    ("Rigel", 5.242, -8.202, 0.13, 'B'),
+    ("Procyon", 7.655, 5.225, 0.34, 'F'),
+    ("Achernar", 1.629, -57.237, 0.46, 'B'),
+    ("Betelgeuse", 5.919, 7.407, 0.50, 'M'),
+    ("Hadar", 14.064, -60.373, 0.61, 'B'),
+    ("Altair", 19.846, 8.868, 0.77, 'A'),
+    ("Acrux", 12.443, -63.099, 0.76, 'B'),
+    ("Aldebaran", 4.599, 16.509, 0.86, 'K'),
+    ("Antares", 16.490, -26.432, 0.96, 'M'),
+    ("Spica", 13.420, -11.161, 0.97, 'B'),
+    ("Pollux", 7.755, 28.026, 1.14, 'K'),
+    ("Fomalhaut", 22.961, -29.622, 1.16, 'A'),
+    ("Deneb", 20.690, 45.280, 1.25, 'A'),
+    ("Mimosa", 12.795, -59.689, 1.25, 'B'),
+    ("Regulus", 10.139, 11.967, 1.35, 'B'),
+    ("Adhara", 6.977, -28.972, 1.50, 'B'),
+    ("Castor", 7.577, 31.888, 1.58, 'A'),
+    ("Shaula", 17.560, -37.104, 1.62, 'B'),
+    ("Gacrux", 12.519, -57.113, 1.63, 'M'),
+    ("Bellatrix", 5.419, 6.350, 1.64, 'B'),
+    ("Elnath", 5.438, 28.608, 1.65, 'B'),
+    ("Miaplacidus", 9.220, -69.717, 1.68, 'A'),
+    ("Alnilam", 5.603, -1.202, 1.69, 'B'),
+    ("Alnair", 22.137, -46.961, 1.74, 'B'),
+    ("Alnitak", 5.679, -1.943, 1.77, 'O'),
+    ("Alioth", 12.900, 55.960, 1.77, 'A'),
+    ("Dubhe", 11.062, 61.751, 1.79, 'K'),
+    ("Mirfak", 3.405, 49.861, 1.79, 'F'),
+    ("Wezen", 7.140, -26.393, 1.83, 'F'),
+    ("Kaus Australis", 18.403, -34.385, 1.85, 'B'),
+    ("Avior", 8.375, -59.510, 1.86, 'K'),
+    ("Sargas", 17.622, -42.998, 1.86, 'F'),
+    ("Alkaid", 13.792, 49.313, 1.86, 'B'),
+    ("Menkalinan", 5.992, 44.947, 1.90, 'A'),
+    ("Atria", 16.811, -69.028, 1.91, 'K'),
+    ("Alhena", 6.629, 16.399, 1.92, 'A'),
+    ("Peacock", 20.427, -56.735, 1.94, 'B'),
+    ("Polaris", 2.530, 89.264, 1.98, 'F'),
+    ("Mirzam", 6.378, -17.956, 1.98, 'B'),
+    ("Alphard", 9.460, -8.659, 1.98, 'K'),
+    ("Hamal", 2.120, 23.462, 2.00, 'K'),
+    ("Algieba", 10.333, 19.842, 2.08, 'K'),
+    ("Diphda", 0.727, -17.987, 2.04, 'K'),
+    ("Nunki", 18.921, -26.297, 2.05, 'B'),
+    ("Menkent", 14.111, -36.370, 2.06, 'K'),
+    ("Mirach", 1.162, 35.621, 2.05, 'M'),
+    ("Alpheratz", 0.140, 29.091, 2.06, 'B'),
+    ("Rasalhague", 17.582, 12.560, 2.07, 'A'),
+    ("Kochab", 14.845, 74.156, 2.08, 'K'),
+    ("Saiph", 5.796, -9.670, 2.09, 'B'),
+    ("Almach", 2.065, 42.330, 2.10, 'K'),
+    ("Algol", 3.136, 40.956, 2.12, 'B'),
+    ("Denebola", 11.818, 14.572, 2.14, 'A'),
+    ("Mintaka", 5.533, -0.299, 2.23, 'O'),
+    ("Mizar", 13.399, 54.925, 2.23, 'A'),
+    ("Sadr", 20.370, 40.257, 2.23, 'F'),
+    ("Alphecca", 15.578, 26.715, 2.23, 'A'),
+    ("Eltanin", 17.943, 51.489, 2.24, 'K'),
+    ("Schedar", 0.675, 56.537, 2.24, 'K'),
+    ("Caph", 0.153, 59.150, 2.28, 'F'),
+    ("Dschubba", 16.006, -22.622, 2.29, 'B'),
+    ("Larawag", 16.836, -34.293, 2.29, 'K'),
+    ("Merak", 11.031, 56.382, 2.37, 'A'),
+    ("Izar", 14.750, 27.074, 2.37, 'K'),
+    ("Enif", 21.736, 9.875, 2.39, 'K'),
+    ("Scheat", 23.063, 28.083, 2.42, 'M'),
+    ("Phecda", 11.897, 53.695, 2.44, 'A'),
+    ("Navi", 0.945, 60.717, 2.47, 'B'),
+    ("Markab", 23.079, 15.205, 2.48, 'B'),
+    ("Aljanah", 20.770, 33.970, 2.48, 'K'),
+    ("Acrab", 16.091, -19.806, 2.56, 'B'),
+    ("Zosma", 11.235, 20.524, 2.56, 'A'),
+    ("Ascella", 19.044, -29.880, 2.60, 'A'),
+    ("Unukalhai", 15.738, 6.426, 2.63, 'K'),
+    ("Ruchbah", 1.430, 60.235, 2.68, 'A'),
+    ("Kaus Media", 18.350, -29.828, 2.70, 'K'),
+    ("Tarazed", 19.771, 10.613, 2.72, 'K'),
+    ("Imai", 12.252, -58.749, 2.79, 'B'),
+    ("Kaus Borealis", 18.466, -25.421, 2.81, 'K'),
+    ("Algenib", 0.220, 15.184, 2.83, 'B'),
+    ("Fawaris", 19.750, 45.131, 2.87, 'B'),
+    ("Alcyone", 3.791, 24.105, 2.87, 'B'),
+    ("Gomeisa", 7.453, 8.289, 2.89, 'B'),
+    ("Albireo", 19.512, 27.960, 3.05, 'K'),
+    ("Megrez", 12.257, 57.033, 3.31, 'A'),
+    ("Meissa", 5.585, 9.934, 3.33, 'O'),
+    ("Segin", 1.907, 63.670, 3.37, 'B'),
+    ("Sheliak", 18.835, 33.363, 3.52, 'B'),
+    ("Sulafat", 18.982, 32.690, 3.25, 'B'),
+    ("Zubeneschamali", 15.283, -9.383, 2.61, 'B'),
+];
+
+// Rough star color for a spectral class
+fn class_color(class: char) -> (u8, u8, u8) {
+    match class {
+        'O' | 'B' => (175, 195, 255),
+        'A' => (220, 230, 255),
+        'F' => (255, 250, 235),
+        'G' => (255, 240, 200),
+        'K' => (255, 210, 160),
+        _ => (255, 180, 140),
+    }
+}
+
+// A star fixed on the celestial sphere
+struct SkyStar {
+    // Right ascension in radians, and the sine and cosine of declination
+    ra: f32,
+    sin_dec: f32,
+    cos_dec: f32,
+    magnitude: f32,
+    color: (u8, u8, u8),
+}
+
+// A star where it shows on screen right now
+struct Placed {
+    x: f32,
+    y: f32,
+    color: (u8, u8, u8),
+    alpha: f32,
+    size: i32,
+}
+
+impl Placed {
+    fn bounds(&self) -> Rect {
+        Rect::around(self.x, self.y, self.size as f32 / 2.0 + 1.0)
+    }
+}
+
+pub struct Planetarium {
+    // Sine and cosine of the latitude, and the longitude in degrees east
+    sin_lat: f32,
+    cos_lat: f32,
+    longitude: f32,
+    stars: Vec<SkyStar>,
+    placed: Vec<Placed>,
+    bins: TileBins,
+}
+
+impl Planetarium {
+    pub fn new(rng: &mut impl Rng, latitude: f32, longitude: f32, background: usize) -> Self {
+        let catalog = CATALOG.iter().map(|&(_, ra, dec, magnitude, class)| {
+            let dec = dec.to_radians();
+            SkyStar {
+                ra: ra / 24.0 * TAU,
+                sin_dec: dec.sin(),
+                cos_dec: dec.cos(),
+                magnitude,
+                color: class_color(class),
+            }
+        });
+        // Evenly over the sphere, with faint stars far outnumbering bright ones
+        let palette = ['B', 'A', 'F', 'G', 'K', 'M'];
+        let made_up = (0..background)
+            .map(|_| {
+                let sin_dec: f32 = rng.gen_range(-1.0..1.0);
+                let magnitude =
+                    FAINTEST - (FAINTEST - BACKGROUND_BRIGHTEST) * rng.r#gen::<f32>().powi(3);
+                SkyStar {
+                    ra: rng.gen_range(0.0..TAU),
+                    sin_dec,
+                    cos_dec: (1.0 - sin_dec * sin_dec).sqrt(),
+                    magnitude,
+                    color: class_color(palette[rng.gen_range(0..palette.len())]),
+                }
+            })
+            .collect::<Vec<_>>();
+        let latitude = latitude.to_radians();
+        Self {
+            sin_lat: latitude.sin(),
+            cos_lat: latitude.cos(),
+            longitude,
+            stars: catalog.chain(made_up).collect(),
+            placed: Vec::with_capacity(CATALOG.len() + background),
+            bins: TileBins::new(),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut UpdateContext) {
+        let lst = sidereal_time(ctx.now, self.longitude);
+        let view = View::new(ctx.screen_details, self.sin_lat >= 0.0);
+        self.placed.clear();
+        for star in &self.stars {
+            let (sin_alt, cos_alt, az) = self.horizontal(star, lst);
+            // Below the ground
+            if sin_alt < -GROUND.to_radians().sin() {
+                continue;
+            }
+            let Some((x, y)) = view.project(sin_alt, cos_alt, az) else {
+                continue;
+            };
+            let altitude = sin_alt.asin().to_degrees();
+            let murk = (altitude / EXTINCTION).clamp(0.0, 1.0);
+            let alpha = 10f32
+                .powf(-0.2 * (star.magnitude - FULL_MAGNITUDE))
+                .min(1.0)
+                * murk;
+            if alpha < 0.02 {
+                continue;
+            }
+            let size = match star.magnitude {
+                m if m < 0.0 => 5,
+                m if m < 1.5 => 4,
+                m if m < 3.0 => 3,
+                _ => 1,
+            };
+            self.placed.push(Placed {
+                x,
+                y,
+                color: star.color,
+                alpha,
+                size,
+            });
+        }
+    }
+
+    // Altitude (as its sine and cosine) and azimuth (radians east of north)
+    // of a star at local sidereal time `lst`
+    fn horizontal(&self, star: &SkyStar, lst: f32) -> (f32, f32, f32) {
+        let hour_angle = lst - star.ra;
+        let (sin_h, cos_h) = hour_angle.sin_cos();
+        let sin_alt = self.sin_lat * star.sin_dec + self.cos_lat * star.cos_dec * cos_h;
+        let cos_alt = (1.0 - sin_alt * sin_alt).max(0.0).sqrt();
+        let az = (-star.cos_dec * sin_h)
+            .atan2(star.sin_dec * self.cos_lat - star.cos_dec * cos_h * self.sin_lat);
+        (sin_alt, cos_alt, az)
+    }
+
+    // Bin the stars for drawing the tiles of a frame covering `view`
+    pub fn bin(&mut self, view: Rect) {
+        self.bins
+            .rebuild(view, self.placed.iter().map(Placed::bounds));
+    }
+
+    pub fn draw(&self, tile: usize, target: &mut Target) {
+        for i in self.bins.get(tile) {
+            let star = &self.placed[i];
+            if star.size <= 1 {
+                target.blend(star.x as i32, star.y as i32, star.color, star.alpha);
+            } else {
+                target.soft_point(star.x, star.y, star.color, star.alpha, star.size);
+            }
+        }
+    }
+}
+
+// Where the screen looks: facing the equator with the horizon near the
+// bottom edge, scaled so FIELD_OF_VIEW spans the longer side
+struct View {
+    center: (f32, f32),
+    scale: f32,
+    forward: [f32; 3],
+    right: [f32; 3],
+    up: [f32; 3],
+}
+
+impl View {
+    fn new(screen_details: &ScreenDetails, northern: bool) -> Self {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        let scale = width.max(height) / 2.0 / (FIELD_OF_VIEW.to_radians() / 4.0).tan();
+        // Tilted up so the bottom edge sits GROUND degrees below the horizon
+        let half_height = 2.0 * (height / 2.0 / scale).atan();
+        let pitch = half_height - GROUND.to_radians();
+        let facing: f32 = if northern { PI } else { 0.0 };
+        let (sin_a, cos_a) = facing.sin_cos();
+        let (sin_p, cos_p) = pitch.sin_cos();
+        // East, north and up
+        let forward = [sin_a * cos_p, cos_a * cos_p, sin_p];
+        let right = [cos_a, -sin_a, 0.0];
+        let up = [-sin_a * sin_p, -cos_a * sin_p, cos_p];
+        Self {
+            center: (width / 2.0, height / 2.0),
+            scale,
+            forward,
+            right,
+            up,
+        }
+    }
+
+    // Screen position of a direction in the sky, unless it's behind the viewer
+    fn project(&self, sin_alt: f32, cos_alt: f32, az: f32) -> Option<(f32, f32)> {
+        let (sin_az, cos_az) = az.sin_cos();
+        let v = [cos_alt * sin_az, cos_alt * cos_az, sin_alt];
+        let dot = |a: &[f32; 3]| a[0] * v[0] + a[1] * v[1] + a[2] * v[2];
+        let ahead = 1.0 + dot(&self.forward);
+        if ahead < 0.1 {
+            return None;
+        }
+        let x = self.center.0 + dot(&self.right) / ahead * self.scale;
+        let y = self.center.1 - dot(&self.up) / ahead * self.scale;
+        let margin = TILE_SIZE as f32;
+        let reach = -margin..self.center.0 * 2.0 + margin;
+        (reach.contains(&x) && (-margin..self.center.1 * 2.0 + margin).contains(&y))
+            .then_some((x, y))
+    }
+}
+
+// Local mean sidereal time at `time` and `longitude` degrees east, in radians
+pub fn sidereal_time(time: SystemTime, longitude: f32) -> f32 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64());
+    let days = (secs - J2000) / 86_400.0;
+    let degrees = 280.460_618_37 + 360.985_647_366_29 * days + longitude as f64;
+    degrees.rem_euclid(360.0).to_radians() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, MockScreen};
+    use std::time::Duration;
+
+    fn at(secs: f64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs_f64(secs)
+    }
+
+    #[test]
+    fn sidereal_time_follows_the_earths_turning() {
+        // At J2000.0 Greenwich stood at 280.46 degrees
+        let gmst = sidereal_time(at(J2000), 0.0).to_degrees();
+        assert!((gmst - 280.46).abs() < 0.01, "{gmst}");
+        // A sidereal day is about four minutes shorter than a solar one
+        let later = sidereal_time(at(J2000 + 86_164.09), 0.0).to_degrees();
+        assert!((later - gmst).abs() < 0.01, "{later}");
+        // And 90 degrees east it's six hours later
+        let east = sidereal_time(at(J2000), 90.0).to_degrees();
+        assert!((east - (gmst + 90.0 - 360.0)).abs() < 0.01, "{east}");
+    }
+
+    #[test]
+    fn polaris_stands_at_the_latitude_and_stars_rise_in_the_east() {
+        let mut screen = MockScreen::landscape();
+        let planetarium = Planetarium::new(&mut screen.rng, 52.0, 0.0, 0);
+        let polaris = &planetarium.stars[43];
+        assert_eq!(CATALOG[43].0, "Polaris");
+        for hours in 0..24 {
+            let lst = hours as f32 / 24.0 * TAU;
+            let (sin_alt, _, _) = planetarium.horizontal(polaris, lst);
+            let altitude = sin_alt.asin().to_degrees();
+            assert!((altitude - 52.0).abs() < 1.0, "{altitude}");
+        }
+        // Six hours before it crosses the meridian, an equator star is rising
+        // due east
+        let star = SkyStar {
+            ra: 0.0,
+            sin_dec: 0.0,
+            cos_dec: 1.0,
+            magnitude: 1.0,
+            color: (255, 255, 255),
+        };
+        let (sin_alt, _, az) = planetarium.horizontal(&star, -PI / 2.0);
+        assert!(sin_alt.abs() < 0.01);
+        assert!((az.to_degrees() - 90.0).abs() < 0.5, "{}", az.to_degrees());
+    }
+
+    #[test]
+    fn the_sky_turns_with_the_clock() {
+        let mut screen = MockScreen::landscape();
+        let mut planetarium = Planetarium::new(&mut screen.rng, 40.0, -75.0, 2000);
+        let details = screen.details;
+        let mut ctx = screen.ctx(0.0);
+        ctx.now = at(1_700_000_000.0);
+        planetarium.update(&mut ctx);
+        // Plenty is up, and draws
+        assert!(planetarium.placed.len() > 500);
+        let mut frame = Frame::new(&details);
+        planetarium.bin(Rect::new(0, 0, 1920, 1080));
+        let mut target = frame.target();
+        for tile in 0..1920usize.div_ceil(64) * 1080usize.div_ceil(64) {
+            planetarium.draw(tile, &mut target);
+        }
+        assert!(frame.lit(20) > 300);
+
+        // Facing south, a star on the meridian moves west (right) and down
+        // over the next hour
+        let view = View::new(&details, true);
+        let lst = sidereal_time(ctx.now, -75.0);
+        let star = SkyStar {
+            ra: lst,
+            sin_dec: 0.0,
+            cos_dec: 1.0,
+            magnitude: 1.0,
+            color: (255, 255, 255),
+        };
+        let place = |lst| {
+            let (sin_alt, cos_alt, az) = planetarium.horizontal(&star, lst);
+            view.project(sin_alt, cos_alt, az).unwrap()
+        };
+        let (before, after) = (place(lst), place(lst + TAU / 24.0));
+        assert!((before.0 - 960.0).abs() < 1.0);
+        assert!(
+            after.0 > before.0 + 50.0 && after.1 > before.1,
+            "{before:?} -> {after:?}"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+// Where a frame's time goes, for `wl-starfield bench`. While profiling is on,
+// the busiest parts of updating and drawing are timed as they run and added
+// up until taken. Drawing is timed tile by tile on every render thread, so
+// its times are summed over the threads rather than wall-clock.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Time spent in each part since the profile was last taken
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Profile {
+    pub star_update: Duration,
+    pub star_draw: Duration,
+    pub shooting_star_draw: Duration,
+}
+
+// Adds up how long what it's given takes, from any thread, when on
+pub(crate) struct Stopwatch {
+    on: bool,
+    nanos: AtomicU64,
+}
+
+impl Stopwatch {
+    pub fn new(on: bool) -> Self {
+        Self {
+            on,
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub fn time<R>(&self, work: impl FnOnce() -> R) -> R {
+        if !self.on {
+            return work();
+        }
+        let started = Instant::now();
+        let result = work();
+        let nanos = started.elapsed().as_nanos() as u64;
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+        result
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_only_when_on() {
+        let work = || std::thread::sleep(Duration::from_millis(2));
+        let off = Stopwatch::new(false);
+        off.time(work);
+        assert_eq!(off.total(), Duration::ZERO);
+
+        let on = Stopwatch::new(true);
+        std::thread::scope(|scope| {
+            scope.spawn(|| on.time(work));
+            scope.spawn(|| on.time(work));
+        });
+        assert!(on.total() >= Duration::from_millis(4));
+    }
+}
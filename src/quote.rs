@@ -0,0 +1,96 @@
+// Quote-of-the-moment overlay. Lines are read from a plain text file, one
+// quote per line; every `quote_interval` seconds a random one fades in, stays
+// for QUOTE_SHOW_TIME seconds and fades out again.
+
+use crate::render::{Rect, Target};
+use crate::text;
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+use std::fs;
+use std::path::Path;
+
+pub const QUOTE_SHOW_TIME: f32 = 20.0;
+const QUOTE_FADE_TIME: f32 = 2.5;
+// Widest a line may get, as a fraction of the screen width
+const QUOTE_MAX_WIDTH: f32 = 0.6;
+const QUOTE_SCALE: i32 = 3;
+
+pub struct QuoteOverlay {
+    quotes: Vec<String>,
+    // Seconds since the current quote started showing
+    timer: f32,
+    lines: Vec<String>,
+    // Seconds from one quote to the next
+    interval: f32,
+    // Where the centre of the text block sits, as a fraction of the screen
+    anchor: (f32, f32),
+}
+
+impl QuoteOverlay {
+    // None if the file is missing or has no quotes, which disables the overlay
+    pub fn load(path: &Path, interval: f32, anchor: (f32, f32)) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let quotes: Vec<String> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+
+        (!quotes.is_empty()).then_some(Self {
+            quotes,
+            // Due straight away, so the first quote shows at startup
+            timer: interval,
+            lines: Vec::new(),
+            interval,
+            anchor,
+        })
+    }
+
+    pub(crate) fn update(&mut self, ctx: &mut UpdateContext) {
+        self.timer += ctx.dt;
+        if self.timer >= self.interval {
+            self.timer = 0.0;
+            let quote = &self.quotes[ctx.rng.gen_range(0..self.quotes.len())];
+            let max_width = (ctx.screen_details.width as f32 * QUOTE_MAX_WIDTH) as i32;
+            self.lines = text::wrap(quote, max_width, QUOTE_SCALE);
+        }
+    }
+
+    fn alpha(&self) -> f32 {
+        let fade_in = self.timer / QUOTE_FADE_TIME;
+        let fade_out = (QUOTE_SHOW_TIME - self.timer) / QUOTE_FADE_TIME;
+        fade_in.min(fade_out).clamp(0.0, 1.0)
+    }
+
+    pub(crate) fn bounds(&self, screen_details: &ScreenDetails) -> Rect {
+        let (w, h) = text::measure(&self.lines, QUOTE_SCALE);
+        let cx = (screen_details.width as f32 * self.anchor.0) as i32;
+        let cy = (screen_details.height as f32 * self.anchor.1) as i32;
+        Rect::new(cx - w / 2, cy - h / 2, cx - w / 2 + w, cy - h / 2 + h)
+    }
+
+    pub(crate) fn draw(&self, target: &mut Target, screen_details: &ScreenDetails) {
+        let alpha = self.alpha();
+        let rect = self.bounds(screen_details);
+        if alpha <= 0.0 || !target.overlaps(rect) {
+            return;
+        }
+
+        for (i, line) in self.lines.iter().enumerate() {
+            // Center each line within the block
+            let (w, _) = text::measure(&[line], QUOTE_SCALE);
+            let x = rect.x0 + (rect.x1 - rect.x0 - w) / 2;
+            let y = rect.y0 + i as i32 * text::line_height(QUOTE_SCALE);
+            text::draw_line(
+                target,
+                x,
+                y,
+                line,
+                (200, 210, 235),
+                alpha * 0.85,
+                QUOTE_SCALE,
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// Animated recordings for sharing, as APNG. Frames are kept at a steady rate
+// of their own whatever the sky is running at, shrunk by averaging, and
+// handed to an encoder on its own thread as they come, so a recording holds
+// only a few frames in memory however long it runs. When frames come slower
+// than the recording's rate, each is kept as many times as it stayed on
+// screen, so the recording still plays back in real time.
+
+use crate::ScreenDetails;
+use std::io::{self, BufWriter, Write};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+// Frames waiting for the encoder before adding another waits for it
+const QUEUE: usize = 8;
+
+pub struct Recording {
+    screen_details: ScreenDetails,
+    size: ScreenDetails,
+    // Seconds between kept frames, and until the next one
+    interval: f32,
+    until_next: f32,
+    // Frames still to keep
+    remaining: u32,
+    frames: Option<SyncSender<Vec<u8>>>,
+    encoder: JoinHandle<io::Result<()>>,
+}
+
+impl Recording {
+    // Record `seconds` of the screen to `out`, `fps` frames a second, scaled
+    // by `scale` (at most 1)
+    pub fn new<W: Write + Send + 'static>(
+        out: W,
+        screen_details: ScreenDetails,
+        seconds: f32,
+        fps: f32,
+        scale: f32,
+    ) -> Self {
+        let scale = scale.min(1.0);
+        let scaled = |pixels: u32| ((pixels as f32 * scale).round() as u32).max(1);
+        let size = ScreenDetails {
+            width: scaled(screen_details.width),
+            height: scaled(screen_details.height),
+        };
+        let interval = 1.0 / fps;
+        let count = ((seconds * fps).round() as u32).max(1);
+        let (frames, queue) = mpsc::sync_channel::<Vec<u8>>(QUEUE);
+        let encoder = thread::spawn(move || {
+            let mut encoder = png::Encoder::new(BufWriter::new(out), size.width, size.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_compression(png::Compression::Fast);
+            encoder.validate_sequence(true);
+            // Looping forever, as animations for sharing usually do
+            encoder.set_animated(count, 0)?;
+            encoder.set_frame_delay((interval * 1000.0).round() as u16, 1000)?;
+            let mut writer = encoder.write_header()?;
+            for frame in queue {
+                writer.write_image_data(&frame)?;
+            }
+            // Fails if the recording was cut short
+            writer.finish()?;
+            Ok(())
+        });
+        Self {
+            screen_details,
+            size,
+            interval,
+            until_next: 0.0,
+            remaining: count,
+            frames: Some(frames),
+            encoder,
+        }
+    }
+
+    // The screen being recorded
+    pub fn screen_details(&self) -> ScreenDetails {
+        self.screen_details
+    }
+
+    // Add an RGBA8 frame of the whole screen that was on screen for `dt`
+    // seconds. Returns true once the recording has all its frames.
+    pub fn add(&mut self, frame: &[u8], dt: f32) -> bool {
+        let Some(frames) = &self.frames else {
+            return true;
+        };
+        // Kept once for every tick of the recording's clock it was on screen
+        if self.until_next < dt {
+            let shrunk = self.shrink(frame);
+            while self.until_next < dt && self.remaining > 0 {
+                // The encoder only goes away on an error, which finish reports
+                if frames.send(shrunk.clone()).is_err() {
+                    self.remaining = 0;
+                }
+                self.remaining = self.remaining.saturating_sub(1);
+                self.until_next += self.interval;
+            }
+        }
+        self.until_next -= dt;
+        if self.remaining == 0 {
+            self.frames = None;
+        }
+        self.frames.is_none()
+    }
+
+    // Wait for the encoder to write out everything it was given. A recording
+    // finished before all its frames were added is left incomplete and
+    // reported as an error.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.frames = None;
+        self.encoder
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("encoder panicked")))
+    }
+
+    // An RGB8 frame of `size`, each pixel the average of the screen pixels
+    // under it
+    fn shrink(&self, frame: &[u8]) -> Vec<u8> {
+        let (width, height) = (
+            self.screen_details.width as usize,
+            self.screen_details.height as usize,
+        );
+        let (size_width, size_height) = (self.size.width as usize, self.size.height as usize);
+        // The screen pixels from `i` of `n` recorded pixels across `across`
+        let span = |i: usize, n: usize, across: usize| {
+            let start = i * across / n;
+            start..((i + 1) * across / n).max(start + 1)
+        };
+        let mut shrunk = Vec::with_capacity(size_width * size_height * 3);
+        for y in 0..size_height {
+            let rows = span(y, size_height, height);
+            for x in 0..size_width {
+                let columns = span(x, size_width, width);
+                let mut sum = [0u32; 3];
+                for row in rows.clone() {
+                    let pixels = &frame[(row * width + columns.start) * 4..][..columns.len() * 4];
+                    for pixel in pixels.chunks_exact(4) {
+                        for (sum, &channel) in sum.iter_mut().zip(pixel) {
+                            *sum += channel as u32;
+                        }
+                    }
+                }
+                let count = (rows.len() * columns.len()) as u32;
+                shrunk.extend(sum.map(|channel| (channel / count) as u8));
+            }
+        }
+        shrunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const SCREEN: ScreenDetails = ScreenDetails {
+        width: 4,
+        height: 2,
+    };
+
+    // A writer the test can still read once the encoder thread is done
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn records_frames_at_its_own_rate_shrunk() {
+        let out = Shared::default();
+        // One second at 4 frames a second, half size
+        let mut recording = Recording::new(out.clone(), SCREEN, 1.0, 4.0, 0.5);
+        let mut frame = vec![0; 4 * 2 * 4];
+        // The left half lit
+        for y in 0..2 {
+            frame[y * 16..y * 16 + 8].fill(200);
+        }
+        // Frames at 8 a second keep every other one, and slow frames are
+        // kept twice
+        let mut added = 0;
+        for dt in [0.125, 0.125, 0.125, 0.125, 0.5] {
+            added += 1;
+            if recording.add(&frame, dt) {
+                break;
+            }
+        }
+        assert_eq!(added, 5);
+        recording.finish().unwrap();
+
+        let apng = out.0.lock().unwrap().clone();
+        let mut decoder = png::Decoder::new(apng.as_slice()).read_info().unwrap();
+        let info = decoder.info();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.animation_control.map(|ac| ac.num_frames), Some(4));
+        let mut image = vec![0; decoder.output_buffer_size()];
+        decoder.next_frame(&mut image).unwrap();
+        assert_eq!(&image[..6], &[200, 200, 200, 0, 0, 0]);
+    }
+
+    #[test]
+    fn stopping_early_is_an_error() {
+        let mut recording = Recording::new(Shared::default(), SCREEN, 1.0, 4.0, 1.0);
+        assert!(!recording.add(&[0; 4 * 2 * 4], 0.25));
+        assert!(recording.finish().is_err());
+    }
+}
@@ -0,0 +1,636 @@
+// Tile-based compositing for the CPU frame buffer.
+//
+// The frame is drawn in TILE_SIZE x TILE_SIZE tiles. Each object type keeps a
+// TileBins built from its bounding boxes, so drawing a tile only touches the
+// objects overlapping it and all writes stay inside one small block of memory.
+// Tiles in the same row band share a contiguous slice of the frame, which is
+// what allows bands to be handed to separate threads.
+//
+// Everything is drawn in sky coordinates. A frame usually covers the whole
+// sky, but when one sky spans several outputs each frame is a view onto just
+// its part of it, and targets and bins take care of the offset.
+
+use crate::workers::Workers;
+use std::sync::Mutex;
+
+pub const TILE_SIZE: i32 = 64;
+
+// One RGBA8 frame pixel. Writing a whole pixel at once lets opaque fills
+// compile down to single 32-bit stores instead of four byte writes.
+pub type Pixel = [u8; 4];
+
+// Color of empty sky, which everything else is drawn over
+pub const BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+
+// Precompose an opaque packed pixel, once per object rather than per write
+pub fn pack((r, g, b): (u8, u8, u8)) -> Pixel {
+    [r, g, b, 255]
+}
+
+// How bright a pixel looks, 0 to 255, weighing the channels as the eye does
+pub fn luma([r, g, b, _]: Pixel) -> f32 {
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+}
+
+pub fn blend(pixel: &mut Pixel, (r, g, b): (u8, u8, u8), alpha: f32) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let [old_r, old_g, old_b, _] = *pixel;
+    let mix = |old: u8, new: u8| (old as f32 * (1.0 - alpha) + new as f32 * alpha) as u8;
+    *pixel = [mix(old_r, r), mix(old_g, g), mix(old_b, b), 255];
+}
+
+// Add `amount` of a color's light to a pixel, as over a lit background
+pub fn add(pixel: &mut Pixel, (r, g, b): (u8, u8, u8), amount: f32) {
+    let amount = amount.clamp(0.0, 1.0);
+    let [old_r, old_g, old_b, _] = *pixel;
+    let add = |old: u8, new: u8| (old as f32 + new as f32 * amount).min(255.0) as u8;
+    *pixel = [add(old_r, r), add(old_g, g), add(old_b, b), 255];
+}
+
+// Share of a Gaussian of spread `sigma`, centered on 0, that falls in the
+// pixel-wide stretch `from`..`from + 1`
+pub fn gaussian_share(from: f32, sigma: f32) -> f32 {
+    let scale = 1.0 / (sigma * std::f32::consts::SQRT_2);
+    0.5 * (erf((from + 1.0) * scale) - erf(from * scale))
+}
+
+// The error function, good to about 1e-7 (Abramowitz and Stegun 7.1.26)
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_6
+            + t * (-0.284_496_74 + t * (1.421_413_8 + t * (-1.453_152_1 + t * 1.061_405_4))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
+
+// Depth cueing: desaturate a color and lift it toward the background, both by
+// `amount` (0 leaves it alone, 1 leaves plain background)
+pub fn haze((r, g, b): (u8, u8, u8), amount: f32) -> (u8, u8, u8) {
+    if amount <= 0.0 {
+        return (r, g, b);
+    }
+    let amount = amount.min(1.0);
+    let gray = luma(pack((r, g, b)));
+    let (bg_r, bg_g, bg_b) = BACKGROUND;
+    let cue = |c: u8, bg: u8| {
+        let desaturated = c as f32 + (gray - c as f32) * amount;
+        (desaturated + (bg as f32 - desaturated) * amount * 0.5) as u8
+    };
+    (cue(r, bg_r), cue(g, bg_g), cue(b, bg_b))
+}
+
+// Half-open pixel rectangle: x0..x1, y0..y1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+impl Rect {
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    // Smallest rect covering every pixel within `radius` of (x, y)
+    pub fn around(x: f32, y: f32, radius: f32) -> Self {
+        Self::new(
+            (x - radius).floor() as i32,
+            (y - radius).floor() as i32,
+            (x + radius).floor() as i32 + 1,
+            (y + radius).floor() as i32 + 1,
+        )
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            self.x0.min(other.x0),
+            self.y0.min(other.y0),
+            self.x1.max(other.x1),
+            self.y1.max(other.y1),
+        )
+    }
+
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect::new(
+            self.x0.max(other.x0),
+            self.y0.max(other.y0),
+            self.x1.min(other.x1),
+            self.y1.min(other.y1),
+        );
+        (rect.x0 < rect.x1 && rect.y0 < rect.y1).then_some(rect)
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+}
+
+// A clipped view into a band of frame rows, whose first pixel is at `origin`
+// in the sky. Writes outside `clip` are dropped.
+pub struct Target<'a> {
+    rows: &'a mut [Pixel],
+    width: u32,
+    left: i32,
+    top: i32,
+    clip: Rect,
+}
+
+impl<'a> Target<'a> {
+    pub fn new(rows: &'a mut [Pixel], width: u32, (left, top): (i32, i32), clip: Rect) -> Self {
+        Self {
+            rows,
+            width,
+            left,
+            top,
+            clip,
+        }
+    }
+
+    // The part of the sky it holds
+    pub fn clip(&self) -> Rect {
+        self.clip
+    }
+
+    pub fn overlaps(&self, rect: Rect) -> bool {
+        self.clip.intersect(&rect).is_some()
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        self.clip
+            .contains(x, y)
+            .then(|| (y - self.top) as usize * self.width as usize + (x - self.left) as usize)
+    }
+
+    // Rows of `rect` that survive clipping, as (y, first x, pixels). The clip
+    // is resolved once up front so callers can write whole spans without
+    // per-pixel bounds checks.
+    pub fn span_rows(&mut self, rect: Rect) -> impl Iterator<Item = (i32, i32, &mut [Pixel])> + '_ {
+        let width = self.width as usize;
+        let rect = rect.intersect(&self.clip).unwrap_or(Rect::new(
+            self.clip.x0,
+            self.clip.y0,
+            self.clip.x0,
+            self.clip.y0,
+        ));
+        let (x0, x1) = (
+            (rect.x0 - self.left) as usize,
+            (rect.x1 - self.left) as usize,
+        );
+
+        let (_, below) = self
+            .rows
+            .split_at_mut((rect.y0 - self.top) as usize * width);
+        below
+            .chunks_exact_mut(width)
+            .take((rect.y1 - rect.y0) as usize)
+            .zip(rect.y0..)
+            .map(move |(row, y)| (y, rect.x0, &mut row[x0..x1]))
+    }
+
+    // Every pixel that survives clipping
+    pub fn pixels(&mut self) -> impl Iterator<Item = &mut Pixel> + '_ {
+        let clip = self.clip;
+        self.span_rows(clip).flat_map(|(_, _, row)| row.iter_mut())
+    }
+
+    pub fn fill(&mut self, px: Pixel) {
+        for (_, _, row) in self.span_rows(self.clip) {
+            row.fill(px);
+        }
+    }
+
+    // Pull everything drawn so far `amount` of the way back to empty sky
+    pub fn fade(&mut self, amount: f32) {
+        for (_, _, row) in self.span_rows(self.clip) {
+            for pixel in row {
+                blend(pixel, BACKGROUND, amount);
+            }
+        }
+    }
+
+    // Blend `amount` of an earlier frame of `view` back over what's drawn
+    pub fn dissolve(&mut self, from: &[Pixel], view: Rect, amount: f32) {
+        let width = (view.x1 - view.x0) as usize;
+        for (y, left, row) in self.span_rows(view) {
+            let start = (y - view.y0) as usize * width + (left - view.x0) as usize;
+            for (pixel, &[r, g, b, _]) in row.iter_mut().zip(&from[start..]) {
+                blend(pixel, (r, g, b), amount);
+            }
+        }
+    }
+
+    // Draw with `emissive`, then take its light back out wherever `occlusion`
+    // says something in front hides the sky, so what's behind the ground
+    // neither shows nor glows through it. What was there before is held on
+    // the stack meanwhile, so the target can be no more than a tile.
+    pub fn occluded(&mut self, occlusion: &Occlusion, emissive: impl FnOnce(&mut Self)) {
+        let Some(band) = self.clip.intersect(&occlusion.rect) else {
+            emissive(self);
+            return;
+        };
+        let width = (band.x1 - band.x0) as usize;
+        let mut kept = [[0; 4]; (TILE_SIZE * TILE_SIZE) as usize];
+        for ((_, _, row), kept) in self.span_rows(band).zip(kept.chunks_exact_mut(width)) {
+            kept.copy_from_slice(row);
+        }
+        emissive(self);
+        for ((y, left, row), kept) in self.span_rows(band).zip(kept.chunks_exact(width)) {
+            let covers = occlusion.row(y, left);
+            for ((pixel, &[r, g, b, _]), &cover) in row.iter_mut().zip(kept).zip(covers) {
+                match cover {
+                    0 => {}
+                    255 => *pixel = pack((r, g, b)),
+                    cover => blend(pixel, (r, g, b), cover as f32 / 255.0),
+                }
+            }
+        }
+    }
+
+    pub fn blend(&mut self, x: i32, y: i32, color: (u8, u8, u8), alpha: f32) {
+        if let Some(idx) = self.index(x, y) {
+            blend(&mut self.rows[idx], color, alpha);
+        }
+    }
+
+    // A one-pixel line from `from` to `to`, anti-aliased by sharing each
+    // step's coverage between the two pixels nearest the line
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), color: (u8, u8, u8), alpha: f32) {
+        let reach = Rect::new(
+            from.0.min(to.0).floor() as i32 - 1,
+            from.1.min(to.1).floor() as i32 - 1,
+            from.0.max(to.0).ceil() as i32 + 1,
+            from.1.max(to.1).ceil() as i32 + 1,
+        );
+        if !self.overlaps(reach) {
+            return;
+        }
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steep = dy.abs() > dx.abs();
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+            let (along, across) = if steep { (y, x) } else { (x, y) };
+            // Measured from pixel centers
+            let across = across - 0.5;
+            let below = across.floor();
+            let share = across - below;
+            for (offset, coverage) in [(0, 1.0 - share), (1, share)] {
+                let (along, across) = (along as i32, below as i32 + offset);
+                let (x, y) = if steep {
+                    (across, along)
+                } else {
+                    (along, across)
+                };
+                self.blend(x, y, color, alpha * coverage);
+            }
+        }
+    }
+
+    // A round blob `size` pixels across, opaque as `alpha` at the center and
+    // falling off softly to nothing at the rim
+    pub fn soft_point(&mut self, x: f32, y: f32, color: (u8, u8, u8), alpha: f32, size: i32) {
+        let center_x = x as i32;
+        let center_y = y as i32;
+        let radius = size as f32 / 2.0;
+
+        let reach = Rect::new(
+            center_x - size / 2,
+            center_y - size / 2,
+            center_x + size / 2 + 1,
+            center_y + size / 2 + 1,
+        );
+
+        for (py, left, row) in self.span_rows(reach) {
+            let dy = py - center_y;
+            for (px, pixel) in (left..).zip(row) {
+                // Soft circular falloff
+                let dx = px - center_x;
+                let dist = ((dx * dx + dy * dy) as f32).sqrt();
+                let falloff = (1.0 - (dist / radius).clamp(0.0, 1.0)).powf(2.0);
+                let final_alpha = (alpha * falloff).clamp(0.0, 1.0);
+
+                blend(pixel, color, final_alpha);
+            }
+        }
+    }
+}
+
+// How much of the sky something in front of it hides at each pixel of
+// `rect`, row by row, from 0 for none of it to 255 for all of it
+pub struct Occlusion<'a> {
+    pub rect: Rect,
+    cover: &'a [u8],
+}
+
+impl<'a> Occlusion<'a> {
+    pub fn new(rect: Rect, cover: &'a [u8]) -> Self {
+        debug_assert_eq!(
+            cover.len(),
+            ((rect.x1 - rect.x0) * (rect.y1 - rect.y0)) as usize
+        );
+        Self { rect, cover }
+    }
+
+    // The cover from (`x`, `y`) to the right-hand edge
+    pub fn row(&self, y: i32, x: i32) -> &'a [u8] {
+        let width = self.rect.x1 - self.rect.x0;
+        &self.cover[((y - self.rect.y0) * width + x - self.rect.x0) as usize..]
+    }
+}
+
+// Light sampled every `cell` pixels and added to the frame, interpolated
+// smoothly in between. Suits broad, soft layers whose detail is far coarser
+// than a pixel: however large the output, only a few thousand points need
+// computing.
+pub struct GlowGrid {
+    cell: usize,
+    width: u32,
+    height: u32,
+    cols: usize,
+    // Light at each grid point, row-major, per channel out of 255
+    light: Vec<[f32; 3]>,
+}
+
+impl GlowGrid {
+    pub fn new(cell: usize) -> Self {
+        Self {
+            cell,
+            width: 0,
+            height: 0,
+            cols: 0,
+            light: Vec::new(),
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.cols = width as usize / self.cell + 2;
+        let rows = height as usize / self.cell + 2;
+        self.light.resize(self.cols * rows, [0.0; 3]);
+    }
+
+    // Every grid point's pixel position and light, to fill in
+    pub fn points_mut(&mut self) -> impl Iterator<Item = (f32, f32, &mut [f32; 3])> {
+        let (cell, cols) = (self.cell, self.cols);
+        self.light.iter_mut().enumerate().map(move |(i, light)| {
+            let (x, y) = (i % cols * cell, i / cols * cell);
+            (x as f32, y as f32, light)
+        })
+    }
+
+    pub fn draw(&self, target: &mut Target) {
+        let screen = Rect::new(0, 0, self.width as i32, self.height as i32);
+        let cell = self.cell as i32;
+
+        for (y, left, row) in target.span_rows(screen) {
+            let (grid_y, ty) = ((y / cell) as usize, (y % cell) as f32 / cell as f32);
+            let above = &self.light[grid_y * self.cols..][..self.cols];
+            let below = &self.light[(grid_y + 1) * self.cols..][..self.cols];
+
+            for (x, pixel) in (left..).zip(row) {
+                let (grid_x, tx) = ((x / cell) as usize, (x % cell) as f32 / cell as f32);
+                let corners = [
+                    above[grid_x],
+                    above[grid_x + 1],
+                    below[grid_x],
+                    below[grid_x + 1],
+                ];
+                if corners.iter().all(|c| c[0] + c[1] + c[2] < 0.5) {
+                    continue;
+                }
+                for (channel, value) in pixel.iter_mut().take(3).enumerate() {
+                    let [tl, tr, bl, br] = corners.map(|c| c[channel]);
+                    let top = tl + (tr - tl) * tx;
+                    let bottom = bl + (br - bl) * tx;
+                    let light = top + (bottom - top) * ty;
+                    *value = (*value as f32 + light).min(255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+fn tiles_across(len: u32) -> usize {
+    (len as usize).div_ceil(TILE_SIZE as usize)
+}
+
+// Row-major indices of the tiles of `view` that a (non-empty) rect inside it
+// touches
+fn covered_tiles(rect: Rect, view: Rect, cols: usize) -> impl Iterator<Item = usize> {
+    let (x0, x1) = (rect.x0 - view.x0, rect.x1 - view.x0);
+    let (y0, y1) = (rect.y0 - view.y0, rect.y1 - view.y0);
+    (y0 / TILE_SIZE..=(y1 - 1) / TILE_SIZE).flat_map(move |row| {
+        (x0 / TILE_SIZE..=(x1 - 1) / TILE_SIZE).map(move |col| row as usize * cols + col as usize)
+    })
+}
+
+// Grow with headroom so small swings in object count don't reallocate
+// every few frames once the scene has warmed up
+fn reserve_for<T>(vec: &mut Vec<T>, len: usize) {
+    if vec.capacity() < len {
+        vec.reserve((len + len / 2).max(64) - vec.len());
+    }
+}
+
+// Per-tile lists of object indices, rebuilt each frame from bounding boxes.
+// Stored flat: tile t's indices are entries[starts[t]..starts[t + 1]].
+pub struct TileBins {
+    starts: Vec<u32>,
+    entries: Vec<u32>,
+    // Scratch for the rebuild: clipped bounds and per-tile write cursors
+    rects: Vec<Option<Rect>>,
+    cursors: Vec<u32>,
+}
+
+impl TileBins {
+    pub fn new() -> Self {
+        Self {
+            starts: Vec::new(),
+            entries: Vec::new(),
+            rects: Vec::new(),
+            cursors: Vec::new(),
+        }
+    }
+
+    // Bin for drawing the tiles of a frame covering `view`
+    pub fn rebuild(&mut self, view: Rect, bounds: impl IntoIterator<Item = Rect>) {
+        let cols = tiles_across((view.x1 - view.x0) as u32);
+        let tiles = cols * tiles_across((view.y1 - view.y0) as u32);
+        let bounds = bounds.into_iter();
+        self.rects.clear();
+        reserve_for(&mut self.rects, bounds.size_hint().0);
+        self.rects.extend(bounds.map(|rect| rect.intersect(&view)));
+
+        // Count per tile, then prefix-sum into start offsets
+        self.starts.clear();
+        self.starts.resize(tiles + 1, 0);
+        for rect in self.rects.iter().flatten() {
+            for tile in covered_tiles(*rect, view, cols) {
+                self.starts[tile + 1] += 1;
+            }
+        }
+        for tile in 0..tiles {
+            self.starts[tile + 1] += self.starts[tile];
+        }
+
+        let total = self.starts[tiles] as usize;
+        self.entries.clear();
+        reserve_for(&mut self.entries, total);
+        self.entries.resize(total, 0);
+        self.cursors.clear();
+        self.cursors.extend_from_slice(&self.starts[..tiles]);
+        for (i, rect) in self.rects.iter().enumerate() {
+            for tile in rect
+                .iter()
+                .flat_map(|&rect| covered_tiles(rect, view, cols))
+            {
+                self.entries[self.cursors[tile] as usize] = i as u32;
+                self.cursors[tile] += 1;
+            }
+        }
+    }
+
+    pub fn get(&self, tile: usize) -> impl Iterator<Item = usize> + '_ {
+        let (start, end) = (self.starts[tile], self.starts[tile + 1]);
+        self.entries[start as usize..end as usize]
+            .iter()
+            .map(|&i| i as usize)
+    }
+}
+
+// Calls `draw` once per tile of a frame covering `view` with a target clipped
+// to it. Row bands go to whichever of the `workers` comes free next, so a
+// busy band doesn't hold up the rest; within a band, tiles go left to right.
+pub fn for_each_tile(
+    frame: &mut [u8],
+    view: Rect,
+    workers: &Workers,
+    draw: impl Fn(usize, &mut Target) + Sync,
+) {
+    let (width, height) = ((view.x1 - view.x0) as u32, (view.y1 - view.y0) as u32);
+    let band_len = width as usize * TILE_SIZE as usize;
+    let (pixels, _) = frame.as_chunks_mut::<4>();
+    let bands = Mutex::new(pixels.chunks_mut(band_len).enumerate());
+    workers.run(tiles_across(height), |_| {
+        let next = bands.lock().unwrap().next();
+        if let Some((row, band)) = next {
+            draw_band(row, band, view, &draw);
+        }
+    });
+}
+
+fn draw_band(row: usize, band: &mut [Pixel], view: Rect, draw: &impl Fn(usize, &mut Target)) {
+    let width = (view.x1 - view.x0) as u32;
+    let cols = tiles_across(width);
+    let top = view.y0 + row as i32 * TILE_SIZE;
+    let bottom = (top + TILE_SIZE).min(view.y1);
+    for col in 0..cols {
+        let left = view.x0 + col as i32 * TILE_SIZE;
+        let right = (left + TILE_SIZE).min(view.x1);
+        let clip = Rect::new(left, top, right, bottom);
+        let mut target = Target::new(band, width, (view.x0, top), clip);
+        draw(row * cols + col, &mut target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn gaussian_shares_add_up() {
+        let total: f32 = (-10..10).map(|x| gaussian_share(x as f32, 1.5)).sum();
+        assert!((total - 1.0).abs() < 1e-4, "{total}");
+        assert!((gaussian_share(-0.5, 1.0) - 0.3829).abs() < 1e-3);
+        assert_eq!(gaussian_share(-3.0, 1.0), gaussian_share(2.0, 1.0));
+    }
+
+    #[test]
+    fn span_rows_are_clipped_to_the_tile() {
+        let (width, height) = (100, 100);
+        let mut frame = vec![0u8; width * height * 4];
+
+        let spans = Mutex::new(Vec::new());
+        let view = Rect::new(0, 0, width as i32, height as i32);
+        for_each_tile(&mut frame, view, &Workers::new(1), |_, target| {
+            for (y, x, row) in target.span_rows(Rect::new(60, 60, 70, 62)) {
+                row.fill(pack((255, 255, 255)));
+                spans.lock().unwrap().push((y, x, row.len()));
+            }
+        });
+
+        // The rect straddles the tile boundary at 64, so each row is split in two
+        assert_eq!(
+            spans.into_inner().unwrap(),
+            [(60, 60, 4), (61, 60, 4), (60, 64, 6), (61, 64, 6)]
+        );
+        let lit = frame.chunks_exact(4).filter(|px| px[0] == 255).count();
+        assert_eq!(lit, 20);
+    }
+
+    #[test]
+    fn views_draw_their_part_of_the_sky() {
+        let view = Rect::new(100, 50, 200, 150);
+        let mut frame = vec![0u8; 100 * 100 * 4];
+        let mut bins = TileBins::new();
+        let star = Rect::new(170, 60, 172, 61);
+        bins.rebuild(view, [Rect::new(10, 10, 12, 12), star]);
+
+        for_each_tile(&mut frame, view, &Workers::new(2), |tile, target| {
+            for i in bins.get(tile) {
+                assert_eq!(i, 1, "binned from outside the view");
+                for (_, _, row) in target.span_rows(star) {
+                    row.fill(pack((255, 255, 255)));
+                }
+            }
+        });
+        let lit: Vec<_> = (0..100 * 100).filter(|i| frame[i * 4] == 255).collect();
+        assert_eq!(lit, [10 * 100 + 70, 10 * 100 + 71]);
+    }
+
+    #[test]
+    fn haze_desaturates_toward_the_background() {
+        assert_eq!(haze((180, 200, 255), 0.0), (180, 200, 255));
+
+        let (r, g, b) = haze((180, 200, 255), 0.6);
+        assert!(b - r < 255 - 180, "still as saturated: {r} {g} {b}");
+        assert!(b < 255 && r < 200, "not faded: {r} {g} {b}");
+    }
+
+    #[test]
+    fn occluded_light_is_taken_back_out() {
+        let (width, height) = (8, 4);
+        let mut frame = vec![0u8; width * height * 4];
+        let view = Rect::new(0, 0, width as i32, height as i32);
+        // The bottom two rows: hidden, half hidden, then open sky across each
+        let cover = [255, 255, 128, 128, 0, 0, 0, 0].repeat(2);
+        let occlusion = Occlusion::new(Rect::new(0, 2, width as i32, height as i32), &cover);
+        for_each_tile(&mut frame, view, &Workers::new(1), |_, target| {
+            target.fill(pack((10, 10, 10)));
+            target.occluded(&occlusion, |target| target.fill(pack((250, 250, 250))));
+        });
+        let (pixels, _) = frame.as_chunks::<4>();
+        let row = |y: usize| {
+            pixels[y * width..][..width]
+                .iter()
+                .map(|p| p[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row(0), [250; 8]);
+        for y in 2..4 {
+            let row = row(y);
+            assert_eq!(row[..2], [10, 10]);
+            assert!(
+                row[2..4].iter().all(|&r| (125..=135).contains(&r)),
+                "{row:?}"
+            );
+            assert_eq!(row[4..], [250; 4]);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+// Tonight's sky, in a few lines for a daily notification: the moon's phase,
+// any meteor shower at its peak, and which of the bright planets are far
+// enough from the sun to see after dusk or before dawn.
+
+use crate::i18n::Locale;
+use crate::{moon, orrery};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The major annual showers: name, peak month and day, and meteors an hour
+// at best under a dark sky
+const SHOWERS: [(&str, u32, u32, u32); 10] = [
+    ("Quadrantids", 1, 3, 110),
+    ("Lyrids", 4, 22, 18),
+    ("Eta Aquariids", 5, 6, 50),
+    ("Southern Delta Aquariids", 7, 30, 25),
+    ("Perseids", 8, 12, 100),
+    ("Draconids", 10, 8, 10),
+    ("Orionids", 10, 21, 20),
+    ("Leonids", 11, 17, 15),
+    ("Geminids", 12, 14, 150),
+    ("Ursids", 12, 22, 10),
+];
+// Peaks drift by a day or so from year to year
+const PEAK_WINDOW: i64 = 1;
+// The naked-eye planets by their place in the orrery, and Earth's
+const PLANETS: [(usize, &str); 5] = [
+    (0, "planet.mercury"),
+    (1, "planet.venus"),
+    (3, "planet.mars"),
+    (4, "planet.jupiter"),
+    (5, "planet.saturn"),
+];
+const EARTH: usize = 2;
+// Planets nearer the sun than this in the sky are lost in its glare, and
+// those further than ALL_NIGHT are up from dusk to dawn
+const MIN_ELONGATION: f32 = 20.0;
+const ALL_NIGHT: f32 = 150.0;
+
+// The name of a moon phase, 0..1 from new moon
+pub fn phase_name(phase: f32, locale: Locale) -> &'static str {
+    locale.tr(&format!("moon.{}", (phase * 8.0).round() as usize % 8))
+}
+
+// The phase's name and how much of the moon is lit, e.g. "Full moon (100% lit)"
+pub fn phase_line(phase: f32, locale: Locale) -> String {
+    let lit = (1.0 - (phase * std::f32::consts::TAU).cos()) / 2.0 * 100.0;
+    locale.format(
+        "moon.lit",
+        &[
+            ("phase", phase_name(phase, locale)),
+            ("lit", &format!("{lit:.0}")),
+        ],
+    )
+}
+
+// What's on in the sky tonight, one item per line, where local time is
+// `utc_offset` seconds ahead of UTC
+pub fn tonight(now: SystemTime, utc_offset: i64, locale: Locale) -> Vec<String> {
+    let mut lines = vec![phase_line(moon::phase_at(now), locale)];
+
+    let unix = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64);
+    let today = (unix + utc_offset).div_euclid(86_400);
+    let (year, _, _) = civil_from_days(today);
+    for (name, month, day, rate) in SHOWERS {
+        if (days_from_civil(year, month, day) - today).abs() <= PEAK_WINDOW {
+            lines.push(locale.format(
+                "report.shower",
+                &[("name", name), ("rate", &rate.to_string())],
+            ));
+        }
+    }
+
+    let mut evening = Vec::new();
+    let mut morning = Vec::new();
+    let mut all_night = Vec::new();
+    for (i, key) in PLANETS {
+        let name = locale.tr(key);
+        let elongation = elongation(i, now);
+        if elongation.abs() >= ALL_NIGHT {
+            all_night.push(name);
+        } else if elongation >= MIN_ELONGATION {
+            // East of the sun, so still up once it has set
+            evening.push(name);
+        } else if elongation <= -MIN_ELONGATION {
+            morning.push(name);
+        }
+    }
+    for (planets, when) in [
+        (all_night, "report.all_night"),
+        (evening, "report.evening"),
+        (morning, "report.morning"),
+    ] {
+        if !planets.is_empty() {
+            lines.push(locale.format(when, &[("planets", &planets.join(", "))]));
+        }
+    }
+    lines
+}
+
+// How far planet `i` is from the sun as seen from Earth, in degrees, east
+// of it positive
+fn elongation(i: usize, now: SystemTime) -> f32 {
+    let (px, py) = orrery::heliocentric(i, now);
+    let (ex, ey) = orrery::heliocentric(EARTH, now);
+    let planet = (py - ey).atan2(px - ex);
+    let sun = (-ey).atan2(-ex);
+    let east = (planet - sun).to_degrees().rem_euclid(360.0);
+    if east > 180.0 { east - 360.0 } else { east }
+}
+
+// Days since 1970-01-01 of a date, and back (Howard Hinnant's algorithms)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted + 2) / 5 + 1) as u32;
+    let month = (shifted + if shifted < 10 { 3 } else { -9 }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(unix: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(unix)
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in [-1, 0, 11_016, 20_000, 30_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn reports_showers_and_planets() {
+        // Jupiter at opposition, Mars behind the sun
+        let lines = tonight(at(1_768_082_400), 0, Locale::En);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("Jupiter all night")),
+            "{lines:?}"
+        );
+        assert!(lines.iter().all(|line| !line.contains("Mars")), "{lines:?}");
+        assert!(lines.iter().all(|line| !line.contains("peak")), "{lines:?}");
+
+        // The Perseids, and Venus a morning star near greatest elongation
+        let lines = tonight(at(1_786_572_000), 0, Locale::En);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("The Perseids peak")),
+            "{lines:?}"
+        );
+        let lines = tonight(at(1_748_815_200), 0, Locale::En);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.contains("Venus") && line.ends_with("before dawn")),
+            "{lines:?}"
+        );
+
+        assert_eq!(phase_name(0.98, Locale::En), "New moon");
+        assert_eq!(phase_name(0.26, Locale::En), "First quarter");
+        assert_eq!(phase_line(0.5, Locale::En), "Full moon (100% lit)");
+        assert_eq!(phase_line(0.5, Locale::De), "Vollmond (100 % beleuchtet)");
+    }
+}
@@ -0,0 +1,129 @@
+// Simulation RNG: xoshiro256++, which is several times cheaper per draw than
+// thread_rng's ChaCha and has no thread-local lookup. Not for anything that
+// needs to be unpredictable, but stars don't care. The output sequence is
+// fixed for a given seed, so runs can be reproduced.
+
+use rand::{RngCore, SeedableRng};
+
+#[derive(Clone, Debug)]
+pub struct FastRng {
+    s: [u64; 4],
+}
+
+impl RngCore for FastRng {
+    fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.s;
+        let result = s0.wrapping_add(*s3).rotate_left(23).wrapping_add(*s0);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        // The high bits are the strongest
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for FastRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut s = [0; 4];
+        for (word, bytes) in s.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        // An all-zero state would only ever produce zeros
+        if s == [0; 4] {
+            return Self::seed_from_u64(0);
+        }
+        Self { s }
+    }
+
+    // Expand with SplitMix64, as the xoshiro authors recommend
+    fn seed_from_u64(mut state: u64) -> Self {
+        let mut s = [0; 4];
+        for word in &mut s {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *word = z ^ (z >> 31);
+        }
+        Self { s }
+    }
+}
+
+// SplitMix64 over a pair of grid coordinates, for procedural detail that has
+// to look the same every time a cell is revisited
+pub fn hash_cell(x: i64, y: i64) -> u64 {
+    let mut z = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).rotate_left(32);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Lattice noise in 0..1, smoothly interpolated between hashed corners
+pub fn value_noise(x: f32, y: f32, layer: i64) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let (sx, sy) = (tx * tx * (3.0 - 2.0 * tx), ty * ty * (3.0 - 2.0 * ty));
+    let (ix, iy) = (x0 as i64, y0 as i64);
+
+    let corner = |dx: i64, dy: i64| {
+        let hash = hash_cell(ix.wrapping_add(dx).wrapping_add(layer), iy.wrapping_add(dy));
+        (hash >> 40) as f32 / (1u64 << 24) as f32
+    };
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * sx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * sx;
+    top + (bottom - top) * sy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_reference_sequence() {
+        let mut rng = FastRng { s: [1, 2, 3, 4] };
+        let first: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            first,
+            [41943041, 58720359, 3588806011781223, 3591011842654386]
+        );
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = FastRng::seed_from_u64(99);
+        let mut b = FastRng::seed_from_u64(99);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+    }
+
+    #[test]
+    fn noise_is_continuous_and_in_range() {
+        let mut last = value_noise(0.0, 0.5, 3);
+        for i in 1..1000 {
+            let now = value_noise(i as f32 * 0.01, 0.5, 3);
+            assert!((0.0..1.0).contains(&now));
+            assert!((now - last).abs() < 0.05, "jump at {i}: {last} -> {now}");
+            last = now;
+        }
+    }
+}
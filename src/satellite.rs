@@ -0,0 +1,184 @@
+// A satellite catching the sunlight: a small, steady point crossing the sky
+// in a straight line at constant speed, far slower than a shooting star. Some
+// are tumbling and glint now and then as a panel catches the sun.
+
+use crate::render::{Rect, Target};
+use crate::{CelestialObject, ScreenDetails, UpdateContext};
+use rand::Rng;
+
+// Seconds to cross the screen's diagonal, slowest and fastest
+const CROSSING_TIME: (f32, f32) = (40.0, 90.0);
+// Pixels beyond the edge to start at least, so it slides in rather than popping
+const MARGIN: f32 = 4.0;
+const COLOR: (u8, u8, u8) = (235, 238, 245);
+// Share of satellites that tumble and glint
+const GLINTING_SHARE: f64 = 0.3;
+// Seconds between glints, shortest and longest, and how long each lasts
+const GLINT_GAP: (f32, f32) = (3.0, 8.0);
+const GLINT_TIME: f32 = 0.4;
+// Steady brightness as a share of the configured brightness, which a glint
+// rises to in full
+const STEADY: f32 = 0.6;
+
+pub struct Satellite {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    age: f32,
+    // Seconds to get from its start to the far side
+    crossing: f32,
+    // Configured brightness, 0..1
+    brightness: f32,
+    // Seconds between glints, for tumbling ones
+    glint_gap: Option<f32>,
+    // Where in its first glint cycle it started, so tumblers don't all glint
+    // in step
+    glint_offset: f32,
+}
+
+impl Satellite {
+    // Start off-screen, headed across it on a straight line somewhere through
+    // its middle
+    pub fn spawn(rng: &mut impl Rng, screen_details: &ScreenDetails, brightness: f32) -> Self {
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        let heading = rng.gen_range(0.0..std::f32::consts::TAU);
+        let (sin, cos) = heading.sin_cos();
+        let miss = width.min(height) / 3.0;
+        let offset = rng.gen_range(-1.0..1.0) * miss;
+        let (cx, cy) = (width / 2.0 - sin * offset, height / 2.0 + cos * offset);
+        // Far enough out that both ends are off-screen, however far it misses
+        // the center by
+        let reach = width.hypot(height) / 2.0 + miss + MARGIN;
+        let crossing = rng.gen_range(CROSSING_TIME.0..CROSSING_TIME.1);
+        let speed = 2.0 * reach / crossing;
+        let glint_gap = rng
+            .gen_bool(GLINTING_SHARE)
+            .then(|| rng.gen_range(GLINT_GAP.0..GLINT_GAP.1));
+        Self {
+            x: cx - cos * reach,
+            y: cy - sin * reach,
+            vx: cos * speed,
+            vy: sin * speed,
+            age: 0.0,
+            crossing,
+            brightness,
+            glint_gap,
+            glint_offset: rng.r#gen(),
+        }
+    }
+
+    // How bright it is right now, 0..1
+    pub fn shine(&self) -> f32 {
+        let glint = self.glint_gap.map_or(0.0, |gap| {
+            let since = (self.age / gap + self.glint_offset).fract() * gap;
+            // A quick rise and fall
+            let t = since / GLINT_TIME;
+            if t < 1.0 {
+                (t * std::f32::consts::PI).sin()
+            } else {
+                0.0
+            }
+        });
+        self.brightness * (STEADY + (1.0 - STEADY) * glint)
+    }
+}
+
+impl CelestialObject for Satellite {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        self.x += self.vx * ctx.dt;
+        self.y += self.vy * ctx.dt;
+        self.age += ctx.dt;
+    }
+
+    fn draw(&self, target: &mut Target) {
+        let shine = self.shine();
+        let (x, y) = (self.x as i32, self.y as i32);
+        target.blend(x, y, COLOR, shine);
+        // Glints bloom into the neighboring pixels
+        let bloom = (shine - self.brightness * STEADY) * 0.6;
+        if bloom > 0.01 {
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                target.blend(x + dx, y + dy, COLOR, bloom);
+            }
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::around(self.x, self.y, 2.0)
+    }
+
+    fn is_alive(&self, screen_details: &ScreenDetails) -> bool {
+        // Starts off-screen, so only done once it has crossed and left again
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        self.age < self.crossing
+            || (self.x > -MARGIN
+                && self.x < width + MARGIN
+                && self.y > -MARGIN
+                && self.y < height + MARGIN)
+    }
+
+    fn motion(&self, _: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        ((self.x, self.y), (self.vx, self.vy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, MockScreen};
+
+    const SMALL: ScreenDetails = ScreenDetails {
+        width: 200,
+        height: 200,
+    };
+
+    #[test]
+    fn satellites_cross_slowly_in_a_straight_line() {
+        let mut screen = MockScreen::landscape().with_seed(9);
+        let details = screen.details;
+        for _ in 0..20 {
+            let mut satellite = Satellite::spawn(&mut screen.rng, &details, 1.0);
+            let (x0, y0) = (satellite.x, satellite.y);
+            let lived = screen.run(&mut satellite, FixedClock::fps(30.0), 120.0);
+            assert!(
+                (CROSSING_TIME.0..CROSSING_TIME.1 + 1.0).contains(&lived),
+                "{lived}"
+            );
+            // Straight along its heading, at the speed it started with
+            let speed = satellite.vx.hypot(satellite.vy);
+            let travelled = (satellite.x - x0).hypot(satellite.y - y0);
+            assert!((travelled - speed * lived).abs() < 1.0);
+            assert!(speed < 100.0, "{speed} px/s is shooting-star fast");
+        }
+    }
+
+    #[test]
+    fn only_tumbling_satellites_glint() {
+        let mut screen = MockScreen::new(SMALL);
+        let details = screen.details;
+        let mut satellite = Satellite::spawn(&mut screen.rng, &details, 1.0);
+        (satellite.x, satellite.y) = (100.0, 100.0);
+        (satellite.vx, satellite.vy) = (0.0, 0.0);
+
+        let brightest = |satellite: &mut Satellite, screen: &mut MockScreen| {
+            let mut brightest = 0;
+            for dt in FixedClock::fps(30.0).ticks(GLINT_GAP.1 + 1.0) {
+                satellite.update(&mut screen.ctx(dt));
+                let mut frame = Frame::new(&details);
+                frame.draw(satellite);
+                brightest = brightest.max(frame.brightness(100, 100));
+                frame.assert_dark(105, 100);
+            }
+            brightest
+        };
+        satellite.glint_gap = None;
+        let steady = brightest(&mut satellite, &mut screen);
+        satellite.glint_gap = Some(GLINT_GAP.0);
+        let glinting = brightest(&mut satellite, &mut screen);
+        assert!(
+            steady > 100 && glinting > steady + 40,
+            "{steady} {glinting}"
+        );
+    }
+}
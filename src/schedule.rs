@@ -0,0 +1,149 @@
+// Scene schedules: which scene to show when, from a `scene_schedule` string.
+// Either a timetable of local times of day, each starting a scene that runs
+// until the next one ("07:00 starfield, 19:30 tunnel, 23:00 orrery"), or a
+// change every so often to another scene picked at random ("every 2h",
+// "every 45m"). The random picks come from the time alone, so every output
+// (and every copy of a shared sky) on the same schedule picks the same one.
+
+use crate::Scene;
+use crate::rng::FastRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schedule {
+    // Scenes by the second of the local day they start at, in order
+    Timetable(Vec<(u64, Scene)>),
+    // A new scene every this many seconds, counted from local midnight
+    Every(u64),
+}
+
+impl Schedule {
+    // The scene due at `local`, in seconds since the Unix epoch shifted to
+    // local time
+    pub fn scene_at(&self, local: u64) -> Scene {
+        let time_of_day = local % SECONDS_PER_DAY;
+        match self {
+            // The last one to have started today, or else yesterday's last
+            Schedule::Timetable(entries) => entries
+                .iter()
+                .rev()
+                .find(|&&(start, _)| start <= time_of_day)
+                .or(entries.last())
+                .map_or(Scene::Starfield, |&(_, scene)| scene),
+            // A random walk through the scenes from midnight, never staying
+            // on one for two turns running
+            &Schedule::Every(period) => {
+                let day = local / SECONDS_PER_DAY;
+                let mut rng = FastRng::seed_from_u64(day);
+                let count = Scene::ALL.len();
+                let mut scene = rng.gen_range(0..count);
+                for _ in 0..time_of_day / period {
+                    scene = (scene + rng.gen_range(1..count)) % count;
+                }
+                Scene::ALL[scene]
+            }
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let Some(period) = text.strip_prefix("every ") else {
+            let mut entries = text
+                .split(',')
+                .map(timetable_entry)
+                .collect::<Result<Vec<_>, String>>()?;
+            entries.sort_by_key(|&(start, _)| start);
+            return Ok(Schedule::Timetable(entries));
+        };
+
+        let period = period.trim();
+        let bad = || format!("`{period}` should be like 2h or 45m");
+        let (number, unit) = period.split_at(period.len().saturating_sub(1));
+        let unit = match unit {
+            "h" => 3600,
+            "m" => 60,
+            _ => return Err(bad()),
+        };
+        let seconds = unit * number.parse::<u64>().map_err(|_| bad())?;
+        if !(60..=SECONDS_PER_DAY).contains(&seconds) {
+            return Err("a scene should last between a minute and a day".into());
+        }
+        Ok(Schedule::Every(seconds))
+    }
+}
+
+// One "07:00 starfield" of a timetable, as its start in seconds into the day
+fn timetable_entry(entry: &str) -> Result<(u64, Scene), String> {
+    let entry = entry.trim();
+    let (time, scene) = entry
+        .split_once(' ')
+        .ok_or_else(|| format!("`{entry}` should be like 07:00 starfield"))?;
+    let scene = scene.trim();
+    let scene = scene
+        .parse()
+        .map_err(|()| format!("unknown scene `{scene}`"))?;
+    Ok((time_of_day(time)?, scene))
+}
+
+// "19:30" as seconds into the day
+pub fn time_of_day(time: &str) -> Result<u64, String> {
+    let (hours, minutes) = time
+        .trim()
+        .split_once(':')
+        .and_then(|(h, m)| Some((h.parse::<u64>().ok()?, m.parse::<u64>().ok()?)))
+        .filter(|&(h, m)| h < 24 && m < 60)
+        .ok_or_else(|| format!("`{time}` isn't a time of day"))?;
+    Ok(hours * 3600 + minutes * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+    // Some day's local midnight
+    const MIDNIGHT: u64 = 19_000 * SECONDS_PER_DAY;
+
+    #[test]
+    fn timetables_run_each_scene_until_the_next() {
+        let schedule: Schedule = "19:30 tunnel, 07:00 starfield,23:00 orrery"
+            .parse()
+            .unwrap();
+        let at = |hours: f32| schedule.scene_at(MIDNIGHT + (hours * HOUR as f32) as u64);
+        assert_eq!(at(3.0), Scene::Orrery);
+        assert_eq!(at(7.0), Scene::Starfield);
+        assert_eq!(at(19.0), Scene::Starfield);
+        assert_eq!(at(20.0), Scene::Tunnel);
+        assert_eq!(at(23.5), Scene::Orrery);
+
+        assert!("7pm tunnel".parse::<Schedule>().is_err());
+        assert!("07:00 aurora".parse::<Schedule>().is_err());
+        assert!("25:00 tunnel".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn rotations_change_scene_every_period() {
+        let schedule: Schedule = "every 2h".parse().unwrap();
+        assert_eq!(schedule, Schedule::Every(2 * HOUR));
+        let scenes: Vec<Scene> = (0..12)
+            .map(|turn| schedule.scene_at(MIDNIGHT + turn * 2 * HOUR + HOUR))
+            .collect();
+        assert!(scenes.windows(2).all(|pair| pair[0] != pair[1]));
+        // Steady within a turn
+        assert_eq!(
+            schedule.scene_at(MIDNIGHT + 10),
+            schedule.scene_at(MIDNIGHT + 2 * HOUR - 10)
+        );
+
+        assert!("every 10s".parse::<Schedule>().is_err());
+        assert!("every 0h".parse::<Schedule>().is_err());
+        assert!("every few hours".parse::<Schedule>().is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// Daily sky report: once a day at `sky_report_time`, local time, a desktop
+// notification through notify-send about tonight's sky. It goes by the
+// system clock, not the sky's, so a timelapse doesn't send one a minute.
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use wl_starfield::config::Config;
+use wl_starfield::i18n::Locale;
+use wl_starfield::report;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub struct SkyReport {
+    // Seconds into the local day to send it, and local time's lead on UTC
+    at: Option<i64>,
+    utc_offset: i64,
+    // The local day it was last sent on, counted from 1970
+    sent_on: Option<i64>,
+}
+
+impl SkyReport {
+    pub fn new(config: &Config) -> Self {
+        let at = config.sky_report_time.map(|at| at as i64);
+        let utc_offset = if at.is_some() { crate::utc_offset() } else { 0 };
+        let mut report = Self {
+            at,
+            utc_offset,
+            sent_on: None,
+        };
+        // Starting after today's time has gone by waits for tomorrow's
+        if let Some(at) = at {
+            let (day, time) = report.local(SystemTime::now());
+            if time >= at {
+                report.sent_on = Some(day);
+            }
+        }
+        report
+    }
+
+    // Send today's report if it's time
+    pub fn check(&mut self) {
+        let Some(at) = self.at else {
+            return;
+        };
+        let now = SystemTime::now();
+        let (day, time) = self.local(now);
+        if time < at || self.sent_on == Some(day) {
+            return;
+        }
+        self.sent_on = Some(day);
+        let locale = Locale::detect();
+        send(
+            locale.tr("report.title"),
+            &report::tonight(now, self.utc_offset, locale).join("\n"),
+        );
+    }
+
+    // The local day, and seconds into it
+    fn local(&self, now: SystemTime) -> (i64, i64) {
+        let unix = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() as i64);
+        let local = unix + self.utc_offset;
+        (
+            local.div_euclid(SECONDS_PER_DAY),
+            local.rem_euclid(SECONDS_PER_DAY),
+        )
+    }
+}
+
+fn send(title: &str, body: &str) {
+    let child = Command::new("notify-send")
+        .arg("--app-name=wl-starfield")
+        .arg(title)
+        .arg(body)
+        .stdin(Stdio::null())
+        .spawn();
+    match child {
+        // Reap it off the render thread whenever it finishes
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => eprintln!("wl-starfield: can't send the sky report: {err}"),
+    }
+}
@@ -0,0 +1,455 @@
+// The skyline: a dark silhouette of the ground along the bottom of the
+// screen, in front of the whole sky, so stars and shooting stars go down
+// behind it. It's mountains, a treeline or a city, shaped afresh for each
+// size of screen from a seed the sky hands it, or cut from a mask image:
+// white where the ground is, black or transparent where the sky shows
+// through, scaled to the width of the screen and stood on its bottom edge. A
+// city's windows light up and go dark one at a time through the night, and
+// flicker a little while they're lit.
+
+use crate::backdrop::Image;
+use crate::render::{Occlusion, Rect, Target, blend, luma, pack};
+use crate::rng::FastRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+
+// Near black, with a touch of the night sky's blue
+const SILHOUETTE_COLOR: (u8, u8, u8) = (4, 5, 10);
+// Mountains: bumps at ever finer spacings, as shares of the screen's width,
+// each as tall as this share of the skyline
+const RIDGES: [(f32, f32); 4] = [(0.3, 0.55), (0.09, 0.25), (0.025, 0.12), (0.006, 0.04)];
+// Trees: rolling ground this share of the skyline high, with a tree every
+// TREE_SPACING pixels or so, each between these shares tall and as wide at
+// the foot as TREE_WIDTH of its height
+const GROUND_SPACING: f32 = 0.2;
+const GROUND_HEIGHT: f32 = 0.3;
+const TREE_SPACING: f32 = 5.0;
+const TREE_HEIGHT: (f32, f32) = (0.25, 0.7);
+const TREE_WIDTH: f32 = 0.35;
+// City: buildings this many pixels wide and between these shares of the
+// skyline tall, some with a mast on top
+const BUILDING_WIDTH: (f32, f32) = (24.0, 90.0);
+const BUILDING_HEIGHT: (f32, f32) = (0.25, 1.0);
+const MAST_CHANCE: f64 = 0.15;
+const MAST_HEIGHT: f32 = 0.2;
+// Windows: pixels across and down, the grid they're set on, how many of the
+// places on it have one and how many of those are lit at a time
+const WINDOW_SIZE: (i32, i32) = (2, 3);
+const WINDOW_PITCH: (f32, f32) = (6.0, 8.0);
+const WINDOW_CHANCE: f64 = 0.6;
+const WINDOW_LIT: f32 = 0.4;
+// Times a second any one window changes, and how brightly and quickly lit
+// ones flicker
+const WINDOW_CHANGE_RATE: f32 = 0.02;
+const WINDOW_COLOR: (u8, u8, u8) = (255, 205, 130);
+const WINDOW_BRIGHTNESS: f32 = 0.8;
+const WINDOW_FLICKER: f32 = 0.15;
+const WINDOW_FLICKER_SPEED: f32 = 3.0;
+
+// The shapes drawn for the skyline when there's no mask
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkylineShape {
+    Mountains,
+    Trees,
+    City,
+}
+
+impl FromStr for SkylineShape {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        match name {
+            "mountains" => Ok(SkylineShape::Mountains),
+            "trees" => Ok(SkylineShape::Trees),
+            "city" => Ok(SkylineShape::City),
+            _ => Err(()),
+        }
+    }
+}
+
+pub(crate) enum Outline {
+    Shape(SkylineShape),
+    Mask(Image),
+}
+
+struct Window {
+    x: i32,
+    y: i32,
+    lit: bool,
+    // Where it is in its flicker
+    phase: f32,
+}
+
+pub(crate) struct Skyline {
+    outline: Outline,
+    // Share of the view's height a shape stands, and whether a city is lit
+    height: f32,
+    lights: bool,
+    seed: u64,
+    // The view it's laid out for
+    size: (usize, usize),
+    // The first row of the view the ground reaches, and how much of each
+    // pixel from there down it covers, out of 255
+    top: usize,
+    cover: Vec<u8>,
+    windows: Vec<Window>,
+    // Its own, so the windows don't draw on the sky's randomness
+    rng: FastRng,
+}
+
+impl Skyline {
+    // `height` is the share of the screen shapes stand; `lights` lights the
+    // windows of a city
+    pub fn new(outline: Outline, height: f32, lights: bool, seed: u64) -> Self {
+        Self {
+            outline,
+            height,
+            lights,
+            seed,
+            size: (0, 0),
+            top: 0,
+            cover: Vec::new(),
+            windows: Vec::new(),
+            rng: FastRng::seed_from_u64(seed),
+        }
+    }
+
+    // Lay out for a view this size, if it's changed
+    pub fn prepare(&mut self, width: usize, height: usize) {
+        if (width, height) == self.size {
+            return;
+        }
+        self.size = (width, height);
+        // The same shape each time for the same size
+        let mut rng = FastRng::seed_from_u64(self.seed);
+        let tall = self.height * height as f32;
+        self.windows.clear();
+        let heights = match &self.outline {
+            Outline::Shape(SkylineShape::Mountains) => mountains(&mut rng, width, tall),
+            Outline::Shape(SkylineShape::Trees) => trees(&mut rng, width, tall),
+            Outline::Shape(SkylineShape::City) => {
+                let (heights, windows) = city(&mut rng, width, tall, self.lights);
+                self.windows = windows
+                    .into_iter()
+                    .map(|(x, from_bottom)| Window {
+                        x,
+                        y: height as i32 - from_bottom,
+                        lit: rng.gen_range(0.0..1.0) < WINDOW_LIT,
+                        phase: rng.gen_range(0.0..std::f32::consts::TAU),
+                    })
+                    .collect();
+                heights
+            }
+            Outline::Mask(image) => {
+                let (image_width, image_height) = image.size();
+                let scale = image_width as f32 / width as f32;
+                let rows = ((image_height as f32 / scale).round() as usize).min(height);
+                self.top = height - rows;
+                self.cover.clear();
+                self.cover.reserve(width * rows);
+                // Bottom edges together, cropped at the top if it's taller
+                let offset = image_height as f32 - rows as f32 * scale;
+                for y in 0..rows {
+                    let image_y = offset + (y as f32 + 0.5) * scale;
+                    self.cover.extend((0..width).map(|x| {
+                        let (r, g, b) = image.sample((x as f32 + 0.5) * scale, image_y);
+                        let color = (r.round() as u8, g.round() as u8, b.round() as u8);
+                        luma(pack(color)).round() as u8
+                    }));
+                }
+                return;
+            }
+        };
+        self.outline_columns(&heights);
+    }
+
+    // Cover each column from as many pixels `heights` gives it up from the
+    // bottom, in part where that falls partway through a pixel
+    fn outline_columns(&mut self, heights: &[f32]) {
+        let (width, height) = self.size;
+        let bottom = height as f32;
+        let highest = heights.iter().copied().fold(0.0, f32::max);
+        self.top = ((bottom - highest).floor().max(0.0) as usize).min(height);
+        self.cover.clear();
+        self.cover.resize(width * (height - self.top), 0);
+        for (x, &tall) in heights.iter().enumerate() {
+            let top = bottom - tall;
+            for (y, cover) in (self.top..).zip(self.cover[x..].iter_mut().step_by(width)) {
+                let covered = (y as f32 + 1.0 - top).clamp(0.0, 1.0);
+                *cover = (covered * 255.0).round() as u8;
+            }
+        }
+    }
+
+    // Switch windows on and off, and on through their flicker
+    pub fn update(&mut self, dt: f32) {
+        for window in &mut self.windows {
+            let rate = if window.lit {
+                1.0 - WINDOW_LIT
+            } else {
+                WINDOW_LIT
+            };
+            if self.rng.gen_range(0.0..1.0) < dt * WINDOW_CHANGE_RATE * rate {
+                window.lit = !window.lit;
+            }
+            window.phase = (window.phase + dt * WINDOW_FLICKER_SPEED) % std::f32::consts::TAU;
+        }
+    }
+
+    // How much of the sky it hides, over a frame covering `view`, once it's
+    // laid out for one that size
+    pub fn occlusion(&self, view: Rect) -> Option<Occlusion<'_>> {
+        let (width, height) = self.size;
+        let band = Rect::new(view.x0, view.y0 + self.top as i32, view.x1, view.y1);
+        (self.cover.len() == width * (height - self.top)
+            && (view.x1 - view.x0, view.y1 - view.y0) == (width as i32, height as i32))
+            .then(|| Occlusion::new(band, &self.cover))
+    }
+
+    // Stand the ground in the part of a frame covering `view` that the
+    // target holds
+    pub fn draw(&self, target: &mut Target, view: Rect) {
+        let Some(occlusion) = self.occlusion(view) else {
+            return;
+        };
+        if !target.overlaps(occlusion.rect) {
+            return;
+        }
+        let solid = pack(SILHOUETTE_COLOR);
+        for (y, left, row) in target.span_rows(occlusion.rect) {
+            for (pixel, &cover) in row.iter_mut().zip(occlusion.row(y, left)) {
+                match cover {
+                    0 => {}
+                    255 => *pixel = solid,
+                    cover => blend(pixel, SILHOUETTE_COLOR, cover as f32 / 255.0),
+                }
+            }
+        }
+        for window in self.windows.iter().filter(|window| window.lit) {
+            let (x, y) = (view.x0 + window.x, view.y0 + window.y);
+            let rect = Rect::new(x, y, x + WINDOW_SIZE.0, y + WINDOW_SIZE.1);
+            if !target.overlaps(rect) {
+                continue;
+            }
+            let alpha =
+                WINDOW_BRIGHTNESS * (1.0 - WINDOW_FLICKER * (0.5 + 0.5 * window.phase.sin()));
+            for (_, _, row) in target.span_rows(rect) {
+                for pixel in row {
+                    blend(pixel, WINDOW_COLOR, alpha);
+                }
+            }
+        }
+    }
+}
+
+// Smooth bumps `spacing` pixels apart, up to `tall` high, across `width`
+fn bumps(rng: &mut FastRng, width: usize, spacing: f32, tall: f32) -> impl Iterator<Item = f32> {
+    let spacing = spacing.max(1.0);
+    let peaks: Vec<f32> = (0..=(width as f32 / spacing) as usize + 1)
+        .map(|_| rng.gen_range(0.0..tall))
+        .collect();
+    (0..width).map(move |x| {
+        let at = x as f32 / spacing;
+        let (before, t) = (at as usize, at.fract());
+        let t = t * t * (3.0 - 2.0 * t);
+        peaks[before] + (peaks[before + 1] - peaks[before]) * t
+    })
+}
+
+// Heights of a mountain range, column by column
+fn mountains(rng: &mut FastRng, width: usize, tall: f32) -> Vec<f32> {
+    let mut heights = vec![tall * 0.1; width];
+    for (spacing, share) in RIDGES {
+        for (height, bump) in
+            heights
+                .iter_mut()
+                .zip(bumps(rng, width, spacing * width as f32, share * tall))
+        {
+            *height += bump;
+        }
+    }
+    heights
+}
+
+// Heights of a treeline of pointed firs on rolling ground
+fn trees(rng: &mut FastRng, width: usize, tall: f32) -> Vec<f32> {
+    let mut heights: Vec<f32> = bumps(
+        rng,
+        width,
+        GROUND_SPACING * width as f32,
+        GROUND_HEIGHT * tall,
+    )
+    .collect();
+    let ground = heights.clone();
+    for _ in 0..(width as f32 / TREE_SPACING) as usize {
+        let x = rng.gen_range(0.0..width as f32);
+        let tree = rng.gen_range(TREE_HEIGHT.0..TREE_HEIGHT.1) * tall;
+        let half = tree * TREE_WIDTH / 2.0;
+        let foot = ground[(x as usize).min(width - 1)];
+        let from = (x - half).max(0.0) as usize;
+        let to = ((x + half).ceil() as usize).min(width);
+        for (column, height) in heights.iter_mut().enumerate().take(to).skip(from) {
+            let across = ((column as f32 + 0.5 - x).abs() / half).min(1.0);
+            *height = height.max(foot + tree * (1.0 - across));
+        }
+    }
+    heights
+}
+
+// Heights of a city's buildings, and where their windows are: pixels across
+// and up from the bottom
+fn city(rng: &mut FastRng, width: usize, tall: f32, lights: bool) -> (Vec<f32>, Vec<(i32, i32)>) {
+    let mut heights = Vec::with_capacity(width);
+    let mut windows = Vec::new();
+    while heights.len() < width {
+        let left = heights.len();
+        let wide = (rng.gen_range(BUILDING_WIDTH.0..BUILDING_WIDTH.1) as usize).min(width - left);
+        let high = rng.gen_range(BUILDING_HEIGHT.0..BUILDING_HEIGHT.1) * tall;
+        heights.resize(left + wide, high.round());
+        if rng.gen_bool(MAST_CHANCE) {
+            let mast = left + wide / 2;
+            heights[mast] = (high + MAST_HEIGHT * tall).round();
+        }
+        if !lights {
+            continue;
+        }
+        // On a grid inset from the building's edges and top
+        let across = ((wide as f32 - WINDOW_PITCH.0) / WINDOW_PITCH.0).max(0.0) as usize;
+        let up = ((high - WINDOW_PITCH.1) / WINDOW_PITCH.1).max(0.0) as usize;
+        for row in 0..up {
+            for col in 0..across {
+                if rng.gen_bool(WINDOW_CHANCE) {
+                    let x = left as f32 + WINDOW_PITCH.0 * (col as f32 + 1.0)
+                        - WINDOW_SIZE.0 as f32 / 2.0;
+                    let y = WINDOW_PITCH.1 * (row as f32 + 1.0) + WINDOW_SIZE.1 as f32;
+                    windows.push((x as i32, y as i32));
+                }
+            }
+        }
+    }
+    (heights, windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    const VIEW: Rect = Rect {
+        x0: 0,
+        y0: 0,
+        x1: 1920,
+        y1: 1080,
+    };
+
+    fn lit_sky(skyline: &Skyline) -> Frame {
+        let mut frame = Frame::new(&LANDSCAPE);
+        frame.target().fill(pack((200, 200, 200)));
+        skyline.draw(&mut frame.target(), VIEW);
+        frame
+    }
+
+    #[test]
+    fn shapes_cover_the_bottom_of_the_sky_and_no_more() {
+        for shape in [
+            SkylineShape::Mountains,
+            SkylineShape::Trees,
+            SkylineShape::City,
+        ] {
+            let mut skyline = Skyline::new(Outline::Shape(shape), 0.2, false, 7);
+            skyline.prepare(1920, 1080);
+            let frame = lit_sky(&skyline);
+            for x in (0..1920).step_by(37) {
+                assert_eq!(
+                    frame.pixel(x, 1079),
+                    pack(SILHOUETTE_COLOR),
+                    "{shape:?} at {x}"
+                );
+                assert_eq!(frame.brightness(x, 500), 200, "{shape:?} at {x}");
+            }
+            // Not flat along the top
+            let covered = |x| (0..1080).filter(|&y| frame.brightness(x, y) < 100).count();
+            let heights: Vec<_> = (0..1920).step_by(97).map(covered).collect();
+            assert!(
+                heights.iter().any(|&h| h != heights[0]),
+                "{shape:?} {heights:?}"
+            );
+            assert!(heights.iter().all(|&h| h <= 300), "{shape:?} {heights:?}");
+
+            // The same again for the same seed
+            let mut again = Skyline::new(Outline::Shape(shape), 0.2, false, 7);
+            again.prepare(1920, 1080);
+            assert_eq!(again.cover, skyline.cover);
+        }
+    }
+
+    #[test]
+    fn city_windows_light_up_and_go_dark() {
+        let mut skyline = Skyline::new(Outline::Shape(SkylineShape::City), 0.2, true, 3);
+        skyline.prepare(1920, 1080);
+        assert!(skyline.windows.len() > 100);
+        let lit = |skyline: &Skyline| skyline.windows.iter().filter(|w| w.lit).count();
+        let before: Vec<bool> = skyline.windows.iter().map(|w| w.lit).collect();
+        let share = lit(&skyline) as f32 / skyline.windows.len() as f32;
+        assert!((0.3..0.5).contains(&share), "{share}");
+
+        let window = skyline.windows.iter().find(|w| w.lit).unwrap();
+        let mut frame = Frame::new(&LANDSCAPE);
+        skyline.draw(&mut frame.target(), VIEW);
+        frame.assert_brighter_than(window.x as u32, window.y as u32, 100);
+        // Windows sit inside the buildings
+        let unlit = skyline.windows.iter().find(|w| !w.lit).unwrap();
+        assert_eq!(
+            frame.pixel(unlit.x as u32, unlit.y as u32),
+            pack(SILHOUETTE_COLOR)
+        );
+
+        for _ in 0..600 {
+            skyline.update(0.1);
+        }
+        let changed = skyline
+            .windows
+            .iter()
+            .zip(&before)
+            .filter(|(w, lit)| w.lit != **lit)
+            .count();
+        assert!(changed > 10, "{changed}");
+        let share = lit(&skyline) as f32 / skyline.windows.len() as f32;
+        assert!((0.3..0.5).contains(&share), "{share}");
+    }
+
+    #[test]
+    fn a_mask_stands_on_the_bottom_edge() {
+        // Twice as wide as it is tall, ground on its lower half and a soft
+        // edge between
+        let path =
+            std::env::temp_dir().join(format!("wl-starfield-mask-{}.png", std::process::id()));
+        let mut encoder = png::Encoder::new(std::fs::File::create(&path).unwrap(), 100, 50);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let rows: Vec<u8> = (0..50)
+            .flat_map(|y| {
+                let value = match y {
+                    0..25 => 0,
+                    25 => 128,
+                    _ => 255,
+                };
+                [value; 100]
+            })
+            .collect();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&rows).unwrap();
+        writer.finish().unwrap();
+        let mask = Image::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mut skyline = Skyline::new(Outline::Mask(mask.unwrap()), 0.2, true, 1);
+        skyline.prepare(1920, 1080);
+        // 960 pixels tall at this width
+        assert_eq!(skyline.top, 120);
+        let frame = lit_sky(&skyline);
+        assert_eq!(frame.brightness(300, 400), 200);
+        assert_eq!(frame.pixel(300, 1000), pack(SILHOUETTE_COLOR));
+        let edge = frame.brightness(300, 120 + 25 * 960 / 50 + 9);
+        assert!((50..150).contains(&edge), "{edge}");
+    }
+}
@@ -0,0 +1,119 @@
+// Staggered events across outputs. Normally every output's sky starts its own
+// shooting stars and comets whenever it likes. With `stagger_events` only the
+// leftmost output does; each of its events is then repeated on every other
+// output after a delay that grows with how far right that output sits in the
+// compositor's layout, so they sweep across a row of monitors together.
+
+use std::time::{Duration, Instant};
+use wl_starfield::{SkyEvent, Starfield};
+
+// How fast a sweep crosses the layout, in layout pixels per second
+const SWEEP_SPEED: f32 = 1500.0;
+
+// An event on its way to another output
+struct Pending {
+    at: Instant,
+    sky: u64,
+    event: SkyEvent,
+}
+
+pub struct Stagger {
+    enabled: bool,
+    pending: Vec<Pending>,
+}
+
+impl Stagger {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            pending: Vec::new(),
+        }
+    }
+
+    // After `starfield` (sky number `sky`) updates: pass its events on if it
+    // leads, or start whatever has reached it if it follows. `layout` is each
+    // sky's number and the x of its output's left edge.
+    pub fn frame(
+        &mut self,
+        sky: u64,
+        starfield: &mut Starfield,
+        layout: &[(u64, i32)],
+        now: Instant,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let Some(&(leader, left)) = layout.iter().min_by_key(|&&(_, x)| x) else {
+            return;
+        };
+        starfield.set_following(sky != leader);
+        if sky != leader {
+            self.pending.retain(|pending| {
+                let arrived = pending.sky == sky && pending.at <= now;
+                if arrived {
+                    starfield.trigger(pending.event);
+                }
+                !arrived
+            });
+            return;
+        }
+
+        // Forget outputs that have gone
+        self.pending
+            .retain(|pending| layout.iter().any(|&(sky, _)| sky == pending.sky));
+        let swept = |event: &&SkyEvent| matches!(event, SkyEvent::ShootingStar | SkyEvent::Comet);
+        for &event in starfield.events().iter().filter(swept) {
+            for &(other, x) in layout.iter().filter(|&&(other, _)| other != leader) {
+                let delay = (x - left) as f32 / SWEEP_SPEED;
+                self.pending.push(Pending {
+                    at: now + Duration::from_secs_f32(delay),
+                    sky: other,
+                    event,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use wl_starfield::ScreenDetails;
+    use wl_starfield::config::Config;
+    use wl_starfield::rng::FastRng;
+
+    #[test]
+    fn events_sweep_from_the_leftmost_output() {
+        let config = Config {
+            star_count: 10,
+            ..Config::default()
+        };
+        let screen_details = ScreenDetails {
+            width: 1920,
+            height: 1080,
+        };
+        let mut skies: Vec<Starfield> = (0..3)
+            .map(|i| Starfield::new(FastRng::seed_from_u64(i), screen_details, config.clone()))
+            .collect();
+        // Sky 1 is on the left, then 0, then 2
+        let layout = [(0, 1920), (1, 0), (2, 3840)];
+        let mut stagger = Stagger::new(true);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs_f32(secs);
+
+        skies[1].trigger(SkyEvent::ShootingStar);
+        stagger.frame(1, &mut skies[1], &layout, at(0.0));
+        let mut arrivals = |secs| {
+            for sky in [0, 2] {
+                stagger.frame(sky, &mut skies[sky as usize], &layout, at(secs));
+            }
+            [skies[0].events().len(), skies[2].events().len()]
+        };
+        assert_eq!(arrivals(1.0), [0, 0]);
+        assert_eq!(arrivals(1.5), [1, 0]);
+        assert_eq!(arrivals(2.5), [1, 0]);
+        assert_eq!(arrivals(2.6), [1, 1]);
+        assert_eq!(skies[2].events(), [SkyEvent::ShootingStar]);
+    }
+}
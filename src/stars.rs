@@ -0,0 +1,1136 @@
+// The drifting stars, kept column by column rather than star by star. What
+// every step touches for every star (position, speed, depth and twinkle)
+// sits in arrays of its own, so the usual drift runs as straight passes over
+// them that the compiler can vectorize, and what is only read when a star is
+// drawn, wraps or warps stays out of the way in columns of its own. Stars
+// are picked out by index, which stays the same until they're taken away.
+
+use crate::accent::Tint;
+use crate::config::{Config, StarColors};
+use crate::render::{self, Pixel, Rect, Target, blend, pack};
+use crate::rng::FastRng;
+use crate::{
+    GLARE_ALPHA, GLARE_REACH, GLARE_SIGMA, GLARE_SPIKE_ALPHA, HAZE_FAR, HAZE_NEAR, HEAT_GLOW,
+    HEAT_SIZE, HOVER_RADIUS, INTRO_TIME, PARALLAX_SHIFT, PERSPECTIVE_FADE, PERSPECTIVE_FOCAL,
+    PERSPECTIVE_MAX_SIZE, PERSPECTIVE_NEAR, PERSPECTIVE_SIZE_AT, SMOOTH_ROUND_SIZE, SPARKLE_ALPHA,
+    SPARKLE_GHOSTS, SPLAT_COLUMNS, SPLAT_REACH, SPLAT_SIGMA, ScreenDetails, UpdateContext,
+    WARP_FALLOFF, WARP_SPAWN, WARP_SPEED, WARP_STREAK, WARP_STREAK_ALPHA, blackbody, lag_behind,
+};
+use rand::{Rng, SeedableRng};
+
+// Where a star is in space, in perspective mode: across and down in units
+// of the focal length at distance 1, and how far ahead of the camera, up to
+// 1. `size` is its size PERSPECTIVE_SIZE_AT away.
+#[derive(Clone, Copy)]
+pub(crate) struct Place {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub size: u32,
+}
+
+// A soft glow and four diffraction spikes on the biggest stars, as a
+// telescope photo shows them
+#[derive(Clone, Copy)]
+pub(crate) struct Glare {
+    // Stars at least this many pixels across get it
+    pub min_size: u32,
+    // Spikes reach this many times a star's size out from its center
+    pub length: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct Stars {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub speed: Vec<f32>,
+    // Terminal velocity that drag pulls `speed` toward
+    pub cruise_speed: Vec<f32>,
+    pub depth: Vec<f32>,
+    pub twinkle_phase: Vec<f32>,
+    pub twinkle_speed: Vec<f32>,
+    pub can_twinkle: Vec<bool>,
+    pub color: Vec<(u8, u8, u8)>,
+    pub size: Vec<u32>,
+    // How bright each shines for its depth, 0..1: hotter stars are brighter
+    pub glow: Vec<f32>,
+    // Position before the last time jump, used to smear the skipped motion
+    pub echo: Vec<(f32, f32)>,
+    // Identity for procedural lore; re-rolled on respawn
+    pub seed: Vec<u64>,
+    // Depth cue, 0 (crisp) to the configured haze strength
+    pub haze: Vec<f32>,
+    // Intro flight from `intro_from` to the star's drifting position; 1 once settled
+    pub intro_from: Vec<(f32, f32)>,
+    pub intro_progress: Vec<f32>,
+    // Where the streak behind each starts while warping
+    pub tail: Vec<Option<(f32, f32)>>,
+    // Where each is in space in perspective mode, which sets where it shows
+    pub place: Vec<Option<Place>>,
+    // How far the pointer's parallax moves each from where it is
+    pub parallax: Vec<(f32, f32)>,
+    // Where each was before the last step, and how far back towards there
+    // it is drawn, for the time since the step
+    pub last: Vec<(f32, f32)>,
+    pub lag: Vec<(f32, f32)>,
+    // Which way they all drifted at the last update, a step of length 1
+    pub drift: (f32, f32),
+    // The accent color their own is pulled towards, if there is one
+    pub tint: Option<Tint>,
+    pub glare: Option<Glare>,
+    // Whether they add their light to what's beneath, such as a background
+    // image, rather than covering it
+    pub additive: bool,
+}
+
+// The colors of `star_colors = "palette"`
+pub const PALETTE: [(u8, u8, u8); 5] = [
+    (180, 200, 255), // blue
+    (255, 255, 255), // white
+    (255, 255, 200), // yellow
+    (255, 220, 180), // orange
+    (255, 180, 180), // red
+];
+
+impl Stars {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    // Add a star somewhere on a `width` by `height` screen
+    pub fn spawn(&mut self, rng: &mut impl Rng, config: &Config, width: u32, height: u32) {
+        let sizes = config.star_min_size..=config.star_max_size;
+        let (color, size, glow) = match config.star_colors {
+            StarColors::Palette => {
+                let color = PALETTE[rng.gen_range(0..PALETTE.len())];
+                (color, rng.gen_range(sizes), 1.0)
+            }
+            // Hotter stars are bigger and brighter as well as bluer, give or
+            // take how far away they are
+            StarColors::Blackbody => {
+                let kelvin = blackbody::temperature(rng);
+                let heat = blackbody::heat(kelvin);
+                let spread = (sizes.end() - sizes.start() + 1) as f32;
+                let size = sizes.start()
+                    + (spread * (HEAT_SIZE * heat + (1.0 - HEAT_SIZE) * rng.r#gen::<f32>())) as u32;
+                let glow = 1.0 - HEAT_GLOW * (1.0 - heat);
+                (blackbody::color(kelvin), size.min(*sizes.end()), glow)
+            }
+        };
+
+        let x = rng.gen_range(0.0..width as f32);
+        let y = rng.gen_range(0.0..height as f32);
+        let depth = rng.gen_range(0.5..4.0);
+
+        self.x.push(x);
+        self.y.push(y);
+        self.speed
+            .push(rng.gen_range(config.star_min_speed..config.star_max_speed));
+        self.cruise_speed
+            .push(rng.gen_range(config.star_min_speed..config.star_max_speed));
+        self.can_twinkle.push(rng.gen_bool(0.15));
+        self.twinkle_phase
+            .push(rng.gen_range(0.0..std::f32::consts::TAU));
+        // Max 1 blink every 2 seconds
+        self.twinkle_speed
+            .push(rng.gen_range(0.5..std::f32::consts::PI));
+        self.depth.push(depth);
+        self.color.push(color);
+        self.size.push(size);
+        self.glow.push(glow);
+        self.echo.push((x, y));
+        self.seed.push(rng.r#gen());
+        self.haze.push(haze_for(depth, config.haze));
+        self.intro_from.push((x, y));
+        self.intro_progress.push(1.0);
+        self.tail.push(None);
+        self.place.push(None);
+        self.parallax.push((0.0, 0.0));
+        self.last.push((x, y));
+        self.lag.push((0.0, 0.0));
+    }
+
+    // Keep the first `count` stars, if there are more
+    pub fn truncate(&mut self, count: usize) {
+        self.x.truncate(count);
+        self.y.truncate(count);
+        self.speed.truncate(count);
+        self.cruise_speed.truncate(count);
+        self.depth.truncate(count);
+        self.twinkle_phase.truncate(count);
+        self.twinkle_speed.truncate(count);
+        self.can_twinkle.truncate(count);
+        self.color.truncate(count);
+        self.size.truncate(count);
+        self.glow.truncate(count);
+        self.echo.truncate(count);
+        self.seed.truncate(count);
+        self.haze.truncate(count);
+        self.intro_from.truncate(count);
+        self.intro_progress.truncate(count);
+        self.tail.truncate(count);
+        self.place.truncate(count);
+        self.parallax.truncate(count);
+        self.last.truncate(count);
+        self.lag.truncate(count);
+    }
+
+    pub fn update(&mut self, ctx: &mut UpdateContext) {
+        let (dt, screen_details, config) = (ctx.dt, ctx.screen_details, ctx.config);
+        let drag = 1.0 - (-config.star_drag * dt).exp();
+        for (speed, &cruise_speed) in self.speed.iter_mut().zip(&self.cruise_speed) {
+            *speed += (cruise_speed - *speed) * drag;
+        }
+        // Advance by dt so blink frequency is independent of frame rate and uptime
+        for ((phase, &speed), &can_twinkle) in self
+            .twinkle_phase
+            .iter_mut()
+            .zip(&self.twinkle_speed)
+            .zip(&self.can_twinkle)
+        {
+            if can_twinkle {
+                *phase = (*phase + speed * dt) % std::f32::consts::TAU;
+            }
+        }
+
+        // Drift along the long axis unless told otherwise: leftwards on
+        // landscape outputs, downwards on portrait ones, in step with where
+        // shooting stars come from. Stars in perspective don't drift.
+        self.drift = if config.perspective {
+            (0.0, 0.0)
+        } else {
+            screen_details.drift(config.drift_angle)
+        };
+        let (drift_x, drift_y) = self.drift;
+        let (width, height) = (screen_details.width as f32, screen_details.height as f32);
+        let vanishing = (width * config.warp_center.0, height * config.warp_center.1);
+        let center = (width / 2.0, height / 2.0);
+        let focal = PERSPECTIVE_FOCAL * width.min(height);
+        let drifting = !config.perspective && !config.warp;
+        if drifting {
+            for (((x, y), &speed), &depth) in self
+                .x
+                .iter_mut()
+                .zip(&mut self.y)
+                .zip(&self.speed)
+                .zip(&self.depth)
+            {
+                let travel = speed * depth * dt;
+                *x += drift_x * travel;
+                *y += drift_y * travel;
+            }
+        }
+
+        for i in 0..self.len() {
+            if !config.perspective
+                && let Some(place) = self.place[i].take()
+            {
+                // Back to drifting, at its own size
+                self.size[i] = place.size;
+            }
+            let wrapped = if config.perspective {
+                self.tail[i] = None;
+                // Joining the flight somewhere ahead, where it shows now
+                let half = self.size[i] as f32 / 2.0;
+                let (x, y) = (self.x[i] + half - center.0, self.y[i] + half - center.1);
+                let size = self.size[i];
+                let place = self.place[i].get_or_insert_with(|| {
+                    let z = ctx.rng.gen_range(PERSPECTIVE_NEAR..1.0);
+                    Place {
+                        x: x / focal * z,
+                        y: y / focal * z,
+                        z,
+                        size,
+                    }
+                });
+                // The camera flies forward, so everything comes closer
+                place.z -= config.camera_speed * dt;
+                let x = center.0 + place.x / place.z * focal;
+                let y = center.1 + place.y / place.z * focal;
+                place.z < PERSPECTIVE_NEAR
+                    || !(0.0..width).contains(&x)
+                    || !(0.0..height).contains(&y)
+            } else if config.warp {
+                self.warp(i, dt, vanishing, width.min(height));
+                !(0.0..width).contains(&self.x[i]) || !(0.0..height).contains(&self.y[i])
+            } else {
+                self.tail[i] = None;
+                let (x, y) = (self.x[i], self.y[i]);
+                x < 0.0 || x > width || y < 0.0 || y > height
+            };
+
+            if self.intro_progress[i] < 1.0 {
+                self.intro_progress[i] = (self.intro_progress[i] + dt / INTRO_TIME).min(1.0);
+            }
+
+            if wrapped {
+                self.respawn(i, config, vanishing, center, focal, (width, height));
+            }
+            if let Some(place) = self.place[i] {
+                self.project(i, place, center, focal, config.haze);
+            }
+        }
+    }
+
+    // A new life for star `i`, which has gone off the screen. It follows
+    // from the old one's seed alone, so copies of a sky spanning several
+    // outputs stay the same whenever they happen to update.
+    fn respawn(
+        &mut self,
+        i: usize,
+        config: &Config,
+        vanishing: (f32, f32),
+        center: (f32, f32),
+        focal: f32,
+        (width, height): (f32, f32),
+    ) {
+        let rng = &mut FastRng::seed_from_u64(self.seed[i]);
+        let (drift_x, drift_y) = self.drift;
+        // Re-enter at a fresh spot along an edge the stars drift away from,
+        // each as often as it faces the drift, or when warping come out
+        // again near the vanishing point. In perspective it goes back to the
+        // far end, somewhere in view.
+        if let Some(place) = &mut self.place[i] {
+            let (x, y) = (rng.gen_range(0.0..width), rng.gen_range(0.0..height));
+            place.x = (x - center.0) / focal;
+            place.y = (y - center.1) / focal;
+            place.z = 1.0;
+        } else if config.warp {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let out = rng.gen_range(1.0..WARP_SPAWN * width.min(height));
+            self.x[i] = (vanishing.0 + out * angle.cos()).clamp(0.0, width - 1.0);
+            self.y[i] = (vanishing.1 + out * angle.sin()).clamp(0.0, height - 1.0);
+            self.tail[i] = None;
+        } else {
+            let (side, top) = (drift_x.abs() * height, drift_y.abs() * width);
+            let through_side = match (side, top) {
+                (_, 0.0) => true,
+                (0.0, _) => false,
+                _ => rng.gen_bool((side / (side + top)) as f64),
+            };
+            if through_side {
+                self.x[i] = if drift_x < 0.0 { width } else { 0.0 };
+                self.y[i] = rng.gen_range(0.0..height);
+            } else {
+                self.x[i] = rng.gen_range(0.0..width);
+                self.y[i] = if drift_y > 0.0 { 0.0 } else { height };
+            }
+        }
+        self.depth[i] = rng.gen_range(0.5..2.0);
+        self.twinkle_phase[i] = rng.gen_range(0.0..std::f32::consts::TAU);
+        self.twinkle_speed[i] = rng.gen_range(0.5..std::f32::consts::PI); // Max 1 blink every 2 seconds
+        self.speed[i] = rng.gen_range(config.star_min_speed..config.star_max_speed);
+        self.cruise_speed[i] = rng.gen_range(config.star_min_speed..config.star_max_speed);
+        self.size[i] = rng.gen_range(config.star_min_size..=config.star_max_size);
+        self.seed[i] = rng.r#gen();
+        self.haze[i] = haze_for(self.depth[i], config.haze);
+        self.intro_progress[i] = 1.0;
+        if let Some(place) = &mut self.place[i] {
+            place.size = self.size[i];
+        }
+    }
+
+    // Rush straight out from the vanishing point, faster the further out it
+    // is, as if the stars were coming at us
+    fn warp(&mut self, i: usize, dt: f32, (cx, cy): (f32, f32), short_side: f32) {
+        let (dx, dy) = (self.x[i] - cx, self.y[i] - cy);
+        let out = dx.hypot(dy).max(0.5);
+        let speed =
+            WARP_SPEED * self.speed[i] * self.depth[i] * (1.0 + out / (WARP_FALLOFF * short_side));
+        let (dx, dy) = if dx == 0.0 && dy == 0.0 {
+            (1.0, 0.0)
+        } else {
+            (dx / out, dy / out)
+        };
+        // Never reaching back past the vanishing point
+        let streak = (speed * WARP_STREAK).min(out);
+        self.tail[i] = (self.intro_progress[i] >= 1.0)
+            .then_some((self.x[i] - dx * streak, self.y[i] - dy * streak));
+        self.x[i] += dx * speed * dt;
+        self.y[i] += dy * speed * dt;
+    }
+
+    // Show it where the camera sees it: the nearer, the further out from the
+    // middle, the bigger and the brighter
+    fn project(&mut self, i: usize, place: Place, (cx, cy): (f32, f32), focal: f32, haze: f32) {
+        let size = (place.size as f32 * PERSPECTIVE_SIZE_AT / place.z)
+            .round()
+            .clamp(1.0, PERSPECTIVE_MAX_SIZE);
+        self.size[i] = size as u32;
+        self.x[i] = cx + place.x / place.z * focal - size / 2.0;
+        self.y[i] = cy + place.y / place.z * focal - size / 2.0;
+        // Faint far away, and fading in from the far end
+        let fade = ((1.0 - place.z) / PERSPECTIVE_FADE).clamp(0.01, 1.0);
+        self.depth[i] = (0.5 + 3.5 * place.z) / fade;
+        self.haze[i] = haze_for(self.depth[i], haze);
+    }
+
+    // Remember where every star is before a step
+    pub fn mark_last(&mut self) {
+        for (last, (&x, &y)) in self.last.iter_mut().zip(self.x.iter().zip(&self.y)) {
+            *last = (x, y);
+        }
+    }
+
+    // Remember where every star is before a time jump
+    pub fn mark_echo(&mut self) {
+        for (echo, (&x, &y)) in self.echo.iter_mut().zip(self.x.iter().zip(&self.y)) {
+            *echo = (x, y);
+        }
+    }
+
+    // Draw every star `behind` of a step short of where it is, back towards
+    // where it was
+    pub fn lag_behind(&mut self, behind: f32, screen_details: &ScreenDetails) {
+        for i in 0..self.len() {
+            let now = (self.x[i], self.y[i]);
+            self.lag[i] = lag_behind(self.last[i], now, behind, screen_details);
+        }
+    }
+
+    // Shift every star by the pointer's `parallax`, the deeper the further
+    pub fn shift(&mut self, parallax: (f32, f32)) {
+        for (shifted, &depth) in self.parallax.iter_mut().zip(&self.depth) {
+            let shift = -PARALLAX_SHIFT * depth;
+            *shifted = (parallax.0 * shift, parallax.1 * shift);
+        }
+    }
+
+    // Start the intro: every star flies out from (x, y) to wherever it has
+    // drifted
+    pub fn expand_from(&mut self, x: f32, y: f32) {
+        self.intro_from.fill((x, y));
+        self.intro_progress.fill(0.0);
+    }
+
+    pub fn rescale(&mut self, scale_x: f32, scale_y: f32) {
+        let scale = |(x, y): &mut (f32, f32)| (*x, *y) = (*x * scale_x, *y * scale_y);
+        self.x.iter_mut().for_each(|x| *x *= scale_x);
+        self.y.iter_mut().for_each(|y| *y *= scale_y);
+        self.echo.iter_mut().for_each(scale);
+        self.intro_from.iter_mut().for_each(scale);
+        self.last.iter_mut().for_each(scale);
+        self.lag.iter_mut().for_each(scale);
+    }
+
+    // Index of the star nearest `(cx, cy)`, if any is close enough to hover
+    pub fn hovered(&self, (cx, cy): (f32, f32)) -> Option<usize> {
+        (0..self.len())
+            .map(|i| {
+                let (x, y) = self.center(i);
+                (i, (x - cx).hypot(y - cy))
+            })
+            .filter(|&(_, dist)| dist <= HOVER_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Where star `i` is drawn, which differs from (x, y) during the intro,
+    // by the parallax and by how far it is between steps
+    pub fn position(&self, i: usize) -> (f32, f32) {
+        let (parallax, lag) = (self.parallax[i], self.lag[i]);
+        let (shift_x, shift_y) = (parallax.0 + lag.0, parallax.1 + lag.1);
+        let (x, y) = (self.x[i], self.y[i]);
+        if self.intro_progress[i] >= 1.0 {
+            return (x + shift_x, y + shift_y);
+        }
+        // Ease out: burst away from the center, then glide into place
+        let t = 1.0 - (1.0 - self.intro_progress[i]).powi(3);
+        let (from_x, from_y) = self.intro_from[i];
+        (
+            from_x + (x - from_x) * t + shift_x,
+            from_y + (y - from_y) * t + shift_y,
+        )
+    }
+
+    pub fn center(&self, i: usize) -> (f32, f32) {
+        let half = self.size[i] as f32 / 2.0;
+        let (x, y) = self.position(i);
+        (x + half, y + half)
+    }
+
+    // The star's own square, without any streak
+    pub fn body(&self, i: usize) -> Rect {
+        let (x, y) = self.position(i);
+        let (x, y) = (x as i32, y as i32);
+        let size = self.size[i] as i32;
+        Rect::new(x, y, x + size, y + size)
+    }
+
+    pub fn bounds(&self, i: usize) -> Rect {
+        let body = match self.tail[i] {
+            Some((x, y)) => self.body(i).union(&Rect::around(x, y, self.size[i] as f32)),
+            None => self.body(i),
+        };
+        match self.glare_reach(i) {
+            Some(reach) => {
+                let (cx, cy) = self.center(i);
+                body.union(&Rect::around(cx, cy, reach))
+            }
+            None => body,
+        }
+    }
+
+    // How far star `i`'s glow and spikes reach from its center, if it has them
+    fn glare_reach(&self, i: usize) -> Option<f32> {
+        let glare = self.glare.filter(|glare| self.size[i] >= glare.min_size)?;
+        let size = self.size[i] as f32;
+        Some((glare.length * size).max(size * GLARE_SIGMA * GLARE_REACH))
+    }
+
+    pub fn splat_bounds(&self, i: usize) -> Rect {
+        let (cx, cy) = self.center(i);
+        let reach = self.size[i] as f32 * SPLAT_SIGMA * SPLAT_REACH;
+        self.bounds(i).union(&Rect::around(cx, cy, reach))
+    }
+
+    pub fn echo_bounds(&self, i: usize) -> Rect {
+        let half = self.size[i] as f32 / 2.0;
+        let (x, y) = self.echo[i];
+        Rect::around(x + half, y + half, 0.0).union(&self.body(i))
+    }
+
+    // Where star `i` is and how fast it's going, for the debug overlays
+    pub fn motion(&self, i: usize) -> ((f32, f32), (f32, f32)) {
+        let speed = self.speed[i] * self.depth[i];
+        let velocity = match self.tail[i] {
+            Some((x, y)) => ((self.x[i] - x) / WARP_STREAK, (self.y[i] - y) / WARP_STREAK),
+            None => (self.drift.0 * speed, self.drift.1 * speed),
+        };
+        (self.center(i), velocity)
+    }
+
+    // Current brightness before clamping; near stars at the top of a twinkle
+    // go well past what can be shown
+    pub fn brightness(&self, i: usize) -> f32 {
+        let twinkle = self.twinkle_phase[i].sin() * 0.5 + 0.5;
+        twinkle * 255.0 * self.glow[i] / self.depth[i]
+    }
+
+    pub fn shade(&self, i: usize) -> (u8, u8, u8) {
+        let intensity = self.brightness(i).min(200.0) as u8;
+
+        let (base_r, base_g, base_b) = match self.tint {
+            Some(tint) => tint.apply(self.color[i]),
+            None => self.color[i],
+        };
+        let r = ((base_r as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
+        let g = ((base_g as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
+        let b = ((base_b as f32 * (intensity as f32 / 255.0)).min(255.0)) as u8;
+        render::haze((r, g, b), self.haze[i])
+    }
+
+    pub fn draw(&self, i: usize, target: &mut Target) {
+        self.draw_streak(i, target);
+        let color = self.shade(i);
+        for (_, _, row) in target.span_rows(self.body(i)) {
+            if self.additive {
+                row.iter_mut()
+                    .for_each(|pixel| render::add(pixel, color, 1.0));
+            } else {
+                row.fill(pack(color));
+            }
+        }
+        self.draw_glare(i, target);
+    }
+
+    // How a star's light goes onto the pixels it covers part of
+    fn paint(&self) -> fn(&mut Pixel, (u8, u8, u8), f32) {
+        if self.additive { render::add } else { blend }
+    }
+
+    // Drawn as a Gaussian splat holding as much light as its square, with
+    // each pixel taking the share of it that falls on that pixel
+    pub fn draw_splat(&self, i: usize, target: &mut Target) {
+        self.draw_streak(i, target);
+        let size = self.size[i] as f32;
+        let (cx, cy) = self.center(i);
+        let sigma = size * SPLAT_SIGMA;
+        let color = self.shade(i);
+        let paint = self.paint();
+        let rect = Rect::around(cx, cy, sigma * SPLAT_REACH);
+        let across_share = |x: i32| render::gaussian_share(x as f32 - cx, sigma);
+        // Each column's share once, rather than again on every row
+        let mut across = [0.0; SPLAT_COLUMNS];
+        for (x, share) in (rect.x0..rect.x1).zip(&mut across) {
+            *share = across_share(x);
+        }
+        for (y, left, row) in target.span_rows(rect) {
+            let down = size * size * render::gaussian_share(y as f32 - cy, sigma);
+            for (x, pixel) in (left..).zip(row) {
+                let share = across
+                    .get((x - rect.x0) as usize)
+                    .map_or_else(|| across_share(x), |&share| share);
+                paint(pixel, color, down * share);
+            }
+        }
+        self.draw_glare(i, target);
+    }
+
+    // A Gaussian glow around star `i` and four spikes fading out from it,
+    // up, down, left and right, if it's big enough for them
+    pub fn draw_glare(&self, i: usize, target: &mut Target) {
+        let Some(glare) = self.glare.filter(|glare| self.size[i] >= glare.min_size) else {
+            return;
+        };
+        let size = self.size[i] as f32;
+        let (cx, cy) = self.center(i);
+        let color = self.shade(i);
+        let sigma = size * GLARE_SIGMA;
+        let spread = 2.0 * sigma * sigma;
+        for (y, left, row) in target.span_rows(Rect::around(cx, cy, sigma * GLARE_REACH)) {
+            let dy = y as f32 + 0.5 - cy;
+            for (x, pixel) in (left..).zip(row) {
+                let dx = x as f32 + 0.5 - cx;
+                blend(
+                    pixel,
+                    color,
+                    GLARE_ALPHA * (-(dx * dx + dy * dy) / spread).exp(),
+                );
+            }
+        }
+        let length = glare.length * size;
+        let (x, y) = (cx.floor() as i32, cy.floor() as i32);
+        for out in 1..length.ceil() as i32 {
+            let alpha = GLARE_SPIKE_ALPHA * (1.0 - out as f32 / length).powi(2);
+            for (dx, dy) in [(out, 0), (-out, 0), (0, out), (0, -out)] {
+                target.blend(x + dx, y + dy, color, alpha);
+            }
+        }
+    }
+
+    // Drawn as a disc covering each pixel by as much as overlaps it: the
+    // pixels a small star straddles get bilinear shares of it, and bigger
+    // ones are rounded off with a soft rim
+    pub fn draw_smooth(&self, i: usize, target: &mut Target) {
+        self.draw_streak(i, target);
+        let radius = self.size[i] as f32 / 2.0;
+        let (cx, cy) = self.center(i);
+        let color = self.shade(i);
+        let round = self.size[i] >= SMOOTH_ROUND_SIZE;
+        let paint = self.paint();
+        // How much of the star's span around `middle` falls in the pixel at `at`
+        let overlap = |middle: f32, at: i32| {
+            let (from, to) = (at as f32, at as f32 + 1.0);
+            ((middle + radius).min(to) - (middle - radius).max(from)).max(0.0)
+        };
+        for (y, left, row) in target.span_rows(self.smooth_bounds(i)) {
+            let down = overlap(cy, y);
+            for (x, pixel) in (left..).zip(row) {
+                let mut coverage = down * overlap(cx, x);
+                if round {
+                    let out = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+                    coverage *= (radius + 0.5 - out).clamp(0.0, 1.0);
+                }
+                if coverage > 0.0 {
+                    paint(pixel, color, coverage);
+                }
+            }
+        }
+        self.draw_glare(i, target);
+    }
+
+    pub fn smooth_bounds(&self, i: usize) -> Rect {
+        let (cx, cy) = self.center(i);
+        let reach = self.size[i] as f32 / 2.0;
+        self.bounds(i).union(&Rect::around(cx, cy, reach))
+    }
+
+    // The streak behind it while warping, fainter toward the tail
+    pub fn draw_streak(&self, i: usize, target: &mut Target) {
+        if let Some((x, y)) = self.tail[i] {
+            let half = self.size[i] as f32 / 2.0;
+            let (cx, cy) = self.center(i);
+            target.line(
+                (x + half, y + half),
+                (cx, cy),
+                self.shade(i),
+                WARP_STREAK_ALPHA,
+            );
+        }
+    }
+
+    // Where each sparkle ghost of star `i` goes, with its color
+    pub fn sparkle_ghosts(&self, i: usize) -> impl Iterator<Item = (Rect, (u8, u8, u8))> + '_ {
+        let star = self.body(i);
+        SPARKLE_GHOSTS.into_iter().map(move |(dy, color)| {
+            let rect = Rect::new(star.x0, star.y0 + dy, star.x1, star.y1 + dy);
+            (rect, color)
+        })
+    }
+
+    pub fn draw_sparkle(&self, i: usize, target: &mut Target) {
+        for (rect, color) in self.sparkle_ghosts(i) {
+            for (_, _, row) in target.span_rows(rect) {
+                for pixel in row {
+                    blend(pixel, color, SPARKLE_ALPHA);
+                }
+            }
+        }
+    }
+
+    // Smear from the pre-jump position to the current one, brightest at the star
+    pub fn draw_echo(&self, i: usize, target: &mut Target, strength: f32) {
+        let (echo_x, echo_y) = self.echo[i];
+        let dx = self.x[i] - echo_x;
+        let dy = self.y[i] - echo_y;
+
+        // Moving against the drift means it wrapped, which isn't motion.
+        // Warping stars have streaks enough already, and stars in
+        // perspective don't drift.
+        if dx * self.drift.0 + dy * self.drift.1 < 0.0
+            || self.tail[i].is_some()
+            || self.place[i].is_some()
+        {
+            return;
+        }
+
+        let steps = (dx * dx + dy * dy).sqrt().ceil() as usize;
+        if steps < 2 {
+            return;
+        }
+
+        let color = self.shade(i);
+        let half = self.size[i] as f32 / 2.0;
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            let x = echo_x + dx * t + half;
+            let y = echo_y + dy * t + half;
+            target.blend(x as i32, y as i32, color, strength * t * t);
+        }
+    }
+}
+
+// How much haze a star at `depth` sits behind, given the configured strength
+fn haze_for(depth: f32, strength: f32) -> f32 {
+    ((depth - HAZE_NEAR) / (HAZE_FAR - HAZE_NEAR)).clamp(0.0, 1.0) * strength
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Orientation;
+    use crate::test_support::{FixedClock, Frame, LANDSCAPE as SCREEN, MockScreen};
+
+    fn spawn(screen: &mut MockScreen, count: usize) -> Stars {
+        let (width, height) = (screen.details.width, screen.details.height);
+        let mut stars = Stars::default();
+        for _ in 0..count {
+            stars.spawn(&mut screen.rng, &screen.config, width, height);
+        }
+        stars
+    }
+
+    fn twinkling_star(screen: &mut MockScreen) -> Stars {
+        let mut stars = spawn(screen, 1);
+        stars.can_twinkle[0] = true;
+        stars.twinkle_speed[0] = 2.0;
+        stars.twinkle_phase[0] = 0.0;
+        // Keep it from wrapping, which re-rolls the phase
+        stars.speed[0] = 0.0;
+        stars.cruise_speed[0] = 0.0;
+        stars
+    }
+
+    // Count rising zero crossings of the twinkle wave over `secs` at `fps`
+    fn count_blinks(stars: &mut Stars, screen: &mut MockScreen, fps: f32, secs: f32) -> u32 {
+        let mut blinks = 0;
+        let mut last = stars.twinkle_phase[0].sin();
+        for dt in FixedClock::fps(fps).ticks(secs) {
+            stars.update(&mut screen.ctx(dt));
+            let now = stars.twinkle_phase[0].sin();
+            if last < 0.0 && now >= 0.0 {
+                blinks += 1;
+            }
+            last = now;
+        }
+        blinks
+    }
+
+    #[test]
+    fn twinkle_frequency_matches_across_frame_rates() {
+        let mut screen = MockScreen::landscape().with_seed(7);
+        let expected = (2.0 * 60.0 / std::f32::consts::TAU) as i32;
+
+        for fps in [24.0, 60.0, 144.0] {
+            let mut star = twinkling_star(&mut screen);
+            let blinks = count_blinks(&mut star, &mut screen, fps, 60.0) as i32;
+            assert!((blinks - expected).abs() <= 1, "{fps} fps: {blinks} blinks");
+        }
+    }
+
+    #[test]
+    fn twinkle_frequency_is_constant_over_time() {
+        let mut screen = MockScreen::landscape().with_seed(7);
+        let mut star = twinkling_star(&mut screen);
+
+        let early = count_blinks(&mut star, &mut screen, 60.0, 60.0) as i32;
+        count_blinks(&mut star, &mut screen, 60.0, 600.0);
+        let late = count_blinks(&mut star, &mut screen, 60.0, 60.0) as i32;
+        assert!(
+            (early - late).abs() <= 1,
+            "{early} blinks early vs {late} late"
+        );
+    }
+
+    #[test]
+    fn warping_stars_streak_out_from_the_vanishing_point() {
+        let mut screen = MockScreen::landscape();
+        screen.config.warp = true;
+        screen.config.warp_center = (0.25, 0.5);
+        let vanishing = (480.0, 540.0);
+        let out = |(x, y): (f32, f32)| (x - vanishing.0).hypot(y - vanishing.1);
+        let mut stars = spawn(&mut screen, 200);
+        let before: Vec<f32> = (0..200).map(|i| out((stars.x[i], stars.y[i]))).collect();
+        stars.update(&mut screen.ctx(1.0 / 60.0));
+        for (i, before) in before.into_iter().enumerate() {
+            let now = out((stars.x[i], stars.y[i]));
+            // Those that left the screen came back in near the middle
+            assert!(now > before || now < WARP_SPAWN * 1080.0);
+            let tail = stars.tail[i].unwrap_or((stars.x[i], stars.y[i]));
+            assert!(out(tail) <= now + 0.01);
+        }
+
+        // Dropping out of warp goes back to drifting, without streaks
+        screen.config.warp = false;
+        stars.update(&mut screen.ctx(1.0 / 60.0));
+        assert!(stars.tail.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn star_speeds_stay_stable_over_long_runs() {
+        let mut screen = MockScreen::landscape().with_seed(11);
+        let config = screen.config.clone();
+        let mut stars = spawn(&mut screen, 500);
+        let mean = |stars: &Stars| stars.speed.iter().sum::<f32>() / stars.len() as f32;
+        let mut run = |stars: &mut Stars, minutes: u32| {
+            let mut ctx = screen.ctx(0.1);
+            for _ in 0..minutes * 600 {
+                stars.update(&mut ctx);
+            }
+        };
+
+        // Let the population settle, then make sure it stays put at 10 fps
+        run(&mut stars, 5);
+        let settled = mean(&stars);
+        run(&mut stars, 10);
+        let later = mean(&stars);
+
+        assert!(
+            (later - settled).abs() / settled < 0.1,
+            "mean speed drifted {settled} -> {later}"
+        );
+        assert!(
+            stars
+                .speed
+                .iter()
+                .all(|speed| (config.star_min_speed..=config.star_max_speed).contains(speed)),
+            "speeds left the spawn range"
+        );
+    }
+
+    #[test]
+    fn drag_settles_speeds_at_the_configured_rate() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = spawn(&mut screen, 50);
+        // Far from the edge they'd wrap at, which would roll new speeds
+        stars.x.fill(1800.0);
+        let start = stars.speed.clone();
+        let gap = |stars: &Stars| {
+            (stars.speed.iter().zip(&stars.cruise_speed))
+                .map(|(speed, cruise)| (speed - cruise).abs())
+                .fold(0.0, f32::max)
+        };
+
+        // No drag keeps every star at the speed it started with
+        screen.config.star_drag = 0.0;
+        stars.update(&mut screen.ctx(1.0));
+        assert_eq!(stars.speed, start);
+
+        // Strong drag closes the gap to the cruise speed within a second
+        screen.config.star_drag = 10.0;
+        let before = gap(&stars);
+        for _ in 0..10 {
+            stars.update(&mut screen.ctx(0.1));
+        }
+        assert!(gap(&stars) < before * 1e-3, "{before} -> {}", gap(&stars));
+    }
+
+    #[test]
+    fn splats_spread_a_star_between_pixels() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = spawn(&mut screen, 1);
+        (stars.size[0], stars.glow[0], stars.depth[0]) = (2, 1.0, 0.5);
+        stars.twinkle_phase[0] = std::f32::consts::FRAC_PI_2;
+        stars.haze[0] = 0.0;
+        let total = |frame: &Frame| frame.pixels().iter().map(|p| p[1] as f32).sum::<f32>();
+
+        // On the grid the square and the splat hold about the same light
+        (stars.x[0], stars.y[0]) = (100.0, 100.0);
+        let mut square = Frame::new(&SCREEN);
+        stars.draw(0, &mut square.target());
+        let mut splat = Frame::new(&SCREEN);
+        stars.draw_splat(0, &mut splat.target());
+        let (square_total, splat_total) = (total(&square), total(&splat));
+        assert!(
+            (splat_total / square_total - 1.0).abs() < 0.1,
+            "{square_total} {splat_total}"
+        );
+
+        // Half a pixel along, the square jumps but the splat straddles two
+        // columns evenly
+        stars.x[0] = 100.5;
+        let mut splat = Frame::new(&SCREEN);
+        stars.draw_splat(0, &mut splat.target());
+        assert_eq!(splat.pixel(100, 101), splat.pixel(102, 101));
+        assert!(splat.brightness(101, 101) > splat.brightness(100, 101));
+    }
+
+    #[test]
+    fn smooth_stars_share_themselves_between_pixels() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = spawn(&mut screen, 1);
+        (stars.size[0], stars.glow[0], stars.depth[0]) = (1, 1.0, 0.5);
+        stars.twinkle_phase[0] = std::f32::consts::FRAC_PI_2;
+        stars.haze[0] = 0.0;
+        let (r, g, b) = stars.shade(0);
+
+        // On the grid it fills its one pixel, as a square would
+        (stars.x[0], stars.y[0]) = (100.0, 100.0);
+        let mut frame = Frame::new(&SCREEN);
+        stars.draw_smooth(0, &mut frame.target());
+        assert_eq!(frame.pixel(100, 100), [r, g, b, 255]);
+        assert_eq!(frame.lit(0), 1);
+
+        // Halfway between four pixels, each gets a quarter of it
+        (stars.x[0], stars.y[0]) = (100.5, 100.5);
+        let mut frame = Frame::new(&SCREEN);
+        stars.draw_smooth(0, &mut frame.target());
+        let quarter = (g as f32 / 4.0) as u8;
+        for (x, y) in [(100, 100), (101, 100), (100, 101), (101, 101)] {
+            assert_eq!(frame.pixel(x, y)[1], quarter);
+        }
+        assert_eq!(frame.lit(0), 4);
+
+        // Bigger stars are round, their corners left dark
+        (stars.x[0], stars.y[0], stars.size[0]) = (100.0, 100.0, 6);
+        let mut frame = Frame::new(&SCREEN);
+        stars.draw_smooth(0, &mut frame.target());
+        frame.assert_brighter_than(103, 103, g / 2);
+        frame.assert_brighter_than(100, 103, 0);
+        frame.assert_dark(100, 100);
+    }
+
+    #[test]
+    fn big_stars_get_glare_when_asked() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = spawn(&mut screen, 2);
+        for i in 0..2 {
+            (stars.glow[i], stars.depth[i], stars.haze[i]) = (1.0, 0.5, 0.0);
+            stars.twinkle_phase[i] = std::f32::consts::FRAC_PI_2;
+        }
+        (stars.x[0], stars.y[0], stars.size[0]) = (100.0, 100.0, 4);
+        (stars.x[1], stars.y[1], stars.size[1]) = (300.0, 100.0, 2);
+        let draw = |stars: &Stars| {
+            let mut frame = Frame::new(&SCREEN);
+            for i in 0..2 {
+                stars.draw(i, &mut frame.target());
+            }
+            frame
+        };
+
+        // Left plain by default
+        let plain = draw(&stars);
+        assert_eq!(plain.lit(0), 16 + 4);
+
+        stars.glare = Some(Glare {
+            min_size: 4,
+            length: 3.0,
+        });
+        let glaring = draw(&stars);
+        // Spikes reach out along the axes, fading, but not along the diagonals
+        glaring.assert_brighter_than(112, 102, 0);
+        glaring.assert_brighter_than(102, 92, 0);
+        assert!(glaring.brightness(106, 102) > glaring.brightness(110, 102));
+        assert!(glaring.brightness(110, 110) < glaring.brightness(110, 102));
+        assert!(stars.bounds(0).contains(112, 102) && stars.bounds(0).contains(102, 92));
+        // Smaller stars stay as they were
+        assert_eq!(glaring.pixel(303, 101), plain.pixel(303, 101));
+        assert!(glaring.lit(0) > plain.lit(0) + 100);
+    }
+
+    #[test]
+    fn additive_stars_light_up_a_background_rather_than_cover_it() {
+        let mut screen = MockScreen::landscape();
+        let mut stars = spawn(&mut screen, 1);
+        (stars.x[0], stars.y[0], stars.size[0]) = (100.0, 100.0, 4);
+        let background = (60, 70, 110);
+        let draw = |stars: &Stars, draw: fn(&Stars, usize, &mut Target)| {
+            let mut frame = Frame::new(&SCREEN);
+            frame.target().fill(pack(background));
+            draw(stars, 0, &mut frame.target());
+            frame.pixel(101, 101)
+        };
+        let star = pack(stars.shade(0));
+        assert_eq!(draw(&stars, Stars::draw), star);
+
+        stars.additive = true;
+        for draw_with in [Stars::draw, Stars::draw_smooth, Stars::draw_splat] {
+            let [r, g, b, _] = draw(&stars, draw_with);
+            assert!(r > background.0 && g > background.1 && b > background.2);
+            assert!(r >= star[0] && g >= star[1] && b >= star[2]);
+        }
+    }
+
+    #[test]
+    fn stars_wrap_back_in_on_the_far_edge() {
+        for mut screen in [MockScreen::landscape(), MockScreen::portrait()] {
+            let (width, height) = (screen.details.width, screen.details.height);
+            let mut stars = spawn(&mut screen, 1);
+            // One step short of the edge it drifts toward
+            (stars.x[0], stars.y[0]) = (1.0, height as f32 - 1.0);
+            stars.depth[0] = 1.0;
+            stars.speed[0] = 100.0;
+            stars.cruise_speed[0] = 100.0;
+            stars.update(&mut screen.ctx(0.1));
+
+            match screen.details.orientation() {
+                Orientation::Landscape => {
+                    assert_eq!(stars.x[0], width as f32);
+                    assert!((0.0..height as f32).contains(&stars.y[0]));
+                }
+                Orientation::Portrait => {
+                    assert_eq!(stars.y[0], 0.0);
+                    assert!((0.0..width as f32).contains(&stars.x[0]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn intro_flies_stars_from_the_center_into_place() {
+        let mut screen = MockScreen::landscape().with_seed(13);
+        let mut stars = spawn(&mut screen, 1);
+        stars.expand_from(960.0, 540.0);
+        assert_eq!(stars.position(0), (960.0, 540.0));
+
+        let clock = FixedClock::fps(60.0);
+        for dt in clock.ticks(INTRO_TIME + clock.dt) {
+            stars.update(&mut screen.ctx(dt));
+        }
+        assert_eq!(stars.position(0), (stars.x[0], stars.y[0]));
+    }
+
+    #[test]
+    fn stars_drift_along_the_long_axis() {
+        for mut mock in [MockScreen::landscape(), MockScreen::portrait()] {
+            let screen = mock.details;
+            let mut stars = spawn(&mut mock, 1);
+            stars.x[0] = screen.width as f32 / 2.0;
+            stars.y[0] = screen.height as f32 / 2.0;
+            stars.update(&mut mock.ctx(0.5));
+
+            let (dx, dy) = (
+                stars.x[0] - screen.width as f32 / 2.0,
+                stars.y[0] - screen.height as f32 / 2.0,
+            );
+            match screen.orientation() {
+                Orientation::Landscape => assert!(dx < 0.0 && dy == 0.0, "{dx} {dy}"),
+                Orientation::Portrait => assert!(dx == 0.0 && dy > 0.0, "{dx} {dy}"),
+            }
+        }
+    }
+
+    #[test]
+    fn stars_in_perspective_come_closer_and_spread_out() {
+        let mut mock = MockScreen::landscape();
+        mock.config.perspective = true;
+        let mut stars = spawn(&mut mock, 200);
+        let out = |stars: &Stars, i| {
+            let (x, y) = stars.center(i);
+            (x - 960.0).hypot(y - 540.0)
+        };
+        stars.update(&mut mock.ctx(1.0 / 60.0));
+        let before: Vec<(f32, f32, u32)> = (0..200)
+            .map(|i| (stars.place[i].unwrap().z, out(&stars, i), stars.size[i]))
+            .collect();
+        stars.update(&mut mock.ctx(1.0));
+        for (i, &(z, from_middle, size)) in before.iter().enumerate() {
+            let place = stars.place[i].unwrap();
+            if place.z == 1.0 {
+                // Passed by, and back at the far end
+                continue;
+            }
+            assert!(place.z < z);
+            assert!(out(&stars, i) >= from_middle - 1.0 && stars.size[i] >= size);
+            assert!(stars.x[i] >= -PERSPECTIVE_MAX_SIZE && stars.x[i] < 1920.0);
+        }
+
+        // Back to drifting, at the size it had
+        mock.config.perspective = false;
+        let sizes = mock.config.star_min_size..=mock.config.star_max_size;
+        stars.update(&mut mock.ctx(1.0 / 60.0));
+        assert!(stars.place.iter().all(Option::is_none));
+        assert!(stars.size.iter().all(|size| sizes.contains(size)));
+    }
+
+    #[test]
+    fn stars_drift_at_the_set_angle() {
+        let mut mock = MockScreen::landscape();
+        mock.config.drift_angle = Some(225.0);
+        let mut stars = spawn(&mut mock, 1);
+        (stars.x[0], stars.y[0]) = (960.0, 540.0);
+        stars.update(&mut mock.ctx(0.5));
+        let (dx, dy) = (stars.x[0] - 960.0, stars.y[0] - 540.0);
+        assert!(dx < 0.0 && (dx + dy).abs() < 0.01, "{dx} {dy}");
+
+        // Off the bottom or left, and back in along the top or right
+        let mut entries = [0; 2];
+        for _ in 0..100 {
+            (stars.x[0], stars.y[0]) = (1.0, 1079.0);
+            stars.speed[0] = 100.0;
+            stars.depth[0] = 1.0;
+            stars.update(&mut mock.ctx(0.1));
+            if stars.y[0] == 0.0 {
+                entries[0] += 1;
+            } else {
+                assert_eq!(stars.x[0], SCREEN.width as f32);
+                entries[1] += 1;
+            }
+        }
+        // The top is the longer edge
+        assert!(entries[0] > entries[1] && entries[1] > 0, "{entries:?}");
+    }
+
+    #[test]
+    fn hotter_stars_are_bigger_and_brighter() {
+        let mut screen = MockScreen::landscape();
+        let stars = spawn(&mut screen, 2000);
+        let average = |hot: bool, of: &dyn Fn(usize) -> f32| {
+            let group: Vec<f32> = (0..stars.len())
+                .filter(|&i| (stars.color[i].2 > stars.color[i].0) == hot)
+                .map(of)
+                .collect();
+            group.iter().sum::<f32>() / group.len() as f32
+        };
+        let size = |i: usize| stars.size[i] as f32;
+        let glow = |i: usize| stars.glow[i];
+        assert!(average(true, &size) > average(false, &size) + 0.5);
+        assert!(average(true, &glow) > average(false, &glow));
+
+        // The old palette is still there, with sizes left to chance
+        screen.config.star_colors = StarColors::Palette;
+        let star = spawn(&mut screen, 1);
+        assert_eq!(star.glow[0], 1.0);
+        assert!(
+            [
+                (180, 200, 255),
+                (255, 255, 255),
+                (255, 255, 200),
+                (255, 220, 180),
+                (255, 180, 180)
+            ]
+            .contains(&star.color[0])
+        );
+    }
+}
@@ -0,0 +1,245 @@
+// Lifetime statistics: how many shooting stars have been wished on, and how
+// many comets, satellites, supernovae and sparkles have shown, over every run
+// so far and for how long. The running sky counts what happens on the first
+// output, like the status snapshot, and saves the totals to
+// $XDG_STATE_HOME/wl-starfield/stats every so often and when it stops.
+// `wl-starfield stats` prints them, and `stats_overlay` shows them in a
+// corner of the screen.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use wl_starfield::i18n::Locale;
+use wl_starfield::{SkyEvent, Starfield};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// How often the overlay's lines are brought up to date
+const OVERLAY_INTERVAL: Duration = Duration::from_secs(1);
+
+// $XDG_STATE_HOME/wl-starfield/stats, or under ~/.local/state without one
+fn stats_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("wl-starfield").join("stats"))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Stats {
+    shooting_stars: u64,
+    comets: u64,
+    satellites: u64,
+    supernovae: u64,
+    sparkles: u64,
+    focus_sessions: u64,
+    // Time spent running, in seconds
+    uptime: f64,
+}
+
+impl Stats {
+    fn count(&mut self, event: SkyEvent) {
+        match event {
+            SkyEvent::ShootingStar => self.shooting_stars += 1,
+            SkyEvent::Comet => self.comets += 1,
+            SkyEvent::Satellite => self.satellites += 1,
+            SkyEvent::Supernova => self.supernovae += 1,
+            SkyEvent::Sparkle => self.sparkles += 1,
+            SkyEvent::FocusStart => self.focus_sessions += 1,
+            SkyEvent::FocusEnd => {}
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "shooting_stars = {}\ncomets = {}\nsatellites = {}\nsupernovae = {}\n\
+             sparkles = {}\nfocus_sessions = {}\nuptime = {:.0}\n",
+            self.shooting_stars,
+            self.comets,
+            self.satellites,
+            self.supernovae,
+            self.sparkles,
+            self.focus_sessions,
+            self.uptime
+        )
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let mut stats = Self::default();
+        for line in text.lines() {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            match key.trim() {
+                "shooting_stars" => stats.shooting_stars = value.parse().ok()?,
+                "comets" => stats.comets = value.parse().ok()?,
+                "satellites" => stats.satellites = value.parse().ok()?,
+                "supernovae" => stats.supernovae = value.parse().ok()?,
+                "sparkles" => stats.sparkles = value.parse().ok()?,
+                "focus_sessions" => stats.focus_sessions = value.parse().ok()?,
+                "uptime" => stats.uptime = value.parse().ok()?,
+                // Written by a newer version
+                _ => {}
+            }
+        }
+        Some(stats)
+    }
+
+    fn lines(&self, locale: Locale) -> Vec<String> {
+        vec![
+            locale.plural("stats.shooting_stars", self.shooting_stars),
+            locale.plural("stats.comets", self.comets),
+            locale.plural("stats.satellites", self.satellites),
+            locale.plural("stats.supernovae", self.supernovae),
+            locale.plural("stats.sparkles", self.sparkles),
+            locale.plural("stats.focus_sessions", self.focus_sessions),
+            locale.format("stats.uptime", &[("time", &duration(self.uptime, locale))]),
+        ]
+    }
+}
+
+// "3d 4h 12m", leaving out leading zeros
+fn duration(seconds: f64, locale: Locale) -> String {
+    let minutes = (seconds / 60.0) as u64;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    let key = match (days, hours) {
+        (0, 0) => "time.minutes",
+        (0, _) => "time.hours",
+        _ => "time.days",
+    };
+    locale.format(
+        key,
+        &[
+            ("d", &days.to_string()),
+            ("h", &hours.to_string()),
+            ("m", &minutes.to_string()),
+        ],
+    )
+}
+
+fn load() -> Stats {
+    stats_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| Stats::decode(&text))
+        .unwrap_or_default()
+}
+
+// Keeps count of one output's sky, and the overlay showing the totals
+pub struct StatsKeeper {
+    path: Option<PathBuf>,
+    stats: Stats,
+    last_frame: Instant,
+    last_save: Instant,
+    // Lines for the overlay if it's on, and when they were last brought up
+    // to date
+    overlay: Option<Vec<String>>,
+    locale: Locale,
+    last_overlay: Instant,
+    failed: bool,
+}
+
+impl StatsKeeper {
+    pub fn new(overlay: bool) -> Self {
+        let stats = load();
+        let now = Instant::now();
+        let locale = Locale::detect();
+        Self {
+            path: stats_path(),
+            stats,
+            last_frame: now,
+            last_save: now,
+            overlay: overlay.then(|| stats.lines(locale)),
+            locale,
+            last_overlay: now,
+            failed: false,
+        }
+    }
+
+    pub fn frame(&mut self, starfield: &Starfield) {
+        let now = Instant::now();
+        self.stats.uptime += now.duration_since(self.last_frame).as_secs_f64();
+        self.last_frame = now;
+        for &event in starfield.events() {
+            self.stats.count(event);
+        }
+        if let Some(lines) = &mut self.overlay
+            && now.duration_since(self.last_overlay) >= OVERLAY_INTERVAL
+        {
+            *lines = self.stats.lines(self.locale);
+            self.last_overlay = now;
+        }
+        if now.duration_since(self.last_save) >= SAVE_INTERVAL {
+            self.save();
+            self.last_save = now;
+        }
+    }
+
+    // Put the overlay, if it's on, on a sky
+    pub fn show_on(&self, starfield: &mut Starfield) {
+        if let Some(lines) = &self.overlay {
+            starfield.hud.clone_from(lines);
+        }
+    }
+
+    fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if self.failed {
+            return;
+        }
+        // Write beside it and rename, so a crash never leaves half the file
+        let partial = path.with_extension("partial");
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&partial, self.stats.encode()))
+            .and_then(|()| fs::rename(&partial, path));
+        if let Err(err) = written {
+            eprintln!("wl-starfield: can't write {}: {err}", path.display());
+            self.failed = true;
+        }
+    }
+}
+
+impl Drop for StatsKeeper {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+// `wl-starfield stats`: print the totals so far
+pub fn show() {
+    println!("{}", load().lines(Locale::detect()).join("\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_count_events_and_round_trip() {
+        let mut stats = Stats::default();
+        for event in [
+            SkyEvent::ShootingStar,
+            SkyEvent::ShootingStar,
+            SkyEvent::Supernova,
+            SkyEvent::FocusStart,
+            SkyEvent::FocusEnd,
+        ] {
+            stats.count(event);
+        }
+        stats.uptime = 3.0 * 86_400.0 + 4.0 * 3600.0 + 12.0 * 60.0 + 30.0;
+        let decoded = Stats::decode(&stats.encode()).unwrap();
+        assert_eq!(decoded.shooting_stars, 2);
+        assert_eq!(decoded.focus_sessions, 1);
+        assert_eq!(Stats::decode("comets: 3"), None);
+
+        let lines = decoded.lines(Locale::En);
+        assert_eq!(lines[0], "2 shooting stars wished on");
+        assert_eq!(lines[3], "1 supernova");
+        assert_eq!(lines[6], "3d 4h 12m under the stars");
+        assert_eq!(duration(59.0, Locale::En), "0m");
+        assert_eq!(decoded.lines(Locale::Fr)[6], "3j 4h 12min sous les étoiles");
+    }
+}
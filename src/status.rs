@@ -0,0 +1,254 @@
+// Status for bars like waybar. The running sky writes a small snapshot of
+// itself to the runtime directory about once a second, and `wl-starfield
+// status` reads it back, as plain text or as a waybar custom-module JSON line.
+// With --follow it keeps printing a line whenever the snapshot changes, which
+// is what a waybar `exec` module without an interval wants.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use wl_starfield::i18n::Locale;
+use wl_starfield::{SkyStatus, Starfield, report};
+
+// How often the running sky writes a snapshot, and followers look for one
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+// A snapshot older than this was left by a sky that has stopped
+const STALE_AFTER: Duration = Duration::from_secs(5);
+const PHASES: [&str; 8] = ["🌑", "🌒", "🌓", "🌔", "🌕", "🌖", "🌗", "🌘"];
+
+// $XDG_RUNTIME_DIR/wl-starfield.status, or in the temp dir without one
+fn snapshot_path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join("wl-starfield.status")
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Snapshot {
+    fps: f32,
+    sky: SkyStatus,
+}
+
+impl Snapshot {
+    fn encode(&self) -> String {
+        let sky = &self.sky;
+        format!(
+            "fps = {:.1}\nfocused = {}\nbreathing = {}\nmoon_phase = {:.3}\ncomet = {}\nsatellites = {}\n",
+            self.fps, sky.focused, sky.breathing, sky.moon_phase, sky.comet, sky.satellites
+        )
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let mut snapshot = Self::default();
+        for line in text.lines() {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            match key.trim() {
+                "fps" => snapshot.fps = value.parse().ok()?,
+                "focused" => snapshot.sky.focused = value.parse().ok()?,
+                "breathing" => snapshot.sky.breathing = value.parse().ok()?,
+                "moon_phase" => snapshot.sky.moon_phase = value.parse().ok()?,
+                "comet" => snapshot.sky.comet = value.parse().ok()?,
+                "satellites" => snapshot.sky.satellites = value.parse().ok()?,
+                // Written by a newer version
+                _ => {}
+            }
+        }
+        Some(snapshot)
+    }
+}
+
+// Counts frames on one output and writes out a snapshot once a second
+pub struct StatusWriter {
+    path: PathBuf,
+    frames: u32,
+    since: Instant,
+    failed: bool,
+}
+
+impl StatusWriter {
+    pub fn new() -> Self {
+        Self {
+            path: snapshot_path(),
+            frames: 0,
+            since: Instant::now(),
+            failed: false,
+        }
+    }
+
+    pub fn frame(&mut self, starfield: &Starfield) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed < WRITE_INTERVAL || self.failed {
+            return;
+        }
+        let snapshot = Snapshot {
+            fps: self.frames as f32 / elapsed.as_secs_f32(),
+            sky: starfield.status(),
+        };
+        self.frames = 0;
+        self.since = Instant::now();
+
+        // Write beside it and rename, so readers never see half a snapshot
+        let partial = self.path.with_extension("partial");
+        let written =
+            fs::write(&partial, snapshot.encode()).and_then(|()| fs::rename(&partial, &self.path));
+        if let Err(err) = written {
+            eprintln!("wl-starfield: can't write {}: {err}", self.path.display());
+            self.failed = true;
+        }
+    }
+}
+
+// The current snapshot, or None if no sky is running
+fn read_snapshot() -> Option<Snapshot> {
+    let path = snapshot_path();
+    let age = fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+    if age > STALE_AFTER {
+        return None;
+    }
+    Snapshot::decode(&fs::read_to_string(path).ok()?)
+}
+
+fn phase(moon_phase: f32) -> (&'static str, &'static str) {
+    (
+        PHASES[(moon_phase * 8.0).round() as usize % 8],
+        // Named for waybar's `alt`, which styles match on, so never translated
+        report::phase_name(moon_phase, Locale::En),
+    )
+}
+
+// What's happening, one item per line
+fn details(snapshot: &Snapshot, locale: Locale) -> Vec<String> {
+    let sky = &snapshot.sky;
+    let mut lines = vec![
+        report::phase_line(sky.moon_phase, locale),
+        format!("{:.0} fps", snapshot.fps),
+    ];
+    if sky.focused {
+        lines.push(locale.tr("status.focus").into());
+    }
+    if sky.breathing {
+        lines.push(locale.tr("status.breathing").into());
+    }
+    if sky.comet {
+        lines.push(locale.tr("status.comet").into());
+    }
+    if sky.satellites > 0 {
+        lines.push(locale.plural("status.satellites", sky.satellites as u64));
+    }
+    lines
+}
+
+fn plain(snapshot: Option<&Snapshot>, locale: Locale) -> String {
+    match snapshot {
+        Some(snapshot) => details(snapshot, locale).join("\n"),
+        None => "not running".into(),
+    }
+}
+
+// One line of waybar custom-module JSON
+fn waybar(snapshot: Option<&Snapshot>, locale: Locale) -> String {
+    let Some(snapshot) = snapshot else {
+        return r#"{"text": "", "tooltip": "wl-starfield is not running", "class": "stopped"}"#
+            .into();
+    };
+    let sky = &snapshot.sky;
+    let (icon, name) = phase(sky.moon_phase);
+    let class = if sky.focused { "focus" } else { "running" };
+    let percentage = (sky.moon_phase * 100.0).round() as u32;
+    format!(
+        r#"{{"text": {}, "alt": {}, "tooltip": {}, "class": "{class}", "percentage": {percentage}}}"#,
+        json_string(icon),
+        json_string(&name.to_lowercase().replace(' ', "-")),
+        json_string(&details(snapshot, locale).join("\n")),
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// `wl-starfield status`: print the running sky's status, then with `follow`
+// keep printing it whenever it changes
+pub fn show(json: bool, follow: bool) -> io::Result<()> {
+    let locale = Locale::detect();
+    let format = |snapshot: Option<Snapshot>| {
+        if json {
+            waybar(snapshot.as_ref(), locale)
+        } else {
+            plain(snapshot.as_ref(), locale)
+        }
+    };
+    let mut stdout = io::stdout();
+    let mut last = format(read_snapshot());
+    writeln!(stdout, "{last}")?;
+    if !follow {
+        return Ok(());
+    }
+    loop {
+        stdout.flush()?;
+        thread::sleep(WRITE_INTERVAL);
+        let now = format(read_snapshot());
+        if now != last {
+            writeln!(stdout, "{now}")?;
+            last = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_round_trip_into_waybar_json() {
+        let snapshot = Snapshot {
+            fps: 59.94,
+            sky: SkyStatus {
+                focused: true,
+                breathing: false,
+                moon_phase: 0.5,
+                comet: true,
+                satellites: 2,
+            },
+        };
+        let decoded = Snapshot::decode(&snapshot.encode()).unwrap();
+        assert!((decoded.fps - 59.9).abs() < 1e-3);
+        assert_eq!(decoded.sky, snapshot.sky);
+        assert_eq!(Snapshot::decode("fps: 60"), None);
+
+        assert_eq!(
+            waybar(Some(&decoded), Locale::En),
+            "{\"text\": \"🌕\", \"alt\": \"full-moon\", \
+             \"tooltip\": \"Full moon (100% lit)\\n60 fps\\nFocus session\\n\
+             A comet is crossing\\n2 satellites overhead\", \
+             \"class\": \"focus\", \"percentage\": 50}"
+        );
+        assert!(waybar(None, Locale::En).contains("\"stopped\""));
+        assert!(waybar(Some(&decoded), Locale::Fr).contains("\"alt\": \"full-moon\""));
+        assert!(waybar(Some(&decoded), Locale::Fr).contains("Pleine lune"));
+        assert_eq!(phase(0.98).1, "New moon");
+        assert_eq!(phase(0.26).1, "First quarter");
+    }
+}
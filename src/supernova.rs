@@ -0,0 +1,275 @@
+// A supernova: one of the background stars flares up over a few seconds and
+// throws off a ring of glow that spreads and thins out, along with a burst of
+// debris that carries on with the star's drift, then fades back down to a
+// dim, reddish remnant that lingers for a minute before it's gone. It rides
+// along with its star, and ends early if the star drifts off the edge.
+
+use crate::particles::{Emitter, Kind};
+use crate::render::{Rect, Target};
+use crate::{CelestialObject, ScreenDetails, UpdateContext};
+
+// Seconds to flare up to full brightness, then to fade down to the remnant
+const RISE_TIME: f32 = 3.0;
+const FADE_TIME: f32 = 8.0;
+// Seconds the remnant lingers after that, fading out over the last of them
+const REMNANT_TIME: f32 = 60.0;
+const REMNANT_FADE: f32 = 15.0;
+// Pixels across the flare at its peak, and the remnant
+const PEAK_SIZE: i32 = 16;
+const REMNANT_SIZE: i32 = 8;
+const REMNANT_ALPHA: f32 = 0.3;
+// The ring leaves at the peak and spreads at RING_SPEED pixels a second,
+// thinning out to nothing over RING_TIME seconds
+const RING_SPEED: f32 = 12.0;
+const RING_TIME: f32 = 6.0;
+const RING_ALPHA: f32 = 0.5;
+// The ring is built from overlapping glows every this many pixels around
+const RING_STEP: f32 = 2.0;
+const RING_SIZE: i32 = 6;
+const RING_COLOR: (u8, u8, u8) = (170, 200, 255);
+const REMNANT_COLOR: (u8, u8, u8) = (255, 140, 160);
+// Debris blown out at the peak, outrunning the ring, in no hurry to fade
+const DEBRIS: usize = 48;
+const DEBRIS_KIND: Kind = Kind {
+    inherit: 1.0,
+    speed: (8.0, 30.0),
+    life: 5.0,
+    color: (255, 200, 170),
+    alpha: 0.6,
+    size: 2,
+    gravity: 0.0,
+};
+
+pub struct Supernova {
+    // The star that went off, and its seed, which changes if it wraps around
+    // and comes back as another
+    star: usize,
+    seed: u64,
+    x: f32,
+    y: f32,
+    // The star's own color, which the flare starts from
+    color: (u8, u8, u8),
+    age: f32,
+    // Where it was at the last update, to tell how fast its star is drifting
+    last: (f32, f32),
+    debris: Emitter,
+    // Set once its star has gone
+    lost: bool,
+}
+
+impl Supernova {
+    pub fn new(star: usize, seed: u64, (x, y): (f32, f32), color: (u8, u8, u8)) -> Self {
+        Self {
+            star,
+            seed,
+            x,
+            y,
+            color,
+            age: 0.0,
+            last: (x, y),
+            debris: Emitter::new(DEBRIS_KIND, seed),
+            lost: false,
+        }
+    }
+
+    // Index and seed of the star that went off
+    pub fn star(&self) -> (usize, u64) {
+        (self.star, self.seed)
+    }
+
+    // Keep to the star as it drifts, given where its center is now, or None
+    // once it has drifted off
+    pub fn follow(&mut self, center: Option<(f32, f32)>) {
+        match center {
+            Some((x, y)) => (self.x, self.y) = (x, y),
+            None => self.lost = true,
+        }
+    }
+
+    // The flare's brightness, 0..1: up over RISE_TIME, then back down
+    pub fn brightness(&self) -> f32 {
+        let t = if self.age < RISE_TIME {
+            self.age / RISE_TIME
+        } else {
+            1.0 - ((self.age - RISE_TIME) / FADE_TIME).min(1.0)
+        };
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // How strongly the remnant shows, 0..1: in as the flare fades, out at the end
+    fn remnant(&self) -> f32 {
+        let settled = ((self.age - RISE_TIME) / FADE_TIME).clamp(0.0, 1.0);
+        let left = (RISE_TIME + FADE_TIME + REMNANT_TIME - self.age) / REMNANT_FADE;
+        settled.min(left.clamp(0.0, 1.0))
+    }
+
+    // Radius and opacity of the ring, while there is one
+    fn ring(&self) -> Option<(f32, f32)> {
+        let age = self.age - RISE_TIME;
+        (0.0..RING_TIME)
+            .contains(&age)
+            .then(|| (age * RING_SPEED, RING_ALPHA * (1.0 - age / RING_TIME)))
+    }
+}
+
+impl CelestialObject for Supernova {
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        let peaked = self.age < RISE_TIME && self.age + ctx.dt >= RISE_TIME;
+        self.age += ctx.dt;
+        if peaked && ctx.dt > 0.0 {
+            let drift = (
+                (self.x - self.last.0) / ctx.dt,
+                (self.y - self.last.1) / ctx.dt,
+            );
+            self.debris
+                .burst(ctx.particles, DEBRIS, (self.x, self.y), drift);
+        }
+        self.last = (self.x, self.y);
+    }
+
+    fn draw(&self, target: &mut Target) {
+        let remnant = self.remnant();
+        if remnant > 0.0 {
+            target.soft_point(
+                self.x,
+                self.y,
+                REMNANT_COLOR,
+                REMNANT_ALPHA * remnant,
+                REMNANT_SIZE,
+            );
+        }
+        if let Some((radius, alpha)) = self.ring() {
+            let steps = (std::f32::consts::TAU * radius / RING_STEP).ceil().max(8.0);
+            for i in 0..steps as u32 {
+                let angle = i as f32 / steps * std::f32::consts::TAU;
+                let (sin, cos) = angle.sin_cos();
+                let (x, y) = (self.x + cos * radius, self.y + sin * radius);
+                target.soft_point(x, y, RING_COLOR, alpha, RING_SIZE);
+            }
+        }
+        let flare = self.brightness();
+        if flare > 0.01 {
+            // Whitening as it brightens
+            let (r, g, b) = self.color;
+            let whiten = |c: u8| c + ((255 - c) as f32 * flare) as u8;
+            let size = 2 + (PEAK_SIZE as f32 * flare) as i32;
+            target.soft_point(
+                self.x,
+                self.y,
+                (whiten(r), whiten(g), whiten(b)),
+                flare,
+                size,
+            );
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        let reach = match self.ring() {
+            Some((radius, _)) => radius + RING_SIZE as f32,
+            None => PEAK_SIZE as f32,
+        };
+        Rect::around(self.x, self.y, reach)
+    }
+
+    fn is_alive(&self, _: &ScreenDetails) -> bool {
+        !self.lost && self.age < RISE_TIME + FADE_TIME + REMNANT_TIME
+    }
+
+    // Only ever moves with its star
+    fn motion(&self, _: &ScreenDetails) -> ((f32, f32), (f32, f32)) {
+        ((self.x, self.y), (0.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, MockScreen};
+
+    const SMALL: ScreenDetails = ScreenDetails {
+        width: 200,
+        height: 200,
+    };
+
+    #[test]
+    fn supernovas_flare_then_leave_a_dim_remnant() {
+        let mut screen = MockScreen::new(SMALL);
+        let mut supernova = Supernova::new(0, 0, (100.0, 100.0), (255, 180, 180));
+        let clock = FixedClock::fps(30.0);
+        let mut advance = |supernova: &mut Supernova, secs| {
+            screen.run(supernova, clock, secs);
+            let mut frame = Frame::new(&SMALL);
+            frame.draw(supernova);
+            frame
+        };
+
+        let peak = advance(&mut supernova, RISE_TIME);
+        assert!(peak.brightness(100, 100) > 240);
+        peak.assert_dark(100 + PEAK_SIZE as u32, 100);
+
+        // The ring spreads out past the flare as it fades, with dark sky
+        // between them
+        let ring = advance(&mut supernova, 2.0);
+        let radius = (2.0 * RING_SPEED) as u32;
+        let across = |x: &dyn Fn(u32) -> (u32, u32)| {
+            (radius - 2..=radius + 2)
+                .map(|r| {
+                    let (x, y) = x(r);
+                    ring.brightness(x, y)
+                })
+                .max()
+                .unwrap()
+        };
+        assert!(across(&|r| (100 + r, 100)) > 40);
+        assert!(across(&|r| (100, 100 - r)) > 40);
+        ring.assert_dark(100 + radius / 2, 100);
+        ring.assert_dark(100 + radius + RING_SIZE as u32, 100);
+
+        let remnant = advance(&mut supernova, FADE_TIME + RING_TIME);
+        let dim = remnant.brightness(100, 100);
+        assert!((20..120).contains(&dim), "{dim}");
+        assert!(remnant.pixel(100, 100)[0] > remnant.pixel(100, 100)[2]);
+        // Just the remnant's small glow is left
+        assert!(remnant.lit(0) <= ((REMNANT_SIZE + 1) * (REMNANT_SIZE + 1)) as usize);
+        assert!(supernova.is_alive(&SMALL));
+
+        advance(&mut supernova, REMNANT_TIME);
+        assert!(!supernova.is_alive(&SMALL));
+    }
+
+    #[test]
+    fn debris_bursts_out_at_the_peak_and_drifts_with_the_star() {
+        let mut screen = MockScreen::new(SMALL);
+        let mut supernova = Supernova::new(0, 0, (100.0, 100.0), (255, 255, 255));
+        let dt = 0.1;
+        // Its star drifting right at 50 pixels a second
+        for step in 1..=(RISE_TIME / dt) as usize + 1 {
+            supernova.follow(Some((100.0 + 50.0 * dt * step as f32, 100.0)));
+            supernova.update(&mut screen.ctx(dt));
+        }
+        assert_eq!(screen.particles.len(), DEBRIS);
+        let middle = |screen: &MockScreen| {
+            let sum = screen
+                .particles
+                .bounds()
+                .fold(0.0, |sum, rect| sum + (rect.x0 + rect.x1) as f32 / 2.0);
+            sum / DEBRIS as f32
+        };
+        let start = middle(&screen);
+        screen.particles.update(1.0, 0.0);
+        let drifted = middle(&screen) - start;
+        assert!((40.0..60.0).contains(&drifted), "{drifted}");
+        // Just the once
+        supernova.update(&mut screen.ctx(dt));
+        assert_eq!(screen.particles.len(), DEBRIS);
+    }
+
+    #[test]
+    fn supernovas_end_with_their_star() {
+        let mut supernova = Supernova::new(0, 0, (100.0, 100.0), (255, 255, 255));
+        supernova.follow(Some((90.0, 100.0)));
+        assert_eq!(supernova.motion(&SMALL).0, (90.0, 100.0));
+        supernova.follow(None);
+        assert!(!supernova.is_alive(&SMALL));
+    }
+}
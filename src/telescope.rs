@@ -0,0 +1,190 @@
+// Observatory "telescope view": a circular inset in the bottom-right corner
+// showing a magnified patch of sky. Besides the real stars in the patch it
+// fills in a denser layer of faint background stars, generated on demand from
+// a hash of sky cells so the same patch always looks the same.
+
+use crate::render::{Rect, Target, blend, pack};
+use crate::rng::hash_cell;
+use crate::stars::Stars;
+use crate::{ScreenDetails, UpdateContext};
+use rand::Rng;
+
+const RADIUS: f32 = 150.0;
+const MARGIN: f32 = 40.0;
+const MAGNIFICATION: f32 = 4.0;
+// Pick a new target this often, then take SLEW_TIME seconds to swing over
+const RETARGET_TIME: f32 = 60.0;
+const SLEW_TIME: f32 = 8.0;
+// Sky is split into cells of this many screen pixels; each may hold one faint star
+const FAINT_CELL: f32 = 5.0;
+const FAINT_CHANCE: u64 = 35; // percent of cells with a star
+// Extra sky margin when picking visible stars, so discs straddling the edge still show
+const STAR_REACH: f32 = 4.0;
+
+pub struct Telescope {
+    from: (f32, f32),
+    to: (f32, f32),
+    since_retarget: f32,
+    // Indices of stars inside the current field of view
+    visible: Vec<usize>,
+}
+
+impl Telescope {
+    pub fn new(rng: &mut impl Rng, screen_details: &ScreenDetails) -> Self {
+        let to = Self::pick_target(rng, screen_details);
+        Self {
+            from: to,
+            to,
+            since_retarget: SLEW_TIME,
+            visible: Vec::new(),
+        }
+    }
+
+    // Somewhere the whole field of view is sky, or the middle of a surface
+    // too small for that
+    fn pick_target(rng: &mut impl Rng, screen_details: &ScreenDetails) -> (f32, f32) {
+        let mut along = |size: u32| {
+            let size = size as f32;
+            let reach = (RADIUS / MAGNIFICATION).min(size / 2.0);
+            rng.gen_range(reach..=size - reach)
+        };
+        (along(screen_details.width), along(screen_details.height))
+    }
+
+    // Where the telescope is pointing right now, easing between targets
+    fn pointing(&self) -> (f32, f32) {
+        let t = (self.since_retarget / SLEW_TIME).clamp(0.0, 1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        (
+            self.from.0 + (self.to.0 - self.from.0) * eased,
+            self.from.1 + (self.to.1 - self.from.1) * eased,
+        )
+    }
+
+    fn center(screen_details: &ScreenDetails) -> (f32, f32) {
+        (
+            screen_details.width as f32 - MARGIN - RADIUS,
+            screen_details.height as f32 - MARGIN - RADIUS,
+        )
+    }
+
+    pub fn update(&mut self, ctx: &mut UpdateContext, stars: &Stars) {
+        self.since_retarget += ctx.dt;
+        if self.since_retarget >= RETARGET_TIME {
+            self.from = self.pointing();
+            self.to = Self::pick_target(ctx.rng, ctx.screen_details);
+            self.since_retarget = 0.0;
+        }
+
+        let (px, py) = self.pointing();
+        let reach = RADIUS / MAGNIFICATION + STAR_REACH;
+        self.visible.clear();
+        self.visible.extend(
+            (0..stars.len())
+                .filter(|&i| (stars.x[i] - px).abs() < reach && (stars.y[i] - py).abs() < reach),
+        );
+    }
+
+    pub fn bounds(&self, screen_details: &ScreenDetails) -> Rect {
+        let (cx, cy) = Self::center(screen_details);
+        Rect::around(cx, cy, RADIUS + 2.0)
+    }
+
+    pub fn draw(&self, target: &mut Target, screen_details: &ScreenDetails, stars: &Stars) {
+        if !target.overlaps(self.bounds(screen_details)) {
+            return;
+        }
+
+        let (cx, cy) = Self::center(screen_details);
+        let (px, py) = self.pointing();
+        let inside = |x: i32, y: i32| (x as f32 - cx).hypot(y as f32 - cy) < RADIUS;
+
+        // Dark glass with a thin rim
+        let glass = pack((2, 3, 10));
+        for (y, left, row) in target.span_rows(self.bounds(screen_details)) {
+            for (x, pixel) in (left..).zip(row) {
+                let dist = (x as f32 - cx).hypot(y as f32 - cy);
+                if dist < RADIUS {
+                    *pixel = glass;
+                } else if dist < RADIUS + 2.0 {
+                    blend(pixel, (120, 130, 150), 0.6);
+                }
+            }
+        }
+
+        // Map a sky position into the inset
+        let to_inset = |sx: f32, sy: f32| {
+            (
+                cx + (sx - px) * MAGNIFICATION,
+                cy + (sy - py) * MAGNIFICATION,
+            )
+        };
+
+        // Faint background stars, one potential star per sky cell
+        let reach = RADIUS / MAGNIFICATION;
+        let (cell_x0, cell_x1) = (
+            ((px - reach) / FAINT_CELL).floor() as i64,
+            ((px + reach) / FAINT_CELL).ceil() as i64,
+        );
+        let (cell_y0, cell_y1) = (
+            ((py - reach) / FAINT_CELL).floor() as i64,
+            ((py + reach) / FAINT_CELL).ceil() as i64,
+        );
+        for cell_y in cell_y0..cell_y1 {
+            for cell_x in cell_x0..cell_x1 {
+                let hash = hash_cell(cell_x, cell_y);
+                if hash % 100 >= FAINT_CHANCE {
+                    continue;
+                }
+                let offset_x = (hash >> 8 & 0xff) as f32 / 256.0;
+                let offset_y = (hash >> 16 & 0xff) as f32 / 256.0;
+                let brightness = 40 + (hash >> 24 & 0x5f) as u8;
+                let (ix, iy) = to_inset(
+                    (cell_x as f32 + offset_x) * FAINT_CELL,
+                    (cell_y as f32 + offset_y) * FAINT_CELL,
+                );
+                if inside(ix as i32, iy as i32) {
+                    let tint = (brightness, brightness, brightness.saturating_add(20));
+                    target.blend(ix as i32, iy as i32, tint, 1.0);
+                }
+            }
+        }
+
+        // The real stars in view, as magnified soft discs
+        for &i in &self.visible {
+            let half = stars.size[i] as f32 / 2.0;
+            let (ix, iy) = to_inset(stars.x[i] + half, stars.y[i] + half);
+            let disc = (half * MAGNIFICATION * 0.5).max(1.5);
+            let color = stars.shade(i);
+
+            for (y, left, row) in target.span_rows(Rect::around(ix, iy, disc)) {
+                for (x, pixel) in (left..).zip(row) {
+                    let dist = (x as f32 - ix).hypot(y as f32 - iy);
+                    if dist < disc && inside(x, y) {
+                        blend(pixel, color, 1.0 - (dist / disc).powi(2));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::FastRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn targets_stay_on_tiny_surfaces() {
+        let mut rng = FastRng::seed_from_u64(1);
+        for (width, height) in [(1, 1), (20, 600), (75, 75), (800, 30)] {
+            let screen_details = ScreenDetails { width, height };
+            for _ in 0..50 {
+                let (x, y) = Telescope::pick_target(&mut rng, &screen_details);
+                assert!((0.0..=width as f32).contains(&x), "{x} in {width}");
+                assert!((0.0..=height as f32).contains(&y), "{y} in {height}");
+            }
+        }
+    }
+}
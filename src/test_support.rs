@@ -0,0 +1,164 @@
+// Scaffolding for behavioral tests of sky objects without a window or a GPU: a
+// mock screen that hands out update contexts, a fixed-step clock, seeded RNGs,
+// and a frame buffer to draw into and make assertions about.
+
+use crate::config::Config;
+use crate::particles::Particles;
+use crate::render::{Pixel, Rect, Target};
+use crate::rng::FastRng;
+use crate::{CelestialObject, ScreenDetails, UpdateContext};
+use rand::SeedableRng;
+use std::time::SystemTime;
+
+pub const LANDSCAPE: ScreenDetails = ScreenDetails {
+    width: 1920,
+    height: 1080,
+};
+pub const PORTRAIT: ScreenDetails = ScreenDetails {
+    width: 1080,
+    height: 1920,
+};
+
+// The same numbers every run, so failures reproduce
+pub fn seeded_rng(seed: u64) -> FastRng {
+    FastRng::seed_from_u64(seed)
+}
+
+// Everything an object sees of the world while updating: the output it is on,
+// the config and the random numbers
+pub struct MockScreen {
+    pub details: ScreenDetails,
+    pub config: Config,
+    pub rng: FastRng,
+    pub particles: Particles,
+}
+
+impl MockScreen {
+    pub fn new(details: ScreenDetails) -> Self {
+        Self {
+            details,
+            config: Config::default(),
+            rng: seeded_rng(0),
+            particles: Particles::new(),
+        }
+    }
+
+    pub fn landscape() -> Self {
+        Self::new(LANDSCAPE)
+    }
+
+    pub fn portrait() -> Self {
+        Self::new(PORTRAIT)
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seeded_rng(seed);
+        self
+    }
+
+    pub fn ctx(&mut self, dt: f32) -> UpdateContext<'_> {
+        UpdateContext {
+            dt,
+            now: SystemTime::now(),
+            rng: &mut self.rng,
+            screen_details: &self.details,
+            config: &self.config,
+            particles: &mut self.particles,
+        }
+    }
+
+    // Step `object` on `clock` for up to `secs`, stopping early if it dies.
+    // Returns how long it lived.
+    pub fn run(&mut self, object: &mut impl CelestialObject, clock: FixedClock, secs: f32) -> f32 {
+        let mut lived = 0.0;
+        for dt in clock.ticks(secs) {
+            if !object.is_alive(&self.details) {
+                break;
+            }
+            object.update(&mut self.ctx(dt));
+            lived += dt;
+        }
+        lived
+    }
+}
+
+// A frame clock that always ticks by exactly the same step
+#[derive(Clone, Copy)]
+pub struct FixedClock {
+    pub dt: f32,
+}
+
+impl FixedClock {
+    pub fn fps(fps: f32) -> Self {
+        Self { dt: 1.0 / fps }
+    }
+
+    // One step per frame in the next `secs`
+    pub fn ticks(self, secs: f32) -> impl Iterator<Item = f32> {
+        let frames = (secs / self.dt).round() as u32;
+        (0..frames).map(move |_| self.dt)
+    }
+}
+
+// A whole-screen frame buffer on black, drawn as a single tile
+pub struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<Pixel>,
+}
+
+impl Frame {
+    pub fn new(screen_details: &ScreenDetails) -> Self {
+        let (width, height) = (screen_details.width, screen_details.height);
+        Self {
+            width,
+            height,
+            pixels: vec![[0, 0, 0, 255]; (width * height) as usize],
+        }
+    }
+
+    pub fn target(&mut self) -> Target<'_> {
+        let clip = Rect::new(0, 0, self.width as i32, self.height as i32);
+        Target::new(&mut self.pixels, self.width, (0, 0), clip)
+    }
+
+    pub fn draw(&mut self, object: &impl CelestialObject) {
+        object.draw(&mut self.target());
+    }
+
+    pub fn pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Pixel {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    // Brightest channel at a pixel
+    pub fn brightness(&self, x: u32, y: u32) -> u8 {
+        let [r, g, b, _] = self.pixel(x, y);
+        r.max(g).max(b)
+    }
+
+    // Pixels with some channel brighter than `level`
+    pub fn lit(&self, level: u8) -> usize {
+        self.pixels
+            .iter()
+            .filter(|p| p[..3].iter().any(|&c| c > level))
+            .count()
+    }
+
+    #[track_caller]
+    pub fn assert_brighter_than(&self, x: u32, y: u32, level: u8) {
+        let brightness = self.brightness(x, y);
+        assert!(
+            brightness > level,
+            "pixel ({x}, {y}) is {brightness}, wanted brighter than {level}"
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_dark(&self, x: u32, y: u32) {
+        assert_eq!(self.pixel(x, y), [0, 0, 0, 255], "pixel ({x}, {y}) is lit");
+    }
+}
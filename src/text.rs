@@ -0,0 +1,265 @@
+// Minimal bitmap text: the classic 5x8 column-major LCD font for printable
+// ASCII, drawn with integer scaling. Accented Latin letters fold onto their
+// base letter, and ß and the æ and œ ligatures are spelled out in two; anything
+// else outside the table renders as '?'.
+
+use crate::render::{Rect, Target, blend};
+
+const GLYPH_W: i32 = 5;
+const GLYPH_H: i32 = 8;
+// One blank column between glyphs, one blank row between lines
+const ADVANCE: i32 = GLYPH_W + 1;
+const LINE_HEIGHT: i32 = GLYPH_H + 2;
+
+// Columns for ' '..='~'; bit 0 is the top row
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x08, 0x07, 0x03, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x80, 0x70, 0x30, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x00, 0x60, 0x60, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x72, 0x49, 0x49, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x49, 0x4D, 0x33], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x31], // 6
+    [0x41, 0x21, 0x11, 0x09, 0x07], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x46, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x00, 0x14, 0x00, 0x00], // :
+    [0x00, 0x40, 0x34, 0x00, 0x00], // ;
+    [0x00, 0x08, 0x14, 0x22, 0x41], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x59, 0x09, 0x06], // ?
+    [0x3E, 0x41, 0x5D, 0x59, 0x4E], // @
+    [0x7C, 0x12, 0x11, 0x12, 0x7C], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x41, 0x3E], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x73], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x1C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x26, 0x49, 0x49, 0x49, 0x32], // S
+    [0x03, 0x01, 0x7F, 0x01, 0x03], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x59, 0x49, 0x4D, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x41], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x41, 0x7F], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x03, 0x07, 0x08, 0x00], // `
+    [0x20, 0x54, 0x54, 0x78, 0x40], // a
+    [0x7F, 0x28, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x28], // c
+    [0x38, 0x44, 0x44, 0x28, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x00, 0x08, 0x7E, 0x09, 0x02], // f
+    [0x18, 0xA4, 0xA4, 0x9C, 0x78], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x40, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x78, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0xFC, 0x18, 0x24, 0x24, 0x18], // p
+    [0x18, 0x24, 0x24, 0x18, 0xFC], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x24], // s
+    [0x04, 0x04, 0x3F, 0x44, 0x24], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x4C, 0x90, 0x90, 0x90, 0x7C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x77, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+];
+
+// The letters to draw for `c`, one or two
+fn fold(c: char) -> (char, Option<char>) {
+    let pair = match c {
+        'ß' => ('s', 's'),
+        'ẞ' => ('S', 'S'),
+        'Æ' => ('A', 'E'),
+        'æ' => ('a', 'e'),
+        'Œ' => ('O', 'E'),
+        'œ' => ('o', 'e'),
+        _ => return (fold_accent(c), None),
+    };
+    (pair.0, Some(pair.1))
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'À'..='Å' => 'A',
+        'Ç' => 'C',
+        'È'..='Ë' => 'E',
+        'Ì'..='Ï' => 'I',
+        'Ñ' => 'N',
+        'Ò'..='Ö' => 'O',
+        'Ù'..='Ü' => 'U',
+        'à'..='å' => 'a',
+        'ç' => 'c',
+        'è'..='ë' => 'e',
+        'ì'..='ï' => 'i',
+        'ñ' => 'n',
+        'ò'..='ö' => 'o',
+        'ù'..='ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+// The characters of `text` as the font draws them
+fn folded(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars().flat_map(|c| {
+        let (first, second) = fold(c);
+        std::iter::once(first).chain(second)
+    })
+}
+
+fn glyph(c: char) -> &'static [u8; 5] {
+    let index = match c {
+        c @ ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    &FONT[index]
+}
+
+// Pixel size of a block of lines at the given scale
+pub fn measure<S: AsRef<str>>(lines: &[S], scale: i32) -> (i32, i32) {
+    let cols = lines
+        .iter()
+        .map(|line| folded(line.as_ref()).count())
+        .max()
+        .unwrap_or(0) as i32;
+    let width = (cols * ADVANCE - 1).max(0) * scale;
+    let height = (lines.len() as i32 * LINE_HEIGHT - 2).max(0) * scale;
+    (width, height)
+}
+
+// Greedy word wrap so no line is wider than `max_width` pixels. Words longer
+// than a whole line are left to overflow rather than split.
+pub fn wrap(text: &str, max_width: i32, scale: i32) -> Vec<String> {
+    let max_cols = ((max_width / scale + 1) / ADVANCE).max(1) as usize;
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let needed = folded(&line).count() + 1 + folded(word).count();
+        if !line.is_empty() && needed > max_cols {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn line_height(scale: i32) -> i32 {
+    LINE_HEIGHT * scale
+}
+
+// Draw one line with its top-left corner at (x, y)
+pub fn draw_line(
+    target: &mut Target,
+    x: i32,
+    y: i32,
+    text: &str,
+    color: (u8, u8, u8),
+    alpha: f32,
+    scale: i32,
+) {
+    let (width, _) = measure(&[text], scale);
+    if !target.overlaps(Rect::new(x, y, x + width, y + GLYPH_H * scale)) {
+        return;
+    }
+
+    for (i, c) in folded(text).enumerate() {
+        let left = x + i as i32 * ADVANCE * scale;
+        for (col, bits) in glyph(c).iter().enumerate() {
+            for row in 0..GLYPH_H {
+                if bits >> row & 1 == 0 {
+                    continue;
+                }
+                let cell_x = left + col as i32 * scale;
+                let cell_y = y + row * scale;
+                let cell = Rect::new(cell_x, cell_y, cell_x + scale, cell_y + scale);
+                for (_, _, span) in target.span_rows(cell) {
+                    for pixel in span {
+                        blend(pixel, color, alpha);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_keeps_lines_within_width() {
+        let lines = wrap(
+            "the quick brown fox jumps over the lazy dog",
+            10 * ADVANCE,
+            1,
+        );
+        assert_eq!(
+            lines,
+            ["the quick", "brown fox", "jumps over", "the lazy", "dog"]
+        );
+        assert!(lines.iter().all(|l| measure(&[l], 1).0 <= 10 * ADVANCE));
+    }
+
+    #[test]
+    fn spells_out_letters_the_font_lacks() {
+        assert_eq!(
+            folded("Straße, Œuvre").collect::<String>(),
+            "Strasse, OEuvre"
+        );
+        assert_eq!(folded("Júpiter").collect::<String>(), "Jupiter");
+        assert_eq!(measure(&["Straße"], 1), measure(&["Strasse"], 1));
+    }
+}
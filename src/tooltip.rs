@@ -0,0 +1,71 @@
+// Small translucent text box that hangs next to a point of interest, flipping
+// sides when it would run off the screen. The first line is drawn as a title.
+
+use crate::ScreenDetails;
+use crate::render::{Rect, Target, blend};
+use crate::text;
+
+const SCALE: i32 = 2;
+const PADDING: i32 = 8;
+const OFFSET: i32 = 14;
+
+pub struct Tooltip {
+    lines: Vec<String>,
+    anchor: (f32, f32),
+}
+
+impl Tooltip {
+    pub fn new(lines: Vec<String>, anchor: (f32, f32)) -> Self {
+        Self { lines, anchor }
+    }
+
+    pub fn move_to(&mut self, anchor: (f32, f32)) {
+        self.anchor = anchor;
+    }
+
+    pub fn bounds(&self, screen_details: &ScreenDetails) -> Rect {
+        let (text_w, text_h) = text::measure(&self.lines, SCALE);
+        let (w, h) = (text_w + PADDING * 2, text_h + PADDING * 2);
+        let (ax, ay) = (self.anchor.0 as i32, self.anchor.1 as i32);
+
+        let x = if ax + OFFSET + w > screen_details.width as i32 {
+            ax - OFFSET - w
+        } else {
+            ax + OFFSET
+        };
+        let y = if ay + OFFSET + h > screen_details.height as i32 {
+            ay - OFFSET - h
+        } else {
+            ay + OFFSET
+        };
+        Rect::new(x, y, x + w, y + h)
+    }
+
+    pub fn draw(&self, target: &mut Target, screen_details: &ScreenDetails) {
+        let rect = self.bounds(screen_details);
+        if !target.overlaps(rect) {
+            return;
+        }
+
+        for (y, left, row) in target.span_rows(rect) {
+            for (x, pixel) in (left..).zip(row) {
+                let edge = x == rect.x0 || x == rect.x1 - 1 || y == rect.y0 || y == rect.y1 - 1;
+                if edge {
+                    blend(pixel, (110, 120, 160), 0.8);
+                } else {
+                    blend(pixel, (8, 10, 22), 0.8);
+                }
+            }
+        }
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let color = if i == 0 {
+                (255, 240, 200)
+            } else {
+                (180, 190, 210)
+            };
+            let y = rect.y0 + PADDING + i as i32 * text::line_height(SCALE);
+            text::draw_line(target, rect.x0 + PADDING, y, line, color, 1.0, SCALE);
+        }
+    }
+}
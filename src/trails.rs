@@ -0,0 +1,115 @@
+// Star trails: rather than starting each frame from empty sky, the sky is
+// drawn over the last one faded a little toward it, so everything that moves
+// leaves a trail behind it like a long-exposure photograph. `trail_decay` of the light
+// left fades each second, whatever the frame rate. Only the sky builds up;
+// the dimming and overlays drawn over it each frame don't.
+
+use crate::render::{Pixel, Rect, Target};
+
+pub(crate) struct Trails {
+    // Share of the light left that fades in a second
+    decay: f32,
+    // Seconds since the sky was last kept
+    elapsed: f32,
+    // The sky as last drawn, before what's drawn over it
+    sky: Vec<Pixel>,
+}
+
+impl Trails {
+    pub fn new(decay: f32) -> Self {
+        Self {
+            decay,
+            elapsed: 0.0,
+            sky: Vec::new(),
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    // Start afresh from empty sky
+    pub fn clear(&mut self) {
+        self.sky.clear();
+    }
+
+    // Lay the last sky over the empty sky drawn in the part of a frame
+    // covering `view` that the target holds, faded for the time since
+    pub fn draw(&self, target: &mut Target, view: Rect) {
+        let width = (view.x1 - view.x0) as usize;
+        if self.sky.len() != width * (view.y1 - view.y0) as usize {
+            return;
+        }
+        let keep = (1.0 - self.decay).powf(self.elapsed);
+        // Rounded toward the empty sky, so trails end rather than lingering
+        // a step off it
+        let fade = |from: u8, to: u8| {
+            let left = ((from as f32 - to as f32) * keep) as i32;
+            (to as i32 + left) as u8
+        };
+        for (y, left, row) in target.span_rows(view) {
+            let start = (y - view.y0) as usize * width + (left - view.x0) as usize;
+            for (pixel, &[r, g, b, _]) in row.iter_mut().zip(&self.sky[start..]) {
+                let [to_r, to_g, to_b, _] = *pixel;
+                *pixel = [fade(r, to_r), fade(g, to_g), fade(b, to_b), 255];
+            }
+        }
+    }
+
+    // Keep the sky just drawn to fade into the next
+    pub fn keep(&mut self, pixels: &[Pixel]) {
+        self.sky.clear();
+        self.sky.extend_from_slice(pixels);
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{BACKGROUND, pack};
+    use crate::test_support::{Frame, LANDSCAPE};
+
+    #[test]
+    fn light_fades_at_the_decay_rate_and_then_is_gone() {
+        let view = Rect::new(0, 0, 1920, 1080);
+        let mut trails = Trails::new(0.5);
+        let mut frame = Frame::new(&LANDSCAPE);
+        // Empty sky, then the trails over it
+        let redraw = |frame: &mut Frame, trails: &Trails, empty| {
+            frame.target().fill(pack(empty));
+            trails.draw(&mut frame.target(), view);
+        };
+        redraw(&mut frame, &trails, BACKGROUND);
+        assert_eq!(frame.pixel(100, 100), pack(BACKGROUND));
+
+        frame.target().blend(100, 100, (255, 255, 255), 1.0);
+        trails.keep(frame.pixels());
+        // Nothing has changed until time passes
+        redraw(&mut frame, &trails, BACKGROUND);
+        frame.assert_brighter_than(100, 100, 254);
+        trails.advance(1.0);
+        redraw(&mut frame, &trails, BACKGROUND);
+        let half = frame.brightness(100, 100);
+        assert!((126..=130).contains(&half), "{half}");
+
+        for _ in 0..200 {
+            trails.keep(frame.pixels());
+            trails.advance(0.1);
+            redraw(&mut frame, &trails, BACKGROUND);
+        }
+        assert_eq!(frame.pixel(100, 100), pack(BACKGROUND));
+
+        // Whatever the empty sky is, it fades to that
+        trails.keep(frame.pixels());
+        trails.advance(1.0);
+        redraw(&mut frame, &trails, (100, 100, 100));
+        assert_eq!(frame.brightness(100, 100), 50);
+
+        frame.target().blend(100, 100, (255, 255, 255), 1.0);
+        trails.keep(frame.pixels());
+        trails.clear();
+        redraw(&mut frame, &trails, BACKGROUND);
+        assert_eq!(frame.pixel(100, 100), pack(BACKGROUND));
+    }
+}
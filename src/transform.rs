@@ -0,0 +1,112 @@
+// Parent/child transforms for objects made of parts, such as a planet and the
+// moons going round it. Each part is a node placed relative to its parent, so
+// it goes wherever the parent goes and the object never has to add one part's
+// position to another's by hand. Parents are added before their children, so
+// a node's parents always sit earlier in the list than it does.
+
+// An affine map of the plane: (x, y) goes to
+// (xx * x + xy * y + offset.0, yx * x + yy * y + offset.1)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    xx: f32,
+    xy: f32,
+    yx: f32,
+    yy: f32,
+    offset: (f32, f32),
+}
+
+impl Transform {
+    // Moved by (`x`, `y`)
+    pub fn translate(x: f32, y: f32) -> Self {
+        Self {
+            xx: 1.0,
+            xy: 0.0,
+            yx: 0.0,
+            yy: 1.0,
+            offset: (x, y),
+        }
+    }
+
+    // Stretched by `x` across and `y` down, about the origin
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self {
+            xx: x,
+            yy: y,
+            ..Self::translate(0.0, 0.0)
+        }
+    }
+
+    // This, then `outer`
+    pub fn then(self, outer: Transform) -> Self {
+        Self {
+            xx: outer.xx * self.xx + outer.xy * self.yx,
+            xy: outer.xx * self.xy + outer.xy * self.yy,
+            yx: outer.yx * self.xx + outer.yy * self.yx,
+            yy: outer.yx * self.xy + outer.yy * self.yy,
+            offset: outer.apply(self.offset),
+        }
+    }
+
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.xx * x + self.xy * y + self.offset.0,
+            self.yx * x + self.yy * y + self.offset.1,
+        )
+    }
+
+    // Where the origin ends up
+    pub fn origin(&self) -> (f32, f32) {
+        self.offset
+    }
+}
+
+// A part in a `Hierarchy`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Node(usize);
+
+#[derive(Default)]
+pub struct Hierarchy {
+    // Each node's parent, if it has one, and where it is relative to it
+    nodes: Vec<(Option<Node>, Transform)>,
+}
+
+impl Hierarchy {
+    pub fn add(&mut self, parent: Option<Node>, local: Transform) -> Node {
+        self.nodes.push((parent, local));
+        Node(self.nodes.len() - 1)
+    }
+
+    // Move `node` relative to its parent, taking its children with it
+    pub fn set(&mut self, node: Node, local: Transform) {
+        self.nodes[node.0].1 = local;
+    }
+
+    // Where `node` is on the screen, through every parent above it
+    pub fn world(&self, node: Node) -> Transform {
+        match self.nodes[node.0] {
+            (Some(parent), local) => local.then(self.world(parent)),
+            (None, local) => local,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_go_wherever_their_parents_do() {
+        let mut parts = Hierarchy::default();
+        let body = parts.add(None, Transform::translate(100.0, 50.0));
+        let orbit = parts.add(Some(body), Transform::scale(1.0, 0.5));
+        let moon = parts.add(Some(orbit), Transform::translate(0.0, 10.0));
+        // Squashed by its orbit, then carried along with the body
+        assert_eq!(parts.world(moon).origin(), (100.0, 55.0));
+        assert_eq!(parts.world(orbit).apply((4.0, 4.0)), (104.0, 52.0));
+
+        parts.set(body, Transform::translate(20.0, 30.0));
+        assert_eq!(parts.world(moon).origin(), (20.0, 35.0));
+        parts.set(moon, Transform::translate(8.0, 0.0));
+        assert_eq!(parts.world(moon).origin(), (28.0, 30.0));
+    }
+}
@@ -0,0 +1,196 @@
+// Picking a quality tier and star density for the machine. The first time the
+// sky runs, it spends a couple of seconds drawing frames nobody sees, at each
+// quality tier, and keeps the answer in $XDG_STATE_HOME/wl-starfield/tuning
+// for every run after. Only settings config.toml leaves at their defaults are
+// tuned; `auto_tune = false` leaves them all alone, and --retune measures
+// again, say after a new graphics card.
+
+use rand::SeedableRng;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use wl_starfield::config::{Config, Quality};
+use wl_starfield::rng::FastRng;
+use wl_starfield::{ScreenDetails, Starfield};
+
+// Time spent drawing at each quality tier
+const MEASURE_TIME: Duration = Duration::from_secs(1);
+// Frames drawn first and not counted, while caches fill
+const WARM_UP: usize = 10;
+const DT: f32 = 1.0 / 60.0;
+// Measured at 1080p, since this runs before any window is open
+const SCREEN: ScreenDetails = ScreenDetails {
+    width: 1920,
+    height: 1080,
+};
+// High quality is picked when its frames take under HIGH_SHARE of the frame
+// budget, leaving room for clouds, nebula and the rest
+const HIGH_SHARE: f32 = 0.35;
+// Stars are thinned out when standard frames take more than STARS_SHARE of
+// the budget, but never below MIN_STARS of the default
+const STARS_SHARE: f32 = 0.5;
+const MIN_STARS: f32 = 0.25;
+
+// $XDG_STATE_HOME/wl-starfield/tuning, or under ~/.local/state without one
+fn tuning_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("wl-starfield").join("tuning"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Tuning {
+    quality: Quality,
+    star_count: usize,
+}
+
+impl Tuning {
+    // Pick from how long a frame took at each tier
+    fn choose(standard: Duration, high: Duration, budget: Duration, stars: usize) -> Self {
+        let budget = budget.as_secs_f32();
+        let quality = if high.as_secs_f32() < budget * HIGH_SHARE {
+            Quality::High
+        } else {
+            Quality::Standard
+        };
+        let share = budget * STARS_SHARE / standard.as_secs_f32().max(f32::EPSILON);
+        let star_count = (stars as f32 * share.clamp(MIN_STARS, 1.0)) as usize;
+        Self {
+            quality,
+            star_count,
+        }
+    }
+
+    fn encode(&self) -> String {
+        let quality = match self.quality {
+            Quality::Standard => "standard",
+            Quality::Smooth => "smooth",
+            Quality::High => "high",
+        };
+        format!("quality = {quality}\nstar_count = {}\n", self.star_count)
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let (mut quality, mut star_count) = (None, None);
+        for line in text.lines() {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            match key.trim() {
+                "quality" => quality = Some(value.parse().ok()?),
+                "star_count" => star_count = Some(value.parse().ok()?),
+                // Written by a newer version
+                _ => {}
+            }
+        }
+        Some(Self {
+            quality: quality?,
+            star_count: star_count?,
+        })
+    }
+}
+
+// The mean time to update and draw a frame of this sky at a quality tier
+fn measure(config: &Config, quality: Quality) -> Duration {
+    let config = Config {
+        quality,
+        ..config.clone()
+    };
+    let mut starfield = Starfield::new(FastRng::seed_from_u64(1), SCREEN, config);
+    let mut frame = vec![0; SCREEN.width as usize * SCREEN.height as usize * 4];
+    for _ in 0..WARM_UP {
+        starfield.update(DT);
+        starfield.render(&mut frame);
+    }
+    let start = Instant::now();
+    let mut frames = 0;
+    while start.elapsed() < MEASURE_TIME {
+        starfield.update(DT);
+        starfield.render(&mut frame);
+        frames += 1;
+    }
+    start.elapsed() / frames
+}
+
+// Use what was picked for this machine, measuring first if nothing has been
+// or `retune` asks
+pub fn apply(config: &mut Config, budget: Duration, retune: bool) {
+    let defaults = Config::default();
+    if !config.auto_tune
+        || (config.quality != defaults.quality && config.star_count != defaults.star_count)
+    {
+        return;
+    }
+    let path = tuning_path();
+    let saved = path
+        .as_ref()
+        .filter(|_| !retune)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| Tuning::decode(&text));
+    let tuning = saved.unwrap_or_else(|| {
+        let standard = measure(config, Quality::Standard);
+        let high = measure(config, Quality::High);
+        let tuning = Tuning::choose(standard, high, budget, config.star_count);
+        eprintln!(
+            "wl-starfield: tuned for this machine: {:?} quality, {} stars \
+             (pass --retune to measure again)",
+            tuning.quality, tuning.star_count
+        );
+        if let Some(path) = &path {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(path, tuning.encode()));
+            if let Err(err) = written {
+                eprintln!("wl-starfield: can't write {}: {err}", path.display());
+            }
+        }
+        tuning
+    });
+    if config.quality == defaults.quality {
+        config.quality = tuning.quality;
+    }
+    if config.star_count == defaults.star_count {
+        config.star_count = tuning.star_count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn fast_machines_get_high_quality_and_slow_ones_fewer_stars() {
+        let fast = Tuning::choose(BUDGET / 10, BUDGET / 4, BUDGET, 5000);
+        assert_eq!(
+            fast,
+            Tuning {
+                quality: Quality::High,
+                star_count: 5000
+            }
+        );
+
+        // Standard frames taking the whole budget halve the stars
+        let slow = Tuning::choose(BUDGET, BUDGET * 2, BUDGET, 5000);
+        assert_eq!(slow.quality, Quality::Standard);
+        assert_eq!(slow.star_count, 2500);
+        // But never by more than MIN_STARS
+        let crawling = Tuning::choose(BUDGET * 100, BUDGET * 200, BUDGET, 5000);
+        assert_eq!(crawling.star_count, 1250);
+    }
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let tuning = Tuning {
+            quality: Quality::High,
+            star_count: 3200,
+        };
+        assert_eq!(Tuning::decode(&tuning.encode()), Some(tuning));
+        assert_eq!(Tuning::decode("quality = high\n"), None);
+        assert_eq!(Tuning::decode("quality = ultra\nstar_count = 10\n"), None);
+    }
+}
@@ -0,0 +1,221 @@
+// Warp tunnel scene: rings of stars rushing out of the middle of the screen
+// toward the viewer, as if flying down a tube of them at warp speed. The
+// camera sits on the tube's axis and rolls one way and then the other. Each
+// ring is a circle of stars at some depth ahead; stars are drawn as streaks
+// from where they were a moment ago, lengthening as they come close. A ring
+// that passes the viewer goes back to the far end, turned to a new angle so
+// the pattern doesn't repeat.
+
+use crate::UpdateContext;
+use crate::render::{Rect, Target, TileBins};
+use rand::Rng;
+use std::f32::consts::TAU;
+
+// Rings in the tube, this far apart, and stars on each
+const RINGS: usize = 48;
+const RING_SPACING: f32 = 1.0;
+const STARS_PER_RING: usize = 24;
+// Rings are this nearest before they go back to the far end
+const NEAR: f32 = 0.2;
+// Rings passed each second
+const SPEED: f32 = 6.0;
+// A ring of the tube at depth 1 spans this fraction of the shorter side
+const FOCAL: f32 = 0.25;
+// Stars sit up to this fraction in or out from the ring
+const RING_JITTER: f32 = 0.15;
+// Streaks reach back this much depth behind their stars
+const STREAK: f32 = 0.6;
+// Fastest roll in radians a second, swinging back and forth this often
+const ROLL_SPEED: f32 = 0.25;
+const ROLL_PERIOD: f32 = 40.0;
+// Near stars grow to this many pixels across
+const MAX_SIZE: f32 = 4.0;
+// Streaks are fainter than the stars at their heads
+const STREAK_ALPHA: f32 = 0.6;
+const COLORS: [(u8, u8, u8); 3] = [(225, 235, 255), (150, 185, 255), (190, 160, 255)];
+
+struct Ring {
+    depth: f32,
+    // Each star's angle around the ring and distance from the axis
+    stars: [(f32, f32); STARS_PER_RING],
+    color: (u8, u8, u8),
+}
+
+impl Ring {
+    fn new(rng: &mut impl Rng, depth: f32) -> Self {
+        let turn = rng.gen_range(0.0..TAU);
+        Self {
+            depth,
+            stars: std::array::from_fn(|i| {
+                let angle = turn + i as f32 / STARS_PER_RING as f32 * TAU;
+                (angle, 1.0 + rng.gen_range(-RING_JITTER..RING_JITTER))
+            }),
+            color: COLORS[rng.gen_range(0..COLORS.len())],
+        }
+    }
+}
+
+// A star's streak on the screen this frame
+struct Streak {
+    from: (f32, f32),
+    to: (f32, f32),
+    color: (u8, u8, u8),
+    alpha: f32,
+    size: f32,
+}
+
+impl Streak {
+    fn bounds(&self) -> Rect {
+        let reach = self.size / 2.0 + 1.0;
+        Rect::around(self.from.0, self.from.1, reach)
+            .union(&Rect::around(self.to.0, self.to.1, reach))
+    }
+}
+
+pub struct Tunnel {
+    rings: Vec<Ring>,
+    roll: f32,
+    age: f32,
+    streaks: Vec<Streak>,
+    bins: TileBins,
+}
+
+impl Tunnel {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            rings: (0..RINGS)
+                .map(|i| Ring::new(rng, NEAR + (i + 1) as f32 * RING_SPACING))
+                .collect(),
+            roll: 0.0,
+            age: 0.0,
+            streaks: Vec::with_capacity(RINGS * STARS_PER_RING),
+            bins: TileBins::new(),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut UpdateContext) {
+        let dt = ctx.dt;
+        self.age += dt;
+        self.roll += ROLL_SPEED * (self.age / ROLL_PERIOD * TAU).sin() * dt;
+        let far = RINGS as f32 * RING_SPACING;
+        for ring in &mut self.rings {
+            ring.depth -= SPEED * RING_SPACING * dt;
+            if ring.depth < NEAR {
+                *ring = Ring::new(ctx.rng, ring.depth + far);
+            }
+        }
+
+        let (width, height) = (
+            ctx.screen_details.width as f32,
+            ctx.screen_details.height as f32,
+        );
+        let center = (width / 2.0, height / 2.0);
+        let focal = FOCAL * width.min(height);
+        self.streaks.clear();
+        for ring in &self.rings {
+            // Fading in from the far end, and brightest up close
+            let nearness = 1.0 - ring.depth / far;
+            let alpha = nearness * nearness;
+            let size = 1.0 + (MAX_SIZE - 1.0) * nearness.powi(4);
+            let tail = ring.depth + STREAK * nearness;
+            for &(angle, radius) in &ring.stars {
+                let (sin, cos) = (angle + self.roll).sin_cos();
+                let project = |depth: f32| {
+                    let reach = focal * radius / depth;
+                    (center.0 + reach * cos, center.1 + reach * sin)
+                };
+                self.streaks.push(Streak {
+                    from: project(tail),
+                    to: project(ring.depth),
+                    color: ring.color,
+                    alpha,
+                    size,
+                });
+            }
+        }
+    }
+
+    // Bin the streaks for drawing the tiles of a frame covering `view`
+    pub fn bin(&mut self, view: Rect) {
+        self.bins
+            .rebuild(view, self.streaks.iter().map(Streak::bounds));
+    }
+
+    pub fn draw(&self, tile: usize, target: &mut Target) {
+        for i in self.bins.get(tile) {
+            let streak = &self.streaks[i];
+            target.line(
+                streak.from,
+                streak.to,
+                streak.color,
+                streak.alpha * STREAK_ALPHA,
+            );
+            if streak.size >= 2.0 {
+                let (x, y) = streak.to;
+                target.soft_point(x, y, streak.color, streak.alpha, streak.size as i32);
+            } else {
+                target.blend(
+                    streak.to.0 as i32,
+                    streak.to.1 as i32,
+                    streak.color,
+                    streak.alpha,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FixedClock, Frame, MockScreen};
+
+    #[test]
+    fn rings_rush_past_and_come_round_again() {
+        let mut screen = MockScreen::landscape();
+        let mut tunnel = Tunnel::new(&mut screen.rng);
+        let nearest = |tunnel: &Tunnel| {
+            tunnel
+                .rings
+                .iter()
+                .map(|ring| ring.depth)
+                .fold(f32::MAX, f32::min)
+        };
+        let start = nearest(&tunnel);
+        tunnel.update(&mut screen.ctx(0.05));
+        assert!(nearest(&tunnel) < start);
+
+        for dt in FixedClock::fps(60.0).ticks(20.0) {
+            tunnel.update(&mut screen.ctx(dt));
+            assert!(tunnel.rings.iter().all(|ring| ring.depth >= NEAR));
+        }
+        assert_eq!(tunnel.rings.len(), RINGS);
+        assert_eq!(tunnel.streaks.len(), RINGS * STARS_PER_RING);
+        // It has rolled
+        assert!(tunnel.roll.abs() > 0.1, "{}", tunnel.roll);
+    }
+
+    #[test]
+    fn streaks_point_out_from_the_middle() {
+        let mut screen = MockScreen::landscape();
+        let details = screen.details;
+        let mut tunnel = Tunnel::new(&mut screen.rng);
+        tunnel.update(&mut screen.ctx(0.1));
+        let center = (960.0, 540.0);
+        let out = |(x, y): (f32, f32)| (x - center.0).hypot(y - center.1);
+        assert!(
+            tunnel
+                .streaks
+                .iter()
+                .all(|streak| out(streak.to) >= out(streak.from))
+        );
+
+        let mut frame = Frame::new(&details);
+        tunnel.bin(Rect::new(0, 0, 1920, 1080));
+        let mut target = frame.target();
+        for tile in 0..1920usize.div_ceil(64) * 1080usize.div_ceil(64) {
+            tunnel.draw(tile, &mut target);
+        }
+        assert!(frame.lit(40) > 200);
+    }
+}
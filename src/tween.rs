@@ -0,0 +1,394 @@
+// Easing settings to new values. A setting that opts in moves from where it
+// is to where it was asked to go over `transition_seconds`, following a
+// smoothstep, rather than jumping there: the sky speeds up and slows down,
+// brightens and dims, and gains and loses stars gradually. Setting one
+// directly stops any easing of it under way.
+//
+// Animations can also be spelled out, for choreographed sequences: a
+// starting value, how long and along which curve, as in
+// "brightness 0.3->1.0 over 10s ease-in-out". A timeline is a list of them
+// played one after another, separated by semicolons, with "wait 5s" for a
+// pause between: "brightness 0->1 over 10s; wait 5s; speed 120x over 20s".
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+// The settings that can be eased
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Param {
+    // How many times faster than real time the sky runs
+    Speed,
+    // How bright the sky is, 0..1
+    Brightness,
+    // How many stars there are
+    Stars,
+}
+
+impl Param {
+    pub const ALL: [Param; 3] = [Param::Speed, Param::Brightness, Param::Stars];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Param::Speed => "speed",
+            Param::Brightness => "brightness",
+            Param::Stars => "stars",
+        }
+    }
+
+    // A value for this setting: a number within its range, and for speed
+    // optionally followed by x, as in 120x
+    pub fn value(self, text: &str) -> Result<f32, String> {
+        let number = match self {
+            Param::Speed => text.strip_suffix('x').unwrap_or(text),
+            _ => text,
+        };
+        let value: f32 = number
+            .parse()
+            .map_err(|_| format!("`{}` needs a number, got `{text}`", self.name()))?;
+        let (low, high) = match self {
+            Param::Speed => (0.01, 10_000.0),
+            Param::Brightness => (0.0, 1.0),
+            Param::Stars => (0.0, 100_000.0),
+        };
+        if !(low..=high).contains(&value) {
+            return Err(format!(
+                "`{}` must be between {low} and {high}",
+                self.name()
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        Param::ALL
+            .into_iter()
+            .find(|param| param.name() == text)
+            .ok_or_else(|| format!("can't animate `{text}`"))
+    }
+}
+
+// The curve an animation follows from start to end
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    Linear,
+    // Starting slowly
+    EaseIn,
+    // Arriving slowly
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    // How far along at `t` of the way through, both 0..1
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        match text {
+            "linear" => Ok(Easing::Linear),
+            "ease-in" => Ok(Easing::EaseIn),
+            "ease-out" => Ok(Easing::EaseOut),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            _ => Err(format!(
+                "`{text}` isn't an easing: linear, ease-in, ease-out or ease-in-out"
+            )),
+        }
+    }
+}
+
+// Easing one setting to a value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub param: Param,
+    // Where to start, or from wherever it is
+    pub from: Option<f32>,
+    pub to: f32,
+    // How long to take, or transition_seconds
+    pub seconds: Option<f32>,
+    pub easing: Easing,
+}
+
+impl Animation {
+    // Easing to `to` from wherever it is, over transition_seconds
+    pub fn to(param: Param, to: f32) -> Self {
+        Self {
+            param,
+            from: None,
+            to,
+            seconds: None,
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl FromStr for Animation {
+    type Err = String;
+
+    // "<param> [<from>->]<to> [over <N>s] [<easing>]"
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let param: Param = words.next().ok_or("nothing to animate")?.parse()?;
+        let values = words
+            .next()
+            .ok_or_else(|| format!("`{}` needs a value", param.name()))?;
+        let (from, to) = match values.split_once("->").or_else(|| values.split_once('→')) {
+            Some((from, to)) => (Some(param.value(from)?), param.value(to)?),
+            None => (None, param.value(values)?),
+        };
+        let mut animation = Animation {
+            from,
+            ..Animation::to(param, to)
+        };
+        let mut word = words.next();
+        if word == Some("over") {
+            let duration = words.next().ok_or("`over` needs a duration, e.g. 10s")?;
+            animation.seconds = Some(seconds(duration)?);
+            word = words.next();
+        }
+        if let Some(easing) = word {
+            animation.easing = easing.parse()?;
+        }
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected `{extra}`"));
+        }
+        Ok(animation)
+    }
+}
+
+// "10s", "1.5s", "2m" or a bare number of seconds
+fn seconds(text: &str) -> Result<f32, String> {
+    let (number, unit) = match text.strip_suffix('m') {
+        Some(minutes) => (minutes, 60.0),
+        None => (text.strip_suffix('s').unwrap_or(text), 1.0),
+    };
+    let seconds = number
+        .parse::<f32>()
+        .map_err(|_| format!("`{text}` should be a duration like 10s or 2m"))?
+        * unit;
+    if !(0.0..=86_400.0).contains(&seconds) {
+        return Err(format!("`{text}` should be between 0 and a day"));
+    }
+    Ok(seconds)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Animate(Animation),
+    Wait(f32),
+}
+
+// Animations to play one after another
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline {
+    steps: Vec<Step>,
+}
+
+impl FromStr for Timeline {
+    type Err = String;
+
+    // Steps separated by semicolons; "off" for none
+    fn from_str(text: &str) -> Result<Self, String> {
+        if text.trim() == "off" {
+            return Ok(Timeline::default());
+        }
+        let steps = text
+            .split(';')
+            .map(|step| {
+                let step = step.trim();
+                match step.strip_prefix("wait ") {
+                    Some(duration) => seconds(duration.trim()).map(Step::Wait),
+                    None => step.parse().map(Step::Animate),
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Timeline { steps })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Tween {
+    from: f32,
+    to: f32,
+    elapsed: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+impl Tween {
+    fn value(&self) -> f32 {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        };
+        self.from + (self.to - self.from) * self.easing.apply(t)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Tweens {
+    running: [Option<Tween>; Param::ALL.len()],
+    // The rest of the timeline playing, and how long until its next step
+    timeline: VecDeque<Step>,
+    waiting: f32,
+}
+
+impl Tweens {
+    // Ease `param` from `from` to `to` over `duration` seconds
+    pub fn start(&mut self, param: Param, from: f32, to: f32, duration: f32, easing: Easing) {
+        self.running[param.index()] = Some(Tween {
+            from,
+            to,
+            elapsed: 0.0,
+            duration,
+            easing,
+        });
+    }
+
+    pub fn cancel(&mut self, param: Param) {
+        self.running[param.index()] = None;
+    }
+
+    // Play `timeline` from its start, in place of any already playing
+    pub fn play(&mut self, timeline: &Timeline) {
+        self.timeline = timeline.steps.iter().copied().collect();
+        self.waiting = 0.0;
+    }
+
+    // Move the timeline on by `dt` seconds
+    pub fn wait(&mut self, dt: f32) {
+        if !self.timeline.is_empty() {
+            self.waiting -= dt;
+        }
+    }
+
+    // The timeline's next animation, if it's due: each starts once the one
+    // before has finished, those without a duration taking `default_seconds`
+    pub fn next_due(&mut self, default_seconds: f32) -> Option<Animation> {
+        while self.waiting <= 0.0 {
+            match self.timeline.pop_front()? {
+                Step::Wait(seconds) => self.waiting += seconds,
+                Step::Animate(animation) => {
+                    self.waiting += animation.seconds.unwrap_or(default_seconds);
+                    return Some(animation);
+                }
+            }
+        }
+        None
+    }
+
+    // Move `param` on by `dt` seconds, giving its new value if it's being
+    // eased. The last step lands exactly on the value asked for.
+    pub fn step(&mut self, param: Param, dt: f32) -> Option<f32> {
+        let slot = &mut self.running[param.index()];
+        let tween = slot.as_mut()?;
+        tween.elapsed += dt;
+        let value = tween.value();
+        if tween.elapsed >= tween.duration {
+            *slot = None;
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eases_smoothly_and_lands_on_the_value() {
+        let mut tweens = Tweens::default();
+        assert_eq!(tweens.step(Param::Speed, 0.1), None);
+
+        tweens.start(Param::Speed, 1.0, 3.0, 1.0, Easing::EaseInOut);
+        let values: Vec<f32> = (0..8)
+            .map_while(|_| tweens.step(Param::Speed, 0.125))
+            .collect();
+        assert_eq!(values.len(), 8);
+        assert!(values.windows(2).all(|pair| pair[1] > pair[0]));
+        // Slow at the ends, quick in the middle
+        assert!(values[0] - 1.0 < values[4] - values[3]);
+        assert_eq!(values[7], 3.0);
+        assert_eq!(tweens.step(Param::Speed, 0.125), None);
+
+        // No duration snaps straight there, and cancelling leaves it be
+        tweens.start(Param::Stars, 100.0, 50.0, 0.0, Easing::Linear);
+        assert_eq!(tweens.step(Param::Stars, 0.0), Some(50.0));
+        tweens.start(Param::Brightness, 1.0, 0.0, 2.0, Easing::Linear);
+        tweens.cancel(Param::Brightness);
+        assert_eq!(tweens.step(Param::Brightness, 0.1), None);
+    }
+
+    #[test]
+    fn parses_animations_and_timelines() {
+        assert_eq!(
+            "brightness 0.3->1.0 over 10s ease-in".parse(),
+            Ok(Animation {
+                param: Param::Brightness,
+                from: Some(0.3),
+                to: 1.0,
+                seconds: Some(10.0),
+                easing: Easing::EaseIn,
+            })
+        );
+        assert_eq!("speed 120x".parse(), Ok(Animation::to(Param::Speed, 120.0)));
+        assert_eq!(
+            "stars 1000→2000 linear"
+                .parse::<Animation>()
+                .map(|a| a.from),
+            Ok(Some(1000.0))
+        );
+        assert!("brightness 0.3->2".parse::<Animation>().is_err());
+        assert!("brightness 1 over soon".parse::<Animation>().is_err());
+        assert!("brightness 1 bouncy".parse::<Animation>().is_err());
+        assert!("volume 1".parse::<Animation>().is_err());
+
+        let timeline: Timeline = "brightness 0->1 over 2s; wait 1.5s; speed 60x over 1m"
+            .parse()
+            .unwrap();
+        assert_eq!(timeline.steps.len(), 3);
+        assert_eq!(timeline.steps[1], Step::Wait(1.5));
+        assert_eq!("off".parse(), Ok(Timeline::default()));
+        assert!("brightness 1; wait".parse::<Timeline>().is_err());
+    }
+
+    #[test]
+    fn timeline_steps_wait_for_the_one_before() {
+        let mut tweens = Tweens::default();
+        tweens.play(
+            &"brightness 0->1 over 2s; wait 1s; stars 10"
+                .parse()
+                .unwrap(),
+        );
+        let due = |tweens: &mut Tweens, dt| {
+            tweens.wait(dt);
+            tweens.next_due(0.5).map(|animation| animation.param)
+        };
+        assert_eq!(due(&mut tweens, 0.0), Some(Param::Brightness));
+        assert_eq!(due(&mut tweens, 1.0), None);
+        assert_eq!(due(&mut tweens, 1.5), None);
+        assert_eq!(due(&mut tweens, 0.5), Some(Param::Stars));
+        // Then nothing, once the last one has had its transition_seconds
+        assert_eq!(due(&mut tweens, 0.5), None);
+        assert!(tweens.timeline.is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+// Video export: --record-video renders a sky with no window, a frame at a
+// time at a fixed step, and pipes the raw RGBA frames to ffmpeg to encode.
+// Nothing depends on how long frames take to make, so the video plays
+// smoothly at its frame rate on any machine, however slow the encoding.
+
+use crate::Sky;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use wl_starfield::{ScreenDetails, Starfield};
+
+// Render `seconds` of sky at `fps` frames a second into the video at `path`
+pub fn export(
+    path: &Path,
+    screen_details: ScreenDetails,
+    seconds: f32,
+    fps: f32,
+    new_starfield: impl Fn(ScreenDetails, Sky, Option<&str>) -> Starfield,
+) -> io::Result<()> {
+    let mut starfield = new_starfield(screen_details, Sky::Own(0), None);
+    // Full detail throughout, and the sky's clock kept to the frames
+    starfield.set_frame_budget(None);
+    starfield.set_fixed_clock(true);
+
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args(ffmpeg_args(path, screen_details, fps))
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| io::Error::new(err.kind(), format!("can't run ffmpeg: {err}")))?;
+    let mut input = ffmpeg.stdin.take().expect("stdin is piped");
+
+    let frames = (seconds * fps).round() as u32;
+    let mut frame = vec![0; screen_details.width as usize * screen_details.height as usize * 4];
+    let started = Instant::now();
+    eprintln!(
+        "wl-starfield: rendering {frames} frames to {}",
+        path.display()
+    );
+    let written = (0..frames).try_for_each(|_| {
+        starfield.update(1.0 / fps);
+        starfield.render(&mut frame);
+        input.write_all(&frame)
+    });
+    // Closing its input tells ffmpeg the video is over
+    drop(input);
+    let status = ffmpeg.wait()?;
+    written?;
+    if !status.success() {
+        return Err(io::Error::other(format!("ffmpeg failed, {status}")));
+    }
+    eprintln!(
+        "wl-starfield: saved {} in {:.1} s",
+        path.display(),
+        started.elapsed().as_secs_f32()
+    );
+    Ok(())
+}
+
+// Raw RGBA frames in on stdin; H.264 in the widely playable 4:2:0 out,
+// unless the file name asks for another format
+fn ffmpeg_args(path: &Path, screen_details: ScreenDetails, fps: f32) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-loglevel",
+        "error",
+        "-y",
+        "-f",
+        "rawvideo",
+        "-pix_fmt",
+        "rgba",
+        "-s",
+    ]
+    .map(String::from)
+    .into();
+    args.push(format!(
+        "{}x{}",
+        screen_details.width, screen_details.height
+    ));
+    args.extend(["-r".into(), fps.to_string(), "-i".into(), "-".into()]);
+    if path
+        .extension()
+        .is_some_and(|ext| ext == "mp4" || ext == "mkv")
+    {
+        args.extend(["-c:v", "libx264", "-pix_fmt", "yuv420p"].map(String::from));
+    }
+    args.push(path.display().to_string());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffmpeg_reads_raw_frames_of_the_right_size() {
+        let screen_details = ScreenDetails {
+            width: 1280,
+            height: 720,
+        };
+        let args = ffmpeg_args(Path::new("loop.mp4"), screen_details, 30.0);
+        let at = |flag: &str| {
+            let i = args.iter().position(|arg| arg == flag).unwrap();
+            args[i + 1].as_str()
+        };
+        assert_eq!(at("-s"), "1280x720");
+        assert_eq!(at("-r"), "30");
+        assert_eq!(at("-c:v"), "libx264");
+        assert_eq!(args.last().map(String::as_str), Some("loop.mp4"));
+
+        // Other containers are left to ffmpeg's defaults
+        let args = ffmpeg_args(Path::new("loop.webm"), screen_details, 30.0);
+        assert!(!args.iter().any(|arg| arg == "-c:v"));
+    }
+}
@@ -0,0 +1,208 @@
+// Render threads, started with the sky and kept for as long as it lasts, so
+// spreading a frame over cores doesn't mean spawning threads for every one.
+// They take one job at a time, split into numbered parts that are handed out
+// as threads come free; the thread that hands the job over takes parts too,
+// and doesn't return until every part is done. That wait is what lets a job
+// borrow from the stack of whoever hands it over, as the frame being drawn
+// does. Nothing is allocated once they're running.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+type Job = dyn Fn(usize) + Sync;
+
+pub struct Workers {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+    // Held through a job, so two are never in hand at once
+    turn: Mutex<()>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Woken when there's a job, or it's time to stop
+    work: Condvar,
+    // Woken as the last part of a job is done
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    // The job in hand, made to look as if it lives forever. It's only ever
+    // reached while `run` waits for it, and gone before `run` returns.
+    job: Option<&'static Job>,
+    parts: usize,
+    // The next part to hand out, and how many aren't done yet
+    next: usize,
+    unfinished: usize,
+    panicked: bool,
+    stop: bool,
+}
+
+impl State {
+    // The next part of the job in hand, if there are any left
+    fn take(&mut self) -> Option<(&'static Job, usize)> {
+        let job = self.job.filter(|_| self.next < self.parts)?;
+        self.next += 1;
+        Some((job, self.next - 1))
+    }
+
+    fn finish(&mut self, ok: bool) {
+        self.panicked |= !ok;
+        self.unfinished -= 1;
+    }
+}
+
+impl Workers {
+    // `threads` in all, counting the one jobs are handed over from
+    pub fn new(threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            work: Condvar::new(),
+            done: Condvar::new(),
+        });
+        let threads = (1..threads)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name("wl-starfield render".into())
+                    .spawn(move || work(&shared))
+                    .expect("can't start a render thread")
+            })
+            .collect();
+        Self {
+            shared,
+            threads,
+            turn: Mutex::new(()),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads.len() + 1
+    }
+
+    // Call `job` with each of 0..`parts`, spread over the threads, and return
+    // once it's done them all. A panic in any part comes back out here.
+    pub fn run(&self, parts: usize, job: impl Fn(usize) + Sync) {
+        if self.threads.is_empty() || parts <= 1 {
+            (0..parts).for_each(job);
+            return;
+        }
+        // A job that panicked leaves nothing half done to worry about
+        let _turn = self.turn.lock().unwrap_or_else(PoisonError::into_inner);
+        let job: &(dyn Fn(usize) + Sync + '_) = &job;
+        // SAFETY: the job is taken back out of the shared state below before
+        // this returns, and only once every part handed out is done, so no
+        // thread can reach it after it's gone
+        let job: &'static Job = unsafe { std::mem::transmute(job) };
+        let mut state = self.shared.state.lock().unwrap();
+        *state = State {
+            job: Some(job),
+            parts,
+            unfinished: parts,
+            ..State::default()
+        };
+        self.shared.work.notify_all();
+        while let Some((job, part)) = state.take() {
+            drop(state);
+            let ok = panic::catch_unwind(AssertUnwindSafe(|| job(part))).is_ok();
+            state = self.shared.state.lock().unwrap();
+            state.finish(ok);
+        }
+        while state.unfinished > 0 {
+            state = self.shared.done.wait(state).unwrap();
+        }
+        state.job = None;
+        let panicked = state.panicked;
+        drop(state);
+        if panicked {
+            panic!("a render thread panicked");
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stop = true;
+        self.shared.work.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+// A render thread: parts of whatever job is in hand, until told to stop
+fn work(shared: &Shared) {
+    let mut state = shared.state.lock().unwrap();
+    while !state.stop {
+        let Some((job, part)) = state.take() else {
+            state = shared.work.wait(state).unwrap();
+            continue;
+        };
+        drop(state);
+        let ok = panic::catch_unwind(AssertUnwindSafe(|| job(part))).is_ok();
+        state = shared.state.lock().unwrap();
+        state.finish(ok);
+        if state.unfinished == 0 {
+            shared.done.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn every_part_is_done_once_on_threads_kept_between_jobs() {
+        let workers = Workers::new(4);
+        assert_eq!(workers.threads(), 4);
+        let mut seen = HashSet::new();
+        for _ in 0..20 {
+            let counts: Vec<AtomicUsize> = (0..50).map(|_| AtomicUsize::new(0)).collect();
+            let threads = Mutex::new(HashSet::new());
+            workers.run(counts.len(), |part| {
+                counts[part].fetch_add(1, Ordering::Relaxed);
+                threads.lock().unwrap().insert(thread::current().id());
+                thread::yield_now();
+            });
+            assert!(
+                counts
+                    .iter()
+                    .all(|count| count.load(Ordering::Relaxed) == 1)
+            );
+            seen.extend(threads.into_inner().unwrap());
+        }
+        // The same few threads every time, not new ones for each job
+        assert!(seen.len() <= 4, "{}", seen.len());
+    }
+
+    #[test]
+    fn one_thread_does_it_all_itself() {
+        let workers = Workers::new(1);
+        let me = thread::current().id();
+        let parts = AtomicUsize::new(0);
+        workers.run(10, |_| {
+            assert_eq!(thread::current().id(), me);
+            parts.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(parts.into_inner(), 10);
+    }
+
+    #[test]
+    fn panics_come_back_out_and_the_threads_carry_on() {
+        let workers = Workers::new(3);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            workers.run(8, |part| assert_ne!(part, 5));
+        }));
+        assert!(result.is_err());
+        let parts = AtomicUsize::new(0);
+        workers.run(8, |_| {
+            parts.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(parts.into_inner(), 8);
+    }
+}